@@ -0,0 +1,18 @@
+#version 450
+
+// NOTE: BUFFERS AREA
+
+layout(set = 0, binding = 0) uniform texture2D t_cursor;
+layout(set = 0, binding = 1) uniform sampler s_cursor;
+
+// NOTE: IN VARIABLES
+
+layout(location = 0) in vec2 uv;
+
+// NOTE: OUT VARIABLES
+
+layout(location = 0) out vec4 frag_color;
+
+void main() {
+    frag_color = texture(sampler2D(t_cursor, s_cursor), uv);
+}
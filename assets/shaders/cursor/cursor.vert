@@ -0,0 +1,24 @@
+#version 450
+
+// NOTE: IN VARIABLES
+
+// vertex
+layout(location = 0) in vec4 v_pos;
+
+// instance: the rect of cursor image in `NDC`
+layout(location = 1) in vec2 rect_min;
+layout(location = 2) in vec2 rect_max;
+
+// NOTE: OUT VARIABLES
+
+// The uv of cursor image, (0, 0) is the left-top corner.
+layout(location = 0) out vec2 uv;
+
+void main() {
+    // (0, 0) is the left-bottom corner of quad, (1, 1) is the right-top corner.
+    const vec2 st = v_pos.xy + 0.5;
+
+    uv = vec2(st.x, 1.0 - st.y);
+
+    gl_Position = vec4(mix(rect_min, rect_max, st), 0.0, 1.0);
+}
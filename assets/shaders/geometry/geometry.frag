@@ -522,4 +522,12 @@ void main() {
                 break;
         }
     }
-}
\ No newline at end of file
+
+#ifdef STENCIL_MASK
+    // NOTE: the uncovered part of quad is discarded, so it doesn't write the stencil of `RenderGroup` mask.
+    // Only the masks do it, `discard` disables the early depth test of the other pipelines.
+    if(o_Target.w <= 0.0) {
+        discard;
+    }
+#endif
+}
@@ -0,0 +1,59 @@
+#version 450
+
+// NOTE: BUFFERS AREA
+
+layout(binding = 0) uniform texture2D t_source;
+layout(binding = 1) uniform sampler s_source;
+
+layout(binding = 2) uniform Params {
+    // The parameters of effect, see `PostEffect::params`.
+    vec4 params0;
+    vec4 params1;
+
+    // The size of a texel in uv.
+    vec2 texel_size;
+    // Total time
+    float t_total;
+};
+
+// NOTE: IN VARIABLES
+
+layout(location = 0) in vec2 uv;
+
+// NOTE: OUT VARIABLES
+
+layout(location = 0) out vec4 frag_color;
+
+// NOTE: FUNCTIONS AREA
+
+vec4 sample_source(vec2 st) {
+    return texture(sampler2D(t_source, s_source), st);
+}
+
+// Keep the part of color brighter than the threshold.
+vec3 bright_pass(vec3 color, float threshold) {
+    return max(color - vec3(threshold), vec3(0.0));
+}
+
+void main() {
+    float threshold = params0.x;
+    float intensity = params0.y;
+    float radius    = params0.z;
+
+    vec4 source = sample_source(uv);
+
+    // NOTE: a single pass gaussian blur of the bright part, 7x7 taps spread over the radius.
+    vec3 bloom = vec3(0.0);
+    float weight_sum = 0.0;
+    for (int x = -3; x <= 3; ++x) {
+        for (int y = -3; y <= 3; ++y) {
+            vec2 offset = vec2(x, y) / 3.0;
+            float weight = exp(-2.0 * dot(offset, offset));
+
+            bloom += weight * bright_pass(sample_source(uv + offset * radius * texel_size).rgb, threshold);
+            weight_sum += weight;
+        }
+    }
+
+    frag_color = vec4(source.rgb + intensity * bloom / weight_sum, source.a);
+}
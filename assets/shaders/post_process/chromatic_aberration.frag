@@ -0,0 +1,38 @@
+#version 450
+
+// NOTE: BUFFERS AREA
+
+layout(binding = 0) uniform texture2D t_source;
+layout(binding = 1) uniform sampler s_source;
+
+layout(binding = 2) uniform Params {
+    // The parameters of effect, see `PostEffect::params`.
+    vec4 params0;
+    vec4 params1;
+
+    // The size of a texel in uv.
+    vec2 texel_size;
+    // Total time
+    float t_total;
+};
+
+// NOTE: IN VARIABLES
+
+layout(location = 0) in vec2 uv;
+
+// NOTE: OUT VARIABLES
+
+layout(location = 0) out vec4 frag_color;
+
+void main() {
+    float strength = params0.x;
+
+    // NOTE: channels are split along the direction from the center, stronger at the edge.
+    vec2 offset = (uv - vec2(0.5)) * strength;
+
+    vec4 source = texture(sampler2D(t_source, s_source), uv);
+    float r = texture(sampler2D(t_source, s_source), uv + offset).r;
+    float b = texture(sampler2D(t_source, s_source), uv - offset).b;
+
+    frag_color = vec4(r, source.g, b, source.a);
+}
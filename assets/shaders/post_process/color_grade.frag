@@ -0,0 +1,43 @@
+#version 450
+
+// NOTE: BUFFERS AREA
+
+layout(binding = 0) uniform texture2D t_source;
+layout(binding = 1) uniform sampler s_source;
+
+layout(binding = 2) uniform Params {
+    // The parameters of effect, see `PostEffect::params`.
+    vec4 params0;
+    vec4 params1;
+
+    // The size of a texel in uv.
+    vec2 texel_size;
+    // Total time
+    float t_total;
+};
+
+// NOTE: IN VARIABLES
+
+layout(location = 0) in vec2 uv;
+
+// NOTE: OUT VARIABLES
+
+layout(location = 0) out vec4 frag_color;
+
+// NOTE: CONSTANTS AREA
+
+const vec3 LUMA = vec3(0.2126, 0.7152, 0.0722);
+
+void main() {
+    float saturation = params0.x;
+    float contrast   = params0.y;
+    float brightness = params0.z;
+
+    vec4 source = texture(sampler2D(t_source, s_source), uv);
+
+    vec3 color = source.rgb * brightness;
+    color = (color - 0.5) * contrast + 0.5;
+    color = mix(vec3(dot(color, LUMA)), color, saturation);
+
+    frag_color = vec4(clamp(color, 0.0, 1.0), source.a);
+}
@@ -0,0 +1,14 @@
+#version 450
+
+// NOTE: OUT VARIABLES
+
+// The uv of the source texture, (0, 0) is the left-top corner.
+layout(location = 0) out vec2 uv;
+
+void main() {
+    // NOTE: draw a triangle which covers the whole screen with 3 vertices and no vertex buffer.
+    vec2 st = vec2((gl_VertexIndex << 1) & 2, gl_VertexIndex & 2);
+
+    uv = vec2(st.x, 1.0 - st.y);
+    gl_Position = vec4(st * 2.0 - 1.0, 0.0, 1.0);
+}
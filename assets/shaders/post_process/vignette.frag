@@ -0,0 +1,39 @@
+#version 450
+
+// NOTE: BUFFERS AREA
+
+layout(binding = 0) uniform texture2D t_source;
+layout(binding = 1) uniform sampler s_source;
+
+layout(binding = 2) uniform Params {
+    // The parameters of effect, see `PostEffect::params`.
+    vec4 params0;
+    vec4 params1;
+
+    // The size of a texel in uv.
+    vec2 texel_size;
+    // Total time
+    float t_total;
+};
+
+// NOTE: IN VARIABLES
+
+layout(location = 0) in vec2 uv;
+
+// NOTE: OUT VARIABLES
+
+layout(location = 0) out vec4 frag_color;
+
+void main() {
+    float radius   = params0.x;
+    float softness = params0.y;
+    vec4 color     = params1;
+
+    vec4 source = texture(sampler2D(t_source, s_source), uv);
+
+    // NOTE: the distance is 1.0 at the corners.
+    float dist = length(uv - vec2(0.5)) * sqrt(2.0);
+    float factor = smoothstep(radius, radius + softness, dist) * color.a;
+
+    frag_color = vec4(mix(source.rgb, color.rgb, factor), source.a);
+}
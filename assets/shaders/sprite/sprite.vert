@@ -0,0 +1,69 @@
+#version 450
+
+// NOTE: BUFFERS AREA
+
+layout(set = 0, binding = 0) uniform Common {
+    // Transform point from `world space` to `eye space`.
+    mat4 MX_VIEW;
+    // Transform point from `eye space` to `NDC`.
+    mat4 MX_PROJECTION;
+    // Transform point from `NDC` to `screen space`.
+    mat4 MX_VIEWPORT;
+
+    // Viewport size
+    vec2 vp_size;
+
+    // Delta time
+    float t_delta;
+    // Total time
+    float t_total;
+};
+
+// NOTE: IN VARIABLES
+
+// vertex
+layout(location = 0) in vec4 v_pos;
+
+// instance: Transform2D
+layout(location = 1) in vec2 position;
+layout(location = 2) in vec2 complex;
+layout(location = 3) in vec2 scale;
+// instance: Sprite2D
+layout(location = 4) in vec2 size;
+layout(location = 5) in vec2 pivot;
+layout(location = 6) in vec2 uv_min;
+layout(location = 7) in vec2 uv_max;
+layout(location = 8) in uint color;
+
+// NOTE: OUT VARIABLES
+
+// The uv of texture, (0, 0) is the left-top corner.
+layout(location = 0) out vec2 uv;
+layout(location = 1) out vec4 tint;
+
+// NOTE: FUNCTIONS AREA
+
+vec4 hex_to_color(uint hex) {
+    uint r = hex >> 24;
+    uint g = hex >> 16 & 0xFF;
+    uint b = hex >> 8 & 0xFF;
+    uint a = hex & 0xFF;
+
+    return vec4(r, g, b, a) / 255.0;
+}
+
+void main() {
+    // (0, 0) is the left-bottom corner of quad, (1, 1) is the right-top corner.
+    const vec2 st = v_pos.xy + 0.5;
+
+    const vec2 local = (st - pivot) * size * scale;
+    const vec2 world = position + vec2(
+        complex.x * local.x - complex.y * local.y,
+        complex.y * local.x + complex.x * local.y
+    );
+
+    uv = mix(uv_min, uv_max, vec2(st.x, 1.0 - st.y));
+    tint = hex_to_color(color);
+
+    gl_Position = MX_PROJECTION * MX_VIEW * vec4(world, 0.0, 1.0);
+}
@@ -0,0 +1,49 @@
+#version 450
+
+// NOTE: BUFFERS AREA
+
+layout(set = 0, binding = 0) uniform Common {
+    // Transform point from `world space` to `eye space`.
+    mat4 MX_VIEW;
+    // Transform point from `eye space` to `NDC`.
+    mat4 MX_PROJECTION;
+    // Transform point from `NDC` to `screen space`.
+    mat4 MX_VIEWPORT;
+
+    // Viewport size
+    vec2 vp_size;
+
+    // Delta time
+    float t_delta;
+    // Total time
+    float t_total;
+};
+
+// NOTE: IN VARIABLES
+
+// vertex
+layout(location = 0) in vec4 v_pos;
+
+// instance: TileInstance
+layout(location = 1) in vec2 position;
+layout(location = 2) in vec2 size;
+layout(location = 3) in vec2 uv_min;
+layout(location = 4) in vec2 uv_max;
+
+// NOTE: OUT VARIABLES
+
+// The uv of tileset, (0, 0) is the left-top corner.
+layout(location = 0) out vec2 uv;
+
+// NOTE: FUNCTIONS AREA
+
+void main() {
+    // (0, 0) is the left-bottom corner of quad, (1, 1) is the right-top corner.
+    const vec2 st = v_pos.xy + 0.5;
+
+    const vec2 world = position + v_pos.xy * size;
+
+    uv = mix(uv_min, uv_max, vec2(st.x, 1.0 - st.y));
+
+    gl_Position = MX_PROJECTION * MX_VIEW * vec4(world, 0.0, 1.0);
+}
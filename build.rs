@@ -3,6 +3,10 @@ use glob::glob;
 use std::fs::{read_to_string, write};
 use std::path::PathBuf;
 
+// The shaders compiled again with a macro defined: (source, macro, spir-v file name).
+const VARIANTS: &[(&str, &str, &str)] =
+    &[("geometry.frag", "STENCIL_MASK", "geometry_mask.frag.spv")];
+
 struct ShaderData {
     src: String,
     src_path: PathBuf,
@@ -74,7 +78,28 @@ fn main() -> Result<()> {
             "main",
             None,
         )?;
-        write(shader.spv_path, compiled.as_binary_u8())?;
+        write(&shader.spv_path, compiled.as_binary_u8())?;
+
+        for (_, name, spv_name) in VARIANTS
+            .iter()
+            .filter(|(src_name, ..)| shader.src_path.ends_with(src_name))
+        {
+            let mut options =
+                shaderc::CompileOptions::new().context("Unable to create compile options")?;
+            options.add_macro_definition(name, None);
+
+            let compiled = compiler.compile_into_spirv(
+                &shader.src,
+                shader.kind,
+                &shader.src_path.to_str().unwrap(),
+                "main",
+                Some(&options),
+            )?;
+            write(
+                shader.spv_path.with_file_name(spv_name),
+                compiled.as_binary_u8(),
+            )?;
+        }
     }
 
     Ok(())
@@ -12,14 +12,24 @@ use yam::legion::*;
 use yam::*;
 
 fn main() -> Result<(), AppBuildError> {
+    create_app_builder()?
+        // Consume the `AppBuilder` to build the `App`.
+        .build()
+        // Hijack the main thread to run the `App`.
+        .run();
+
+    Ok(())
+}
+
+fn create_app_builder() -> Result<AppBuilder, AppBuildError> {
     // Create `Appbuilder` to build a `App`.
-    AppBuilder::new()
+    Ok(AppBuilder::new()
         // `AppStage` is the basic module of yam, `App` consists of serveral `AppStage`s.
         //
         // When the `App` starts running, it will execute the `AppStage`s it owns in turn.
         //
         // Create `AppStageBuilder` which used to build a `AppStage` from `AppBuilder`.
-        .create_stage_builder(String::from("default"))?
+        .create_stage_builder("default")?
         // `AppStage` has three different types of callbacks: `startup`, `process` and `destroy`.
         //
         // `startup` will be called only once at the start of the running of the `App`.
@@ -41,13 +51,7 @@ fn main() -> Result<(), AppBuildError> {
         .add_thread_local_fn_process(thread_local_fn_process)
         .add_thread_local_fn_destroy(thread_local_fn_destroy)
         // Convert to `AppBuilder` after finish building the `AppStage`.
-        .into_app_builder()
-        // Consume the `AppBuilder` to build the `App`.
-        .build()
-        // Hijack the main thread to run the `App`.
-        .run();
-
-    Ok(())
+        .into_app_builder())
 }
 
 #[system]
@@ -91,3 +95,16 @@ fn thread_local_fn_process(_world: &mut World, _resources: &mut Resources) {
 fn thread_local_fn_destroy(_world: &mut World, _resources: &mut Resources) {
     println!("thread local fn destroy");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn run_all_callbacks() {
+        let mut app = create_app_builder().unwrap().build_for_test();
+
+        app.tick_n(3);
+        assert!(!app.is_quit());
+    }
+}
@@ -2,17 +2,19 @@ use yam::legion::*;
 use yam::*;
 
 fn main() -> Result<(), AppBuildError> {
-    AppBuilder::new()
-        .create_stage_builder(String::from("default"))?
-        .add_system_process(parallel_process_system())
-        .add_thread_local_system_process(thread_local_process_system())
-        .into_app_builder()
-        .build()
-        .run();
+    create_app_builder()?.build().run();
 
     Ok(())
 }
 
+fn create_app_builder() -> Result<AppBuilder, AppBuildError> {
+    Ok(AppBuilder::new()
+        .create_stage_builder("default")?
+        .add_system_process(parallel_process_system())
+        .add_thread_local_system_process(thread_local_process_system())
+        .into_app_builder())
+}
+
 // Get `Input` from `Resources`.
 //
 // `Resources` is the data container which stores the shared datas can be retrieved in systems.
@@ -75,3 +77,32 @@ fn parallel_process(#[resource] input: &Input) {
 fn thread_local_process(#[resource] _input: &Input) {
     // NOTE: You can do the same things as in the `parallel_system`.
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // `TestApp` can inject the events as if they came from the OS.
+    #[test]
+    fn press_space() {
+        let mut app = create_app_builder().unwrap().build_for_test();
+
+        app.inject_event(SyntheticWindowEvent::KeyDown(KeyCode::Space));
+        app.tick();
+        assert!(app
+            .resources()
+            .get::<Input>()
+            .unwrap()
+            .keyboard
+            .just_pressed(KeyCode::Space));
+
+        app.inject_event(SyntheticWindowEvent::KeyUp(KeyCode::Space));
+        app.tick();
+        assert!(app
+            .resources()
+            .get::<Input>()
+            .unwrap()
+            .keyboard
+            .just_released(KeyCode::Space));
+    }
+}
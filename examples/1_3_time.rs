@@ -2,18 +2,20 @@ use yam::legion::*;
 use yam::*;
 
 fn main() -> Result<(), AppBuildError> {
-    AppBuilder::new()
-        .create_stage_builder(String::from("default"))?
+    create_app_builder()?.build().run();
+
+    Ok(())
+}
+
+fn create_app_builder() -> Result<AppBuilder, AppBuildError> {
+    Ok(AppBuilder::new()
+        .create_stage_builder("default")?
         .add_system_process(println_time_system())
         // .add_system_process(diagnostic_time_cost_system(DiagnosticTimer::new()))
         // //                                              ^^^^^^^^^^^^^^^^^^^^^^+-------------+
         // //                                                                                  |
         // // Construct the system-local variable and move it into the system. <---------------+
-        .into_app_builder()
-        .build()
-        .run();
-
-    Ok(())
+        .into_app_builder())
 }
 
 // Get `Time` from `Resources`.
@@ -50,3 +52,16 @@ fn diagnostic_time_cost(#[state] d_timer: &mut DiagnosticTimer)
 
     println!("{}", d_timer);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record_every_frame() {
+        let mut app = create_app_builder().unwrap().build_for_test();
+
+        app.tick_n(3);
+        assert_eq!(app.resources().get::<Time>().unwrap().record_count(), 3);
+    }
+}
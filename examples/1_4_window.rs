@@ -5,7 +5,7 @@ use yam::*;
 
 fn main() -> Result<(), AppBuildError> {
     AppBuilder::new()
-        .create_stage_builder(String::from("default"))?
+        .create_stage_builder("default")?
         .add_thread_local_system_process(control_fullscreen_system())
         .into_app_builder()
         .build()
@@ -17,10 +17,32 @@ fn main() -> Result<(), AppBuildError> {
 #[system]
 fn control_fullscreen(#[resource] window: &mut Window, #[resource] input: &Input) {
     if input.keyboard.just_pressed(KeyCode::F) {
-        if let Some(_) = window.fullscreen() {
-            window.set_fullscreen(None);
-        } else {
-            window.set_fullscreen(Some(Fullscreen::Borderless(window.current_monitor())));
-        }
+        window.set_fullscreen(next_fullscreen(window.fullscreen()));
+    }
+}
+
+// Switch between the windowed mode and the borderless fullscreen.
+fn next_fullscreen(mode: FullscreenMode) -> FullscreenMode {
+    if mode == FullscreenMode::Windowed {
+        FullscreenMode::Borderless
+    } else {
+        FullscreenMode::Windowed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn toggle_fullscreen() {
+        assert_eq!(
+            next_fullscreen(FullscreenMode::Windowed),
+            FullscreenMode::Borderless
+        );
+        assert_eq!(
+            next_fullscreen(FullscreenMode::Borderless),
+            FullscreenMode::Windowed
+        );
     }
 }
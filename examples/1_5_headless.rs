@@ -0,0 +1,53 @@
+//! `App` can run without window and gpu, which is useful for game servers and CI.
+//!
+//! `App::run_headless()` skips all `AppStage`s tagged with `AppStageTag::Render`.
+
+use yam::legion::*;
+use yam::*;
+
+fn main() -> Result<(), AppBuildError> {
+    create_app_builder()?
+        .build()
+        // Run the `App` without window, it returns after `AppSettings::quit()` is called.
+        .run_headless();
+
+    Ok(())
+}
+
+fn create_app_builder() -> Result<AppBuilder, AppBuildError> {
+    Ok(AppBuilder::new()
+        .create_stage_builder("logic")?
+        // Tag the `AppStage`, so `App` knows what it is used for.
+        .tag(AppStageTag::Logic)
+        // `AppSettings` is not thread safe, access it in thread local system.
+        .add_thread_local_system_process(count_frame_system(0))
+        .into_app_builder())
+}
+
+#[system]
+fn count_frame(#[state] frame: &mut u32, #[resource] settings: &mut AppSettings) {
+    *frame += 1;
+
+    println!("frame {}", frame);
+
+    if *frame == 60 {
+        settings.quit();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // `TestApp` runs the same stages frame by frame.
+    #[test]
+    fn quit_at_frame_60() {
+        let mut app = create_app_builder().unwrap().build_for_test();
+
+        app.tick_n(60);
+        assert!(!app.is_quit());
+
+        app.tick();
+        assert!(app.is_quit());
+    }
+}
@@ -0,0 +1,59 @@
+use yam::legion::*;
+use yam::*;
+
+fn main() -> Result<(), AppBuildError> {
+    create_app_builder()?.build().run();
+
+    Ok(())
+}
+
+fn create_app_builder() -> Result<AppBuilder, AppBuildError> {
+    Ok(AppBuilder::new()
+        .create_stage_builder("default")?
+        .add_system_process(shortcuts_system(InputChord::new(&[
+            KeyCode::LControl,
+            KeyCode::S,
+        ])))
+        .into_app_builder())
+}
+
+#[system]
+fn shortcuts(#[state] save: &mut InputChord, #[resource] input: &Input) {
+    let keyboard = &input.keyboard;
+
+    // Check the longer chord first, Ctrl+Z is also held while Ctrl+Shift+Z is held.
+    if keyboard.chord_just_pressed(&[KeyCode::LControl, KeyCode::LShift, KeyCode::Z]) {
+        println!("Redo (Ctrl+Shift+Z)");
+    } else if keyboard.chord_just_pressed(&[KeyCode::LControl, KeyCode::Z])
+        && !keyboard.chord_pressed(&[KeyCode::LShift])
+    {
+        println!("Undo (Ctrl+Z)");
+    }
+
+    // `InputChord` keeps the keys of a shortcut.
+    save.update(keyboard);
+    if save.just_completed {
+        println!("Save (Ctrl+S)");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn press_save_chord() {
+        let mut app = create_app_builder().unwrap().build_for_test();
+
+        app.inject_event(SyntheticWindowEvent::KeyDown(KeyCode::LControl));
+        app.tick();
+        app.inject_event(SyntheticWindowEvent::KeyDown(KeyCode::S));
+        app.tick();
+
+        let input = app.resources().get::<Input>().unwrap();
+        assert!(input
+            .keyboard
+            .chord_just_pressed(&[KeyCode::LControl, KeyCode::S]));
+        assert!(!input.keyboard.chord_pressed(&[KeyCode::LShift]));
+    }
+}
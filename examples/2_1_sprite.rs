@@ -4,18 +4,24 @@ use yam::*;
 
 fn main() -> Result<(), AppBuildError> {
     AppBuilder::new()
-        .create_stage_builder(String::from("default"))?
-        .add_thread_local_system_startup(introduction_system())
-        .add_thread_local_system_startup(init_entities_system())
-        .add_thread_local_system_process(control_camera_system())
-        .add_thread_local_system_process(control_sprite_system())
-        .into_app_builder()
+        .add_stage_builder(
+            create_stage_builder().add_thread_local_system_startup(init_camera_system()),
+        )?
         .build()
         .run();
 
     Ok(())
 }
 
+// The stage without the camera, which reads `Window`.
+fn create_stage_builder() -> AppStageBuilder {
+    AppStageBuilder::new("default")
+        .add_thread_local_system_startup(introduction_system())
+        .add_thread_local_system_startup(init_entities_system())
+        .add_thread_local_system_process(control_camera_system())
+        .add_thread_local_system_process(control_sprite_system())
+}
+
 #[system]
 fn introduction() {
     println!("Introduction:");
@@ -24,11 +30,15 @@ fn introduction() {
 }
 
 #[system]
-fn init_entities(commands: &mut CommandBuffer, #[resource] window: &Window) {
+fn init_camera(commands: &mut CommandBuffer, #[resource] window: &Window) {
     let (width, height) = window.resolution();
 
     // Push camera entity to `World`.
     commands.push((Transform2D::default(), Camera2D::new(width, height)));
+}
+
+#[system]
+fn init_entities(commands: &mut CommandBuffer) {
     // Push sprite entity to `World`.
     commands.push((
         Transform2D::with_scale(64.0, 64.0),
@@ -72,3 +82,28 @@ fn control_sprite(
         transform2d.position += Vector2::new(0.0, 1.0) * time.delta().as_secs_f32() * MOVE_SPEED;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sprite_x(app: &TestApp) -> f32 {
+        let mut query = <&Transform2D>::query().filter(component::<Sprite>());
+        query.iter(app.world()).next().unwrap().position.x
+    }
+
+    #[test]
+    fn move_sprite_right() {
+        let mut app = AppBuilder::new()
+            .add_stage_builder(create_stage_builder())
+            .unwrap()
+            .build_for_test();
+
+        app.tick();
+        let x = sprite_x(&app);
+
+        app.inject_event(SyntheticWindowEvent::KeyDown(KeyCode::D));
+        app.tick_n(3);
+        assert!(sprite_x(&app) > x);
+    }
+}
@@ -4,31 +4,40 @@ use yam::*;
 
 fn main() -> Result<(), AppBuildError> {
     AppBuilder::new()
-        .create_stage_builder(String::from("default"))?
-        .add_thread_local_system_startup(init_entities_system())
-        .add_thread_local_system_process(control_camera_system())
-        .add_thread_local_system_process(control_geometry_system())
-        .into_app_builder()
+        .add_stage_builder(
+            create_stage_builder().add_thread_local_system_startup(init_camera_system()),
+        )?
         .build()
         .run();
 
     Ok(())
 }
 
+// The stage without the camera, which reads `Window`.
+fn create_stage_builder() -> AppStageBuilder {
+    AppStageBuilder::new("default")
+        .add_thread_local_system_startup(init_entities_system())
+        .add_thread_local_system_process(control_camera_system())
+        .add_thread_local_system_process(control_geometry_system())
+}
+
 #[system]
-fn init_entities(cmd: &mut CommandBuffer, #[resource] window: &Window) {
+fn init_camera(cmd: &mut CommandBuffer, #[resource] window: &Window) {
     let (width, height) = window.resolution();
 
     // Push camera entity to `World`.
     cmd.push((Transform2D::default(), Camera2D::new(width, height)));
+}
 
+#[system]
+fn init_entities(cmd: &mut CommandBuffer) {
     let th_l = BorderThickness::LocalSpace(2.0);
     let size: f32 = 100.0;
 
     let (x0, x1, x2) = (-0.5 * size, 0.5 * size, 1.5 * size);
 
     cmd.push((
-        Transform2D::with_position(x0, x0),
+        Transform2D::at(x0, x0),
         Geometry::new_2d(
             Geometry2DType::Circle,
             BorderDecoration::DynDash,
@@ -44,7 +53,7 @@ fn init_entities(cmd: &mut CommandBuffer, #[resource] window: &Window) {
     ));
 
     cmd.push((
-        Transform2D::with_position(x1, x0),
+        Transform2D::at(x1, x0),
         Geometry::new_2d(
             Geometry2DType::ETriangle,
             BorderDecoration::Solid,
@@ -60,7 +69,7 @@ fn init_entities(cmd: &mut CommandBuffer, #[resource] window: &Window) {
     ));
 
     cmd.push((
-        Transform2D::with_position(x2, x0),
+        Transform2D::at(x2, x0),
         Geometry::new_2d(
             Geometry2DType::Square,
             BorderDecoration::DynDash,
@@ -76,7 +85,7 @@ fn init_entities(cmd: &mut CommandBuffer, #[resource] window: &Window) {
     ));
 
     cmd.push((
-        Transform2D::with_position(x0, x1),
+        Transform2D::at(x0, x1),
         Geometry::new_2d(
             Geometry2DType::Pentagon,
             BorderDecoration::Solid,
@@ -92,7 +101,7 @@ fn init_entities(cmd: &mut CommandBuffer, #[resource] window: &Window) {
     ));
 
     cmd.push((
-        Transform2D::with_position(x1, x1),
+        Transform2D::at(x1, x1),
         Geometry::new_2d(
             Geometry2DType::Hexagon,
             BorderDecoration::DynDash,
@@ -108,7 +117,7 @@ fn init_entities(cmd: &mut CommandBuffer, #[resource] window: &Window) {
     ));
 
     cmd.push((
-        Transform2D::with_position(x2, x1),
+        Transform2D::at(x2, x1),
         Geometry::new_2d(
             Geometry2DType::Octogon,
             BorderDecoration::Solid,
@@ -124,7 +133,7 @@ fn init_entities(cmd: &mut CommandBuffer, #[resource] window: &Window) {
     ));
 
     cmd.push((
-        Transform2D::with_position(x0, x2),
+        Transform2D::at(x0, x2),
         Geometry::new_2d(
             Geometry2DType::Hexagram,
             BorderDecoration::DynDash,
@@ -140,7 +149,7 @@ fn init_entities(cmd: &mut CommandBuffer, #[resource] window: &Window) {
     ));
 
     cmd.push((
-        Transform2D::with_position(x1, x2),
+        Transform2D::at(x1, x2),
         Geometry::new_2d(
             Geometry2DType::StarFive,
             BorderDecoration::Solid,
@@ -156,7 +165,7 @@ fn init_entities(cmd: &mut CommandBuffer, #[resource] window: &Window) {
     ));
 
     cmd.push((
-        Transform2D::with_position(x2, x2),
+        Transform2D::at(x2, x2),
         Geometry::new_2d(
             Geometry2DType::Heart,
             BorderDecoration::DynDash,
@@ -172,7 +181,7 @@ fn init_entities(cmd: &mut CommandBuffer, #[resource] window: &Window) {
     ));
 
     cmd.push((
-        Transform2D::with_position(x1, x1),
+        Transform2D::at(x1, x1),
         Geometry::new_1d(
             Geometry1DType::Segment,
             BorderDecoration::DynDash,
@@ -192,10 +201,10 @@ fn control_camera(transform: &mut Transform2D, #[resource] input: &Input) {
     const TSPEED: f32 = 4.0;
     const SSPEED: f32 = 1.0;
 
-    if input.mouse.pressed(MouseButton::Middle) {
-        let (dx, dy) = input.mouse.mouse_motion_in_ss();
+    if input.mouse.is_dragging(MouseButton::Middle) {
+        let delta = input.mouse.drag_delta(MouseButton::Middle);
 
-        transform.position -= Vector2::<f32>::new(dx, -dy) * transform.scale.x;
+        transform.position -= Vector2::<f32>::new(delta.x, -delta.y) * transform.scale.x;
     }
 
     let (_, motion) = input.mouse.mouse_wheel_motion();
@@ -218,3 +227,23 @@ fn control_geometry(
 }
 
 struct Marker;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rotate_marked_geometry() {
+        let mut app = AppBuilder::new()
+            .add_stage_builder(create_stage_builder())
+            .unwrap()
+            .build_for_test();
+
+        app.tick_n(3);
+        assert_eq!(<&Geometry>::query().iter(app.world()).count(), 10);
+
+        let mut query = <&Transform2D>::query().filter(component::<Marker>());
+        let transform = query.iter(app.world()).next().unwrap();
+        assert!(transform.rotation.angle() != 0.0);
+    }
+}
@@ -0,0 +1,71 @@
+use yam::legion::{systems::CommandBuffer, *};
+use yam::*;
+
+fn main() -> Result<(), AppBuildError> {
+    AppBuilder::new()
+        .add_stage_builder(
+            create_stage_builder().add_thread_local_system_startup(init_camera_system()),
+        )?
+        .build()
+        .run();
+
+    Ok(())
+}
+
+// The stage without the camera, which reads `Window`.
+fn create_stage_builder() -> AppStageBuilder {
+    AppStageBuilder::new("default")
+        .add_thread_local_system_startup(init_entities_system())
+        .add_system_process(drive_morph_system())
+        .add_system_process(geometry_morph_system())
+}
+
+#[system]
+fn init_camera(cmd: &mut CommandBuffer, #[resource] window: &Window) {
+    let (width, height) = window.resolution();
+
+    cmd.push((Transform2D::default(), Camera2D::new(width, height)));
+}
+
+#[system]
+fn init_entities(cmd: &mut CommandBuffer) {
+    // Morph a circle into a star and back.
+    cmd.push((
+        Transform2D::default(),
+        GeometryMorph::new(Geometry2DType::Circle, Geometry2DType::StarFive, 300.0)
+            .with_color(Rgba::ORANGE)
+            .with_thickness(BorderThickness::LocalSpace(4.0))
+            .with_resolution(120),
+        Assembly::default(),
+    ));
+}
+
+#[system(for_each)]
+fn drive_morph(morph: &mut GeometryMorph, #[resource] time: &Time) {
+    const PERIOD: f32 = 4.0;
+
+    let phase = time.total().as_secs_f32() / PERIOD * std::f32::consts::PI * 2.0;
+    morph.t = 0.5 - 0.5 * phase.cos();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn morph_into_assembly() {
+        let mut app = AppBuilder::new()
+            .add_stage_builder(create_stage_builder())
+            .unwrap()
+            .build_for_test();
+
+        app.tick_n(3);
+
+        let (morph, assembly) = <(&GeometryMorph, &Assembly)>::query()
+            .iter(app.world())
+            .next()
+            .unwrap();
+        assert!((0.0..=1.0).contains(&morph.t));
+        assert!(!assembly.is_empty());
+    }
+}
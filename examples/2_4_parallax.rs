@@ -0,0 +1,115 @@
+use yam::legion::{systems::CommandBuffer, *};
+use yam::nalgebra::Vector2;
+use yam::*;
+
+fn main() -> Result<(), AppBuildError> {
+    AppBuilder::new()
+        .add_stage_builder(
+            create_stage_builder().add_thread_local_system_startup(init_entities_system()),
+        )?
+        .build()
+        .run();
+
+    Ok(())
+}
+
+// The stage without the entities, which read `Window`.
+fn create_stage_builder() -> AppStageBuilder {
+    AppStageBuilder::new("default")
+        .add_system_process(scroll_camera_system())
+        .add_system_process(parallax_system())
+}
+
+// (scroll factor, shape, size, color, order, height), the far layers first.
+const LAYERS: [(f32, Geometry2DType, f32, Rgba, u8, f32); 4] = [
+    (0.9, Geometry2DType::Circle, 120.0, Rgba::WHITE, 10, 200.0),
+    (0.6, Geometry2DType::ETriangle, 400.0, Rgba::VIOLET, 20, 0.0),
+    (
+        0.3,
+        Geometry2DType::ETriangle,
+        250.0,
+        Rgba::BLUE,
+        30,
+        -100.0,
+    ),
+    (0.1, Geometry2DType::Square, 120.0, Rgba::GREEN, 40, -250.0),
+];
+
+#[system]
+fn init_entities(cmd: &mut CommandBuffer, #[resource] window: &Window) {
+    let (width, height) = window.resolution();
+
+    cmd.push((Transform2D::default(), Camera2D::new(width, height)));
+
+    // Every layer repeats a tile as wide as the window, three tiles cover the view at any time.
+    push_layers(cmd, width as f32);
+}
+
+fn push_layers(cmd: &mut CommandBuffer, tile: f32) {
+    for (factor, shape, size, color, order, height) in LAYERS.iter().copied() {
+        let count = (tile / size).ceil() as i32;
+        let spacing = tile / count as f32;
+
+        let assembly = (-count..count * 2)
+            .map(|i| {
+                Geometry::new_2d(
+                    shape,
+                    BorderDecoration::None,
+                    Rgba::SOFT_BLACK,
+                    BorderThickness::LocalSpace(0.0),
+                    InnerDecoration::Solid,
+                    color,
+                    order,
+                    Vector2::new(i as f32 * spacing - tile / 2.0, height),
+                    0.0,
+                    size,
+                )
+            })
+            .collect::<Assembly>();
+
+        cmd.push((
+            Transform2D::default(),
+            assembly,
+            Parallax2D::new(Vector2::new(factor, factor)).tiled(Vector2::new(tile, 0.0)),
+        ));
+    }
+}
+
+#[system(for_each)]
+#[filter(component::<Camera2D>())]
+fn scroll_camera(transform: &mut Transform2D, #[resource] time: &Time) {
+    const SPEED: f32 = 200.0;
+
+    transform.position.x += SPEED * time.delta().as_secs_f32();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scroll_camera_to_right() {
+        let mut app = AppBuilder::new()
+            .add_stage_builder(create_stage_builder())
+            .unwrap()
+            .build_for_test();
+
+        app.world_mut()
+            .push((Transform2D::default(), Camera2D::new(800, 600)));
+        app.tick_n(3);
+
+        let mut query = <&Transform2D>::query().filter(component::<Camera2D>());
+        assert!(query.iter(app.world()).next().unwrap().position.x > 0.0);
+    }
+
+    #[test]
+    fn push_all_layers() {
+        let mut world = World::default();
+
+        let mut cmd = CommandBuffer::new(&world);
+        push_layers(&mut cmd, 800.0);
+        cmd.flush(&mut world, &mut Resources::default());
+
+        assert_eq!(<&Parallax2D>::query().iter(&world).count(), LAYERS.len());
+    }
+}
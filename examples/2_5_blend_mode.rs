@@ -0,0 +1,103 @@
+use yam::legion::{systems::CommandBuffer, *};
+use yam::nalgebra::Vector2;
+use yam::*;
+
+fn main() -> Result<(), AppBuildError> {
+    AppBuilder::new()
+        .add_stage_builder(
+            create_stage_builder().add_thread_local_system_startup(init_camera_system()),
+        )?
+        .build()
+        .run();
+
+    Ok(())
+}
+
+// The stage without the camera, which reads `Window`.
+fn create_stage_builder() -> AppStageBuilder {
+    AppStageBuilder::new("default").add_thread_local_system_startup(init_entities_system())
+}
+
+#[system]
+fn init_camera(cmd: &mut CommandBuffer, #[resource] window: &Window) {
+    let (width, height) = window.resolution();
+
+    cmd.push((Transform2D::default(), Camera2D::new(width, height)));
+}
+
+#[system]
+fn init_entities(cmd: &mut CommandBuffer) {
+    let size: f32 = 160.0;
+    let x0 = -2.5 * size;
+
+    // Stripes from dark to bright behind the shapes.
+    let stripes = [Rgba::SOFT_BLACK, Rgba::BLUE, Rgba::CAMEL, Rgba::WHITE];
+    let cell = size * 0.5;
+    for (i, color) in stripes.iter().enumerate() {
+        let stripe = (0..12)
+            .map(|j| {
+                Geometry::new_2d(
+                    Geometry2DType::Square,
+                    BorderDecoration::None,
+                    *color,
+                    BorderThickness::LocalSpace(0.0),
+                    InnerDecoration::Solid,
+                    *color,
+                    10,
+                    Vector2::new((j as f32 - 5.5) * cell, 0.0),
+                    0.0,
+                    cell,
+                )
+            })
+            .collect::<Assembly>();
+
+        cmd.push((Transform2D::at(0.0, (i as f32 - 1.5) * cell), stripe));
+    }
+
+    // One column for each mode, two overlapping shapes show how the mode blends with itself.
+    for (i, mode) in BlendMode::ALL.iter().enumerate() {
+        let x = x0 + i as f32 * size;
+
+        for (j, color) in [Rgba::ORANGE, Rgba::AZURE].iter().enumerate() {
+            cmd.push((
+                Transform2D::at(x, (j as f32 - 0.5) * size * 0.4),
+                Geometry::new_2d(
+                    Geometry2DType::Circle,
+                    BorderDecoration::None,
+                    *color,
+                    BorderThickness::LocalSpace(0.0),
+                    InnerDecoration::Solid,
+                    *color,
+                    100 + j as u8,
+                    Vector2::new(0.0, 0.0),
+                    0.0,
+                    size * 0.8,
+                ),
+                *mode,
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn two_shapes_for_each_mode() {
+        let mut app = AppBuilder::new()
+            .add_stage_builder(create_stage_builder())
+            .unwrap()
+            .build_for_test();
+
+        app.tick();
+
+        for mode in BlendMode::ALL.iter() {
+            let count = <&BlendMode>::query()
+                .iter(app.world())
+                .filter(|m| *m == mode)
+                .count();
+            assert_eq!(count, 2);
+        }
+    }
+}
@@ -0,0 +1,103 @@
+use yam::legion::{systems::CommandBuffer, *};
+use yam::*;
+
+// The atlas consists of 4 x 2 cells, each cell is 16 x 16 pixels.
+const ATLAS_PATH: &str = "assets/images/sprites/atlas.png";
+const CELL_SIZE: f32 = 16.0;
+
+fn main() -> Result<(), AppBuildError> {
+    let bytes = std::fs::read(ATLAS_PATH).expect("ERR: Not find the atlas image.");
+    let texture = Texture::from_png(&bytes).expect("ERR: Failed to decode the atlas image.");
+
+    let mut atlas = TextureAtlas::new();
+    let handle = atlas.insert(texture);
+
+    AppBuilder::new()
+        .add_stage_builder(
+            create_stage_builder(atlas, handle)
+                .add_thread_local_system_startup(init_camera_system()),
+        )?
+        .build()
+        .run();
+
+    Ok(())
+}
+
+// The stage without the camera, which reads `Window`.
+fn create_stage_builder(atlas: TextureAtlas, handle: TextureHandle) -> AppStageBuilder {
+    AppStageBuilder::new("default")
+        .add_resource(atlas)
+        .add_thread_local_system_startup(init_entities_system(handle))
+        .add_thread_local_system_process(spin_sprites_system())
+}
+
+#[system]
+fn init_camera(commands: &mut CommandBuffer, #[resource] window: &Window) {
+    let (width, height) = window.resolution();
+
+    // Push camera entity to `World`.
+    commands.push((Transform2D::default(), Camera2D::new(width, height)));
+}
+
+#[system]
+fn init_entities(commands: &mut CommandBuffer, #[state] handle: &mut TextureHandle) {
+    // Push a grid of sprites to `World`, each shows a cell of the atlas.
+    const COLS: i32 = 16;
+    const ROWS: i32 = 8;
+    const SPACING: f32 = 48.0;
+
+    for row in 0..ROWS {
+        for col in 0..COLS {
+            let cell = (row * COLS + col) % 8;
+            let region = Rect::new(
+                (cell % 4) as f32 * CELL_SIZE,
+                (cell / 4) as f32 * CELL_SIZE,
+                CELL_SIZE,
+                CELL_SIZE,
+            );
+
+            let mut sprite = Sprite2D::from_atlas_region(*handle, region);
+            sprite.size *= 2.0;
+
+            let x = (col as f32 - (COLS - 1) as f32 / 2.0) * SPACING;
+            let y = (row as f32 - (ROWS - 1) as f32 / 2.0) * SPACING;
+
+            commands.push((Transform2D::at(x, y), sprite));
+        }
+    }
+}
+
+#[system(for_each)]
+#[filter(component::<Sprite2D>())]
+fn spin_sprites(transform2d: &mut Transform2D, #[resource] time: &Time) {
+    const SPIN_SPEED: f32 = 45.0;
+
+    transform2d.rotate(time.delta().as_secs_f32() * SPIN_SPEED);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spin_all_sprites() {
+        // The same layout as the atlas image, 4 x 2 cells.
+        let texture = Texture::from_rgba(64, 32, vec![255; 64 * 32 * 4]).unwrap();
+
+        let mut atlas = TextureAtlas::new();
+        let handle = atlas.insert(texture);
+
+        let mut app = AppBuilder::new()
+            .add_stage_builder(create_stage_builder(atlas, handle))
+            .unwrap()
+            .build_for_test();
+
+        app.tick_n(3);
+
+        let mut query = <&Transform2D>::query().filter(component::<Sprite2D>());
+        assert_eq!(query.iter(app.world()).count(), 128);
+        assert!(query
+            .iter(app.world())
+            .all(|transform| transform.rotation.angle() != 0.0));
+    }
+}
@@ -7,20 +7,28 @@ use yam::legion::{systems::CommandBuffer, *};
 use yam::nalgebra::Vector2;
 use yam::*;
 
+const SQRT_COUNT: usize = 1_024;
+
 fn main() -> Result<(), AppBuildError> {
     AppBuilder::new()
-        .create_stage_builder(String::from("default"))?
-        .add_thread_local_system_startup(introduction_system())
-        .add_thread_local_system_startup(init_entities_system())
-        .add_thread_local_system_process(control_camera_system())
-        .add_thread_local_system_process(wander_system())
-        .into_app_builder()
+        .add_stage_builder(
+            create_stage_builder(SQRT_COUNT).add_thread_local_system_startup(init_camera_system()),
+        )?
         .build()
         .run();
 
     Ok(())
 }
 
+// The stage without the camera, which reads `Window`.
+fn create_stage_builder(sqrt_count: usize) -> AppStageBuilder {
+    AppStageBuilder::new("default")
+        .add_thread_local_system_startup(introduction_system())
+        .add_thread_local_system_startup(init_entities_system(sqrt_count))
+        .add_thread_local_system_process(control_camera_system())
+        .add_thread_local_system_process(wander_system())
+}
+
 #[system]
 fn introduction() {
     println!("Introduction:");
@@ -29,23 +37,25 @@ fn introduction() {
 }
 
 #[system]
-fn init_entities(commands: &mut CommandBuffer, #[resource] window: &Window) {
-    const SPRITE_SIZE: f32 = 8.0;
-
-    const SQRT_COUNT: usize = 1_024;
-    const COUNT: usize = SQRT_COUNT * SQRT_COUNT;
-
+fn init_camera(commands: &mut CommandBuffer, #[resource] window: &Window) {
     let (width, height) = window.resolution();
 
     // Push camera entity to `World`.
     commands.push((Transform2D::default(), Camera2D::new(width, height)));
+}
+
+#[system]
+fn init_entities(commands: &mut CommandBuffer, #[state] sqrt_count: &mut usize) {
+    const SPRITE_SIZE: f32 = 8.0;
+
+    let count = *sqrt_count * *sqrt_count;
 
     // `+8` prevent double the capacity of the vec when push element into.
-    let mut steerings: Instance<Steering> = Instance::with_capacity(COUNT + 8);
-    let mut transform2ds: Instance<Transform2D> = Instance::with_capacity(COUNT + 8);
+    let mut steerings: Instance<Steering> = Instance::with_capacity(count + 8);
+    let mut transform2ds: Instance<Transform2D> = Instance::with_capacity(count + 8);
 
-    for x in 0..SQRT_COUNT {
-        for y in 0..SQRT_COUNT {
+    for x in 0..*sqrt_count {
+        for y in 0..*sqrt_count {
             let (tx, ty) = (1.2 * SPRITE_SIZE * x as f32, 1.2 * SPRITE_SIZE * y as f32);
 
             steerings.push(Steering::new(0.0, 0.0));
@@ -168,3 +178,28 @@ impl Steering {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wander_sprites() {
+        let mut app = AppBuilder::new()
+            .add_stage_builder(create_stage_builder(2))
+            .unwrap()
+            .build_for_test();
+
+        app.tick_n(3);
+
+        let transform2ds = <&Instance<Transform2D>>::query()
+            .iter(app.world())
+            .next()
+            .unwrap();
+        assert_eq!(transform2ds.len(), 4);
+        assert!(transform2ds
+            .iter()
+            .all(|transform2d| transform2d.position.x.is_finite()
+                && transform2d.position.y.is_finite()));
+    }
+}
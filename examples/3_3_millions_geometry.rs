@@ -3,21 +3,22 @@ use yam::nalgebra::Vector2;
 use yam::*;
 
 const SQRT_COUNT: usize = 1024;
-const GEOM_COUNT: usize = SQRT_COUNT * SQRT_COUNT;
 const QUAD_SIZE: f32 = 128.0;
 
 fn main() -> Result<(), AppBuildError> {
-    AppBuilder::new()
-        .create_stage_builder(String::from("default"))?
+    create_app_builder(SQRT_COUNT)?.build().run();
+
+    Ok(())
+}
+
+fn create_app_builder(sqrt_count: usize) -> Result<AppBuilder, AppBuildError> {
+    Ok(AppBuilder::new()
+        .create_stage_builder("default")?
         .add_thread_local_system_startup(introduction_system())
-        .add_thread_local_system_startup(init_entities_system())
+        .add_thread_local_system_startup(init_entities_system(sqrt_count))
         .add_thread_local_system_process(control_camera_system())
         .add_thread_local_system_process(wander_system(0.0, 64.0, 16.0))
-        .into_app_builder()
-        .build()
-        .run();
-
-    Ok(())
+        .into_app_builder())
 }
 
 #[system]
@@ -29,20 +30,22 @@ fn introduction() {
 }
 
 #[system]
-fn init_entities(commands: &mut CommandBuffer) {
+fn init_entities(commands: &mut CommandBuffer, #[state] sqrt_count: &mut usize) {
     // Push camera entity to `World`.
     commands.push((Transform2D::default(), Camera2D::default()));
 
+    let geom_count = *sqrt_count * *sqrt_count;
+
     // `+1` prevent double the capacity of the vec when push element into.
-    let mut steerings: Instance<Steering> = Instance::with_capacity(GEOM_COUNT + 1);
-    let mut transform2ds: Instance<Transform2D> = Instance::with_capacity(GEOM_COUNT + 1);
+    let mut steerings: Instance<Steering> = Instance::with_capacity(geom_count + 1);
+    let mut transform2ds: Instance<Transform2D> = Instance::with_capacity(geom_count + 1);
 
-    for x in 0..SQRT_COUNT {
-        for y in 0..SQRT_COUNT {
+    for x in 0..*sqrt_count {
+        for y in 0..*sqrt_count {
             let (tx, ty) = (QUAD_SIZE * x as f32, QUAD_SIZE * y as f32);
 
             steerings.push(Steering::default());
-            transform2ds.push(Transform2D::with_position(tx, ty));
+            transform2ds.push(Transform2D::at(tx, ty));
         }
     }
 
@@ -222,3 +225,27 @@ impl Default for Steering {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wander_geometries() {
+        let mut app = create_app_builder(2).unwrap().build_for_test();
+
+        app.inject_event(SyntheticWindowEvent::KeyDown(KeyCode::D));
+        app.tick_n(3);
+
+        let (transform2ds, assembly) = <(&Instance<Transform2D>, &Assembly)>::query()
+            .iter(app.world())
+            .next()
+            .unwrap();
+        assert_eq!(transform2ds.len(), 4);
+        assert_eq!(assembly.len(), 2);
+        assert!(transform2ds
+            .iter()
+            .all(|transform2d| transform2d.position.x.is_finite()
+                && transform2d.position.y.is_finite()));
+    }
+}
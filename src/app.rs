@@ -1,53 +1,125 @@
 use crate::{
+    audio::{Audio, AudioOutput},
     components::time::Time,
-    input::Input,
+    config::{AppConfig, ConfigError},
+    events::{update_events_system, Events},
+    input::{Input, InputEventQueue},
     legion::{
-        systems::{Builder, ParallelRunnable, Runnable},
-        Resources, Schedule, World,
+        systems::{Builder, ParallelRunnable, Resource, Runnable},
+        Entity, Resources, Schedule, World,
     },
-    misc::coordinates::Transformation,
-    render::create_app_stage_render,
-    window::Window,
-    DEFAULT_HEIGHT, DEFAULT_WIDTH,
+    misc::{
+        coordinates::Transformation,
+        debug_draw::DebugDraw,
+        frame_stats::{FrameStats, StageMetrics, StageTiming},
+    },
+    plugin::{Plugin, PluginRegistry},
+    scene::SceneComponent,
+    snapshot::{transfer_entity, ComponentRegistry, EntityTransfers, TransferError},
+    window::{CursorSettings, SyntheticWindowEvent, Window, WindowResized},
 };
 
+#[cfg(not(feature = "headless"))]
+use crate::{render::create_app_stage_render, DEFAULT_HEIGHT, DEFAULT_WIDTH};
+
+use winit::{dpi::PhysicalSize, event::Event};
+
+#[cfg(not(feature = "headless"))]
 use winit::{
     dpi::LogicalSize,
-    event::{DeviceEvent, Event, StartCause, WindowEvent},
+    event::{DeviceEvent, StartCause, WindowEvent},
     event_loop::{ControlFlow, EventLoop},
     window::WindowBuilder,
 };
 
 use std::{
-    cell::RefCell,
+    any::{self, Any, TypeId},
+    cell::{Cell, RefCell},
+    collections::{HashMap, HashSet, VecDeque},
+    error::Error,
     fmt,
+    panic::{self, AssertUnwindSafe, PanicInfo},
     rc::Rc,
     slice::{Iter, IterMut},
+    time::Instant,
 };
 
-#[derive(Default, Debug)]
+/// The panic information passed to `PanicHandler`.
+///
+/// NOTE: newer std renames `PanicInfo` of panic hooks to `PanicHookInfo`, use the alias so the handlers
+/// don't depend on the name.
+pub type PanicHookInfo<'a> = PanicInfo<'a>;
+
+pub type PanicHandler = Box<dyn Fn(&PanicHookInfo) + Send + Sync>;
+
+// The panic hook replaced by the `PanicHandler`, it's restored when the `App` exits.
+type PanicHook = Box<dyn Fn(&PanicHookInfo) + Send + Sync + 'static>;
+
+/// Call `$apply!` with the engine resources as `Type => constructor`, they are inserted by
+/// `App::insert_engine_resources` and lent to the stages with own world.
+///
+/// NOTE: `AppSettings` and `Window` are not listed, they are created with the stages and the event loop.
+macro_rules! engine_resources {
+    ($apply:ident) => {
+        $apply!(
+            Input => Input::new(),
+            InputEventQueue => InputEventQueue::new(),
+            Audio => Audio::new(),
+            PanicRecord => PanicRecord::new(),
+            CursorSettings => CursorSettings::new(),
+            DebugDraw => DebugDraw::new(),
+            FrameStats => FrameStats::new(),
+            HashMap<String, StageMetrics> => HashMap::new(),
+            Events<WindowResized> => Events::new(),
+            Events<StageBudgetExceeded> => Events::new(),
+            EntityTransfers => EntityTransfers::new()
+        )
+    };
+}
+
 pub struct App {
     busy_stages: Vec<AppStage>,
+    panic_handler: Option<PanicHandler>,
+    history_capacity: usize,
 }
 
 impl App {
     pub fn new() -> Self {
-        Self {
-            busy_stages: Default::default(),
-        }
+        Self::from_stages(Default::default())
     }
 
     pub fn from_stages(stages: Vec<AppStage>) -> Self {
         Self {
             busy_stages: stages,
+            panic_handler: None,
+            history_capacity: AppSettings::DEFAULT_HISTORY_CAPACITY,
         }
     }
 
+    /// Keep at most `capacity` applied commands in `AppSettings::command_history`, 0 disables the history.
+    pub fn with_history_capacity(mut self, capacity: usize) -> Self {
+        self.history_capacity = capacity;
+
+        self
+    }
+
+    /// Catch the panics of `AppStage`s instead of crashing.
+    ///
+    /// The `handler` is called with the panic information, then the panic is recorded in `PanicRecord`
+    /// and the app continues with the next stage. Call `AppSettings::quit` in a system which checks
+    /// `PanicRecord` if you'd rather quit.
+    pub fn with_panic_handler(mut self, handler: PanicHandler) -> Self {
+        self.panic_handler = Some(handler);
+
+        self
+    }
+
     /// Hijack the main thread to run the `App`.
     ///
     /// # Panics
     ///
     /// Panics if the ownership of `AppSettings` moved to outer.
+    #[cfg(not(feature = "headless"))]
     pub fn run(self) {
         let event_loop = EventLoop::new();
         let window = Window::new(
@@ -58,6 +130,9 @@ impl App {
                 .unwrap(),
         );
 
+        let mut previous_hook = Self::set_panic_hook(self.panic_handler);
+        let catch_panic = previous_hook.is_some();
+
         let busy_stages = Rc::new(RefCell::new(self.busy_stages));
 
         // FIXME: Place the render `AppStage` to right place to prevent removal in accident.
@@ -68,12 +143,18 @@ impl App {
         let mut world = World::default();
         let mut resources = Resources::default();
 
-        resources.insert::<Input>(Input::new());
-        resources.insert::<AppSettings>(AppSettings::new(&busy_stages));
+        Self::insert_engine_resources(&mut resources);
+        resources.insert::<AppSettings>(
+            AppSettings::new(&busy_stages).with_history_capacity(self.history_capacity),
+        );
         resources.insert::<Window>(window);
 
         // local datas
         let mut input_evts: Vec<Event<'static, ()>> = Default::default();
+        let mut audio_output = AudioOutput::open();
+        // NOTE: the app runs without gamepads if gilrs isn't supported on the platform.
+        #[cfg(feature = "gamepad")]
+        let mut gamepads = gilrs::Gilrs::new().ok();
 
         event_loop.run(move |event, _, control_flow| {
             match event {
@@ -86,11 +167,43 @@ impl App {
                     }
                     StartCause::Poll => {
                         // NOTE: apply app_settings added by last frame, if user try to exit, then exit.
+                        Self::exit_stages(
+                            &RefCell::borrow(&busy_stages),
+                            &mut world,
+                            &mut resources,
+                        );
                         if !resources.get_mut::<AppSettings>().unwrap().apply() {
+                            Self::insert_resources(&mut resources);
+                            Self::enter_stages(
+                                &RefCell::borrow(&busy_stages),
+                                &mut world,
+                                &mut resources,
+                            );
+                            Self::reset_stages(
+                                &RefCell::borrow(&busy_stages),
+                                catch_panic,
+                                &mut world,
+                                &mut resources,
+                            );
+                            resources
+                                .get_mut::<Events<WindowResized>>()
+                                .unwrap()
+                                .update();
+                            resources
+                                .get_mut::<Window>()
+                                .unwrap()
+                                .apply_cursor_settings(
+                                    &mut resources.get_mut::<CursorSettings>().unwrap(),
+                                );
+
                             // execute all stages that in work state.
-                            for stage in RefCell::borrow(&busy_stages).iter() {
-                                stage.play(&mut world, &mut resources);
-                            }
+                            Self::play_stages(
+                                &RefCell::borrow(&busy_stages),
+                                catch_panic,
+                                &mut world,
+                                &mut resources,
+                            );
+                            audio_output.update(&resources);
                         } else {
                             *control_flow = ControlFlow::Exit;
                         }
@@ -103,7 +216,11 @@ impl App {
                     WindowEvent::CloseRequested => {
                         *control_flow = ControlFlow::Exit;
                     }
-                    WindowEvent::Resized(_) | WindowEvent::Moved(_) => {
+                    WindowEvent::Resized(size) => {
+                        let scale_factor = resources.get::<Window>().unwrap().window.scale_factor();
+                        Self::resize(&mut resources, *size, scale_factor);
+                    }
+                    WindowEvent::Moved(_) => {
                         // NOTE: To prevent input freeze bug
                         resources.get_mut::<Input>().unwrap().release_all();
                     }
@@ -114,12 +231,19 @@ impl App {
                     | WindowEvent::CursorMoved { .. }
                     | WindowEvent::CursorEntered { .. }
                     | WindowEvent::CursorLeft { .. }
-                    | WindowEvent::KeyboardInput { .. } => {
+                    | WindowEvent::KeyboardInput { .. }
+                    | WindowEvent::ReceivedCharacter(_) => {
                         input_evts.push(event.to_static().unwrap());
                     }
 
-                    WindowEvent::ScaleFactorChanged { .. } => {
-                        todo!()
+                    WindowEvent::ScaleFactorChanged {
+                        scale_factor,
+                        new_inner_size,
+                    } => {
+                        resources
+                            .get_mut::<Events<WindowResized>>()
+                            .unwrap()
+                            .send(WindowResized::new(**new_inner_size, *scale_factor));
                     }
                     _ => {}
                 },
@@ -140,11 +264,33 @@ impl App {
                     todo!()
                 }
                 Event::MainEventsCleared => {
+                    #[cfg(feature = "gamepad")]
+                    {
+                        if let Some(gamepads) = gamepads.as_mut() {
+                            resources.get_mut::<Input>().unwrap().gamepad.poll(gamepads);
+                        }
+                    }
+
+                    let (injected, window_scale_factor) = {
+                        let mut window = resources.get_mut::<Window>().unwrap();
+                        (window.take_injected_events(), window.window.scale_factor())
+                    };
+                    Self::inject_events(
+                        injected,
+                        &mut input_evts,
+                        &mut resources,
+                        window_scale_factor,
+                    );
+
                     let trf = *resources.get_or_default::<Transformation>();
-                    resources
-                        .get_mut::<Input>()
-                        .unwrap()
-                        .apply(&mut input_evts, &trf)
+                    let scale_factor = resources.get::<Window>().unwrap().scale_factor();
+                    let mut queue = resources.get_mut::<InputEventQueue>().unwrap();
+                    resources.get_mut::<Input>().unwrap().apply(
+                        &mut input_evts,
+                        &trf,
+                        scale_factor,
+                        &mut queue,
+                    )
                 }
                 Event::RedrawRequested(_) => {}
                 Event::RedrawEventsCleared => {}
@@ -153,11 +299,529 @@ impl App {
                     for stage in RefCell::borrow(&busy_stages).iter() {
                         stage.free(&mut world, &mut resources);
                     }
+
+                    Self::restore_panic_hook(previous_hook.take());
                 }
                 _ => {}
             }
         });
     }
+
+    /// Run the `App` on the main thread without window and gpu, the `AppStage`s tagged with
+    /// `AppStageTag::Render` are skipped.
+    ///
+    /// Useful for game servers and tests, call `AppSettings::quit` to stop running.
+    ///
+    /// NOTE: the frames are run back to back as fast as possible, there's no vsync to pace them. Sleep in a
+    /// system for a fixed tick rate.
+    pub fn run_headless(self) {
+        let previous_hook = Self::set_panic_hook(self.panic_handler);
+        let catch_panic = previous_hook.is_some();
+
+        let busy_stages: Vec<AppStage> = self
+            .busy_stages
+            .into_iter()
+            .filter(|stage| stage.tag() != Some(&AppStageTag::Render))
+            .collect();
+        let busy_stages = Rc::new(RefCell::new(busy_stages));
+
+        let mut world = World::default();
+        let mut resources = Resources::default();
+
+        Self::insert_engine_resources(&mut resources);
+        resources.insert::<AppSettings>(
+            AppSettings::new(&busy_stages).with_history_capacity(self.history_capacity),
+        );
+
+        // NOTE: there's no output device in headless mode, the sounds are timed silently.
+        let mut audio_output = AudioOutput::silent();
+
+        // NOTE: init all AppStages
+        for stage in RefCell::borrow(&busy_stages).iter() {
+            stage.init(&mut world, &mut resources);
+        }
+
+        // NOTE: apply app_settings added by last frame, if user try to exit, then exit.
+        loop {
+            Self::exit_stages(&RefCell::borrow(&busy_stages), &mut world, &mut resources);
+            if resources.get_mut::<AppSettings>().unwrap().apply() {
+                break;
+            }
+
+            Self::insert_resources(&mut resources);
+            Self::enter_stages(&RefCell::borrow(&busy_stages), &mut world, &mut resources);
+            Self::reset_stages(
+                &RefCell::borrow(&busy_stages),
+                catch_panic,
+                &mut world,
+                &mut resources,
+            );
+            Self::play_stages(
+                &RefCell::borrow(&busy_stages),
+                catch_panic,
+                &mut world,
+                &mut resources,
+            );
+            audio_output.update(&resources);
+        }
+
+        // NOTE: destroy all AppStages
+        for stage in RefCell::borrow(&busy_stages).iter() {
+            stage.free(&mut world, &mut resources);
+        }
+
+        Self::restore_panic_hook(previous_hook);
+    }
+
+    /// Insert the engine resources shared by all stages, except `AppSettings` and `Window`.
+    fn insert_engine_resources(resources: &mut Resources) {
+        macro_rules! insert_resources {
+            ($($ty:ty => $new:expr),*) => {
+                $(
+                    resources.insert::<$ty>($new);
+                )*
+            };
+        }
+
+        engine_resources!(insert_resources);
+    }
+
+    /// Replace the panic hook with the panic handler if there is one, return the previous hook.
+    ///
+    /// The panics should be caught if the previous hook is returned.
+    fn set_panic_hook(panic_handler: Option<PanicHandler>) -> Option<PanicHook> {
+        panic_handler.map(|handler| {
+            let previous = panic::take_hook();
+            panic::set_hook(Box::new(move |info| handler(info)));
+
+            previous
+        })
+    }
+
+    /// Put back the panic hook replaced by `Self::set_panic_hook`.
+    fn restore_panic_hook(previous_hook: Option<PanicHook>) {
+        if let Some(hook) = previous_hook {
+            panic::set_hook(hook);
+        }
+    }
+
+    /// Release the input and send `WindowResized`, the surface follows the size of window in next render.
+    fn resize(resources: &mut Resources, size: PhysicalSize<u32>, scale_factor: f64) {
+        // NOTE: To prevent input freeze bug
+        resources.get_mut::<Input>().unwrap().release_all();
+
+        resources
+            .get_mut::<Events<WindowResized>>()
+            .unwrap()
+            .send(WindowResized::new(size, scale_factor));
+    }
+
+    /// Turn the injected events into the input events of this frame.
+    ///
+    /// A resize resizes the window if there is one, then the OS sends `WindowEvent::Resized` which is
+    /// processed as usual, including the reconfiguration of surface. Without window, it's processed by
+    /// `Self::resize` directly.
+    fn inject_events(
+        events: Vec<SyntheticWindowEvent>,
+        input_evts: &mut Vec<Event<'static, ()>>,
+        resources: &mut Resources,
+        scale_factor: f64,
+    ) {
+        for event in events {
+            match event {
+                SyntheticWindowEvent::Resized(width, height) => {
+                    let resized = match resources.get_mut::<Window>() {
+                        Some(mut window) => {
+                            window.set_resolution(width, height);
+                            true
+                        }
+                        None => false,
+                    };
+
+                    if !resized {
+                        Self::resize(resources, PhysicalSize::new(width, height), scale_factor);
+                    }
+                }
+                SyntheticWindowEvent::GamepadAxis(axis, value) => {
+                    resources
+                        .get_mut::<Input>()
+                        .unwrap()
+                        .gamepad
+                        .set_axis(axis, value);
+                }
+                event => input_evts.extend(event.to_input_event()),
+            }
+        }
+    }
+
+    /// Run the exit schedules of the stages which are going to be made to rest, call it before applying
+    /// `AppSettings`.
+    fn exit_stages(busy_stages: &[AppStage], world: &mut World, resources: &mut Resources) {
+        let stage_names = resources.get::<AppSettings>().unwrap().stages_to_exit();
+
+        for stage_name in stage_names {
+            if let Some(stage) = busy_stages.iter().find(|stage| stage.name() == stage_name) {
+                stage.exit(world, resources);
+            }
+        }
+    }
+
+    /// Run the enter schedules of the stages pushed to work by `AppSettings`.
+    fn enter_stages(busy_stages: &[AppStage], world: &mut World, resources: &mut Resources) {
+        let stage_names = resources
+            .get_mut::<AppSettings>()
+            .unwrap()
+            .take_stages_to_enter();
+
+        for stage_name in stage_names {
+            if let Some(stage) = busy_stages.iter().find(|stage| stage.name() == stage_name) {
+                stage.enter(world, resources);
+            }
+        }
+    }
+
+    /// Insert the resources deferred by `AppSettings::defer_insert_resource` in last frame.
+    fn insert_resources(resources: &mut Resources) {
+        let deferred = resources
+            .get_mut::<AppSettings>()
+            .unwrap()
+            .take_resources_to_insert();
+
+        for (data, insert) in deferred {
+            insert(data, resources);
+        }
+    }
+
+    /// Run the startup schedules of the stages reset by `AppSettings` in last frame.
+    fn reset_stages(
+        busy_stages: &[AppStage],
+        catch_panic: bool,
+        world: &mut World,
+        resources: &mut Resources,
+    ) {
+        let stage_names = resources
+            .get_mut::<AppSettings>()
+            .unwrap()
+            .take_stages_to_reset();
+
+        for stage_name in stage_names {
+            // NOTE: the stage may have been made to rest by an earlier command in the same frame.
+            if let Some(stage) = busy_stages.iter().find(|stage| stage.name() == stage_name) {
+                Self::run_stage(stage, catch_panic, world, resources, AppStage::init);
+            }
+        }
+    }
+
+    /// Run the schedule of the stage by `f`, record the panic in `PanicRecord` if the panics are caught.
+    fn run_stage<F: FnOnce(&AppStage, &mut World, &mut Resources)>(
+        stage: &AppStage,
+        catch_panic: bool,
+        world: &mut World,
+        resources: &mut Resources,
+        f: F,
+    ) {
+        if catch_panic {
+            if let Err(payload) =
+                panic::catch_unwind(AssertUnwindSafe(|| f(stage, world, resources)))
+            {
+                resources
+                    .get_mut::<PanicRecord>()
+                    .unwrap()
+                    .push(stage.name(), payload);
+            }
+        } else {
+            f(stage, world, resources);
+        }
+    }
+
+    fn play_stages(
+        busy_stages: &[AppStage],
+        catch_panic: bool,
+        world: &mut World,
+        resources: &mut Resources,
+    ) {
+        resources.get_mut::<FrameStats>().unwrap().begin_frame();
+        resources
+            .get_mut::<Events<StageBudgetExceeded>>()
+            .unwrap()
+            .update();
+
+        for stage in busy_stages {
+            Self::run_stage(stage, catch_panic, world, resources, AppStage::play);
+
+            if let Some(mut transfers) = resources.get_mut::<EntityTransfers>() {
+                transfers.flush_pending(stage.name());
+            }
+        }
+
+        Self::transfer_entities(busy_stages, world, resources);
+    }
+
+    /// Apply the `EntityTransfers` requested by the stages in this frame.
+    fn transfer_entities(busy_stages: &[AppStage], world: &mut World, resources: &mut Resources) {
+        let requests = match resources.get_mut::<EntityTransfers>() {
+            Some(mut transfers) => transfers.take_requests(),
+            None => return,
+        };
+
+        let find = |name: &str| busy_stages.iter().find(|stage| stage.name() == name);
+        let results = requests
+            .into_iter()
+            .map(|(from, entity, to)| {
+                let result = match (find(&from), find(&to)) {
+                    (Some(from), Some(to)) => from.transfer_entity(to, entity, world, resources),
+                    (None, _) => Err(TransferError::NoSuchStage(from)),
+                    (_, None) => Err(TransferError::NoSuchStage(to)),
+                };
+
+                (entity, result)
+            })
+            .collect();
+
+        resources
+            .get_mut::<EntityTransfers>()
+            .unwrap()
+            .set_results(results);
+    }
+}
+
+impl Default for App {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for App {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("App")
+            .field("busy_stages", &self.busy_stages)
+            .field("panic_handler", &self.panic_handler.is_some())
+            .field("history_capacity", &self.history_capacity)
+            .finish()
+    }
+}
+
+/// An `App` driven frame by frame, without window and gpu, created by `AppBuilder::build_for_test`.
+///
+/// The `AppStage`s tagged with `AppStageTag::Render` are skipped like `App::run_headless`, the stages are
+/// initialized at the first tick and freed when `TestApp` is dropped.
+pub struct TestApp {
+    busy_stages: Rc<RefCell<Vec<AppStage>>>,
+    world: World,
+    resources: Resources,
+    audio_output: AudioOutput,
+
+    initialized: bool,
+    quit: bool,
+    // The events injected by `TestApp::inject_event`, applied to `Input` at the start of next tick.
+    injected_events: Vec<SyntheticWindowEvent>,
+}
+
+impl TestApp {
+    fn new(busy_stages: Vec<AppStage>) -> Self {
+        let busy_stages: Vec<AppStage> = busy_stages
+            .into_iter()
+            .filter(|stage| stage.tag() != Some(&AppStageTag::Render))
+            .collect();
+        let busy_stages = Rc::new(RefCell::new(busy_stages));
+
+        let mut resources = Resources::default();
+
+        App::insert_engine_resources(&mut resources);
+        resources.insert::<AppSettings>(AppSettings::new(&busy_stages));
+
+        Self {
+            busy_stages,
+            world: World::default(),
+            resources,
+            audio_output: AudioOutput::silent(),
+
+            initialized: false,
+            quit: false,
+            injected_events: Vec::new(),
+        }
+    }
+
+    /// Inject the event as if it came from the OS, the stages see it in the next tick.
+    ///
+    /// There's no window, so the scale factor is 1 and the cursor positions are in logical pixels.
+    pub fn inject_event(&mut self, event: SyntheticWindowEvent) {
+        self.injected_events.push(event);
+    }
+
+    /// Run one frame, do nothing after `AppSettings::quit` is applied.
+    ///
+    /// NOTE: panics in the stages are not caught, so they fail the test.
+    pub fn tick(&mut self) {
+        if self.quit {
+            return;
+        }
+
+        if !self.initialized {
+            self.initialized = true;
+
+            for stage in RefCell::borrow(&self.busy_stages).iter() {
+                stage.init(&mut self.world, &mut self.resources);
+            }
+        }
+
+        App::exit_stages(
+            &RefCell::borrow(&self.busy_stages),
+            &mut self.world,
+            &mut self.resources,
+        );
+        if self.resources.get_mut::<AppSettings>().unwrap().apply() {
+            self.quit = true;
+            return;
+        }
+
+        App::insert_resources(&mut self.resources);
+        App::enter_stages(
+            &RefCell::borrow(&self.busy_stages),
+            &mut self.world,
+            &mut self.resources,
+        );
+        App::reset_stages(
+            &RefCell::borrow(&self.busy_stages),
+            false,
+            &mut self.world,
+            &mut self.resources,
+        );
+        self.resources
+            .get_mut::<Events<WindowResized>>()
+            .unwrap()
+            .update();
+        self.apply_input();
+        App::play_stages(
+            &RefCell::borrow(&self.busy_stages),
+            false,
+            &mut self.world,
+            &mut self.resources,
+        );
+        self.audio_output.update(&self.resources);
+    }
+
+    // Apply the injected events to `Input`, the just pressed keys become pressed in the ticks without events.
+    fn apply_input(&mut self) {
+        let mut input_evts = Vec::new();
+        App::inject_events(
+            std::mem::take(&mut self.injected_events),
+            &mut input_evts,
+            &mut self.resources,
+            1.0,
+        );
+
+        let trf = *self.resources.get_or_default::<Transformation>();
+        let mut queue = self.resources.get_mut::<InputEventQueue>().unwrap();
+        self.resources
+            .get_mut::<Input>()
+            .unwrap()
+            .apply(&mut input_evts, &trf, 1.0, &mut queue);
+    }
+
+    pub fn tick_n(&mut self, n: u64) {
+        for _ in 0..n {
+            self.tick();
+        }
+    }
+
+    /// Whether `AppSettings::quit` has been applied.
+    pub fn is_quit(&self) -> bool {
+        self.quit
+    }
+
+    pub fn world(&self) -> &World {
+        &self.world
+    }
+
+    pub fn world_mut(&mut self) -> &mut World {
+        &mut self.world
+    }
+
+    pub fn resources(&self) -> &Resources {
+        &self.resources
+    }
+
+    pub fn resources_mut(&mut self) -> &mut Resources {
+        &mut self.resources
+    }
+}
+
+impl Drop for TestApp {
+    fn drop(&mut self) {
+        // NOTE: don't free the stages again if the test is failing by a panic in the stages.
+        if self.initialized && !std::thread::panicking() {
+            for stage in RefCell::borrow(&self.busy_stages).iter() {
+                stage.free(&mut self.world, &mut self.resources);
+            }
+        }
+    }
+}
+
+impl fmt::Debug for TestApp {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TestApp")
+            .field("busy_stages", &RefCell::borrow(&self.busy_stages))
+            .field("initialized", &self.initialized)
+            .field("quit", &self.quit)
+            .finish()
+    }
+}
+
+/// A panic caught from an `AppStage`.
+#[derive(Debug, Clone)]
+pub struct StagePanic {
+    pub stage_name: String,
+    pub message: String,
+}
+
+/// The resource accumulates the panics caught by the panic handler of `App`.
+#[derive(Debug, Default)]
+pub struct PanicRecord {
+    panics: Vec<StagePanic>,
+}
+
+impl PanicRecord {
+    pub fn new() -> Self {
+        Self {
+            panics: Default::default(),
+        }
+    }
+
+    pub fn iter(&self) -> Iter<StagePanic> {
+        self.panics.iter()
+    }
+
+    pub fn last(&self) -> Option<&StagePanic> {
+        self.panics.last()
+    }
+
+    pub fn len(&self) -> usize {
+        self.panics.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.panics.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.panics.clear();
+    }
+
+    fn push(&mut self, stage_name: &str, payload: Box<dyn Any + Send>) {
+        let message = if let Some(message) = payload.downcast_ref::<&str>() {
+            String::from(*message)
+        } else if let Some(message) = payload.downcast_ref::<String>() {
+            message.clone()
+        } else {
+            String::from("unknown panic")
+        };
+
+        self.panics.push(StagePanic {
+            stage_name: String::from(stage_name),
+            message,
+        });
+    }
 }
 
 #[derive(Default)]
@@ -186,7 +850,7 @@ impl AppBuilder {
 
     pub fn create_stage_builder(
         self,
-        stage_name: String,
+        stage_name: impl Into<String>,
     ) -> Result<AppStageBuilder, AppBuildError> {
         let mut stage_builder = AppStageBuilder::new(stage_name);
 
@@ -198,6 +862,60 @@ impl AppBuilder {
         }
     }
 
+    /// Add a stage for every stage of `config`, in the order they are listed. The stage has the name of
+    /// config and the systems of all stages added by its plugins, merged in order by `AppStageBuilder::merge`.
+    ///
+    /// The stage takes the tag of the plugin stages, fail if a plugin is unknown, the plugin stages have
+    /// different tags, two stages have the same name or a stage doesn't play every frame.
+    pub fn from_config(config: &AppConfig, registry: &PluginRegistry) -> Result<Self, ConfigError> {
+        let mut app_builder = AppBuilder::new();
+
+        for stage_config in config.stages.iter() {
+            if app_builder.has_stage(&stage_config.name) {
+                return Err(ConfigError::DuplicateStage(stage_config.name.clone()));
+            }
+
+            if stage_config.frequency != 0 {
+                return Err(ConfigError::UnsupportedFrequency(
+                    stage_config.name.clone(),
+                    stage_config.frequency,
+                ));
+            }
+
+            let mut stage = AppStageBuilder::new(stage_config.name.clone());
+
+            for plugin_name in stage_config.plugins.iter() {
+                let plugin = registry
+                    .get(plugin_name)
+                    .ok_or_else(|| ConfigError::UnknownPlugin(plugin_name.clone()))?;
+
+                // NOTE: build the plugin alone, its stages are detached from the `AppBuilder` so the merge
+                // never fails.
+                for plugin_stage in plugin.build(AppBuilder::new()).stage_builders {
+                    if stage.tag.is_none() {
+                        stage.tag = plugin_stage.tag.clone();
+                    } else if plugin_stage.tag.is_some() && plugin_stage.tag != stage.tag {
+                        return Err(ConfigError::MixedTags(stage_config.name.clone()));
+                    }
+
+                    stage = match stage.merge(plugin_stage) {
+                        Ok(stage) => stage,
+                        Err(_) => panic!("ERR: The stage of plugin is attached to an AppBuilder."),
+                    };
+                }
+            }
+
+            app_builder.stage_builders.push(stage);
+        }
+
+        Ok(app_builder)
+    }
+
+    /// Let the `Plugin` add its `AppStage`s to the `AppBuilder`.
+    pub fn add_plugin<P: Plugin>(self, plugin: P) -> Self {
+        plugin.build(self)
+    }
+
     pub fn build(self) -> App {
         App::from_stages(
             self.stage_builders
@@ -207,6 +925,38 @@ impl AppBuilder {
         )
     }
 
+    /// Build the `App` only if there is nothing suspicious, otherwise return all warnings.
+    ///
+    /// A stage without any system is valid, but usually the systems were forgotten or added to another
+    /// stage builder.
+    pub fn build_validated(self) -> Result<App, Vec<AppBuildWarning>> {
+        let warnings = self
+            .stage_builders
+            .iter()
+            .filter(|stage_builder| stage_builder.is_empty())
+            .map(|stage_builder| AppBuildWarning::EmptyStage(String::from(stage_builder.name())))
+            .collect::<Vec<_>>();
+
+        if warnings.is_empty() {
+            Ok(self.build())
+        } else {
+            Err(warnings)
+        }
+    }
+
+    /// Build a `TestApp` instead of `App`, the primary way to test systems.
+    ///
+    /// Spawn entities by `TestApp::world_mut`, run frames by `TestApp::tick_n` and check the results in
+    /// `TestApp::world`.
+    pub fn build_for_test(self) -> TestApp {
+        TestApp::new(
+            self.stage_builders
+                .into_iter()
+                .map(|stage_builder| stage_builder.build())
+                .collect(),
+        )
+    }
+
     fn has_stage(&self, stage_name: &str) -> bool {
         self.stage_builders
             .iter()
@@ -218,26 +968,113 @@ impl AppBuilder {
 #[derive(Debug)]
 pub enum AppBuildError {
     DuplicateName(AppStageBuilder),
+    MergeAttachedStage(AppStageBuilder, AppStageBuilder),
+    /// No process system has the name, see `AppStageBuilder::try_add_system_process_before`.
+    UnknownSystem(AppStageBuilder, String),
+}
+
+/// The suspicious setup found by `AppBuilder::build_validated`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AppBuildWarning {
+    /// The stage has no system in any schedule.
+    EmptyStage(String),
+}
+
+impl fmt::Display for AppBuildWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppBuildWarning::EmptyStage(name) => write!(f, "stage `{}` has no system", name),
+        }
+    }
+}
+
+/// The tag describes what an `AppStage` is used for.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum AppStageTag {
+    Render,
+    Physics,
+    Logic,
+    Network,
+    Custom(String),
+}
+
+/// What an `AppStage` does when the process schedule of last frame took longer than its budget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BudgetPolicy {
+    /// Skip the process schedule of this frame and send `StageBudgetExceeded`.
+    ///
+    /// The `Time` of the stage doesn't tick in the skipped frame, the delta of next frame covers it.
+    Skip,
+    /// Run the process schedule as usual.
+    RunAnyway,
+    /// Run the process schedule and send `StageBudgetExceeded`.
+    Warn,
+}
+
+/// Sent when an `AppStage` overran its budget in last frame: (stage name, actual time cost in milliseconds).
+#[derive(Debug, Clone, PartialEq)]
+pub struct StageBudgetExceeded(pub String, pub f32);
+
+/// The max time the process schedule of an `AppStage` can take in a frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct CpuBudget {
+    max_ms: f32,
+    policy: BudgetPolicy,
 }
 
 pub struct AppStage {
     name: String,
+    tag: Option<AppStageTag>,
     time: RefCell<Time>,
+    profiling: bool,
+    budget: Option<CpuBudget>,
+    // The time cost of the process schedule in last frame, 0.0 if it was skipped.
+    last_cpu_ms: Cell<f32>,
+    // The `World` and `Resources` of the stage created by `AppStageBuilder::with_own_world`.
+    own_world: Option<RefCell<(World, Resources)>>,
+    // The count of systems added by user in (startup, process, destroy) schedules, legion doesn't expose them.
+    system_counts: (usize, usize, usize),
 
     startup: RefCell<Schedule>,
     process: RefCell<Schedule>,
     destroy: RefCell<Schedule>,
+    enter: RefCell<Schedule>,
+    exit: RefCell<Schedule>,
 }
 
 impl AppStage {
-    fn new(name: String, startup: Schedule, process: Schedule, destroy: Schedule) -> Self {
+    fn new(
+        name: String,
+        tag: Option<AppStageTag>,
+        profiling: bool,
+        budget: Option<CpuBudget>,
+        own_world: bool,
+        system_counts: (usize, usize, usize),
+        startup: Schedule,
+        process: Schedule,
+        destroy: Schedule,
+        enter: Schedule,
+        exit: Schedule,
+    ) -> Self {
         Self {
             name,
+            tag,
             time: RefCell::new(Time::now()),
+            profiling,
+            budget,
+            last_cpu_ms: Cell::new(0.0),
+            own_world: if own_world {
+                Some(RefCell::new((World::default(), Resources::default())))
+            } else {
+                None
+            },
+            system_counts,
 
             startup: RefCell::new(startup),
             process: RefCell::new(process),
             destroy: RefCell::new(destroy),
+            enter: RefCell::new(enter),
+            exit: RefCell::new(exit),
         }
     }
 
@@ -245,21 +1082,197 @@ impl AppStage {
         self.name.as_str()
     }
 
-    pub(crate) fn init(&self, world: &mut World, resources: &mut Resources) {
+    pub fn tag(&self) -> Option<&AppStageTag> {
+        self.tag.as_ref()
+    }
+
+    /// Whether the stage runs in its own `World` and `Resources` instead of the shared ones.
+    pub fn has_own_world(&self) -> bool {
+        self.own_world.is_some()
+    }
+
+    pub fn startup_system_count(&self) -> usize {
+        self.system_counts.0
+    }
+
+    pub fn process_system_count(&self) -> usize {
+        self.system_counts.1
+    }
+
+    pub fn destroy_system_count(&self) -> usize {
+        self.system_counts.2
+    }
+
+    pub(crate) fn init(&self, world: &mut World, resources: &mut Resources) {
+        self.in_world(world, resources, |world, resources| {
+            self.init_in(world, resources)
+        });
+    }
+
+    pub(crate) fn play(&self, world: &mut World, resources: &mut Resources) {
+        self.in_world(world, resources, |world, resources| {
+            self.play_in(world, resources)
+        });
+    }
+
+    pub(crate) fn free(&self, world: &mut World, resources: &mut Resources) {
+        self.in_world(world, resources, |world, resources| {
+            self.free_in(world, resources)
+        });
+    }
+
+    pub(crate) fn enter(&self, world: &mut World, resources: &mut Resources) {
+        self.in_world(world, resources, |world, resources| {
+            resources.insert::<Time>(*self.time.borrow());
+            self.enter.borrow_mut().execute(world, resources);
+        });
+    }
+
+    pub(crate) fn exit(&self, world: &mut World, resources: &mut Resources) {
+        self.in_world(world, resources, |world, resources| {
+            resources.insert::<Time>(*self.time.borrow());
+            self.exit.borrow_mut().execute(world, resources);
+        });
+    }
+
+    /// Move the entity from the world of `self` to the world of `to`, the stages without own world share
+    /// `world`.
+    ///
+    /// The `ComponentRegistry` in the own resources of `self` is used if there is one.
+    fn transfer_entity(
+        &self,
+        to: &AppStage,
+        entity: Entity,
+        world: &mut World,
+        resources: &Resources,
+    ) -> Result<Entity, TransferError> {
+        let stay = |world: &World| {
+            if world.contains(entity) {
+                Ok(entity)
+            } else {
+                Err(TransferError::NoSuchEntity(entity))
+            }
+        };
+
+        match (&self.own_world, &to.own_world) {
+            (None, None) => stay(world),
+            (Some(from), None) => {
+                let (from_world, from_resources) = &mut *from.borrow_mut();
+                let registry = Self::registry_of(from_resources, resources);
+                transfer_entity(from_world, world, entity, registry)
+            }
+            (None, Some(to)) => {
+                let (to_world, _) = &mut *to.borrow_mut();
+                transfer_entity(world, to_world, entity, resources)
+            }
+            (Some(from), Some(_)) if std::ptr::eq(self, to) => stay(&from.borrow().0),
+            (Some(from), Some(to)) => {
+                let (from_world, from_resources) = &mut *from.borrow_mut();
+                let (to_world, _) = &mut *to.borrow_mut();
+                let registry = Self::registry_of(from_resources, resources);
+                transfer_entity(from_world, to_world, entity, registry)
+            }
+        }
+    }
+
+    /// The resources to find `ComponentRegistry` in, the own ones first.
+    fn registry_of<'a>(own: &'a Resources, shared: &'a Resources) -> &'a Resources {
+        if own.contains::<ComponentRegistry>() {
+            own
+        } else {
+            shared
+        }
+    }
+
+    /// Run `f` in the own world of the stage if it has one, otherwise in the shared world.
+    fn in_world<F: FnOnce(&mut World, &mut Resources)>(
+        &self,
+        world: &mut World,
+        resources: &mut Resources,
+        f: F,
+    ) {
+        match &self.own_world {
+            Some(own_world) => {
+                let (own_world, own_resources) = &mut *own_world.borrow_mut();
+
+                // NOTE: the engine resources are moved back by `drop` even if `f` panics.
+                let mut lent = LentResources::new(resources, own_resources);
+                f(own_world, &mut *lent.own);
+            }
+            None => f(world, resources),
+        }
+    }
+
+    fn init_in(&self, world: &mut World, resources: &mut Resources) {
         *self.time.borrow_mut() = Time::now();
         resources.insert::<Time>(*self.time.borrow_mut());
 
         self.startup.borrow_mut().execute(world, resources);
     }
 
-    pub(crate) fn play(&self, world: &mut World, resources: &mut Resources) {
-        self.time.borrow_mut().tick();
+    fn play_in(&self, world: &mut World, resources: &mut Resources) {
+        let skip = match self.budget {
+            Some(budget) if self.last_cpu_ms.get() > budget.max_ms => {
+                if budget.policy != BudgetPolicy::RunAnyway {
+                    if let Some(mut events) = resources.get_mut::<Events<StageBudgetExceeded>>() {
+                        events.send(StageBudgetExceeded(
+                            self.name.clone(),
+                            self.last_cpu_ms.get(),
+                        ));
+                    }
+                }
+
+                budget.policy == BudgetPolicy::Skip
+            }
+            _ => false,
+        };
+
+        // NOTE: the time of a skipped frame is added to the delta of next played frame.
+        if !skip {
+            self.time.borrow_mut().tick();
+        }
         resources.insert::<Time>(*self.time.borrow_mut());
 
-        self.process.borrow_mut().execute(world, resources);
+        let timing = if skip {
+            // NOTE: reset the time cost, so the stage runs in next frame.
+            self.last_cpu_ms.set(0.0);
+
+            StageTiming {
+                cpu_ms: 0.0,
+                skipped: true,
+            }
+        } else {
+            let start = Instant::now();
+            self.process.borrow_mut().execute(world, resources);
+            let cpu_ms = start.elapsed().as_secs_f32() * 1000.0;
+
+            self.last_cpu_ms.set(cpu_ms);
+
+            StageTiming {
+                cpu_ms,
+                skipped: false,
+            }
+        };
+
+        if let Some(mut metrics) = resources.get_mut::<HashMap<String, StageMetrics>>() {
+            match metrics.get_mut(&self.name) {
+                Some(stage_metrics) => stage_metrics.record(timing),
+                None => {
+                    let mut stage_metrics = StageMetrics::new(self.name.clone());
+                    stage_metrics.record(timing);
+                    metrics.insert(self.name.clone(), stage_metrics);
+                }
+            }
+        }
+
+        if self.profiling {
+            if let Some(mut frame_stats) = resources.get_mut::<FrameStats>() {
+                frame_stats.record_stage(&self.name, timing);
+            }
+        }
     }
 
-    pub(crate) fn free(&self, world: &mut World, resources: &mut Resources) {
+    fn free_in(&self, world: &mut World, resources: &mut Resources) {
         self.time.borrow_mut().tick();
         resources.insert::<Time>(*self.time.borrow_mut());
 
@@ -271,16 +1284,71 @@ impl fmt::Debug for AppStage {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("AppStage")
             .field("name", &self.name)
+            .field("tag", &self.tag)
+            .field("profiling", &self.profiling)
+            .field("budget", &self.budget)
+            .field("own_world", &self.has_own_world())
             .finish()
     }
 }
 
+/// The engine resources moved from the shared `Resources` to the own `Resources` of a stage while it runs,
+/// so the stage can still read `Input`, `Window` or quit by `AppSettings`.
+struct LentResources<'a> {
+    shared: &'a mut Resources,
+    own: &'a mut Resources,
+}
+
+impl<'a> LentResources<'a> {
+    fn new(shared: &'a mut Resources, own: &'a mut Resources) -> Self {
+        Self::move_engine_resources(shared, own);
+
+        Self { shared, own }
+    }
+
+    fn move_engine_resources(from: &mut Resources, to: &mut Resources) {
+        macro_rules! move_resources {
+            ($($ty:ty $(=> $new:expr)?),*) => {
+                $(
+                    if let Some(resource) = from.remove::<$ty>() {
+                        to.insert::<$ty>(resource);
+                    }
+                )*
+            };
+        }
+
+        move_resources!(AppSettings, Window);
+        engine_resources!(move_resources);
+    }
+}
+
+impl Drop for LentResources<'_> {
+    fn drop(&mut self) {
+        Self::move_engine_resources(self.own, self.shared);
+    }
+}
+
+/// A deferred call to the legion `Builder`, so the steps can be moved between `AppStageBuilder`s.
+type BuildStep = Box<dyn FnOnce(&mut Builder)>;
+
 pub struct AppStageBuilder {
     name: String,
-
-    builder_startup: Builder,
-    builder_process: Builder,
-    builder_destroy: Builder,
+    tag: Option<AppStageTag>,
+    profiling: bool,
+    budget: Option<CpuBudget>,
+    own_world: bool,
+
+    steps_startup: Vec<BuildStep>,
+    steps_process: Vec<BuildStep>,
+    steps_destroy: Vec<BuildStep>,
+    steps_enter: Vec<BuildStep>,
+    steps_exit: Vec<BuildStep>,
+    // The names of process systems in the order of `steps_process`, `None` for the unnamed ones, used to
+    // insert systems by `add_system_process_before` and `add_system_process_after`.
+    process_names: Vec<Option<String>>,
+    // The count of (startup, process, destroy) systems added by user, the steps added by `add_event`,
+    // `add_resource` and `register_component` are not counted.
+    system_counts: (usize, usize, usize),
 
     app_builder: Option<AppBuilder>,
 }
@@ -294,13 +1362,21 @@ impl fmt::Debug for AppStageBuilder {
 }
 
 impl AppStageBuilder {
-    pub fn new(name: String) -> Self {
+    pub fn new(name: impl Into<String>) -> Self {
         Self {
-            name,
-
-            builder_startup: Builder::default(),
-            builder_process: Builder::default(),
-            builder_destroy: Builder::default(),
+            name: name.into(),
+            tag: None,
+            profiling: false,
+            budget: None,
+            own_world: false,
+
+            steps_startup: Default::default(),
+            steps_process: Default::default(),
+            steps_destroy: Default::default(),
+            steps_enter: Default::default(),
+            steps_exit: Default::default(),
+            process_names: Default::default(),
+            system_counts: Default::default(),
 
             app_builder: None,
         }
@@ -310,38 +1386,227 @@ impl AppStageBuilder {
         self.name.as_str()
     }
 
+    pub fn tag(mut self, tag: AppStageTag) -> Self {
+        self.tag = Some(tag);
+
+        self
+    }
+
+    /// Measure the time cost of the process schedule every frame and record it in `FrameStats`.
+    pub fn with_profiling(mut self, profiling: bool) -> Self {
+        self.profiling = profiling;
+
+        self
+    }
+
+    /// Limit the time cost of the process schedule, if last frame took longer than `ms`, then this frame
+    /// follows the `BudgetPolicy`(default `Skip`).
+    ///
+    /// `StageBudgetExceeded` is sent unless the policy is `RunAnyway`, read it to log the overruns.
+    pub fn max_cpu_budget_ms(mut self, ms: f32) -> Self {
+        let policy = self
+            .budget
+            .map_or(BudgetPolicy::Skip, |budget| budget.policy);
+        self.budget = Some(CpuBudget { max_ms: ms, policy });
+
+        self
+    }
+
+    /// Set the `BudgetPolicy`, only takes effect with `max_cpu_budget_ms`.
+    pub fn budget_policy(mut self, policy: BudgetPolicy) -> Self {
+        if let Some(budget) = self.budget.as_mut() {
+            budget.policy = policy;
+        } else {
+            self.budget = Some(CpuBudget {
+                max_ms: f32::INFINITY,
+                policy,
+            });
+        }
+
+        self
+    }
+
+    /// Run the stage in its own `World` and `Resources`, isolated from the other stages.
+    ///
+    /// The engine resources(`Input`, `AppSettings`, `Window`, `FrameStats`...) are lent to the stage while it
+    /// runs, request `EntityTransfers` to move entities between worlds.
+    pub fn with_own_world(mut self) -> Self {
+        self.own_world = true;
+
+        self
+    }
+
     pub fn add_system_startup<T: ParallelRunnable + 'static>(mut self, system: T) -> Self {
-        self.builder_startup.add_system(system);
+        self.system_counts.0 += 1;
+        self.steps_startup.push(Box::new(move |builder| {
+            builder.add_system(system);
+        }));
 
         self
     }
 
     pub fn add_system_process<T: ParallelRunnable + 'static>(mut self, system: T) -> Self {
-        self.builder_process.add_system(system);
+        self.system_counts.1 += 1;
+        let name = system.name().map(|id| id.to_string());
+        self.push_process(
+            name,
+            Box::new(move |builder| {
+                builder.add_system(system);
+            }),
+        );
 
         self
     }
 
+    /// Add the process system right before the first process system named `other_system_name`.
+    ///
+    /// The name of a system is the name of `#[system]` fn or the one passed to `SystemBuilder::new`.
+    ///
+    /// # Panics
+    ///
+    /// Panic if no process system has the name, see `AppStageBuilder::try_add_system_process_before`.
+    pub fn add_system_process_before<T: ParallelRunnable + 'static>(
+        self,
+        system: T,
+        other_system_name: &str,
+    ) -> Self {
+        Self::unwrap_inserted(self.try_add_system_process_before(system, other_system_name))
+    }
+
+    /// Add the process system right after the last process system named `other_system_name`.
+    ///
+    /// See `AppStageBuilder::add_system_process_before` for the names.
+    ///
+    /// # Panics
+    ///
+    /// Panic if no process system has the name, see `AppStageBuilder::try_add_system_process_after`.
+    pub fn add_system_process_after<T: ParallelRunnable + 'static>(
+        self,
+        system: T,
+        other_system_name: &str,
+    ) -> Self {
+        Self::unwrap_inserted(self.try_add_system_process_after(system, other_system_name))
+    }
+
+    /// The same as `AppStageBuilder::add_system_process_before`, but return `AppBuildError::UnknownSystem`
+    /// if no process system has the name, the system is dropped.
+    pub fn try_add_system_process_before<T: ParallelRunnable + 'static>(
+        mut self,
+        system: T,
+        other_system_name: &str,
+    ) -> Result<Self, AppBuildError> {
+        let index = self
+            .process_names
+            .iter()
+            .position(|name| name.as_deref() == Some(other_system_name));
+
+        match index {
+            Some(index) => {
+                self.insert_process(system, index);
+                Ok(self)
+            }
+            None => Err(AppBuildError::UnknownSystem(
+                self,
+                String::from(other_system_name),
+            )),
+        }
+    }
+
+    /// The same as `AppStageBuilder::add_system_process_after`, but return `AppBuildError::UnknownSystem`
+    /// if no process system has the name, the system is dropped.
+    pub fn try_add_system_process_after<T: ParallelRunnable + 'static>(
+        mut self,
+        system: T,
+        other_system_name: &str,
+    ) -> Result<Self, AppBuildError> {
+        let index = self
+            .process_names
+            .iter()
+            .rposition(|name| name.as_deref() == Some(other_system_name));
+
+        match index {
+            Some(index) => {
+                self.insert_process(system, index + 1);
+                Ok(self)
+            }
+            None => Err(AppBuildError::UnknownSystem(
+                self,
+                String::from(other_system_name),
+            )),
+        }
+    }
+
+    /// The names of process systems in the order they run, `None` for the unnamed ones like thread local fns.
+    pub fn process_system_names(&self) -> impl Iterator<Item = Option<&str>> {
+        self.process_names.iter().map(|name| name.as_deref())
+    }
+
+    fn push_process(&mut self, name: Option<String>, step: BuildStep) {
+        self.steps_process.push(step);
+        self.process_names.push(name);
+    }
+
+    fn insert_process<T: ParallelRunnable + 'static>(&mut self, system: T, index: usize) {
+        let name = system.name().map(|id| id.to_string());
+
+        self.system_counts.1 += 1;
+        self.steps_process.insert(
+            index,
+            Box::new(move |builder| {
+                builder.add_system(system);
+            }),
+        );
+        self.process_names.insert(index, name);
+    }
+
+    fn unwrap_inserted(result: Result<Self, AppBuildError>) -> Self {
+        match result {
+            Ok(stage_builder) => stage_builder,
+            Err(AppBuildError::UnknownSystem(stage_builder, other_system_name)) => panic!(
+                "ERR: No process system named `{}` in AppStage `{}`",
+                other_system_name,
+                stage_builder.name()
+            ),
+            Err(_) => unreachable!(),
+        }
+    }
+
     pub fn add_system_destroy<T: ParallelRunnable + 'static>(mut self, system: T) -> Self {
-        self.builder_destroy.add_system(system);
+        self.system_counts.2 += 1;
+        self.steps_destroy.push(Box::new(move |builder| {
+            builder.add_system(system);
+        }));
 
         self
     }
 
     pub fn add_thread_local_system_startup<T: Runnable + 'static>(mut self, system: T) -> Self {
-        self.builder_startup.add_thread_local(system);
+        self.system_counts.0 += 1;
+        self.steps_startup.push(Box::new(move |builder| {
+            builder.add_thread_local(system);
+        }));
 
         self
     }
 
     pub fn add_thread_local_system_process<T: Runnable + 'static>(mut self, system: T) -> Self {
-        self.builder_process.add_thread_local(system);
+        self.system_counts.1 += 1;
+        let name = system.name().map(|id| id.to_string());
+        self.push_process(
+            name,
+            Box::new(move |builder| {
+                builder.add_thread_local(system);
+            }),
+        );
 
         self
     }
 
     pub fn add_thread_local_system_destroy<T: Runnable + 'static>(mut self, system: T) -> Self {
-        self.builder_destroy.add_thread_local(system);
+        self.system_counts.2 += 1;
+        self.steps_destroy.push(Box::new(move |builder| {
+            builder.add_thread_local(system);
+        }));
 
         self
     }
@@ -350,7 +1615,10 @@ impl AppStageBuilder {
         mut self,
         f: F,
     ) -> Self {
-        self.builder_startup.add_thread_local_fn(f);
+        self.system_counts.0 += 1;
+        self.steps_startup.push(Box::new(move |builder| {
+            builder.add_thread_local_fn(f);
+        }));
 
         self
     }
@@ -359,7 +1627,13 @@ impl AppStageBuilder {
         mut self,
         f: F,
     ) -> Self {
-        self.builder_process.add_thread_local_fn(f);
+        self.system_counts.1 += 1;
+        self.push_process(
+            None,
+            Box::new(move |builder| {
+                builder.add_thread_local_fn(f);
+            }),
+        );
 
         self
     }
@@ -368,127 +1642,498 @@ impl AppStageBuilder {
         mut self,
         f: F,
     ) -> Self {
-        self.builder_destroy.add_thread_local_fn(f);
+        self.system_counts.2 += 1;
+        self.steps_destroy.push(Box::new(move |builder| {
+            builder.add_thread_local_fn(f);
+        }));
 
         self
     }
 
-    pub fn build(mut self) -> AppStage {
-        AppStage::new(
-            self.name,
-            self.builder_startup.build(),
-            self.builder_process.build(),
-            self.builder_destroy.build(),
-        )
+    /// Add a system which runs when the stage is pushed to work, e.g. by `AppSettings::make_spare_stage_work`.
+    ///
+    /// Unlike startup, it runs every time the stage goes back to work, and not when the `App` starts.
+    pub fn add_system_enter<T: ParallelRunnable + 'static>(mut self, system: T) -> Self {
+        self.steps_enter.push(Box::new(move |builder| {
+            builder.add_system(system);
+        }));
+
+        self
     }
 
-    pub fn into_app_builder(mut self) -> AppBuilder {
-        let app_builder = if self.app_builder.is_some() {
-            self.app_builder.take().unwrap()
-        } else {
-            AppBuilder::default()
-        };
+    /// Add a system which runs when the stage is made to rest by `AppSettings::make_busy_stage_rest`, before
+    /// it leaves the busy stages.
+    pub fn add_system_exit<T: ParallelRunnable + 'static>(mut self, system: T) -> Self {
+        self.steps_exit.push(Box::new(move |builder| {
+            builder.add_system(system);
+        }));
 
-        app_builder.add_stage_builder(self).ok().unwrap()
+        self
     }
-}
 
-pub struct AppSettings {
-    busy_stages: Rc<RefCell<Vec<AppStage>>>,
+    pub fn add_thread_local_system_enter<T: Runnable + 'static>(mut self, system: T) -> Self {
+        self.steps_enter.push(Box::new(move |builder| {
+            builder.add_thread_local(system);
+        }));
 
-    spare_stages: Vec<AppStage>,
-    commands: Vec<AppCommand>,
-}
+        self
+    }
 
-impl AppSettings {
-    fn new(busy_stages: &Rc<RefCell<Vec<AppStage>>>) -> Self {
-        Self {
-            busy_stages: Rc::clone(busy_stages),
+    pub fn add_thread_local_system_exit<T: Runnable + 'static>(mut self, system: T) -> Self {
+        self.steps_exit.push(Box::new(move |builder| {
+            builder.add_thread_local(system);
+        }));
 
-            spare_stages: Default::default(),
-            commands: Default::default(),
-        }
+        self
     }
 
-    /// apply settings for app and return a flag indicating whether user request to quit
-    fn apply(&mut self) -> bool {
-        fn fuck_borrow_checker(busy_stages: &Vec<AppStage>, stage_name: &str) -> usize {
-            busy_stages
-                .iter()
-                .enumerate()
-                .find(|(_, stage)| stage.name() == stage_name)
-                .map(|(index, _)| index)
-                .unwrap()
-        }
-
-        for cmd in self.commands.drain(..) {
-            match cmd {
-                AppCommand::PushStageToWorkBefore {
-                    stage,
-                    after_stage_name,
-                } => {
-                    let index =
-                        fuck_borrow_checker(&self.busy_stages.borrow(), after_stage_name.as_str());
-                    self.busy_stages.borrow_mut().insert(index, stage);
-                }
-                AppCommand::PushStageToWork { stage } => {
-                    self.busy_stages.borrow_mut().push(stage);
-                }
-                AppCommand::PushStageToWorkAfter {
-                    stage,
-                    before_stage_name,
-                } => {
-                    let index =
-                        fuck_borrow_checker(&self.busy_stages.borrow(), before_stage_name.as_str());
-                    self.busy_stages.borrow_mut().insert(index + 1, stage);
-                }
-                AppCommand::MakeBusyStageToRest { stage_name } => {
-                    let index =
-                        fuck_borrow_checker(&self.busy_stages.borrow(), stage_name.as_str());
-                    let stage = self.busy_stages.borrow_mut().remove(index);
-                    self.spare_stages.push(stage);
-                }
-                AppCommand::AppQuit => {
-                    return true;
-                }
-            }
-        }
+    pub fn add_thread_local_fn_enter<F: FnMut(&mut World, &mut Resources) + 'static>(
+        mut self,
+        f: F,
+    ) -> Self {
+        self.steps_enter.push(Box::new(move |builder| {
+            builder.add_thread_local_fn(f);
+        }));
 
-        false
+        self
     }
 
-    pub fn busy_stage<'a>(&'a self, stage_name: &str) -> Option<&'a AppStage> {
-        let stages: &'a Vec<AppStage> = unsafe {
-            // TODO: write safety words
-            &self.busy_stages.try_borrow_unguarded().unwrap()
-        };
+    pub fn add_thread_local_fn_exit<F: FnMut(&mut World, &mut Resources) + 'static>(
+        mut self,
+        f: F,
+    ) -> Self {
+        self.steps_exit.push(Box::new(move |builder| {
+            builder.add_thread_local_fn(f);
+        }));
 
-        stages.iter().find(|stage| stage.name() == stage_name)
+        self
     }
 
-    pub fn busy_stage_iter<'a>(&'a self) -> Iter<'a, AppStage> {
-        let stages: &'a Vec<AppStage> = unsafe {
-            // TODO: write safety words
-            &self.busy_stages.try_borrow_unguarded().unwrap()
-        };
+    /// Insert the `Events<T>` resource at startup and swap its buffers every frame.
+    ///
+    /// Call it before adding systems which use `Events<T>`, so the events are swapped at the start of frame.
+    pub fn add_event<T: Send + Sync + 'static>(mut self) -> Self {
+        self.push_startup_fn(|_, resources| {
+            if !resources.contains::<Events<T>>() {
+                resources.insert(Events::<T>::new());
+            }
+        });
 
-        stages.iter()
-    }
+        let system = update_events_system::<T>();
+        let name = system.name().map(|id| id.to_string());
+        self.push_process(
+            name,
+            Box::new(move |builder| {
+                builder.add_system(system);
+            }),
+        );
 
-    pub fn spare_stage(&self, stage_name: &str) -> Option<&AppStage> {
-        self.spare_stages
-            .iter()
-            .find(|stage| stage.name() == stage_name)
+        self
     }
 
-    pub fn spare_stage_iter(&self) -> Iter<AppStage> {
-        self.spare_stages.iter()
-    }
+    /// Insert the resource at startup, replace the old one if the resource exists.
+    pub fn add_resource<R: Resource>(mut self, resource: R) -> Self {
+        let mut resource = Some(resource);
 
-    pub fn spare_stage_mut(&mut self, stage_name: &str) -> Option<&mut AppStage> {
-        self.spare_stages
-            .iter_mut()
-            .find(|stage| stage.name() == stage_name)
+        self.push_startup_fn(move |_, resources| {
+            if let Some(resource) = resource.take() {
+                resources.insert(resource);
+            }
+        });
+
+        self
+    }
+
+    /// Register the component type to `ComponentRegistry`, so it can be serialized by `snapshot_world` and
+    /// saved to scene files.
+    pub fn register_component<T: SceneComponent + Clone>(mut self) -> Self {
+        self.push_startup_fn(|_, resources| {
+            if !resources.contains::<ComponentRegistry>() {
+                resources.insert(ComponentRegistry::new());
+            }
+
+            resources
+                .get_mut::<ComponentRegistry>()
+                .unwrap()
+                .register_scene::<T>();
+        });
+
+        self
+    }
+
+    // Add a startup fn which is not counted as a system of user.
+    fn push_startup_fn<F: FnMut(&mut World, &mut Resources) + 'static>(&mut self, f: F) {
+        self.steps_startup.push(Box::new(move |builder| {
+            builder.add_thread_local_fn(f);
+        }));
+    }
+
+    /// Append all systems of `other` after the systems of `self`, the name and tag of `self` are retained.
+    ///
+    /// Fails if `other` is attached to an `AppBuilder`(created by `AppBuilder::create_stage_builder`),
+    /// because the `AppBuilder` would be lost.
+    pub fn merge(mut self, mut other: AppStageBuilder) -> Result<Self, AppBuildError> {
+        if other.app_builder.is_some() {
+            Err(AppBuildError::MergeAttachedStage(self, other))
+        } else {
+            self = self
+                .merge_startup_systems(&mut other)
+                .merge_process_systems(&mut other)
+                .merge_destroy_systems(&mut other)
+                .merge_enter_systems(&mut other)
+                .merge_exit_systems(&mut other);
+
+            Ok(self)
+        }
+    }
+
+    /// Move the startup systems of `other` to the end of startup systems of `self`.
+    pub fn merge_startup_systems(mut self, other: &mut AppStageBuilder) -> Self {
+        self.steps_startup.append(&mut other.steps_startup);
+        self.system_counts.0 += std::mem::take(&mut other.system_counts.0);
+
+        self
+    }
+
+    /// Move the process systems of `other` to the end of process systems of `self`.
+    pub fn merge_process_systems(mut self, other: &mut AppStageBuilder) -> Self {
+        self.steps_process.append(&mut other.steps_process);
+        self.process_names.append(&mut other.process_names);
+        self.system_counts.1 += std::mem::take(&mut other.system_counts.1);
+
+        self
+    }
+
+    /// Move the destroy systems of `other` to the end of destroy systems of `self`.
+    pub fn merge_destroy_systems(mut self, other: &mut AppStageBuilder) -> Self {
+        self.steps_destroy.append(&mut other.steps_destroy);
+        self.system_counts.2 += std::mem::take(&mut other.system_counts.2);
+
+        self
+    }
+
+    /// Move the enter systems of `other` to the end of enter systems of `self`.
+    pub fn merge_enter_systems(mut self, other: &mut AppStageBuilder) -> Self {
+        self.steps_enter.append(&mut other.steps_enter);
+
+        self
+    }
+
+    /// Move the exit systems of `other` to the end of exit systems of `self`.
+    pub fn merge_exit_systems(mut self, other: &mut AppStageBuilder) -> Self {
+        self.steps_exit.append(&mut other.steps_exit);
+
+        self
+    }
+
+    /// The count of (startup, process, destroy) systems added so far, which the built `AppStage` will have.
+    ///
+    /// The systems added by `add_event`, `add_resource` and `register_component` are not counted.
+    pub fn system_count_preview(&self) -> (usize, usize, usize) {
+        self.system_counts
+    }
+
+    // Whether no system is added by user, the steps of `add_event`, `add_resource` and `register_component`
+    // are ignored.
+    //
+    // NOTE: all enter and exit steps are added by user.
+    fn is_empty(&self) -> bool {
+        self.system_counts == (0, 0, 0) && self.steps_enter.is_empty() && self.steps_exit.is_empty()
+    }
+
+    pub fn build(self) -> AppStage {
+        fn build_schedule(steps: Vec<BuildStep>) -> Schedule {
+            let mut builder = Builder::default();
+
+            for step in steps {
+                step(&mut builder);
+            }
+
+            builder.build()
+        }
+
+        let system_counts = self.system_count_preview();
+
+        AppStage::new(
+            self.name,
+            self.tag,
+            self.profiling,
+            self.budget,
+            self.own_world,
+            system_counts,
+            build_schedule(self.steps_startup),
+            build_schedule(self.steps_process),
+            build_schedule(self.steps_destroy),
+            build_schedule(self.steps_enter),
+            build_schedule(self.steps_exit),
+        )
+    }
+
+    pub fn into_app_builder(mut self) -> AppBuilder {
+        let app_builder = if self.app_builder.is_some() {
+            self.app_builder.take().unwrap()
+        } else {
+            AppBuilder::default()
+        };
+
+        app_builder.add_stage_builder(self).ok().unwrap()
+    }
+}
+
+pub struct AppSettings {
+    busy_stages: Rc<RefCell<Vec<AppStage>>>,
+
+    spare_stages: Vec<AppStage>,
+    commands: Vec<AppCommand>,
+    // The stages to reset by `App` after the commands are applied.
+    stages_to_reset: Vec<String>,
+    // The stages pushed to work, `App` runs their enter schedules after the commands are applied.
+    stages_to_enter: Vec<String>,
+    // The resources deferred by `defer_insert_resource`, `App` inserts them after the commands are applied.
+    resources_to_insert: Vec<(Box<dyn Any + Send + Sync>, InsertResourceFn)>,
+    // Set by `request_quit`, the app quits when it's still set at the end of the frame.
+    quit_requested: bool,
+
+    // The count of frames applied.
+    frame: u64,
+    // The last applied commands, the oldest first.
+    history: VecDeque<AppliedAppCommand>,
+    history_capacity: usize,
+}
+
+impl AppSettings {
+    fn new(busy_stages: &Rc<RefCell<Vec<AppStage>>>) -> Self {
+        Self {
+            busy_stages: Rc::clone(busy_stages),
+
+            spare_stages: Default::default(),
+            commands: Default::default(),
+            stages_to_reset: Default::default(),
+            stages_to_enter: Default::default(),
+            resources_to_insert: Default::default(),
+            quit_requested: false,
+
+            frame: 0,
+            history: Default::default(),
+            history_capacity: Self::DEFAULT_HISTORY_CAPACITY,
+        }
+    }
+
+    /// The count of applied commands kept in `AppSettings::command_history` by default.
+    pub const DEFAULT_HISTORY_CAPACITY: usize = 32;
+
+    fn with_history_capacity(mut self, capacity: usize) -> Self {
+        self.set_history_capacity(capacity);
+
+        self
+    }
+
+    /// apply settings for app and return a flag indicating whether user request to quit
+    fn apply(&mut self) -> bool {
+        self.frame += 1;
+
+        for cmd in std::mem::take(&mut self.commands) {
+            // NOTE: the commands queued by `undo_last_command` are not recorded, so undo can go back further.
+            let record = match cmd {
+                AppCommand::Undo(cmd) => {
+                    self.apply_command(*cmd);
+                    continue;
+                }
+                cmd => self.apply_command(cmd),
+            };
+
+            let quit = record == AppCommandRecord::AppQuit;
+            self.record(record);
+
+            if quit {
+                return true;
+            }
+        }
+
+        if self.quit_requested {
+            self.quit_requested = false;
+            self.record(AppCommandRecord::AppQuit);
+
+            return true;
+        }
+
+        false
+    }
+
+    /// Apply the command and return the record of it.
+    fn apply_command(&mut self, cmd: AppCommand) -> AppCommandRecord {
+        fn fuck_borrow_checker(busy_stages: &Vec<AppStage>, stage_name: &str) -> usize {
+            busy_stages
+                .iter()
+                .enumerate()
+                .find(|(_, stage)| stage.name() == stage_name)
+                .map(|(index, _)| index)
+                .unwrap()
+        }
+
+        match cmd {
+            AppCommand::PushStageToWorkBefore {
+                stage,
+                after_stage_name,
+            } => {
+                let index =
+                    fuck_borrow_checker(&self.busy_stages.borrow(), after_stage_name.as_str());
+                let stage_name = String::from(stage.name());
+                self.busy_stages.borrow_mut().insert(index, stage);
+                self.stages_to_enter.push(stage_name.clone());
+
+                AppCommandRecord::PushStageToWork { stage_name, index }
+            }
+            AppCommand::PushStageToWork { stage } => {
+                let stage_name = String::from(stage.name());
+                self.stages_to_enter.push(stage_name.clone());
+                let mut busy_stages = self.busy_stages.borrow_mut();
+                busy_stages.push(stage);
+
+                AppCommandRecord::PushStageToWork {
+                    stage_name,
+                    index: busy_stages.len() - 1,
+                }
+            }
+            AppCommand::PushStageToWorkAfter {
+                stage,
+                before_stage_name,
+            } => {
+                let index =
+                    fuck_borrow_checker(&self.busy_stages.borrow(), before_stage_name.as_str());
+                let stage_name = String::from(stage.name());
+                self.busy_stages.borrow_mut().insert(index + 1, stage);
+                self.stages_to_enter.push(stage_name.clone());
+
+                AppCommandRecord::PushStageToWork {
+                    stage_name,
+                    index: index + 1,
+                }
+            }
+            AppCommand::MakeBusyStageToRest { stage_name } => {
+                let index = fuck_borrow_checker(&self.busy_stages.borrow(), stage_name.as_str());
+                let stage = self.busy_stages.borrow_mut().remove(index);
+                self.spare_stages.push(stage);
+
+                AppCommandRecord::MakeBusyStageToRest { stage_name, index }
+            }
+            AppCommand::SwapStages {
+                stage_name_a,
+                stage_name_b,
+            } => {
+                let index_a =
+                    fuck_borrow_checker(&self.busy_stages.borrow(), stage_name_a.as_str());
+                let index_b =
+                    fuck_borrow_checker(&self.busy_stages.borrow(), stage_name_b.as_str());
+                self.busy_stages.borrow_mut().swap(index_a, index_b);
+
+                AppCommandRecord::SwapStages {
+                    stage_name_a,
+                    stage_name_b,
+                }
+            }
+            AppCommand::MoveStageToIndex { stage_name, index } => {
+                let from = fuck_borrow_checker(&self.busy_stages.borrow(), stage_name.as_str());
+                let mut busy_stages = self.busy_stages.borrow_mut();
+                let stage = busy_stages.remove(from);
+                let index = std::cmp::min(index, busy_stages.len());
+                busy_stages.insert(index, stage);
+
+                AppCommandRecord::MoveStageToIndex {
+                    stage_name,
+                    from,
+                    index,
+                }
+            }
+            AppCommand::ResetStage { stage_name } => {
+                self.stages_to_reset.push(stage_name.clone());
+
+                AppCommandRecord::ResetStage { stage_name }
+            }
+            AppCommand::InsertResource {
+                data,
+                type_id: _,
+                type_name,
+                insert,
+            } => {
+                self.resources_to_insert.push((data, insert));
+
+                AppCommandRecord::InsertResource { type_name }
+            }
+            AppCommand::Undo(cmd) => self.apply_command(*cmd),
+            AppCommand::AppQuit => AppCommandRecord::AppQuit,
+        }
+    }
+
+    fn record(&mut self, command: AppCommandRecord) {
+        if self.history_capacity == 0 {
+            return;
+        }
+
+        if self.history.len() == self.history_capacity {
+            self.history.pop_front();
+        }
+
+        self.history.push_back(AppliedAppCommand {
+            frame: self.frame,
+            timestamp: Instant::now(),
+            command,
+        });
+    }
+
+    fn take_stages_to_reset(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.stages_to_reset)
+    }
+
+    fn take_resources_to_insert(&mut self) -> Vec<(Box<dyn Any + Send + Sync>, InsertResourceFn)> {
+        std::mem::take(&mut self.resources_to_insert)
+    }
+
+    fn take_stages_to_enter(&mut self) -> Vec<String> {
+        std::mem::take(&mut self.stages_to_enter)
+    }
+
+    /// The busy stages which are going to be made to rest by the pending commands.
+    fn stages_to_exit(&self) -> Vec<String> {
+        self.commands
+            .iter()
+            .filter_map(|cmd| cmd.resting_stage())
+            .map(String::from)
+            .collect()
+    }
+
+    pub fn busy_stage<'a>(&'a self, stage_name: &str) -> Option<&'a AppStage> {
+        let stages: &'a Vec<AppStage> = unsafe {
+            // TODO: write safety words
+            &self.busy_stages.try_borrow_unguarded().unwrap()
+        };
+
+        stages.iter().find(|stage| stage.name() == stage_name)
+    }
+
+    pub fn busy_stage_iter<'a>(&'a self) -> Iter<'a, AppStage> {
+        let stages: &'a Vec<AppStage> = unsafe {
+            // TODO: write safety words
+            &self.busy_stages.try_borrow_unguarded().unwrap()
+        };
+
+        stages.iter()
+    }
+
+    pub fn spare_stage(&self, stage_name: &str) -> Option<&AppStage> {
+        self.spare_stages
+            .iter()
+            .find(|stage| stage.name() == stage_name)
+    }
+
+    pub fn spare_stage_iter(&self) -> Iter<AppStage> {
+        self.spare_stages.iter()
+    }
+
+    pub fn spare_stage_mut(&mut self, stage_name: &str) -> Option<&mut AppStage> {
+        self.spare_stages
+            .iter_mut()
+            .find(|stage| stage.name() == stage_name)
     }
 
     pub fn spare_stage_iter_mut(&mut self) -> IterMut<AppStage> {
@@ -509,6 +2154,30 @@ impl AppSettings {
         }
     }
 
+    pub fn busy_stage_names(&self) -> Vec<&str> {
+        self.busy_stage_iter().map(|stage| stage.name()).collect()
+    }
+
+    pub fn spare_stage_names(&self) -> Vec<&str> {
+        self.spare_stage_iter().map(|stage| stage.name()).collect()
+    }
+
+    /// The names of busy stages followed by the names of spare stages.
+    pub fn all_stage_names(&self) -> Vec<&str> {
+        self.busy_stage_iter()
+            .chain(self.spare_stage_iter())
+            .map(|stage| stage.name())
+            .collect()
+    }
+
+    pub fn busy_stage_count(&self) -> usize {
+        self.busy_stages.borrow().len()
+    }
+
+    pub fn spare_stage_count(&self) -> usize {
+        self.spare_stages.len()
+    }
+
     pub fn is_in_busy(&self, stage_name: &str) -> bool {
         self.busy_stages
             .borrow()
@@ -608,7 +2277,13 @@ impl AppSettings {
         stage_name: &'a str,
         after_stage_name: &'a str,
     ) -> Result<(), AppSettingsError<'a>> {
-        if let Some(stage) = self.take_spare_stage(stage_name) {
+        // NOTE: check before taking the stage, or it's lost from spare in the error.
+        if !self.is_in_busy(after_stage_name) {
+            Err(AppSettingsError::StageNotExistInBusy(
+                after_stage_name,
+                None,
+            ))
+        } else if let Some(stage) = self.take_spare_stage(stage_name) {
             self.push_stage_to_work_before(stage, after_stage_name)
         } else {
             Err(AppSettingsError::StageNotExistInSpare(stage_name, None))
@@ -631,7 +2306,13 @@ impl AppSettings {
         stage_name: &'a str,
         before_stage_name: &'a str,
     ) -> Result<(), AppSettingsError<'a>> {
-        if let Some(stage) = self.take_spare_stage(stage_name) {
+        // NOTE: check before taking the stage, or it's lost from spare in the error.
+        if !self.is_in_busy(before_stage_name) {
+            Err(AppSettingsError::StageNotExistInBusy(
+                before_stage_name,
+                None,
+            ))
+        } else if let Some(stage) = self.take_spare_stage(stage_name) {
             self.push_stage_to_work_after(stage, before_stage_name)
         } else {
             Err(AppSettingsError::StageNotExistInSpare(stage_name, None))
@@ -665,9 +2346,264 @@ impl AppSettings {
         }
     }
 
+    /// Swap the positions of two busy stages in the execution order.
+    ///
+    /// The swap is deferred and takes effect at the start of next frame.
+    pub fn swap_stages<'a>(
+        &mut self,
+        stage_name_a: &'a str,
+        stage_name_b: &'a str,
+    ) -> Result<(), AppSettingsError<'a>> {
+        self.check_stage_movable(stage_name_a)?;
+        self.check_stage_movable(stage_name_b)?;
+
+        self.commands.push(AppCommand::SwapStages {
+            stage_name_a: String::from(stage_name_a),
+            stage_name_b: String::from(stage_name_b),
+        });
+
+        Ok(())
+    }
+
+    /// Move the busy stage to `index` of the execution order.
+    ///
+    /// The move is deferred and takes effect at the start of next frame.
+    pub fn move_stage_to_index<'a>(
+        &mut self,
+        stage_name: &'a str,
+        index: usize,
+    ) -> Result<(), AppSettingsError<'a>> {
+        self.check_stage_movable(stage_name)?;
+
+        let busy_count = self.busy_stages.borrow().len();
+        if index < busy_count {
+            self.commands.push(AppCommand::MoveStageToIndex {
+                stage_name: String::from(stage_name),
+                index,
+            });
+
+            Ok(())
+        } else {
+            Err(AppSettingsError::IndexOutOfRange(index))
+        }
+    }
+
+    /// The names of busy stages in the execution order, without the pending commands applied.
+    pub fn busy_stage_order(&self) -> Vec<String> {
+        self.busy_stage_names()
+            .into_iter()
+            .map(String::from)
+            .collect()
+    }
+
+    /// Reorder all busy stages to `order`, which must have every busy stage exactly once.
+    ///
+    /// The reorder is deferred and takes effect at the start of next frame.
+    pub fn set_busy_stage_order<'a>(
+        &mut self,
+        order: Vec<&'a str>,
+    ) -> Result<(), AppSettingsError<'a>> {
+        for &stage_name in order.iter() {
+            self.check_stage_movable(stage_name)?;
+        }
+
+        let unique = order.iter().collect::<HashSet<_>>().len();
+        if unique != order.len() || order.len() != self.busy_stages.borrow().len() {
+            return Err(AppSettingsError::InvalidStageOrder);
+        }
+
+        // NOTE: moving the stages one by one from the front, the stages before are already in place.
+        self.commands
+            .extend(order.into_iter().enumerate().map(|(index, stage_name)| {
+                AppCommand::MoveStageToIndex {
+                    stage_name: String::from(stage_name),
+                    index,
+                }
+            }));
+
+        Ok(())
+    }
+
+    /// Run the startup systems of the busy stage again, e.g. to load a new level.
+    ///
+    /// The reset is deferred and takes effect at the start of next frame, before the stages play.
+    ///
+    /// NOTE: the entities and resources created by the last startup are still there, clean them up
+    /// before resetting, e.g. despawn the level entities in a system and then reset the stage.
+    pub fn reset_stage<'a>(&mut self, stage_name: &'a str) -> Result<(), AppSettingsError<'a>> {
+        if self.is_in_busy(stage_name) {
+            self.commands.push(AppCommand::ResetStage {
+                stage_name: String::from(stage_name),
+            });
+
+            Ok(())
+        } else {
+            Err(AppSettingsError::StageNotExistInBusy(stage_name, None))
+        }
+    }
+
+    /// Reset all busy stages in the execution order, see `AppSettings::reset_stage`.
+    pub fn reset_all_stages(&mut self) {
+        let stage_names = self
+            .busy_stage_names()
+            .into_iter()
+            .map(String::from)
+            .collect::<Vec<_>>();
+
+        for stage_name in stage_names {
+            self.commands.push(AppCommand::ResetStage { stage_name });
+        }
+    }
+
+    /// Insert the resource at the start of next frame, it replaces the resource of the same type.
+    ///
+    /// It's inserted before the enter and startup schedules of next frame run, so the stages pushed to
+    /// work in this frame can use it.
+    pub fn defer_insert_resource<T: Resource + Send + Sync>(&mut self, value: T) {
+        self.commands.push(AppCommand::InsertResource {
+            data: Box::new(value),
+            type_id: TypeId::of::<T>(),
+            type_name: any::type_name::<T>(),
+            insert: insert_boxed_resource::<T>,
+        });
+    }
+
+    /// Quit at the start of next frame, it can't be cancelled.
     pub fn quit(&mut self) {
         self.commands.push(AppCommand::AppQuit);
     }
+
+    /// Quit at the start of next frame, when the settings are applied, unless a system calls
+    /// `AppSettings::cancel_quit` before that.
+    ///
+    /// The queued commands are applied before quitting.
+    pub fn request_quit(&mut self) {
+        self.quit_requested = true;
+    }
+
+    /// Cancel the quit requested by `AppSettings::request_quit`, `AppSettings::quit` is not affected.
+    pub fn cancel_quit(&mut self) {
+        self.quit_requested = false;
+    }
+
+    pub fn is_quit_requested(&self) -> bool {
+        self.quit_requested
+    }
+
+    /// The count of commands queued in this frame, they are applied at the start of next frame.
+    pub fn pending_command_count(&self) -> usize {
+        self.commands.len()
+    }
+
+    /// Discard all queued commands, the stages held by the commands go back to spare stages.
+    pub fn clear_pending_commands(&mut self) {
+        for cmd in std::mem::take(&mut self.commands) {
+            if let Some(stage) = cmd.into_stage() {
+                self.spare_stages.push(stage);
+            }
+        }
+    }
+
+    /// Inspect the queued commands in the order they will be applied.
+    pub fn commands_iter(&self) -> impl Iterator<Item = AppCommandView<'_>> {
+        self.commands.iter().map(AppCommand::view)
+    }
+
+    /// The last applied commands with the frame they are applied in, the oldest first.
+    pub fn command_history(&self) -> &VecDeque<AppliedAppCommand> {
+        &self.history
+    }
+
+    /// Keep at most `capacity` applied commands in the history, 0 disables the history.
+    pub fn set_history_capacity(&mut self, capacity: usize) {
+        self.history_capacity = capacity;
+
+        if self.history.len() > capacity {
+            self.history.drain(..self.history.len() - capacity);
+        }
+    }
+
+    pub fn history_capacity(&self) -> usize {
+        self.history_capacity
+    }
+
+    /// Revert the last applied command in the history, the revert is deferred like the other commands.
+    ///
+    /// Pushing, resting, swapping and moving stages are reversible. The record is removed from the history
+    /// even if it can't be reverted, and is given back in the error.
+    pub fn undo_last_command(&mut self) -> Result<(), AppSettingsError> {
+        let applied = self
+            .history
+            .pop_back()
+            .ok_or(AppSettingsError::NoCommandToUndo)?;
+
+        let undo = match &applied.command {
+            AppCommandRecord::PushStageToWork { stage_name, .. } if self.is_movable(stage_name) => {
+                Some(vec![AppCommand::MakeBusyStageToRest {
+                    stage_name: stage_name.clone(),
+                }])
+            }
+            AppCommandRecord::MakeBusyStageToRest { stage_name, index }
+                if self.is_in_spare(stage_name) && !self.is_in_busy(stage_name) =>
+            {
+                let stage = self.take_spare_stage(stage_name).unwrap();
+
+                Some(vec![
+                    AppCommand::PushStageToWork { stage },
+                    AppCommand::MoveStageToIndex {
+                        stage_name: stage_name.clone(),
+                        index: *index,
+                    },
+                ])
+            }
+            AppCommandRecord::SwapStages {
+                stage_name_a,
+                stage_name_b,
+            } if self.is_movable(stage_name_a) && self.is_movable(stage_name_b) => {
+                Some(vec![AppCommand::SwapStages {
+                    stage_name_a: stage_name_a.clone(),
+                    stage_name_b: stage_name_b.clone(),
+                }])
+            }
+            AppCommandRecord::MoveStageToIndex {
+                stage_name, from, ..
+            } if self.is_movable(stage_name) => Some(vec![AppCommand::MoveStageToIndex {
+                stage_name: stage_name.clone(),
+                index: *from,
+            }]),
+            _ => None,
+        };
+
+        match undo {
+            Some(undo) => {
+                self.commands
+                    .extend(undo.into_iter().map(|cmd| AppCommand::Undo(Box::new(cmd))));
+
+                Ok(())
+            }
+            None => Err(AppSettingsError::CommandNotReversible(applied)),
+        }
+    }
+
+    fn is_movable(&self, stage_name: &str) -> bool {
+        self.check_stage_movable(stage_name).is_ok()
+    }
+
+    /// Check the stage is in busy and not referenced by any pending command of this frame.
+    fn check_stage_movable<'a>(&self, stage_name: &'a str) -> Result<(), AppSettingsError<'a>> {
+        if !self.is_in_busy(stage_name) {
+            Err(AppSettingsError::StageNotExistInBusy(stage_name, None))
+        } else if self.is_in_pending(stage_name) {
+            Err(AppSettingsError::StageInPending(stage_name))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Whether the stage is going to be pushed to work or made to rest by a pending command.
+    fn is_in_pending(&self, stage_name: &str) -> bool {
+        self.commands.iter().any(|cmd| cmd.is_pending(stage_name))
+    }
 }
 
 impl fmt::Debug for AppSettings {
@@ -676,10 +2612,21 @@ impl fmt::Debug for AppSettings {
             .field("busy_stages", &RefCell::borrow(&self.busy_stages))
             .field("spare_stages", &self.spare_stages)
             .field("commands", &self.commands)
+            .field("history", &self.history)
             .finish()
     }
 }
 
+// Downcast the data to the type it's created from and insert it.
+type InsertResourceFn = fn(Box<dyn Any + Send + Sync>, &mut Resources);
+
+fn insert_boxed_resource<T: Resource + Send + Sync>(
+    data: Box<dyn Any + Send + Sync>,
+    resources: &mut Resources,
+) {
+    resources.insert(*data.downcast::<T>().unwrap());
+}
+
 #[derive(Debug)]
 enum AppCommand {
     PushStageToWorkBefore {
@@ -696,14 +2643,1080 @@ enum AppCommand {
     MakeBusyStageToRest {
         stage_name: String,
     },
+    SwapStages {
+        stage_name_a: String,
+        stage_name_b: String,
+    },
+    MoveStageToIndex {
+        stage_name: String,
+        index: usize,
+    },
+    ResetStage {
+        stage_name: String,
+    },
+    // NOTE: `Resources` can't insert by `TypeId`, so the command keeps the typed insert function too.
+    InsertResource {
+        data: Box<dyn Any + Send + Sync>,
+        type_id: TypeId,
+        type_name: &'static str,
+        insert: InsertResourceFn,
+    },
+    // The command queued by `AppSettings::undo_last_command`, it's not recorded in the history.
+    Undo(Box<AppCommand>),
+    AppQuit,
+}
+
+impl AppCommand {
+    fn view(&self) -> AppCommandView<'_> {
+        match self {
+            AppCommand::PushStageToWorkBefore {
+                stage,
+                after_stage_name,
+            } => AppCommandView::PushStageToWorkBefore {
+                stage,
+                after_stage_name,
+            },
+            AppCommand::PushStageToWork { stage } => AppCommandView::PushStageToWork { stage },
+            AppCommand::PushStageToWorkAfter {
+                stage,
+                before_stage_name,
+            } => AppCommandView::PushStageToWorkAfter {
+                stage,
+                before_stage_name,
+            },
+            AppCommand::MakeBusyStageToRest { stage_name } => {
+                AppCommandView::MakeBusyStageToRest { stage_name }
+            }
+            AppCommand::SwapStages {
+                stage_name_a,
+                stage_name_b,
+            } => AppCommandView::SwapStages {
+                stage_name_a,
+                stage_name_b,
+            },
+            AppCommand::MoveStageToIndex { stage_name, index } => {
+                AppCommandView::MoveStageToIndex {
+                    stage_name,
+                    index: *index,
+                }
+            }
+            AppCommand::ResetStage { stage_name } => AppCommandView::ResetStage { stage_name },
+            AppCommand::InsertResource {
+                type_id, type_name, ..
+            } => AppCommandView::InsertResource {
+                type_id: *type_id,
+                type_name,
+            },
+            AppCommand::Undo(cmd) => cmd.view(),
+            AppCommand::AppQuit => AppCommandView::AppQuit,
+        }
+    }
+
+    /// Whether the stage is going to be pushed to work or made to rest by the command.
+    fn is_pending(&self, stage_name: &str) -> bool {
+        match self {
+            AppCommand::PushStageToWorkBefore { stage, .. }
+            | AppCommand::PushStageToWork { stage }
+            | AppCommand::PushStageToWorkAfter { stage, .. } => stage.name() == stage_name,
+            AppCommand::MakeBusyStageToRest { stage_name: name } => name == stage_name,
+            AppCommand::Undo(cmd) => cmd.is_pending(stage_name),
+            _ => false,
+        }
+    }
+
+    /// The name of stage made to rest by the command.
+    fn resting_stage(&self) -> Option<&str> {
+        match self {
+            AppCommand::MakeBusyStageToRest { stage_name } => Some(stage_name),
+            AppCommand::Undo(cmd) => cmd.resting_stage(),
+            _ => None,
+        }
+    }
+
+    /// The stage held by the command.
+    fn into_stage(self) -> Option<AppStage> {
+        match self {
+            AppCommand::PushStageToWorkBefore { stage, .. }
+            | AppCommand::PushStageToWork { stage }
+            | AppCommand::PushStageToWorkAfter { stage, .. } => Some(stage),
+            AppCommand::Undo(cmd) => cmd.into_stage(),
+            _ => None,
+        }
+    }
+}
+
+/// What an applied command did, the stages are referenced by name.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AppCommandRecord {
+    /// The stage was inserted to `index` of the execution order.
+    PushStageToWork {
+        stage_name: String,
+        index: usize,
+    },
+    /// The stage was removed from `index` of the execution order.
+    MakeBusyStageToRest {
+        stage_name: String,
+        index: usize,
+    },
+    SwapStages {
+        stage_name_a: String,
+        stage_name_b: String,
+    },
+    MoveStageToIndex {
+        stage_name: String,
+        from: usize,
+        index: usize,
+    },
+    ResetStage {
+        stage_name: String,
+    },
+    /// The resource was deferred to insert at the start of next frame, e.g. `my_game::Score`.
+    InsertResource {
+        type_name: &'static str,
+    },
+    AppQuit,
+}
+
+/// A command applied by `AppSettings`, kept in `AppSettings::command_history`.
+#[derive(Debug, Clone)]
+pub struct AppliedAppCommand {
+    /// The frame the command was applied in, the first frame is 1.
+    pub frame: u64,
+    pub timestamp: Instant,
+    pub command: AppCommandRecord,
+}
+
+/// A read-only view of the command queued in `AppSettings`.
+#[derive(Debug, Clone, Copy)]
+pub enum AppCommandView<'a> {
+    PushStageToWorkBefore {
+        stage: &'a AppStage,
+        after_stage_name: &'a str,
+    },
+    PushStageToWork {
+        stage: &'a AppStage,
+    },
+    PushStageToWorkAfter {
+        stage: &'a AppStage,
+        before_stage_name: &'a str,
+    },
+    MakeBusyStageToRest {
+        stage_name: &'a str,
+    },
+    SwapStages {
+        stage_name_a: &'a str,
+        stage_name_b: &'a str,
+    },
+    MoveStageToIndex {
+        stage_name: &'a str,
+        index: usize,
+    },
+    ResetStage {
+        stage_name: &'a str,
+    },
+    InsertResource {
+        type_id: TypeId,
+        type_name: &'a str,
+    },
     AppQuit,
 }
 
+/// The error of `AppSettings`, the stage passed in is given back if it's not used.
 #[derive(Debug)]
 pub enum AppSettingsError<'a> {
     DuplicateNameInBusy(AppStage),
     DuplicateNameInSpare(AppStage),
     StageNotExist(&'a str),
+    /// The name of the missing stage, and the stage which was going to work with it.
     StageNotExistInBusy(&'a str, Option<AppStage>),
+    /// The name of the missing stage, and the stage which was going to work with it.
     StageNotExistInSpare(&'a str, Option<AppStage>),
+    StageInPending(&'a str),
+    IndexOutOfRange(usize),
+    /// The stage order doesn't have every busy stage exactly once.
+    InvalidStageOrder,
+    NoCommandToUndo,
+    CommandNotReversible(AppliedAppCommand),
+}
+
+impl<'a> fmt::Display for AppSettingsError<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppSettingsError::DuplicateNameInBusy(stage) => {
+                write!(f, "stage `{}` already exists in busy", stage.name())
+            }
+            AppSettingsError::DuplicateNameInSpare(stage) => {
+                write!(f, "stage `{}` already exists in spare", stage.name())
+            }
+            AppSettingsError::StageNotExist(name) => write!(f, "stage `{}` doesn't exist", name),
+            AppSettingsError::StageNotExistInBusy(name, _) => {
+                write!(f, "stage `{}` doesn't exist in busy", name)
+            }
+            AppSettingsError::StageNotExistInSpare(name, _) => {
+                write!(f, "stage `{}` doesn't exist in spare", name)
+            }
+            AppSettingsError::StageInPending(name) => {
+                write!(f, "stage `{}` has a pending command", name)
+            }
+            AppSettingsError::IndexOutOfRange(index) => {
+                write!(f, "index {} is out of range of busy stages", index)
+            }
+            AppSettingsError::InvalidStageOrder => {
+                write!(f, "the order must have every busy stage exactly once")
+            }
+            AppSettingsError::NoCommandToUndo => write!(f, "no command to undo"),
+            AppSettingsError::CommandNotReversible(applied) => write!(
+                f,
+                "command {:?} of frame {} can't be undone",
+                applied.command, applied.frame
+            ),
+        }
+    }
+}
+
+// NOTE: the errors are raised by `AppSettings` itself, so there is no underlying source.
+impl<'a> Error for AppSettingsError<'a> {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        assets::Assets,
+        audio::{AudioClip, AudioInstance},
+        components::transform::Transform2D,
+        legion::IntoQuery,
+        nalgebra::Vector2,
+    };
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    struct Counter(u32);
+
+    // The `Time::record_count` of the frames the process schedule executed.
+    #[derive(Debug, Default)]
+    struct PlayedFrames(Vec<u64>);
+
+    // A stage which takes 5ms a frame, over its budget of 1ms.
+    fn slow_stage(policy: BudgetPolicy) -> TestApp {
+        AppBuilder::new()
+            .create_stage_builder("slow")
+            .unwrap()
+            .max_cpu_budget_ms(1.0)
+            .budget_policy(policy)
+            .add_resource(PlayedFrames::default())
+            .add_thread_local_fn_process(|_, resources| {
+                let record_count = resources.get::<Time>().unwrap().record_count();
+                resources
+                    .get_mut::<PlayedFrames>()
+                    .unwrap()
+                    .0
+                    .push(record_count);
+
+                std::thread::sleep(std::time::Duration::from_millis(5));
+            })
+            .into_app_builder()
+            .build_for_test()
+    }
+
+    fn exceeded_count(app: &TestApp) -> usize {
+        app.resources()
+            .get::<Events<StageBudgetExceeded>>()
+            .unwrap()
+            .len()
+    }
+
+    #[test]
+    fn skip_stage_over_budget() {
+        let mut app = slow_stage(BudgetPolicy::Skip);
+
+        app.tick();
+        assert_eq!(exceeded_count(&app), 0);
+
+        app.tick();
+        assert_eq!(app.resources().get::<PlayedFrames>().unwrap().0.len(), 1);
+        let events = app
+            .resources()
+            .get::<Events<StageBudgetExceeded>>()
+            .unwrap();
+        let event = events.iter().next().unwrap();
+        assert_eq!(event.0, "slow");
+        assert!(event.1 >= 5.0);
+        drop(events);
+
+        // the stage runs again, the skipped frame didn't tick the time.
+        app.tick();
+        let played = app.resources().get::<PlayedFrames>().unwrap();
+        assert_eq!(played.0.len(), 2);
+        assert_eq!(played.0[1], played.0[0] + 1);
+        drop(played);
+
+        let metrics = app
+            .resources()
+            .get::<HashMap<String, StageMetrics>>()
+            .unwrap();
+        assert_eq!(metrics["slow"].skipped_ticks, 1);
+        assert_eq!(metrics["slow"].executed_ticks(), 2);
+    }
+
+    #[test]
+    fn warn_or_run_anyway_over_budget() {
+        let mut app = slow_stage(BudgetPolicy::Warn);
+        app.tick_n(2);
+        assert_eq!(app.resources().get::<PlayedFrames>().unwrap().0.len(), 2);
+        assert_eq!(exceeded_count(&app), 1);
+
+        let mut app = slow_stage(BudgetPolicy::RunAnyway);
+        app.tick_n(2);
+        assert_eq!(app.resources().get::<PlayedFrames>().unwrap().0.len(), 2);
+        assert_eq!(exceeded_count(&app), 0);
+    }
+
+    #[test]
+    fn undo_stage_commands() {
+        let busy_stages = Rc::new(RefCell::new(vec![
+            AppStageBuilder::new("a").build(),
+            AppStageBuilder::new("b").build(),
+        ]));
+        let mut settings = AppSettings::new(&busy_stages);
+
+        let busy_names = |settings: &AppSettings| {
+            settings
+                .busy_stage_names()
+                .into_iter()
+                .map(String::from)
+                .collect::<Vec<_>>()
+        };
+
+        settings.make_busy_stage_rest("a").unwrap();
+        assert!(!settings.apply());
+        assert_eq!(busy_names(&settings), vec!["b"]);
+        assert_eq!(settings.command_history().len(), 1);
+
+        settings.undo_last_command().unwrap();
+        assert!(!settings.apply());
+        assert_eq!(busy_names(&settings), vec!["a", "b"]);
+
+        assert!(settings.command_history().is_empty());
+        assert!(matches!(
+            settings.undo_last_command(),
+            Err(AppSettingsError::NoCommandToUndo)
+        ));
+    }
+
+    #[test]
+    fn keep_last_commands_in_history() {
+        let busy_stages = Rc::new(RefCell::new(vec![
+            AppStageBuilder::new("a").build(),
+            AppStageBuilder::new("b").build(),
+        ]));
+        let mut settings = AppSettings::new(&busy_stages).with_history_capacity(2);
+        assert_eq!(settings.history_capacity(), 2);
+
+        for _ in 0..3 {
+            settings.swap_stages("a", "b").unwrap();
+            assert!(!settings.apply());
+        }
+
+        // the oldest record is dropped, the frames are 2 and 3.
+        let frames = settings
+            .command_history()
+            .iter()
+            .map(|applied| applied.frame)
+            .collect::<Vec<_>>();
+        assert_eq!(frames, vec![2, 3]);
+
+        settings.set_history_capacity(1);
+        assert_eq!(settings.command_history().len(), 1);
+        assert_eq!(settings.command_history().back().unwrap().frame, 3);
+
+        settings.set_history_capacity(0);
+        settings.swap_stages("a", "b").unwrap();
+        assert!(!settings.apply());
+        assert!(settings.command_history().is_empty());
+    }
+
+    #[test]
+    fn test_app_ticks_and_resets() {
+        let mut app = AppBuilder::new()
+            .create_stage_builder("logic")
+            .unwrap()
+            .add_thread_local_fn_startup(|world, _| {
+                world.push((Counter(0),));
+            })
+            .add_thread_local_fn_process(|world, _| {
+                for counter in <&mut Counter>::query().iter_mut(world) {
+                    counter.0 += 1;
+                }
+            })
+            .into_app_builder()
+            .build_for_test();
+
+        app.tick_n(3);
+        let counters = <&Counter>::query()
+            .iter(app.world())
+            .copied()
+            .collect::<Vec<_>>();
+        assert_eq!(counters, vec![Counter(3)]);
+
+        // the startup runs again at the start of next frame, before the stage plays.
+        app.resources_mut()
+            .get_mut::<AppSettings>()
+            .unwrap()
+            .reset_stage("logic")
+            .unwrap();
+        app.tick();
+        assert_eq!(<&Counter>::query().iter(app.world()).count(), 2);
+
+        app.resources_mut().get_mut::<AppSettings>().unwrap().quit();
+        app.tick_n(2);
+        assert!(app.is_quit());
+    }
+
+    #[test]
+    fn transfer_entity_between_stages() {
+        let mut app = AppBuilder::new()
+            .create_stage_builder("menu")
+            .unwrap()
+            .with_own_world()
+            .add_thread_local_fn_startup(|world, _| {
+                world.push((Transform2D::at(1.0, 2.0),));
+                world.push((Transform2D::at(3.0, 4.0), Counter(0)));
+            })
+            .add_thread_local_fn_process(|world, resources| {
+                let mut transfers = resources.get_mut::<EntityTransfers>().unwrap();
+                for entity in <Entity>::query().iter(world) {
+                    transfers.request(*entity, "game");
+                }
+            })
+            .into_app_builder()
+            .create_stage_builder("game")
+            .unwrap()
+            .into_app_builder()
+            .build_for_test();
+
+        app.tick();
+
+        // the entity with `Counter` is not moved, `Counter` is not registered.
+        let transfers = app.resources().get::<EntityTransfers>().unwrap();
+        let (moved, failed): (Vec<_>, Vec<_>) =
+            transfers.results().partition(|(_, result)| result.is_ok());
+        assert_eq!(moved.len(), 1);
+        assert!(matches!(
+            failed[0].1,
+            Err(TransferError::UnregisteredComponents(_))
+        ));
+        drop(transfers);
+
+        let positions = <&Transform2D>::query()
+            .iter(app.world())
+            .map(|transform| transform.position)
+            .collect::<Vec<_>>();
+        assert_eq!(positions, vec![Vector2::new(1.0, 2.0)]);
+    }
+
+    #[test]
+    fn lend_input_event_queue_to_own_world() {
+        use crate::input::{InputEvent, KeyCode};
+        use std::sync::{Arc, Mutex};
+
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let received_in_stage = received.clone();
+
+        let mut app = AppBuilder::new()
+            .create_stage_builder("menu")
+            .unwrap()
+            .with_own_world()
+            .add_thread_local_fn_process(move |_, resources| {
+                let queue = resources.get::<InputEventQueue>().unwrap();
+                received_in_stage
+                    .lock()
+                    .unwrap()
+                    .extend(queue.iter().copied());
+            })
+            .into_app_builder()
+            .build_for_test();
+
+        app.inject_event(SyntheticWindowEvent::KeyDown(KeyCode::Space));
+        app.inject_event(SyntheticWindowEvent::KeyUp(KeyCode::Space));
+        app.tick_n(2);
+
+        assert_eq!(
+            *received.lock().unwrap(),
+            vec![
+                InputEvent::KeyDown(KeyCode::Space),
+                InputEvent::KeyUp(KeyCode::Space),
+            ]
+        );
+        // the queue is moved back to the shared resources after the stage.
+        assert!(app.resources().contains::<InputEventQueue>());
+    }
+
+    #[test]
+    fn drain_audio_every_frame() {
+        let mut app = AppBuilder::new()
+            .create_stage_builder("default")
+            .unwrap()
+            .add_resource(Vec::<AudioInstance>::new())
+            .add_thread_local_fn_process(|_, resources| {
+                let clip = Assets::new().add(AudioClip::new(44100, 1, vec![]));
+                let instance = resources.get_mut::<Audio>().unwrap().play(clip);
+                resources
+                    .get_mut::<Vec<AudioInstance>>()
+                    .unwrap()
+                    .push(instance);
+            })
+            .into_app_builder()
+            .build_for_test();
+
+        app.tick_n(3);
+
+        // the empty sounds are drained and finished in the frames they are played.
+        let audio = app.resources().get::<Audio>().unwrap();
+        let instances = app.resources().get::<Vec<AudioInstance>>().unwrap();
+        assert!(audio.pending_commands().is_empty());
+        assert_eq!(instances.len(), 3);
+        assert!(instances
+            .iter()
+            .all(|instance| !audio.is_playing(*instance)));
+    }
+
+    #[test]
+    fn request_and_cancel_quit() {
+        #[derive(Debug, Default)]
+        struct Dialog {
+            close_pressed: bool,
+            keep_running: bool,
+        }
+
+        let mut app = AppBuilder::new()
+            .create_stage_builder("game")
+            .unwrap()
+            .add_thread_local_fn_process(|_, resources| {
+                if resources.get::<Dialog>().unwrap().close_pressed {
+                    resources.get_mut::<AppSettings>().unwrap().request_quit();
+                }
+            })
+            .add_thread_local_fn_process(|_, resources| {
+                if resources.get::<Dialog>().unwrap().keep_running {
+                    resources.get_mut::<AppSettings>().unwrap().cancel_quit();
+                }
+            })
+            .into_app_builder()
+            .build_for_test();
+        app.resources_mut().insert(Dialog {
+            close_pressed: true,
+            keep_running: true,
+        });
+
+        app.tick_n(2);
+        assert!(!app.is_quit());
+        assert!(!app
+            .resources()
+            .get::<AppSettings>()
+            .unwrap()
+            .is_quit_requested());
+
+        app.resources_mut()
+            .get_mut::<Dialog>()
+            .unwrap()
+            .keep_running = false;
+        app.tick();
+        assert!(!app.is_quit());
+        assert!(app
+            .resources()
+            .get::<AppSettings>()
+            .unwrap()
+            .is_quit_requested());
+
+        app.tick();
+        assert!(app.is_quit());
+    }
+
+    #[test]
+    fn defer_insert_resource() {
+        #[derive(Debug, PartialEq)]
+        struct Score(u32);
+
+        let mut app = AppBuilder::new()
+            .create_stage_builder("game")
+            .unwrap()
+            .add_thread_local_fn_process(|_, resources| {
+                let score = resources.get::<Score>().map(|score| score.0);
+                resources
+                    .get_mut::<AppSettings>()
+                    .unwrap()
+                    .defer_insert_resource(Score(score.unwrap_or(0) + 1));
+            })
+            .into_app_builder()
+            .build_for_test();
+
+        app.tick();
+        assert!(app.resources().get::<Score>().is_none());
+
+        app.tick_n(2);
+        assert_eq!(*app.resources().get::<Score>().unwrap(), Score(2));
+
+        let settings = app.resources().get::<AppSettings>().unwrap();
+        assert!(matches!(
+            settings.command_history().back().unwrap().command,
+            AppCommandRecord::InsertResource { type_name } if type_name.ends_with("Score")
+        ));
+    }
+
+    #[test]
+    fn inject_input_events() {
+        use crate::input::KeyCode;
+
+        #[derive(Debug, Default)]
+        struct Jumps(u32);
+
+        let mut app = AppBuilder::new()
+            .create_stage_builder("logic")
+            .unwrap()
+            .add_resource(Jumps::default())
+            .add_thread_local_fn_process(|_, resources| {
+                if resources
+                    .get::<Input>()
+                    .unwrap()
+                    .keyboard
+                    .just_pressed(KeyCode::Space)
+                {
+                    resources.get_mut::<Jumps>().unwrap().0 += 1;
+                }
+            })
+            .into_app_builder()
+            .build_for_test();
+
+        app.inject_event(SyntheticWindowEvent::KeyDown(KeyCode::Space));
+        app.tick_n(3);
+        assert_eq!(app.resources().get::<Jumps>().unwrap().0, 1);
+        assert!(app
+            .resources()
+            .get::<Input>()
+            .unwrap()
+            .keyboard
+            .pressed(KeyCode::Space));
+
+        app.inject_event(SyntheticWindowEvent::KeyUp(KeyCode::Space));
+        app.inject_event(SyntheticWindowEvent::KeyDown(KeyCode::Space));
+        app.tick();
+        assert_eq!(app.resources().get::<Jumps>().unwrap().0, 2);
+    }
+
+    #[test]
+    fn inject_resize() {
+        use crate::input::KeyCode;
+
+        let mut app = AppBuilder::new().build_for_test();
+
+        app.inject_event(SyntheticWindowEvent::KeyDown(KeyCode::Space));
+        app.tick();
+
+        app.inject_event(SyntheticWindowEvent::Resized(800, 600));
+        app.tick();
+        {
+            let events = app.resources().get::<Events<WindowResized>>().unwrap();
+            let resized: Vec<_> = events.iter().copied().collect();
+            assert_eq!(
+                resized,
+                vec![WindowResized::new(PhysicalSize::new(800, 600), 1.0)]
+            );
+        }
+        // the input is released like the resize from the OS.
+        assert!(!app
+            .resources()
+            .get::<Input>()
+            .unwrap()
+            .keyboard
+            .pressed(KeyCode::Space));
+
+        // the events are dropped after two frames.
+        app.tick_n(2);
+        assert!(app
+            .resources()
+            .get::<Events<WindowResized>>()
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn inject_gamepad_axis() {
+        use crate::input::{virtual_axes_system, GamepadAxis, VirtualAxes};
+
+        let mut app = AppBuilder::new()
+            .create_stage_builder("default")
+            .unwrap()
+            .add_resource(VirtualAxes::wasd())
+            .add_system_process(virtual_axes_system())
+            .into_app_builder()
+            .build_for_test();
+
+        app.inject_event(SyntheticWindowEvent::GamepadAxis(
+            GamepadAxis::LeftStickY,
+            -0.5,
+        ));
+        app.tick();
+        assert_eq!(
+            app.resources()
+                .get::<VirtualAxes>()
+                .unwrap()
+                .value("vertical"),
+            -0.5
+        );
+    }
+
+    #[test]
+    fn order_process_systems_by_name() {
+        use crate::legion::SystemBuilder;
+
+        #[derive(Debug, Default)]
+        struct Log(Vec<&'static str>);
+
+        fn logger(name: &'static str) -> impl ParallelRunnable {
+            SystemBuilder::new(name)
+                .write_resource::<Log>()
+                .build(move |_, _, log, _| log.0.push(name))
+        }
+
+        let mut app = AppBuilder::new()
+            .create_stage_builder("logic")
+            .unwrap()
+            .add_resource(Log::default())
+            .add_system_process(logger("move"))
+            .add_system_process(logger("render"))
+            .add_system_process_before(logger("input"), "move")
+            .add_system_process_after(logger("physics"), "move")
+            .into_app_builder()
+            .build_for_test();
+
+        app.tick();
+        assert_eq!(
+            app.resources().get::<Log>().unwrap().0,
+            vec!["input", "move", "physics", "render"]
+        );
+
+        let result = AppStageBuilder::new("logic")
+            .add_system_process(logger("move"))
+            .try_add_system_process_after(logger("audio"), "missing");
+        match result {
+            Err(AppBuildError::UnknownSystem(stage_builder, name)) => {
+                assert_eq!(name, "missing");
+                assert_eq!(stage_builder.process_system_names().count(), 1);
+            }
+            _ => panic!("the unknown system name is accepted"),
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "No process system named `missing`")]
+    fn panic_on_unknown_system_name() {
+        use crate::legion::SystemBuilder;
+
+        AppStageBuilder::new("logic").add_system_process_before(
+            SystemBuilder::new("audio").build(|_, _, _, _| {}),
+            "missing",
+        );
+    }
+
+    #[test]
+    fn enter_and_exit_stage() {
+        #[derive(Debug, Default)]
+        struct Transitions(Vec<&'static str>);
+
+        let mut app = AppBuilder::new()
+            .create_stage_builder("level")
+            .unwrap()
+            .add_resource(Transitions::default())
+            .add_thread_local_fn_enter(|_, resources| {
+                resources.get_mut::<Transitions>().unwrap().0.push("enter")
+            })
+            .add_thread_local_fn_exit(|_, resources| {
+                resources.get_mut::<Transitions>().unwrap().0.push("exit")
+            })
+            .into_app_builder()
+            .build_for_test();
+
+        app.tick();
+        app.resources_mut()
+            .get_mut::<AppSettings>()
+            .unwrap()
+            .make_busy_stage_rest("level")
+            .unwrap();
+        app.tick();
+        app.resources_mut()
+            .get_mut::<AppSettings>()
+            .unwrap()
+            .make_spare_stage_work("level")
+            .unwrap();
+        app.tick();
+
+        let transitions = app.resources().get::<Transitions>().unwrap();
+        assert_eq!(transitions.0, vec!["exit", "enter"]);
+    }
+
+    #[test]
+    fn failed_spare_stage_work_keeps_stage() {
+        let mut app = AppBuilder::new()
+            .create_stage_builder("a")
+            .unwrap()
+            .into_app_builder()
+            .build_for_test();
+
+        app.resources_mut()
+            .get_mut::<AppSettings>()
+            .unwrap()
+            .make_busy_stage_rest("a")
+            .unwrap();
+        app.tick();
+
+        let mut settings = app.resources_mut().get_mut::<AppSettings>().unwrap();
+        let err = settings
+            .make_spare_stage_work_before("a", "missing")
+            .unwrap_err();
+        assert_eq!(err.to_string(), "stage `missing` doesn't exist in busy");
+        assert!(settings.is_in_spare("a"));
+
+        let err = settings.make_spare_stage_work("missing").unwrap_err();
+        assert!(matches!(
+            err,
+            AppSettingsError::StageNotExistInSpare("missing", None)
+        ));
+    }
+
+    #[test]
+    fn count_systems_of_stage() {
+        let builder = AppStageBuilder::new("counted")
+            .add_thread_local_fn_startup(|_, _| {})
+            .add_thread_local_fn_process(|_, _| {})
+            .add_thread_local_fn_process(|_, _| {});
+        assert_eq!(builder.system_count_preview(), (1, 2, 0));
+
+        let stage = builder.build();
+        assert_eq!(stage.startup_system_count(), 1);
+        assert_eq!(stage.process_system_count(), 2);
+        assert_eq!(stage.destroy_system_count(), 0);
+
+        // the engine steps are not counted, the merged systems are.
+        let builder = AppStageBuilder::new("engine")
+            .add_event::<u32>()
+            .add_resource(0u32)
+            .register_component::<Transform2D>();
+        assert_eq!(builder.system_count_preview(), (0, 0, 0));
+
+        let mut other = AppStageBuilder::new("other").add_thread_local_fn_destroy(|_, _| {});
+        let builder = builder.merge_destroy_systems(&mut other);
+        assert_eq!(builder.system_count_preview(), (0, 0, 1));
+        assert_eq!(other.system_count_preview(), (0, 0, 0));
+    }
+
+    #[test]
+    fn build_validated_reports_empty_stages() {
+        let warnings = AppBuilder::new()
+            .create_stage_builder("empty")
+            .unwrap()
+            .into_app_builder()
+            .create_stage_builder("busy")
+            .unwrap()
+            .add_thread_local_fn_process(|_, _| {})
+            .into_app_builder()
+            .build_validated()
+            .err()
+            .unwrap();
+
+        assert_eq!(
+            warnings,
+            vec![AppBuildWarning::EmptyStage(String::from("empty"))]
+        );
+
+        // the resources and events don't make a stage busy, the enter systems do.
+        let warnings = AppBuilder::new()
+            .create_stage_builder("resources")
+            .unwrap()
+            .add_resource(0u32)
+            .add_event::<u32>()
+            .into_app_builder()
+            .create_stage_builder("enter")
+            .unwrap()
+            .add_thread_local_fn_enter(|_, _| {})
+            .into_app_builder()
+            .build_validated()
+            .err()
+            .unwrap();
+
+        assert_eq!(
+            warnings,
+            vec![AppBuildWarning::EmptyStage(String::from("resources"))]
+        );
+    }
+
+    #[test]
+    fn set_busy_stage_order() {
+        let mut app = AppBuilder::new()
+            .create_stage_builder("a")
+            .unwrap()
+            .into_app_builder()
+            .create_stage_builder("b")
+            .unwrap()
+            .into_app_builder()
+            .create_stage_builder("c")
+            .unwrap()
+            .into_app_builder()
+            .build_for_test();
+
+        {
+            let mut settings = app.resources_mut().get_mut::<AppSettings>().unwrap();
+            assert!(matches!(
+                settings.set_busy_stage_order(vec!["c", "a"]),
+                Err(AppSettingsError::InvalidStageOrder)
+            ));
+            assert!(matches!(
+                settings.set_busy_stage_order(vec!["c", "a", "a"]),
+                Err(AppSettingsError::InvalidStageOrder)
+            ));
+            settings.set_busy_stage_order(vec!["c", "a", "b"]).unwrap();
+        }
+        app.tick();
+
+        let settings = app.resources().get::<AppSettings>().unwrap();
+        assert_eq!(settings.busy_stage_order(), vec!["c", "a", "b"]);
+    }
+
+    #[test]
+    fn stage_metrics_count_ticks() {
+        let mut app = AppBuilder::new()
+            .create_stage_builder("logic")
+            .unwrap()
+            .add_thread_local_fn_process(|_, _| {})
+            .into_app_builder()
+            .build_for_test();
+
+        app.tick_n(3);
+
+        let metrics = app
+            .resources()
+            .get::<HashMap<String, StageMetrics>>()
+            .unwrap();
+        let logic = &metrics["logic"];
+        assert_eq!(logic.name, "logic");
+        assert_eq!(logic.total_ticks, 3);
+        assert_eq!(logic.skipped_ticks, 0);
+        assert!(logic.max_execution_ms >= logic.mean_execution_ms);
+    }
+
+    #[test]
+    fn add_plugin_group() {
+        use crate::plugin::PluginGroup;
+
+        struct StagePlugin(&'static [&'static str]);
+
+        impl Plugin for StagePlugin {
+            fn build(&self, app_builder: AppBuilder) -> AppBuilder {
+                self.0.iter().fold(app_builder, |app_builder, name| {
+                    app_builder
+                        .create_stage_builder(*name)
+                        .unwrap()
+                        .into_app_builder()
+                })
+            }
+        }
+
+        let app = AppBuilder::new()
+            .create_stage_builder("main")
+            .unwrap()
+            .into_app_builder()
+            .add_plugin(
+                PluginGroup::new()
+                    .add(StagePlugin(&["physics", "collision"]))
+                    .add(StagePlugin(&["audio"])),
+            )
+            .build_for_test();
+
+        let settings = app.resources().get::<AppSettings>().unwrap();
+        assert_eq!(
+            settings.busy_stage_names(),
+            vec!["main", "physics", "collision", "audio"]
+        );
+    }
+
+    #[test]
+    fn build_from_config() {
+        struct SpawnPlugin;
+
+        impl Plugin for SpawnPlugin {
+            fn build(&self, app_builder: AppBuilder) -> AppBuilder {
+                app_builder
+                    .create_stage_builder("spawn")
+                    .unwrap()
+                    .tag(AppStageTag::Logic)
+                    .add_thread_local_fn_process(|world, _| {
+                        world.push((0u32,));
+                    })
+                    .into_app_builder()
+                    .create_stage_builder("count")
+                    .unwrap()
+                    .add_thread_local_fn_process(|_, _| {})
+                    .into_app_builder()
+            }
+        }
+
+        struct DrawPlugin;
+
+        impl Plugin for DrawPlugin {
+            fn build(&self, app_builder: AppBuilder) -> AppBuilder {
+                app_builder
+                    .create_stage_builder("draw")
+                    .unwrap()
+                    .tag(AppStageTag::Render)
+                    .add_thread_local_fn_process(|_, _| {})
+                    .into_app_builder()
+            }
+        }
+
+        let registry = PluginRegistry::new()
+            .register("spawn", SpawnPlugin)
+            .register("draw", DrawPlugin);
+        let config = AppConfig::from_toml_str(
+            "[[stage]]\nname = \"logic\"\nplugins = [\"spawn\", \"spawn\"]\n\n[[stage]]\nname = \"idle\"\n",
+        )
+        .unwrap();
+
+        let mut app = AppBuilder::from_config(&config, &registry)
+            .unwrap()
+            .build_for_test();
+        app.tick();
+        // the systems of both plugins are merged into the stage.
+        assert_eq!(app.world().len(), 2);
+
+        let settings = app.resources().get::<AppSettings>().unwrap();
+        assert_eq!(settings.busy_stage_names(), vec!["logic", "idle"]);
+        assert_eq!(
+            settings.busy_stage("logic").unwrap().tag(),
+            Some(&AppStageTag::Logic)
+        );
+        assert_eq!(settings.busy_stage("idle").unwrap().tag(), None);
+        drop(settings);
+
+        let from_toml = |text: &str| {
+            AppBuilder::from_config(&AppConfig::from_toml_str(text).unwrap(), &registry)
+        };
+        assert!(matches!(
+            from_toml("[[stage]]\nname = \"logic\"\nplugins = [\"physics\"]\n"),
+            Err(ConfigError::UnknownPlugin(_))
+        ));
+        assert!(matches!(
+            from_toml("[[stage]]\nname = \"logic\"\n[[stage]]\nname = \"logic\"\n"),
+            Err(ConfigError::DuplicateStage(name)) if name == "logic"
+        ));
+        assert!(matches!(
+            from_toml("[[stage]]\nname = \"logic\"\nplugins = [\"spawn\", \"draw\"]\n"),
+            Err(ConfigError::MixedTags(name)) if name == "logic"
+        ));
+        assert!(matches!(
+            from_toml("[[stage]]\nname = \"logic\"\nfrequency = 30\nplugins = [\"spawn\"]\n"),
+            Err(ConfigError::UnsupportedFrequency(name, 30)) if name == "logic"
+        ));
+    }
 }
@@ -0,0 +1,371 @@
+use crate::components::sprite::Texture;
+
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    error::Error,
+    fmt, fs,
+    hash::{Hash, Hasher},
+    io,
+    marker::PhantomData,
+    path::{Path, PathBuf},
+};
+
+/// A typed reference to an asset stored in `Assets<T>`.
+pub struct Handle<T> {
+    id: u64,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Handle<T> {
+    pub(crate) fn new(id: u64) -> Self {
+        Self {
+            id,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn id(&self) -> u64 {
+        self.id
+    }
+}
+
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Handle<T> {}
+
+impl<T> PartialEq for Handle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.id == other.id
+    }
+}
+
+impl<T> Eq for Handle<T> {}
+
+impl<T> Hash for Handle<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+
+impl<T> fmt::Debug for Handle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Handle").field(&self.id).finish()
+    }
+}
+
+/// Store the assets of type `T`, insert it to `Resources` to share the assets between systems.
+pub struct Assets<T> {
+    assets: HashMap<u64, T>,
+    next_id: u64,
+}
+
+impl<T> Assets<T> {
+    pub fn new() -> Self {
+        Self {
+            assets: Default::default(),
+            next_id: 0,
+        }
+    }
+
+    pub fn add(&mut self, asset: T) -> Handle<T> {
+        let handle = self.reserve();
+
+        self.assets.insert(handle.id, asset);
+
+        handle
+    }
+
+    /// Create a handle whose asset will be inserted later.
+    fn reserve(&mut self) -> Handle<T> {
+        let id = self.next_id;
+        self.next_id += 1;
+
+        Handle::new(id)
+    }
+
+    pub fn get(&self, handle: Handle<T>) -> Option<&T> {
+        self.assets.get(&handle.id)
+    }
+
+    pub fn get_mut(&mut self, handle: Handle<T>) -> Option<&mut T> {
+        self.assets.get_mut(&handle.id)
+    }
+
+    pub fn remove(&mut self, handle: Handle<T>) -> Option<T> {
+        self.assets.remove(&handle.id)
+    }
+
+    pub fn contains(&self, handle: Handle<T>) -> bool {
+        self.assets.contains_key(&handle.id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.assets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.assets.is_empty()
+    }
+}
+
+impl<T> Default for Assets<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> fmt::Debug for Assets<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Assets")
+            .field("len", &self.assets.len())
+            .finish()
+    }
+}
+
+/// The type which can be loaded from a file by `AssetServer`.
+pub trait Asset: Sized + Send + Sync + 'static {
+    fn load(bytes: &[u8]) -> Result<Self, AssetError>;
+}
+
+#[derive(Debug)]
+pub enum AssetError {
+    Io(io::Error),
+    /// The content of file can't be decoded to the asset.
+    Decode(String),
+}
+
+impl fmt::Display for AssetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AssetError::Io(err) => write!(f, "{}", err),
+            AssetError::Decode(reason) => write!(f, "{}", reason),
+        }
+    }
+}
+
+impl Error for AssetError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            AssetError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for AssetError {
+    fn from(err: io::Error) -> Self {
+        AssetError::Io(err)
+    }
+}
+
+impl Asset for Vec<u8> {
+    fn load(bytes: &[u8]) -> Result<Self, AssetError> {
+        Ok(bytes.to_vec())
+    }
+}
+
+impl Asset for String {
+    fn load(bytes: &[u8]) -> Result<Self, AssetError> {
+        String::from_utf8(bytes.to_vec()).map_err(|err| AssetError::Decode(err.to_string()))
+    }
+}
+
+impl Asset for Texture {
+    fn load(bytes: &[u8]) -> Result<Self, AssetError> {
+        if Texture::is_png(bytes) {
+            Texture::from_png(bytes)
+        } else {
+            Texture::from_ppm(bytes)
+        }
+    }
+}
+
+/// Load assets from files under the root directory and keep them in `Assets<T>` of each type.
+///
+/// Assets are loaded synchronously, the same path is loaded only once for each type.
+pub struct AssetServer {
+    root: PathBuf,
+
+    // TypeId::of::<T>() -> Assets<T>
+    storages: HashMap<TypeId, Box<dyn Any + Send + Sync>>,
+    paths: HashMap<(TypeId, PathBuf), u64>,
+    errors: HashMap<(TypeId, u64), AssetError>,
+}
+
+impl AssetServer {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+
+            storages: Default::default(),
+            paths: Default::default(),
+            errors: Default::default(),
+        }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Load the asset from the path relative to root, the handle is valid even if loading failed,
+    /// check it by `Self::is_loaded` and `Self::load_error`.
+    pub fn load<T: Asset>(&mut self, path: &str) -> Handle<T> {
+        let path = self.root.join(path);
+        let key = (TypeId::of::<T>(), path.clone());
+
+        if let Some(&id) = self.paths.get(&key) {
+            return Handle::new(id);
+        }
+
+        let handle = self.assets_mut::<T>().reserve();
+        self.paths.insert(key, handle.id);
+
+        match fs::read(&path)
+            .map_err(AssetError::from)
+            .and_then(|bytes| T::load(&bytes))
+        {
+            Ok(asset) => {
+                self.assets_mut::<T>().assets.insert(handle.id, asset);
+            }
+            Err(err) => {
+                self.errors.insert((TypeId::of::<T>(), handle.id), err);
+            }
+        }
+
+        handle
+    }
+
+    /// Add an asset created in code.
+    pub fn add<T: Asset>(&mut self, asset: T) -> Handle<T> {
+        self.assets_mut::<T>().add(asset)
+    }
+
+    pub fn get<T: Asset>(&self, handle: Handle<T>) -> Option<&T> {
+        self.assets::<T>().and_then(|assets| assets.get(handle))
+    }
+
+    pub fn get_mut<T: Asset>(&mut self, handle: Handle<T>) -> Option<&mut T> {
+        self.assets_mut::<T>().get_mut(handle)
+    }
+
+    pub fn is_loaded<T: Asset>(&self, handle: Handle<T>) -> bool {
+        self.assets::<T>()
+            .map_or(false, |assets| assets.contains(handle))
+    }
+
+    /// The error occurred when loading the asset.
+    pub fn load_error<T: Asset>(&self, handle: Handle<T>) -> Option<&AssetError> {
+        self.errors.get(&(TypeId::of::<T>(), handle.id))
+    }
+
+    pub fn assets<T: Asset>(&self) -> Option<&Assets<T>> {
+        self.storages
+            .get(&TypeId::of::<T>())
+            .and_then(|storage| storage.downcast_ref::<Assets<T>>())
+    }
+
+    pub fn assets_mut<T: Asset>(&mut self) -> &mut Assets<T> {
+        self.storages
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(Assets::<T>::new()))
+            .downcast_mut::<Assets<T>>()
+            .unwrap()
+    }
+}
+
+impl Default for AssetServer {
+    fn default() -> Self {
+        Self::new("assets")
+    }
+}
+
+impl fmt::Debug for AssetServer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AssetServer")
+            .field("root", &self.root)
+            .field("paths", &self.paths.len())
+            .field("errors", &self.errors.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A temporary directory removed when dropped.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!("yam-{}-{}", name, std::process::id()));
+            fs::create_dir_all(&path).unwrap();
+
+            Self(path)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn load_assets_once() {
+        let dir = TempDir::new("load-assets");
+        fs::write(dir.0.join("data.bin"), [1u8, 2, 3]).unwrap();
+
+        let mut server = AssetServer::new(&dir.0);
+        let handle = server.load::<Vec<u8>>("data.bin");
+        assert!(server.is_loaded(handle));
+        assert_eq!(server.get(handle), Some(&vec![1, 2, 3]));
+
+        // the same path is loaded only once.
+        fs::write(dir.0.join("data.bin"), [4u8]).unwrap();
+        assert_eq!(server.load::<Vec<u8>>("data.bin"), handle);
+        assert_eq!(server.get(handle), Some(&vec![1, 2, 3]));
+
+        let added = server.add(vec![5u8]);
+        assert_ne!(added, handle);
+        server.get_mut(added).unwrap().push(6);
+        assert_eq!(server.assets::<Vec<u8>>().unwrap().len(), 2);
+        assert_eq!(server.get(added), Some(&vec![5, 6]));
+    }
+
+    #[test]
+    fn keep_load_errors() {
+        let dir = TempDir::new("load-errors");
+        fs::write(dir.0.join("broken.ppm"), b"P6 1 1 255\n").unwrap();
+
+        let mut server = AssetServer::new(&dir.0);
+
+        let missing = server.load::<Vec<u8>>("missing.bin");
+        assert!(!server.is_loaded(missing));
+        assert!(matches!(
+            server.load_error(missing),
+            Some(AssetError::Io(_))
+        ));
+
+        let broken = server.load::<Texture>("broken.ppm");
+        assert!(!server.is_loaded(broken));
+        let err = server.load_error(broken).unwrap();
+        assert!(matches!(err, AssetError::Decode(_)));
+        assert_eq!(err.to_string(), "ERR: invalid ppm, not enough pixels");
+    }
+
+    #[test]
+    fn reject_oversized_ppm() {
+        let bytes = b"P6 4294967295 4294967295 255\n\0\0\0";
+
+        assert!(matches!(
+            Texture::from_ppm(bytes),
+            Err(AssetError::Decode(_))
+        ));
+    }
+}
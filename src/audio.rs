@@ -0,0 +1,1053 @@
+use crate::{
+    assets::{Asset, AssetError, AssetServer, Assets, Handle},
+    components::transform::Transform2D,
+    legion::{
+        systems::{ParallelRunnable, SystemBuilder},
+        IntoQuery, Resources,
+    },
+    nalgebra::Vector2,
+};
+
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+/// The decoded samples of a sound, loaded from a WAV file by `AssetServer`.
+///
+/// The samples are interleaved by channel and normalized to `[-1.0, 1.0]`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AudioClip {
+    pub sample_rate: u32,
+    pub channels: u16,
+    pub samples: Vec<f32>,
+}
+
+impl AudioClip {
+    pub fn new(sample_rate: u32, channels: u16, samples: Vec<f32>) -> Self {
+        assert!(
+            sample_rate > 0 && channels > 0,
+            "ERR: the sample rate and channels of AudioClip must be positive"
+        );
+
+        Self {
+            sample_rate,
+            channels,
+            samples,
+        }
+    }
+
+    /// Decode a RIFF WAV file, 8/16/24/32-bit integer PCM and 32-bit float PCM are supported.
+    pub fn from_wav(bytes: &[u8]) -> Result<Self, AssetError> {
+        let err = |msg: &str| AssetError::Decode(format!("ERR: invalid wav, {}", msg));
+
+        if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+            return Err(err("missing RIFF/WAVE header"));
+        }
+
+        let u16_at = |i: usize| u16::from_le_bytes([bytes[i], bytes[i + 1]]);
+        let u32_at =
+            |i: usize| u32::from_le_bytes([bytes[i], bytes[i + 1], bytes[i + 2], bytes[i + 3]]);
+
+        // (format, channels, sample_rate, bits_per_sample)
+        let mut format = None;
+        let mut data = None;
+        let mut cursor = 12;
+
+        while cursor + 8 <= bytes.len() {
+            let id = &bytes[cursor..cursor + 4];
+            let size = u32_at(cursor + 4) as usize;
+            let start = cursor + 8;
+            let end = start.saturating_add(size).min(bytes.len());
+
+            match id {
+                b"fmt " if end - start >= 16 => {
+                    format = Some((
+                        u16_at(start),
+                        u16_at(start + 2),
+                        u32_at(start + 4),
+                        u16_at(start + 14),
+                    ));
+                }
+                b"data" => data = Some(&bytes[start..end]),
+                _ => {}
+            }
+
+            // NOTE: the chunks are aligned to 2 bytes.
+            cursor = start.saturating_add(size + size % 2);
+        }
+
+        let (tag, channels, sample_rate, bits) = format.ok_or_else(|| err("missing fmt chunk"))?;
+        let data = data.ok_or_else(|| err("missing data chunk"))?;
+
+        if channels == 0 || sample_rate == 0 {
+            return Err(err("zero channels or sample rate"));
+        }
+
+        let samples = match (tag, bits) {
+            (1, 8) => data.iter().map(|&s| (s as f32 - 128.0) / 128.0).collect(),
+            (1, 16) => data
+                .chunks_exact(2)
+                .map(|s| i16::from_le_bytes([s[0], s[1]]) as f32 / 32768.0)
+                .collect(),
+            (1, 24) => data
+                .chunks_exact(3)
+                .map(|s| (i32::from_le_bytes([0, s[0], s[1], s[2]]) >> 8) as f32 / 8_388_608.0)
+                .collect(),
+            (1, 32) => data
+                .chunks_exact(4)
+                .map(|s| i32::from_le_bytes([s[0], s[1], s[2], s[3]]) as f32 / 2_147_483_648.0)
+                .collect(),
+            (3, 32) => data
+                .chunks_exact(4)
+                .map(|s| f32::from_le_bytes([s[0], s[1], s[2], s[3]]))
+                .collect(),
+            _ => return Err(err("only integer and 32-bit float PCM are supported")),
+        };
+
+        Ok(Self::new(sample_rate, channels, samples))
+    }
+
+    /// The count of samples per channel.
+    pub fn frames(&self) -> usize {
+        self.samples.len() / self.channels as usize
+    }
+
+    pub fn duration(&self) -> Duration {
+        Duration::from_secs_f64(self.frames() as f64 / self.sample_rate as f64)
+    }
+}
+
+impl Asset for AudioClip {
+    fn load(bytes: &[u8]) -> Result<Self, AssetError> {
+        AudioClip::from_wav(bytes)
+    }
+}
+
+/// The id of a sound started by `Audio`, it's invalid after the sound is stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct AudioInstance(u64);
+
+impl AudioInstance {
+    pub fn id(&self) -> u64 {
+        self.0
+    }
+}
+
+/// The playback state of an `AudioInstance`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AudioPlayback {
+    pub clip: Handle<AudioClip>,
+    pub looped: bool,
+    pub volume: f32,
+    pub pitch: f32,
+    /// The position of sound in `world space`, `None` if the sound is not positional.
+    pub spatial: Option<SpatialPlayback>,
+}
+
+/// How the volume of a positional sound falls off between `min_distance` and `max_distance`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RolloffModel {
+    /// Fall off linearly to silence at `max_distance`.
+    Linear,
+    /// `min_distance / distance`, the physically correct falloff.
+    Inverse,
+    /// `(distance / min_distance) ^ -factor`, the larger factor the faster falloff.
+    Exponential(f32),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpatialAudioConfig {
+    /// The sound is at full volume inside the distance.
+    pub min_distance: f32,
+    /// The sound is silent outside the distance.
+    pub max_distance: f32,
+    pub rolloff: RolloffModel,
+}
+
+impl SpatialAudioConfig {
+    /// The gain of the sound at the distance from the listener, in `[0.0, 1.0]`.
+    pub fn gain(&self, distance: f32) -> f32 {
+        let min = self.min_distance.max(f32::EPSILON);
+
+        if distance <= min {
+            return 1.0;
+        }
+        if distance >= self.max_distance {
+            return 0.0;
+        }
+
+        let gain = match self.rolloff {
+            RolloffModel::Linear => 1.0 - (distance - min) / (self.max_distance - min),
+            RolloffModel::Inverse => min / distance,
+            RolloffModel::Exponential(factor) => (distance / min).powf(-factor),
+        };
+
+        gain.max(0.0).min(1.0)
+    }
+}
+
+impl Default for SpatialAudioConfig {
+    fn default() -> Self {
+        Self {
+            min_distance: 64.0,
+            max_distance: 1024.0,
+            rolloff: RolloffModel::Inverse,
+        }
+    }
+}
+
+/// The state of a positional sound, `gain` and `pan` are updated by `spatial_audio_system`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpatialPlayback {
+    pub position: Vector2<f32>,
+    pub config: SpatialAudioConfig,
+    /// Multiplied with the volume of the sound.
+    pub gain: f32,
+    /// From `-1.0`(left) to `1.0`(right).
+    pub pan: f32,
+}
+
+impl SpatialPlayback {
+    /// Compute the gain and pan heard by the listener.
+    pub fn listen(&mut self, listener: &Transform2D) {
+        let offset = listener.rotation.inverse() * (self.position - listener.position);
+        let distance = (self.position - listener.position).norm();
+
+        self.gain = self.config.gain(distance);
+        // NOTE: the sound inside `min_distance` is heard from both sides gradually.
+        self.pan = (offset.x / distance.max(self.config.min_distance).max(f32::EPSILON))
+            .max(-1.0)
+            .min(1.0);
+    }
+}
+
+/// The ear of positional sounds, add it to the camera entity with `Transform2D`.
+///
+/// Only the first listener is used, positional sounds are heard at full volume without listener.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SpatialAudioListener;
+
+/// Attach the positional sound to the entity, the position of the sound follows `Transform2D`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AudioEmitter(pub AudioInstance);
+
+/// The changes made by `Audio` since the last drain, in the order they are made.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AudioCommand {
+    Play(AudioInstance, AudioPlayback),
+    Stop(AudioInstance),
+    SetVolume(AudioInstance, f32),
+    SetPitch(AudioInstance, f32),
+    /// The gain and pan of a positional sound are changed.
+    SetSpatial(AudioInstance, f32, f32),
+}
+
+impl AudioCommand {
+    /// The sound changed by the command.
+    pub fn instance(&self) -> AudioInstance {
+        match *self {
+            AudioCommand::Play(instance, _)
+            | AudioCommand::Stop(instance)
+            | AudioCommand::SetVolume(instance, _)
+            | AudioCommand::SetPitch(instance, _)
+            | AudioCommand::SetSpatial(instance, ..) => instance,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AudioError {
+    /// The pitch must be positive, with the rejected pitch.
+    InvalidPitch(f32),
+}
+
+impl fmt::Display for AudioError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AudioError::InvalidPitch(pitch) => {
+                write!(f, "the pitch must be positive, got {}", pitch)
+            }
+        }
+    }
+}
+
+impl Error for AudioError {}
+
+/// Play `AudioClip`s, it's inserted to `Resources` by `App`, control the sounds from systems.
+///
+/// `Audio` keeps the state of playing sounds and queues the changes as `AudioCommand`s, `App` drains them
+/// once per frame and plays them on the default output device. The clips are read from
+/// `Assets<AudioClip>` or `AssetServer`.
+///
+/// Without output device the sounds are timed silently, so the sounds which are not looped still end.
+#[derive(Debug, Default)]
+pub struct Audio {
+    playing: HashMap<AudioInstance, AudioPlayback>,
+    commands: Vec<AudioCommand>,
+    next_id: u64,
+
+    output_error: Option<String>,
+}
+
+impl Audio {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Play the sound once, the instance is stopped by the backend when the sound ends.
+    pub fn play(&mut self, sound: Handle<AudioClip>) -> AudioInstance {
+        self.start(sound, false, None)
+    }
+
+    /// Play the sound repeatedly until it's stopped.
+    pub fn play_looped(&mut self, sound: Handle<AudioClip>) -> AudioInstance {
+        self.start(sound, true, None)
+    }
+
+    /// Play the sound once at the position in `world space`, the volume and panning are computed from the
+    /// `SpatialAudioListener` by `spatial_audio_system`.
+    pub fn play_at_position(
+        &mut self,
+        sound: Handle<AudioClip>,
+        position: Vector2<f32>,
+        config: SpatialAudioConfig,
+    ) -> AudioInstance {
+        let spatial = SpatialPlayback {
+            position,
+            config,
+            gain: 1.0,
+            pan: 0.0,
+        };
+
+        self.start(sound, false, Some(spatial))
+    }
+
+    /// Move the positional sound, do nothing if the sound is not positional.
+    pub fn set_position(&mut self, instance: AudioInstance, position: Vector2<f32>) {
+        if let Some(spatial) = self
+            .playing
+            .get_mut(&instance)
+            .and_then(|playback| playback.spatial.as_mut())
+        {
+            spatial.position = position;
+        }
+    }
+
+    /// Stop the sound, do nothing if it's already stopped.
+    pub fn stop(&mut self, instance: AudioInstance) {
+        if self.playing.remove(&instance).is_some() {
+            self.push_command(AudioCommand::Stop(instance));
+        }
+    }
+
+    pub fn stop_all(&mut self) {
+        let mut instances = self.playing.keys().copied().collect::<Vec<_>>();
+        instances.sort_by_key(|instance| instance.0);
+
+        for instance in instances {
+            self.stop(instance);
+        }
+    }
+
+    /// Set the volume of the sound, `1.0` is the original volume, negative volumes are clamped to `0.0`.
+    pub fn set_volume(&mut self, instance: AudioInstance, volume: f32) {
+        let volume = volume.max(0.0);
+
+        if let Some(playback) = self.playing.get_mut(&instance) {
+            playback.volume = volume;
+            self.push_command(AudioCommand::SetVolume(instance, volume));
+        }
+    }
+
+    /// Set the playback speed of the sound, `2.0` is an octave higher, fails if the pitch is not positive.
+    pub fn set_pitch(&mut self, instance: AudioInstance, pitch: f32) -> Result<(), AudioError> {
+        // NOTE: `!(pitch > 0.0)` rejects NaN as well.
+        if !(pitch > 0.0) {
+            return Err(AudioError::InvalidPitch(pitch));
+        }
+
+        if let Some(playback) = self.playing.get_mut(&instance) {
+            playback.pitch = pitch;
+            self.push_command(AudioCommand::SetPitch(instance, pitch));
+        }
+
+        Ok(())
+    }
+
+    pub fn is_playing(&self, instance: AudioInstance) -> bool {
+        self.playing.contains_key(&instance)
+    }
+
+    pub fn playback(&self, instance: AudioInstance) -> Option<&AudioPlayback> {
+        self.playing.get(&instance)
+    }
+
+    /// The queued changes, at most one `Play` and one change of each kind are queued for a sound.
+    pub fn pending_commands(&self) -> &[AudioCommand] {
+        &self.commands
+    }
+
+    /// Take the queued changes, it's called by `App` once per frame.
+    pub fn drain_commands(&mut self) -> Vec<AudioCommand> {
+        std::mem::take(&mut self.commands)
+    }
+
+    /// Mark the sound as ended, it's called by `App` when a sound which is not looped ends.
+    pub fn finish(&mut self, instance: AudioInstance) {
+        self.playing.remove(&instance);
+    }
+
+    /// The error occurred when opening the output device, the sounds are timed silently if there is one.
+    pub fn output_error(&self) -> Option<&str> {
+        self.output_error.as_deref()
+    }
+
+    /// Recompute the gain and pan of all positional sounds, a `SetSpatial` is queued if they are changed.
+    pub fn listen(&mut self, listener: &Transform2D) {
+        let mut instances = self.playing.keys().copied().collect::<Vec<_>>();
+        instances.sort_by_key(|instance| instance.0);
+
+        for instance in instances {
+            if let Some(spatial) = self.playing.get_mut(&instance).unwrap().spatial.as_mut() {
+                let (gain, pan) = (spatial.gain, spatial.pan);
+                spatial.listen(listener);
+
+                if (spatial.gain, spatial.pan) != (gain, pan) {
+                    let command = AudioCommand::SetSpatial(instance, spatial.gain, spatial.pan);
+                    self.push_command(command);
+                }
+            }
+        }
+    }
+
+    fn start(
+        &mut self,
+        clip: Handle<AudioClip>,
+        looped: bool,
+        spatial: Option<SpatialPlayback>,
+    ) -> AudioInstance {
+        let instance = AudioInstance(self.next_id);
+        self.next_id += 1;
+
+        let playback = AudioPlayback {
+            clip,
+            looped,
+            volume: 1.0,
+            pitch: 1.0,
+            spatial,
+        };
+
+        self.playing.insert(instance, playback);
+        self.push_command(AudioCommand::Play(instance, playback));
+
+        instance
+    }
+
+    // Queue the command, a change replaces the queued change of the same kind, so the queue doesn't grow
+    // with the changes made every frame.
+    fn push_command(&mut self, command: AudioCommand) {
+        let instance = command.instance();
+
+        if let AudioCommand::Stop(_) = command {
+            // NOTE: the sound started after the last drain is never passed to the output.
+            let started = self.commands.iter().any(|queued| match queued {
+                AudioCommand::Play(played, _) => *played == instance,
+                _ => false,
+            });
+
+            self.commands.retain(|queued| queued.instance() != instance);
+            if !started {
+                self.commands.push(command);
+            }
+
+            return;
+        }
+
+        let kind = std::mem::discriminant(&command);
+        match self
+            .commands
+            .iter_mut()
+            .find(|queued| queued.instance() == instance && std::mem::discriminant(*queued) == kind)
+        {
+            Some(queued) => *queued = command,
+            None => self.commands.push(command),
+        }
+    }
+}
+
+/// Create a system which moves the sounds of `AudioEmitter`s to their `Transform2D`, then computes the gain
+/// and pan of positional sounds from the `SpatialAudioListener`.
+///
+/// `Audio` must be inserted to `Resources`.
+pub fn spatial_audio_system() -> impl ParallelRunnable {
+    SystemBuilder::new("spatial_audio")
+        .write_resource::<Audio>()
+        .with_query(<(&Transform2D, &SpatialAudioListener)>::query())
+        .with_query(<(&Transform2D, &AudioEmitter)>::query())
+        .build(|_, world, audio, (listeners, emitters)| {
+            for (transform, emitter) in emitters.iter(world) {
+                audio.set_position(emitter.0, transform.position);
+            }
+
+            // NOTE: without listener, the sounds are heard as if the listener is on them.
+            if let Some((listener, _)) = listeners.iter(world).next() {
+                audio.listen(listener);
+            }
+        })
+}
+
+/// The current looping track, at most one track is played at a time.
+#[derive(Debug, Default)]
+pub struct BackgroundMusic {
+    current: Option<(Handle<AudioClip>, AudioInstance)>,
+}
+
+impl BackgroundMusic {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Loop the track and stop the previous one, do nothing if the track is already playing.
+    pub fn play(&mut self, audio: &mut Audio, track: Handle<AudioClip>) -> AudioInstance {
+        if let Some((current, instance)) = self.current {
+            if current == track && audio.is_playing(instance) {
+                return instance;
+            }
+            audio.stop(instance);
+        }
+
+        let instance = audio.play_looped(track);
+        self.current = Some((track, instance));
+
+        instance
+    }
+
+    pub fn stop(&mut self, audio: &mut Audio) {
+        if let Some((_, instance)) = self.current.take() {
+            audio.stop(instance);
+        }
+    }
+
+    pub fn set_volume(&mut self, audio: &mut Audio, volume: f32) {
+        if let Some((_, instance)) = self.current {
+            audio.set_volume(instance, volume);
+        }
+    }
+
+    pub fn track(&self) -> Option<Handle<AudioClip>> {
+        self.current.map(|(track, _)| track)
+    }
+
+    pub fn instance(&self) -> Option<AudioInstance> {
+        self.current.map(|(_, instance)| instance)
+    }
+}
+
+/// The output of `Audio` owned by `App`, it drains the `AudioCommand`s once per frame and plays the sounds on
+/// the default output device.
+///
+/// NOTE: the output stream is not `Send`, so it's kept by `App` instead of `Resources`.
+pub(crate) struct AudioOutput {
+    device: Option<(rodio::OutputStream, rodio::OutputStreamHandle)>,
+    device_error: Option<String>,
+
+    sounds: HashMap<AudioInstance, Sound>,
+    last_update: Option<Instant>,
+}
+
+impl AudioOutput {
+    /// Open the default output device, the sounds are timed silently if it fails.
+    pub(crate) fn open() -> Self {
+        match rodio::OutputStream::try_default() {
+            Ok(device) => Self {
+                device: Some(device),
+                ..Self::silent()
+            },
+            Err(err) => Self {
+                device_error: Some(err.to_string()),
+                ..Self::silent()
+            },
+        }
+    }
+
+    /// The output without device, used by `App::run_headless` and `TestApp`.
+    pub(crate) fn silent() -> Self {
+        Self {
+            device: None,
+            device_error: None,
+
+            sounds: Default::default(),
+            last_update: None,
+        }
+    }
+
+    /// Drain the commands of `Audio` in `Resources`, do nothing if there is no `Audio`.
+    pub(crate) fn update(&mut self, resources: &Resources) {
+        let now = Instant::now();
+        let delta = self
+            .last_update
+            .map_or(Duration::default(), |last| now - last);
+        self.last_update = Some(now);
+
+        let mut audio = match resources.get_mut::<Audio>() {
+            Some(audio) => audio,
+            None => return,
+        };
+        let assets = resources.get::<Assets<AudioClip>>();
+        let server = resources.get::<AssetServer>();
+        let clips = assets.as_deref().or_else(|| {
+            server
+                .as_ref()
+                .and_then(|server| server.assets::<AudioClip>())
+        });
+
+        self.update_with(&mut audio, clips, delta);
+    }
+
+    /// Apply the commands, then finish the sounds which are not looped and have ended.
+    pub(crate) fn update_with(
+        &mut self,
+        audio: &mut Audio,
+        clips: Option<&Assets<AudioClip>>,
+        delta: Duration,
+    ) {
+        if let Some(err) = self.device_error.take() {
+            audio.output_error = Some(err);
+        }
+
+        for command in audio.drain_commands() {
+            match command {
+                AudioCommand::Play(instance, playback) => {
+                    let clip = clips.and_then(|clips| clips.get(playback.clip));
+                    let sound = self.start(clip, &playback);
+
+                    self.sounds.insert(instance, sound);
+                }
+                AudioCommand::Stop(instance) => {
+                    if let Some(sink) = self.sounds.remove(&instance).and_then(|sound| sound.sink) {
+                        sink.stop();
+                    }
+                }
+                AudioCommand::SetVolume(instance, volume) => {
+                    if let Some(sound) = self.sounds.get_mut(&instance) {
+                        sound.volume = volume;
+                        sound.apply();
+                    }
+                }
+                AudioCommand::SetPitch(instance, pitch) => {
+                    if let Some(sound) = self.sounds.get_mut(&instance) {
+                        sound.pitch = pitch;
+                        sound.apply();
+                    }
+                }
+                AudioCommand::SetSpatial(instance, gain, pan) => {
+                    if let Some(sound) = self.sounds.get_mut(&instance) {
+                        sound.gain = gain;
+                        sound.pan = pan;
+                        sound.apply();
+                    }
+                }
+            }
+        }
+
+        let delta = delta.as_secs_f64();
+        let mut ended = Vec::new();
+
+        for (instance, sound) in self.sounds.iter_mut() {
+            sound.played += delta * sound.pitch as f64;
+
+            let finished = match &sound.sink {
+                Some(sink) => sink.empty(),
+                None => sound.played >= sound.duration,
+            };
+            if !sound.looped && finished {
+                ended.push(*instance);
+            }
+        }
+
+        for instance in ended {
+            self.sounds.remove(&instance);
+            audio.finish(instance);
+        }
+    }
+
+    fn start(&self, clip: Option<&AudioClip>, playback: &AudioPlayback) -> Sound {
+        let (gain, pan) = playback
+            .spatial
+            .map_or((1.0, 0.0), |spatial| (spatial.gain, spatial.pan));
+        let controls = Arc::new(SoundControls::new(playback.pitch, pan));
+
+        // NOTE: a clip without samples or not loaded is timed as an empty sound.
+        let sink = match (&self.device, clip) {
+            (Some((_, handle)), Some(clip)) if clip.frames() > 0 => {
+                rodio::Sink::try_new(handle).ok().map(|sink| {
+                    sink.append(ClipSource::new(clip, playback.looped, controls.clone()));
+                    sink
+                })
+            }
+            _ => None,
+        };
+
+        let sound = Sound {
+            sink,
+            controls,
+
+            volume: playback.volume,
+            gain,
+            pitch: playback.pitch,
+            pan,
+            looped: playback.looped,
+
+            played: 0.0,
+            duration: clip.map_or(0.0, |clip| clip.duration().as_secs_f64()),
+        };
+        sound.apply();
+
+        sound
+    }
+}
+
+impl fmt::Debug for AudioOutput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AudioOutput")
+            .field("device", &self.device.is_some())
+            .field("sounds", &self.sounds.len())
+            .finish()
+    }
+}
+
+// A sound played by `AudioOutput`.
+struct Sound {
+    // `None` if there is no output device.
+    sink: Option<rodio::Sink>,
+    controls: Arc<SoundControls>,
+
+    volume: f32,
+    gain: f32,
+    pitch: f32,
+    pan: f32,
+    looped: bool,
+
+    // The played time and the duration of clip in seconds, the pitch speeds up the playing.
+    played: f64,
+    duration: f64,
+}
+
+impl Sound {
+    fn apply(&self) {
+        if let Some(sink) = &self.sink {
+            sink.set_volume(self.volume * self.gain);
+        }
+
+        self.controls.set(self.pitch, self.pan);
+    }
+}
+
+// The pitch and pan shared with the `ClipSource` on the audio thread.
+struct SoundControls {
+    pitch: AtomicU32,
+    pan: AtomicU32,
+}
+
+impl SoundControls {
+    fn new(pitch: f32, pan: f32) -> Self {
+        Self {
+            pitch: AtomicU32::new(pitch.to_bits()),
+            pan: AtomicU32::new(pan.to_bits()),
+        }
+    }
+
+    fn set(&self, pitch: f32, pan: f32) {
+        self.pitch.store(pitch.to_bits(), Ordering::Relaxed);
+        self.pan.store(pan.to_bits(), Ordering::Relaxed);
+    }
+
+    fn pitch(&self) -> f32 {
+        f32::from_bits(self.pitch.load(Ordering::Relaxed))
+    }
+
+    fn pan(&self) -> f32 {
+        f32::from_bits(self.pan.load(Ordering::Relaxed))
+    }
+}
+
+// Play the samples of a clip in stereo, resampled by the pitch and balanced by the pan.
+struct ClipSource {
+    samples: Vec<f32>,
+    channels: usize,
+    sample_rate: u32,
+    looped: bool,
+    controls: Arc<SoundControls>,
+
+    // The position in frames and the right sample of the frame being played.
+    position: f64,
+    right: Option<f32>,
+}
+
+impl ClipSource {
+    fn new(clip: &AudioClip, looped: bool, controls: Arc<SoundControls>) -> Self {
+        Self {
+            samples: clip.samples.clone(),
+            channels: clip.channels as usize,
+            sample_rate: clip.sample_rate,
+            looped,
+            controls,
+
+            position: 0.0,
+            right: None,
+        }
+    }
+
+    // The left and right samples at the position, interpolated between two frames.
+    fn frame_at(&self, position: f64) -> (f32, f32) {
+        let frames = self.samples.len() / self.channels;
+        let index = position as usize;
+        let next = if index + 1 < frames {
+            index + 1
+        } else if self.looped {
+            0
+        } else {
+            index
+        };
+        let t = (position - index as f64) as f32;
+
+        // NOTE: a mono clip is heard from both sides, the channels after the second are dropped.
+        let sample = |frame: usize, channel: usize| {
+            self.samples[frame * self.channels + channel.min(self.channels - 1)]
+        };
+        let lerp = |channel: usize| sample(index, channel) * (1.0 - t) + sample(next, channel) * t;
+
+        (lerp(0), lerp(1))
+    }
+}
+
+impl Iterator for ClipSource {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        if let Some(right) = self.right.take() {
+            return Some(right);
+        }
+
+        let frames = (self.samples.len() / self.channels) as f64;
+        if self.position >= frames {
+            if !self.looped || frames == 0.0 {
+                return None;
+            }
+            self.position %= frames;
+        }
+
+        let (left, right) = self.frame_at(self.position);
+        let pan = self.controls.pan();
+
+        self.position += self.controls.pitch() as f64;
+        self.right = Some(right * (1.0 + pan).min(1.0));
+
+        Some(left * (1.0 - pan).min(1.0))
+    }
+}
+
+impl rodio::Source for ClipSource {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        2
+    }
+
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spatial_gain_and_pan() {
+        let config = SpatialAudioConfig {
+            min_distance: 10.0,
+            max_distance: 110.0,
+            rolloff: RolloffModel::Linear,
+        };
+        assert_eq!(config.gain(5.0), 1.0);
+        assert_eq!(config.gain(60.0), 0.5);
+        assert_eq!(config.gain(200.0), 0.0);
+
+        let inverse = SpatialAudioConfig {
+            rolloff: RolloffModel::Inverse,
+            ..config
+        };
+        assert_eq!(inverse.gain(20.0), 0.5);
+
+        let mut audio = Audio::new();
+        let clip = Assets::new().add(AudioClip::new(44100, 1, vec![]));
+        let sound = audio.play_at_position(clip, Vector2::new(60.0, 0.0), config);
+        audio.drain_commands();
+
+        audio.listen(&Transform2D::default());
+        let spatial = audio.playback(sound).unwrap().spatial.unwrap();
+        assert_eq!((spatial.gain, spatial.pan), (0.5, 1.0));
+        assert_eq!(
+            audio.drain_commands(),
+            vec![AudioCommand::SetSpatial(sound, 0.5, 1.0)]
+        );
+
+        // the listener turns 90 degrees, so the sound is in front of it and centered.
+        audio.listen(&Transform2D::with_rotation(90.0));
+        let spatial = audio.playback(sound).unwrap().spatial.unwrap();
+        assert!(spatial.pan.abs() < 1e-4);
+    }
+
+    #[test]
+    fn decode_wav() {
+        let mut wav = Vec::new();
+        wav.extend_from_slice(b"RIFF");
+        wav.extend_from_slice(&36u32.to_le_bytes());
+        wav.extend_from_slice(b"WAVEfmt ");
+        wav.extend_from_slice(&16u32.to_le_bytes());
+        // PCM, mono, 8000 Hz, 16000 bytes/s, block align 2, 16 bits.
+        for field in [1u16, 1].iter() {
+            wav.extend_from_slice(&field.to_le_bytes());
+        }
+        wav.extend_from_slice(&8000u32.to_le_bytes());
+        wav.extend_from_slice(&16000u32.to_le_bytes());
+        for field in [2u16, 16].iter() {
+            wav.extend_from_slice(&field.to_le_bytes());
+        }
+        wav.extend_from_slice(b"data");
+        wav.extend_from_slice(&4u32.to_le_bytes());
+        wav.extend_from_slice(&i16::MIN.to_le_bytes());
+        wav.extend_from_slice(&0i16.to_le_bytes());
+
+        let clip = AudioClip::from_wav(&wav).unwrap();
+        assert_eq!(clip.sample_rate, 8000);
+        assert_eq!(clip.samples, vec![-1.0, 0.0]);
+        assert_eq!(clip.frames(), 2);
+
+        assert!(AudioClip::from_wav(b"RIFF").is_err());
+    }
+
+    #[test]
+    fn play_and_background_music() {
+        let mut clips = Assets::new();
+        let (a, b) = (
+            clips.add(AudioClip::new(44100, 1, vec![])),
+            clips.add(AudioClip::new(44100, 1, vec![])),
+        );
+
+        let mut audio = Audio::new();
+        let sfx = audio.play(a);
+        audio.set_volume(sfx, 0.5);
+        assert_eq!(
+            audio.set_pitch(sfx, 0.0),
+            Err(AudioError::InvalidPitch(0.0))
+        );
+        assert_eq!(audio.playback(sfx).unwrap().volume, 0.5);
+        assert_eq!(audio.playback(sfx).unwrap().pitch, 1.0);
+
+        let mut music = BackgroundMusic::new();
+        let first = music.play(&mut audio, a);
+        assert_eq!(music.play(&mut audio, a), first);
+
+        let second = music.play(&mut audio, b);
+        assert!(!audio.is_playing(first));
+        assert!(audio.playback(second).unwrap().looped);
+
+        music.stop(&mut audio);
+        assert!(!audio.is_playing(second));
+        assert_eq!(music.track(), None);
+
+        // the music started and stopped before the drain are dropped.
+        let commands = audio.drain_commands();
+        assert_eq!(commands.len(), 2);
+        assert_eq!(commands[1], AudioCommand::SetVolume(sfx, 0.5));
+        assert!(audio.drain_commands().is_empty());
+    }
+
+    #[test]
+    fn coalesce_commands() {
+        let clip = Assets::new().add(AudioClip::new(44100, 1, vec![]));
+
+        let mut audio = Audio::new();
+        let sound = audio.play_looped(clip);
+        audio.drain_commands();
+
+        // the volume set every frame replaces the queued one.
+        for i in 0..100 {
+            audio.set_volume(sound, i as f32 / 100.0);
+        }
+        audio.set_pitch(sound, 2.0).unwrap();
+        assert_eq!(
+            audio.pending_commands(),
+            &[
+                AudioCommand::SetVolume(sound, 0.99),
+                AudioCommand::SetPitch(sound, 2.0)
+            ]
+        );
+
+        audio.stop(sound);
+        assert_eq!(audio.pending_commands(), &[AudioCommand::Stop(sound)]);
+    }
+
+    #[test]
+    fn finish_sounds_not_looped() {
+        let mut clips = Assets::new();
+        // 0.5 seconds at 8000 Hz.
+        let clip = clips.add(AudioClip::new(8000, 1, vec![0.0; 4000]));
+
+        let mut audio = Audio::new();
+        let mut output = AudioOutput::silent();
+
+        let once = audio.play(clip);
+        let looped = audio.play_looped(clip);
+        let fast = audio.play(clip);
+        audio.set_pitch(fast, 2.0).unwrap();
+
+        output.update_with(&mut audio, Some(&clips), Duration::from_millis(300));
+        assert!(audio.pending_commands().is_empty());
+        assert!(audio.is_playing(once));
+        assert!(!audio.is_playing(fast));
+
+        output.update_with(&mut audio, Some(&clips), Duration::from_millis(300));
+        assert!(!audio.is_playing(once));
+        assert!(audio.is_playing(looped));
+
+        // the clip not loaded ends at once.
+        let missing = audio.play(Assets::<AudioClip>::new().add(AudioClip::new(8000, 1, vec![])));
+        output.update_with(&mut audio, None, Duration::default());
+        assert!(!audio.is_playing(missing));
+    }
+
+    #[test]
+    fn pan_and_pitch_clip_source() {
+        let clip = AudioClip::new(8000, 1, vec![1.0, 0.0, 0.5, 0.0]);
+        let controls = Arc::new(SoundControls::new(2.0, 0.0));
+
+        // an octave higher skips every other frame, the mono clip is heard from both sides.
+        let source = ClipSource::new(&clip, false, controls.clone());
+        assert_eq!(source.collect::<Vec<_>>(), vec![1.0, 1.0, 0.5, 0.5]);
+
+        // fully panned to the right.
+        controls.set(1.0, 1.0);
+        let mut source = ClipSource::new(&clip, false, controls.clone());
+        assert_eq!(source.next(), Some(0.0));
+        assert_eq!(source.next(), Some(1.0));
+
+        let looped = ClipSource::new(&clip, true, controls);
+        assert_eq!(looped.take(10).count(), 10);
+    }
+}
@@ -0,0 +1,245 @@
+use crate::{
+    assets::{Assets, Handle},
+    components::{
+        sprite::{Rect, Sprite2D},
+        time::Time,
+    },
+    events::Events,
+    legion::{system, Entity},
+};
+
+/// A sequence of atlas regions played by `AnimationPlayer`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnimationClip {
+    /// The regions of the texture in pixels.
+    pub frames: Vec<Rect>,
+    /// The duration of one frame in seconds.
+    pub frame_duration: f32,
+    pub looping: bool,
+}
+
+impl AnimationClip {
+    pub fn new(frames: Vec<Rect>, frame_duration: f32, looping: bool) -> Self {
+        Self {
+            frames,
+            frame_duration,
+            looping,
+        }
+    }
+
+    /// The duration of the whole clip in seconds.
+    pub fn duration(&self) -> f32 {
+        self.frames.len() as f32 * self.frame_duration
+    }
+}
+
+/// Play an `AnimationClip` on the `Sprite2D` of the same entity.
+///
+/// The clip is fetched from the `Assets<AnimationClip>` resource, `animate_sprites_system` does the work.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AnimationPlayer {
+    pub clip: Handle<AnimationClip>,
+    pub current_frame: usize,
+    /// The time elapsed in current frame(in seconds).
+    pub elapsed: f32,
+    pub playing: bool,
+
+    speed: f32,
+    finished: bool,
+}
+
+impl AnimationPlayer {
+    /// Create a playing `AnimationPlayer`.
+    pub fn new(clip: Handle<AnimationClip>) -> Self {
+        Self {
+            clip,
+            current_frame: 0,
+            elapsed: 0.0,
+            playing: true,
+
+            speed: 1.0,
+            finished: false,
+        }
+    }
+
+    /// Resume the playing, or replay the clip if it has finished.
+    pub fn play(&mut self) {
+        if self.finished {
+            self.current_frame = 0;
+            self.elapsed = 0.0;
+            self.finished = false;
+        }
+
+        self.playing = true;
+    }
+
+    pub fn pause(&mut self) {
+        self.playing = false;
+    }
+
+    /// Stop playing and rewind to the first frame.
+    pub fn stop(&mut self) {
+        self.playing = false;
+        self.finished = false;
+        self.current_frame = 0;
+        self.elapsed = 0.0;
+    }
+
+    pub fn speed(&self) -> f32 {
+        self.speed
+    }
+
+    /// Scale the playing speed, negative scale will be clamped to 0.
+    pub fn set_speed(&mut self, scale: f32) {
+        self.speed = scale.max(0.0);
+    }
+
+    /// Whether a non-looping clip has played to the end.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Advance the playing by `dt` seconds, return true if a non-looping clip plays to the end in this call.
+    pub fn advance(&mut self, clip: &AnimationClip, dt: f32) -> bool {
+        if !self.playing || clip.frames.is_empty() || clip.frame_duration <= 0.0 {
+            return false;
+        }
+
+        self.elapsed += dt * self.speed;
+
+        while self.elapsed >= clip.frame_duration {
+            self.elapsed -= clip.frame_duration;
+
+            if self.current_frame + 1 < clip.frames.len() {
+                self.current_frame += 1;
+            } else if clip.looping {
+                self.current_frame = 0;
+            } else {
+                self.elapsed = 0.0;
+                self.playing = false;
+                self.finished = true;
+                return true;
+            }
+        }
+
+        false
+    }
+}
+
+/// Sent when the non-looping clip of the `AnimationPlayer` on the entity plays to the end.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AnimationFinished(pub Entity);
+
+/// Advance `AnimationPlayer`s and write the current frame to their `Sprite2D`s.
+///
+/// It writes `Events<AnimationFinished>`, add it by `AppStageBuilder::add_event::<AnimationFinished>()`.
+#[system(for_each)]
+pub fn animate_sprites(
+    entity: &Entity,
+    player: &mut AnimationPlayer,
+    sprite: &mut Sprite2D,
+    #[resource] clips: &Assets<AnimationClip>,
+    #[resource] time: &Time,
+    #[resource] finished: &mut Events<AnimationFinished>,
+) {
+    let clip = match clips.get(player.clip) {
+        Some(clip) if !clip.frames.is_empty() => clip,
+        _ => return,
+    };
+
+    if player.advance(clip, time.delta().as_secs_f32()) {
+        finished.send(AnimationFinished(*entity));
+    }
+
+    let frame = usize::min(player.current_frame, clip.frames.len() - 1);
+    sprite.region = Some(clip.frames[frame]);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        components::sprite::TextureAtlas,
+        legion::{IntoQuery, Resources, Schedule, World},
+        nalgebra::Vector2,
+    };
+
+    fn clip(looping: bool) -> AnimationClip {
+        AnimationClip::new(
+            (0..3)
+                .map(|i| Rect::new(i as f32 * 16.0, 0.0, 16.0, 16.0))
+                .collect(),
+            0.1,
+            looping,
+        )
+    }
+
+    #[test]
+    fn advance_frames() {
+        let mut clips = Assets::new();
+        let looping = clip(true);
+        let mut player = AnimationPlayer::new(clips.add(looping.clone()));
+
+        assert!(!player.advance(&looping, 0.25));
+        assert_eq!(player.current_frame, 2);
+
+        // wrap around without finishing.
+        assert!(!player.advance(&looping, 0.1));
+        assert_eq!(player.current_frame, 0);
+
+        let once = clip(false);
+        let mut player = AnimationPlayer::new(clips.add(once.clone()));
+        player.set_speed(2.0);
+
+        assert!(player.advance(&once, 0.2));
+        assert!(player.is_finished() && !player.playing);
+        assert_eq!(player.current_frame, 2);
+        // finished only once.
+        assert!(!player.advance(&once, 1.0));
+
+        player.play();
+        assert_eq!(player.current_frame, 0);
+        assert!(player.playing);
+    }
+
+    #[test]
+    fn send_animation_finished() {
+        let mut world = World::default();
+        let mut resources = Resources::default();
+
+        let mut clips = Assets::new();
+        let handle = clips.add(clip(false));
+        let texture = TextureAtlas::new()
+            .insert_rgba(48, 16, vec![0; 48 * 16 * 4])
+            .unwrap();
+
+        // at the end of the last frame, finishes in the next update even if no time elapsed.
+        let mut player = AnimationPlayer::new(handle);
+        player.current_frame = 2;
+        player.elapsed = 0.1;
+        let entity = world.push((player, Sprite2D::new(texture, Vector2::new(16.0, 16.0))));
+
+        resources.insert(clips);
+        resources.insert(Time::now());
+        resources.insert(Events::<AnimationFinished>::new());
+
+        let mut schedule = Schedule::builder()
+            .add_system(animate_sprites_system())
+            .build();
+
+        schedule.execute(&mut world, &mut resources);
+        schedule.execute(&mut world, &mut resources);
+
+        let events = resources.get::<Events<AnimationFinished>>().unwrap();
+        assert_eq!(
+            events.iter().copied().collect::<Vec<_>>(),
+            vec![AnimationFinished(entity)]
+        );
+
+        let (player, sprite) = <(&AnimationPlayer, &Sprite2D)>::query()
+            .get(&world, entity)
+            .unwrap();
+        assert!(player.is_finished());
+        assert_eq!(sprite.region, Some(Rect::new(32.0, 0.0, 16.0, 16.0)));
+    }
+}
@@ -0,0 +1,58 @@
+/// How the entity blends with what is already rendered, entities without `BlendMode` are blended by
+/// `Alpha` in the fastest path.
+///
+/// The render stage draws `Opaque` entities first, then the others by mode, each mode is sorted from back
+/// to front by order.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BlendMode {
+    /// Cover the target and write the depth.
+    Opaque,
+    #[default]
+    Alpha,
+    /// Add to the target, e.g. fire and glow.
+    Additive,
+    /// Multiply the target, the result is darker.
+    Multiply,
+    /// The inverse of multiplying the inverses, the result is brighter.
+    Screen,
+    /// NOTE: blending can't branch on the target color, so overlay is approximated by multiplying twice,
+    /// which is the dark half of overlay.
+    Overlay,
+}
+
+impl BlendMode {
+    /// All modes in the order they are rendered.
+    pub const ALL: [BlendMode; 6] = [
+        BlendMode::Opaque,
+        BlendMode::Alpha,
+        BlendMode::Additive,
+        BlendMode::Multiply,
+        BlendMode::Screen,
+        BlendMode::Overlay,
+    ];
+
+    /// The index of mode in `BlendMode::ALL`.
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn alpha_by_default() {
+        assert_eq!(BlendMode::default(), BlendMode::Alpha);
+    }
+
+    #[test]
+    fn index_in_render_order() {
+        for (index, mode) in BlendMode::ALL.iter().enumerate() {
+            assert_eq!(mode.index(), index);
+        }
+
+        // opaque entities are drawn first.
+        assert_eq!(BlendMode::Opaque.index(), 0);
+    }
+}
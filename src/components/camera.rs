@@ -1,25 +1,303 @@
 use crate::{
-    nalgebra::{Matrix4, Orthographic3},
+    assets::Handle,
+    components::{time::Time, transform::Transform2D},
+    events::{EventReader, Events},
+    legion::{
+        systems::{ParallelRunnable, SystemBuilder},
+        IntoQuery,
+    },
+    misc::viewport::Viewport,
+    nalgebra::{Matrix4, Orthographic3, Point2, Vector2},
+    window::{Window, WindowResized},
     DEFAULT_HEIGHT, DEFAULT_WIDTH,
 };
 
+pub type RenderTextureHandle = Handle<RenderTexture>;
+
+/// Where a `Camera2D` renders to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RenderTarget {
+    /// The swap chain of the window.
+    Screen,
+    /// An off-screen framebuffer stored in the `Assets<RenderTexture>` resource.
+    Texture(RenderTextureHandle),
+}
+
+impl Default for RenderTarget {
+    fn default() -> Self {
+        Self::Screen
+    }
+}
+
+/// An off-screen framebuffer in pixels, add it to the `Assets<RenderTexture>` resource and assign the
+/// handle to `Camera2D::render_target`.
+///
+/// The gpu texture is created by the render stage when a camera first renders to it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RenderTexture {
+    pub width: u32,
+    pub height: u32,
+}
+
+impl RenderTexture {
+    pub fn new(width: u32, height: u32) -> Self {
+        assert!(
+            width > 0 && height > 0,
+            "ERR: the size of render texture must be positive"
+        );
+
+        Self { width, height }
+    }
+}
+
+/// The layer of the entity, a camera only renders the entities whose layer is in its `layer_mask`.
+///
+/// Entities without `RenderLayer` are in layer 0.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RenderLayer(pub u32);
+
+impl RenderLayer {
+    /// The layer of HUD and other overlays, the last of the 32 layers.
+    pub fn for_ui() -> Self {
+        Self(31)
+    }
+
+    /// The bit of the layer in `Camera2D::layer_mask`.
+    pub fn mask(&self) -> u32 {
+        1u32.checked_shl(self.0).unwrap_or(0)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
 pub struct Camera2D {
     pub width: u32,
     pub height: u32,
+    /// Cameras are rendered from low priority to high priority.
+    pub priority: i32,
+    /// The layers the camera sees, bit `n` is `RenderLayer(n)`.
+    pub layer_mask: u32,
+    pub render_target: RenderTarget,
+    /// The half height of the view in world units, the half width follows the aspect ratio.
+    ///
+    /// `None` means one world unit a pixel, the view is `width` x `height`.
+    pub orthographic_size: Option<f32>,
+    /// The width / height ratio the camera always renders in, the rest of the target is left as black
+    /// bars(letterbox or pillarbox).
+    ///
+    /// `None` means the ratio of `width` and `height`.
+    pub fixed_aspect: Option<f32>,
+
+    // The rect of target the camera renders to in normalized coordinates, (top left corner, size).
+    viewport: (Vector2<f32>, Vector2<f32>),
+    zoom: Option<ZoomToFit>,
+}
+
+// The animation started by `Camera2D::zoom_to_fit`.
+#[derive(Debug, Clone, Copy)]
+struct ZoomToFit {
+    rect_min: Vector2<f32>,
+    rect_max: Vector2<f32>,
+    padding: f32,
+    duration: f32,
+    elapsed: f32,
+    // the position and orthographic size when the animation starts, taken in the first update.
+    from: Option<(Vector2<f32>, f32)>,
 }
 
 impl Camera2D {
     pub fn new(width: u32, height: u32) -> Self {
-        Self { width, height }
+        Self {
+            width,
+            height,
+            priority: 0,
+            layer_mask: u32::MAX,
+            render_target: RenderTarget::Screen,
+            orthographic_size: None,
+            fixed_aspect: None,
+
+            viewport: (Vector2::new(0.0, 0.0), Vector2::new(1.0, 1.0)),
+            zoom: None,
+        }
+    }
+
+    pub fn priority(mut self, priority: i32) -> Self {
+        self.priority = priority;
+        self
     }
 
+    pub fn layer_mask(mut self, layer_mask: u32) -> Self {
+        self.layer_mask = layer_mask;
+        self
+    }
+
+    pub fn render_target(mut self, target: RenderTarget) -> Self {
+        self.render_target = target;
+        self
+    }
+
+    pub fn with_orthographic_size(mut self, units: f32) -> Self {
+        self.set_orthographic_size(units);
+        self
+    }
+
+    /// Set the half height of the view in world units, so the view keeps the same world area when the
+    /// resolution changes.
+    pub fn set_orthographic_size(&mut self, units: f32) {
+        assert!(units > 0.0, "ERR: the orthographic size must be positive");
+
+        self.orthographic_size = Some(units);
+    }
+
+    pub fn with_fixed_aspect(mut self, ratio: f32) -> Self {
+        self.set_fixed_aspect(ratio);
+        self
+    }
+
+    /// Keep the view in the width / height `ratio` whatever the size of target is, e.g. `16.0 / 9.0`.
+    pub fn set_fixed_aspect(&mut self, ratio: f32) {
+        assert!(
+            ratio.is_finite() && ratio > 0.0,
+            "ERR: the fixed aspect ratio must be positive"
+        );
+
+        self.fixed_aspect = Some(ratio);
+    }
+
+    pub fn with_viewport(mut self, x: f32, y: f32, w: f32, h: f32) -> Self {
+        self.set_viewport(x, y, w, h);
+        self
+    }
+
+    /// Render to the rect of target in normalized coordinates, `(x, y)` is the top left corner and `(0, 0, 1, 1)`
+    /// is the whole target, e.g. `(0.0, 0.0, 0.5, 1.0)` is the left half for split-screen.
+    ///
+    /// The parts outside the target are cut off. The view keeps the aspect ratio of rect unless `fixed_aspect`
+    /// is set.
+    pub fn set_viewport(&mut self, x: f32, y: f32, w: f32, h: f32) {
+        let (min, max) = (
+            Vector2::new(x.max(0.0), y.max(0.0)),
+            Vector2::new((x + w).min(1.0), (y + h).min(1.0)),
+        );
+        assert!(
+            max.x > min.x && max.y > min.y,
+            "ERR: the viewport must overlap the target"
+        );
+
+        self.viewport = (min, max - min);
+    }
+
+    /// The rect of target the camera renders to in normalized coordinates, (top left corner, size).
+    pub fn viewport(&self) -> (Vector2<f32>, Vector2<f32>) {
+        self.viewport
+    }
+
+    /// Move and zoom the camera in `duration` seconds, until the rect in `world space` is fully visible with
+    /// `padding` world units around it, the aspect ratio is kept.
+    ///
+    /// It's animated by `camera_zoom_system`, and sets `orthographic_size` of the camera. It replaces the
+    /// zoom in progress.
+    pub fn zoom_to_fit(
+        &mut self,
+        rect_min: Vector2<f32>,
+        rect_max: Vector2<f32>,
+        padding: f32,
+        duration: f32,
+    ) {
+        self.zoom = Some(ZoomToFit {
+            rect_min: rect_min.inf(&rect_max),
+            rect_max: rect_min.sup(&rect_max),
+            padding: padding.max(0.0),
+            duration: duration.max(0.0),
+            elapsed: 0.0,
+            from: None,
+        });
+    }
+
+    /// Fit the rect in the next run of `camera_zoom_system`, see `Camera2D::zoom_to_fit`.
+    pub fn zoom_to_fit_instant(
+        &mut self,
+        rect_min: Vector2<f32>,
+        rect_max: Vector2<f32>,
+        padding: f32,
+    ) {
+        self.zoom_to_fit(rect_min, rect_max, padding, 0.0);
+    }
+
+    pub fn is_zooming(&self) -> bool {
+        self.zoom.is_some()
+    }
+
+    /// Stop the zoom in progress where it is.
+    pub fn cancel_zoom(&mut self) {
+        self.zoom = None;
+    }
+
+    /// The half width and half height of the view in world units.
+    pub fn half_extents(&self) -> (f32, f32) {
+        match self.orthographic_size {
+            Some(half_height) => (half_height * self.aspect_ratio(), half_height),
+            None => {
+                let viewport = self.viewport_in(self.width as f32, self.height as f32);
+                (viewport.w / 2.0, viewport.h / 2.0)
+            }
+        }
+    }
+
+    /// The world units a pixel of the viewport covers.
+    pub fn pixel_size(&self) -> f32 {
+        let (_, half_height) = self.half_extents();
+        let viewport = self.viewport_in(self.width as f32, self.height as f32);
+
+        2.0 * half_height / viewport.h.max(1.0)
+    }
+
+    /// The axis-aligned bounds of the view in `world space`, (min, max), the camera may be rotated.
+    pub fn world_bounds(&self, transform: &Transform2D) -> (Vector2<f32>, Vector2<f32>) {
+        let (hw, hh) = self.half_extents();
+
+        let (mut min, mut max) = (
+            Vector2::new(f32::MAX, f32::MAX),
+            Vector2::new(f32::MIN, f32::MIN),
+        );
+        for &(x, y) in [(-hw, -hh), (hw, -hh), (hw, hh), (-hw, hh)].iter() {
+            let corner = transform.transform_point2(&Point2::new(x, y)).coords;
+            min = min.inf(&corner);
+            max = max.sup(&corner);
+        }
+
+        (min, max)
+    }
+
+    /// The rect the camera renders to on the window in logical pixels, (top left corner, size).
+    ///
+    /// It's the whole window unless the viewport is set or `fixed_aspect` doesn't match the window.
+    pub fn viewport_rect(&self, window: &Window) -> (Vector2<f32>, Vector2<f32>) {
+        let (width, height) = window.logical_size();
+        let viewport = self.viewport_in(width, height);
+
+        (
+            Vector2::new(viewport.x, viewport.y),
+            Vector2::new(viewport.w, viewport.h),
+        )
+    }
+
+    /// Whether the camera sees the entities in the layer.
+    pub fn sees(&self, layer: Option<&RenderLayer>) -> bool {
+        let layer = layer.copied().unwrap_or_default();
+
+        self.layer_mask & layer.mask() != 0
+    }
+
+    /// `fixed_aspect` if it's set, otherwise the ratio of the viewport in `width` x `height`.
     pub fn aspect_ratio(&self) -> f32 {
-        self.width as f32 / self.height as f32
+        let (_, size) = self.viewport;
+
+        self.fixed_aspect
+            .unwrap_or((self.width as f32 * size.x) / (self.height as f32 * size.y))
     }
 
     pub fn to_orthographic(&self) -> Orthographic3<f32> {
-        let half_wdith = self.width as f32 / 2.0;
-        let half_height = self.height as f32 / 2.0;
+        let (half_wdith, half_height) = self.half_extents();
 
         Orthographic3::new(
             -half_wdith,
@@ -34,6 +312,52 @@ impl Camera2D {
     pub fn to_orthographic_homogeneous(&self) -> Matrix4<f32> {
         self.to_orthographic().to_homogeneous()
     }
+
+    // Advance the zoom animation by `dt` seconds.
+    fn update_zoom(&mut self, transform: &mut Transform2D, dt: f32) {
+        let mut zoom = match self.zoom {
+            Some(zoom) => zoom,
+            None => return,
+        };
+        let (from_position, from_size) = *zoom
+            .from
+            .get_or_insert((transform.position, self.half_extents().1));
+
+        // NOTE: the rect is fitted in the axes of world, the rotation of camera is ignored.
+        let half_size =
+            (zoom.rect_max - zoom.rect_min) / 2.0 + Vector2::new(zoom.padding, zoom.padding);
+        let (sx, sy) = (transform.scale.x.abs(), transform.scale.y.abs());
+        let to_size = (half_size.y / sy)
+            .max(half_size.x / (self.aspect_ratio() * sx))
+            .max(f32::EPSILON);
+        let to_position = (zoom.rect_min + zoom.rect_max) / 2.0;
+
+        zoom.elapsed += dt;
+        let t = if zoom.duration > 0.0 {
+            (zoom.elapsed / zoom.duration).min(1.0)
+        } else {
+            1.0
+        };
+        // ease in and out.
+        let t = t * t * (3.0 - 2.0 * t);
+
+        transform.position = from_position.lerp(&to_position, t);
+        self.orthographic_size = Some(from_size + (to_size - from_size) * t);
+        self.zoom = if t < 1.0 { Some(zoom) } else { None };
+    }
+
+    /// The largest rect of `aspect_ratio` centered in the viewport of camera on the target of `width` x
+    /// `height`.
+    pub(crate) fn viewport_in(&self, width: f32, height: f32) -> Viewport {
+        let (position, size) = self.viewport;
+
+        let mut viewport =
+            Viewport::new_in_screen(width * size.x, height * size.y, self.aspect_ratio());
+        viewport.x += width * position.x;
+        viewport.y += height * position.y;
+
+        viewport
+    }
 }
 
 impl Default for Camera2D {
@@ -41,3 +365,160 @@ impl Default for Camera2D {
         Self::new(DEFAULT_WIDTH, DEFAULT_HEIGHT)
     }
 }
+
+/// Create a system which resizes the screen cameras to the logical size of window when it's resized, so
+/// the projection follows the new aspect ratio instead of being letterboxed, unless `fixed_aspect` is set.
+///
+/// It reads `Events<WindowResized>`, which only exists when the `App` runs with a window.
+pub fn window_resize_system() -> impl ParallelRunnable {
+    let mut reader = EventReader::<WindowResized>::new();
+
+    SystemBuilder::new("window_resize")
+        .read_resource::<Events<WindowResized>>()
+        .with_query(<&mut Camera2D>::query())
+        .build(move |_, world, events, cameras| {
+            let resized = match reader.read(events).last() {
+                Some(resized) => *resized,
+                None => return,
+            };
+
+            let (width, height) = (
+                resized.logical.0.round() as u32,
+                resized.logical.1.round() as u32,
+            );
+            // NOTE: a minimized window has zero size.
+            if width == 0 || height == 0 {
+                return;
+            }
+
+            cameras.for_each_mut(world, |camera| {
+                if camera.render_target == RenderTarget::Screen {
+                    camera.width = width;
+                    camera.height = height;
+                }
+            });
+        })
+}
+
+/// Create a system which animates the zooms started by `Camera2D::zoom_to_fit`.
+pub fn camera_zoom_system() -> impl ParallelRunnable {
+    SystemBuilder::new("camera_zoom")
+        .read_resource::<Time>()
+        .with_query(<(&mut Camera2D, &mut Transform2D)>::query())
+        .build(move |_, world, time, query| {
+            let dt = time.delta().as_secs_f32();
+
+            query.for_each_mut(world, |(camera, transform)| {
+                camera.update_zoom(transform, dt)
+            });
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn camera_sees_layers_in_mask() {
+        let world = Camera2D::new(800, 600).layer_mask(!RenderLayer::for_ui().mask());
+        let hud = Camera2D::new(800, 600).layer_mask(RenderLayer::for_ui().mask());
+
+        assert!(world.sees(None));
+        assert!(world.sees(Some(&RenderLayer::default())));
+        assert!(!world.sees(Some(&RenderLayer::for_ui())));
+
+        assert!(!hud.sees(None));
+        assert!(hud.sees(Some(&RenderLayer::for_ui())));
+
+        // layers out of the 32 bits are never rendered.
+        assert!(!Camera2D::default().sees(Some(&RenderLayer(32))));
+    }
+
+    #[test]
+    fn orthographic_size_follows_aspect_ratio() {
+        let mut camera = Camera2D::new(800, 400);
+        assert_eq!(camera.half_extents(), (400.0, 200.0));
+        assert_eq!(camera.pixel_size(), 1.0);
+
+        camera.set_orthographic_size(10.0);
+        assert_eq!(camera.half_extents(), (20.0, 10.0));
+        assert_eq!(camera.pixel_size(), 0.05);
+
+        camera.width = 400;
+        assert_eq!(camera.half_extents(), (10.0, 10.0));
+    }
+
+    #[test]
+    fn fixed_aspect_is_letterboxed() {
+        // a 16:9 view in a 4:3 target has black bars at the top and bottom.
+        let camera = Camera2D::new(1600, 1200).with_fixed_aspect(16.0 / 9.0);
+        assert_eq!(camera.half_extents(), (800.0, 450.0));
+
+        let viewport = camera.viewport_in(1600.0, 1200.0);
+        assert_eq!((viewport.x, viewport.y), (0.0, 150.0));
+        assert_eq!((viewport.w, viewport.h), (1600.0, 900.0));
+
+        // pillarbox in a wider target.
+        let viewport = camera.viewport_in(2000.0, 900.0);
+        assert_eq!((viewport.x, viewport.w), (200.0, 1600.0));
+    }
+
+    #[test]
+    fn split_screen_viewports() {
+        let left = Camera2D::new(1600, 900).with_viewport(0.0, 0.0, 0.5, 1.0);
+        let right = Camera2D::new(1600, 900).with_viewport(0.5, 0.0, 0.5, 1.0);
+
+        // each half keeps one world unit a pixel.
+        assert_eq!(left.aspect_ratio(), 800.0 / 900.0);
+        assert_eq!(left.half_extents(), (400.0, 450.0));
+
+        let viewport = left.viewport_in(1600.0, 900.0);
+        assert_eq!(
+            (viewport.x, viewport.y, viewport.w, viewport.h),
+            (0.0, 0.0, 800.0, 900.0)
+        );
+        let viewport = right.viewport_in(1600.0, 900.0);
+        assert_eq!(
+            (viewport.x, viewport.y, viewport.w, viewport.h),
+            (800.0, 0.0, 800.0, 900.0)
+        );
+
+        // the fixed aspect is letterboxed in the viewport.
+        let viewport = right
+            .with_fixed_aspect(16.0 / 9.0)
+            .viewport_in(1600.0, 900.0);
+        assert_eq!((viewport.x, viewport.y), (800.0, 225.0));
+        assert_eq!((viewport.w, viewport.h), (800.0, 450.0));
+
+        // the part out of the target is cut off.
+        let camera = Camera2D::new(800, 600).with_viewport(0.75, -0.5, 0.5, 1.0);
+        assert_eq!(
+            camera.viewport(),
+            (Vector2::new(0.75, 0.0), Vector2::new(0.25, 0.5))
+        );
+    }
+
+    #[test]
+    fn zoom_to_fit_rect() {
+        let mut camera = Camera2D::new(800, 400);
+        let mut transform = Transform2D::default();
+
+        // the rect is 20 x 4, the width decides the zoom.
+        camera.zoom_to_fit(Vector2::new(10.0, -2.0), Vector2::new(30.0, 2.0), 1.0, 1.0);
+        camera.update_zoom(&mut transform, 0.5);
+        assert!(camera.is_zooming());
+        assert_eq!(transform.position, Vector2::new(10.0, 0.0));
+        assert_eq!(camera.orthographic_size, Some(102.75));
+
+        camera.update_zoom(&mut transform, 0.6);
+        assert!(!camera.is_zooming());
+        assert_eq!(transform.position, Vector2::new(20.0, 0.0));
+        assert_eq!(camera.half_extents(), (11.0, 5.5));
+
+        // the height decides the zoom of a tall rect.
+        camera.zoom_to_fit_instant(Vector2::new(-1.0, -8.0), Vector2::new(1.0, 8.0), 0.0);
+        camera.update_zoom(&mut transform, 0.0);
+        assert_eq!(transform.position, Vector2::new(0.0, 0.0));
+        assert_eq!(camera.half_extents(), (16.0, 8.0));
+    }
+}
@@ -0,0 +1,192 @@
+use crate::{
+    components::{time::Time, transform::Transform2D},
+    events::{EventReader, Events},
+    legion::{
+        systems::{ParallelRunnable, SystemBuilder},
+        Entity, IntoQuery,
+    },
+    math::noise::Noise2D,
+    nalgebra::Vector2,
+};
+
+// The rows of noise sampled by the offset x, offset y and angle, far enough to be uncorrelated.
+const NOISE_ROW_X: f32 = 0.0;
+const NOISE_ROW_Y: f32 = 64.0;
+const NOISE_ROW_ANGLE: f32 = 128.0;
+
+/// Shake the camera, processed by `camera_shake_system`.
+///
+/// The strength of shake is `trauma²`, so small trauma barely shakes and stacked trauma shakes hard. The
+/// shake is applied when rendering, the `Transform2D` of camera is left untouched.
+#[derive(Debug, Clone)]
+pub struct CameraShake {
+    /// The shake strength in [0, 1].
+    pub trauma: f32,
+    /// The trauma lost per second.
+    pub decay: f32,
+    /// The max offset in `world space` at full trauma.
+    pub max_offset: Vector2<f32>,
+    /// The max rotation in degrees at full trauma.
+    pub max_angle: f32,
+    /// How fast the shake changes, in noise cells per second.
+    pub frequency: f32,
+
+    noise: Noise2D,
+    elapsed: f32,
+    // The perturbation of this frame.
+    offset: Vector2<f32>,
+    angle: f32,
+}
+
+impl CameraShake {
+    pub fn new() -> Self {
+        Self {
+            trauma: 0.0,
+            decay: 1.0,
+            max_offset: Vector2::new(16.0, 16.0),
+            max_angle: 5.0,
+            frequency: 15.0,
+
+            noise: Noise2D::default(),
+            elapsed: 0.0,
+            offset: Vector2::new(0.0, 0.0),
+            angle: 0.0,
+        }
+    }
+
+    pub fn with_decay(mut self, decay: f32) -> Self {
+        self.decay = decay;
+        self
+    }
+
+    pub fn with_max_offset(mut self, max_offset: Vector2<f32>) -> Self {
+        self.max_offset = max_offset;
+        self
+    }
+
+    pub fn with_max_angle(mut self, max_angle: f32) -> Self {
+        self.max_angle = max_angle;
+        self
+    }
+
+    pub fn with_frequency(mut self, frequency: f32) -> Self {
+        self.frequency = frequency;
+        self
+    }
+
+    /// Use another noise so cameras don't shake the same way.
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.noise = Noise2D::new(seed);
+        self
+    }
+
+    /// Add trauma, the result is clamped to [0, 1].
+    pub fn add_trauma(&mut self, amount: f32) {
+        self.trauma = (self.trauma + amount).max(0.0).min(1.0);
+    }
+
+    /// The current shake strength in [0, 1].
+    pub fn strength(&self) -> f32 {
+        self.trauma * self.trauma
+    }
+
+    /// The offset in `world space` of this frame.
+    pub fn offset(&self) -> Vector2<f32> {
+        self.offset
+    }
+
+    /// The rotation in degrees of this frame.
+    pub fn angle(&self) -> f32 {
+        self.angle
+    }
+
+    /// The transform of camera with the shake of this frame, used by the render stage.
+    pub fn apply(&self, transform: &Transform2D) -> Transform2D {
+        let mut shaken = *transform;
+        shaken.position += self.offset;
+        shaken.rotate(self.angle);
+
+        shaken
+    }
+
+    /// Decay the trauma and update the shake by `dt` seconds.
+    pub fn update(&mut self, dt: f32) {
+        self.trauma = (self.trauma - self.decay * dt).max(0.0);
+        self.elapsed += dt;
+
+        let strength = self.strength();
+        let x = self.elapsed * self.frequency;
+
+        self.offset = Vector2::new(
+            self.max_offset.x * strength * self.noise.simplex(x, NOISE_ROW_X),
+            self.max_offset.y * strength * self.noise.simplex(x, NOISE_ROW_Y),
+        );
+        self.angle = self.max_angle * strength * self.noise.simplex(x, NOISE_ROW_ANGLE);
+    }
+}
+
+impl Default for CameraShake {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Add trauma to the `CameraShake` of the entity.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ShakeEvent(pub Entity, pub f32);
+
+/// Create a system which applies `ShakeEvent`s and shakes the cameras with `CameraShake`.
+///
+/// It reads `Events<ShakeEvent>`, add it by `AppStageBuilder::add_event::<ShakeEvent>()`.
+pub fn camera_shake_system() -> impl ParallelRunnable {
+    let mut reader = EventReader::<ShakeEvent>::new();
+
+    SystemBuilder::new("camera_shake")
+        .read_resource::<Time>()
+        .read_resource::<Events<ShakeEvent>>()
+        .with_query(<&mut CameraShake>::query())
+        .build(move |_, world, (time, events), query| {
+            for ShakeEvent(entity, amount) in reader.read(events) {
+                if let Ok(shake) = query.get_mut(world, *entity) {
+                    shake.add_trauma(*amount);
+                }
+            }
+
+            let dt = time.delta().as_secs_f32();
+            query.for_each_mut(world, |shake| shake.update(dt));
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shake_decays_and_restores_transform() {
+        let mut shake = CameraShake::new().with_decay(0.5);
+        let transform = Transform2D::at(10.0, 20.0);
+
+        shake.add_trauma(0.6);
+        shake.add_trauma(0.6);
+        assert_eq!(shake.trauma, 1.0);
+
+        for _ in 0..10 {
+            shake.update(0.05);
+        }
+        assert!((shake.trauma - 0.75).abs() < 1e-5);
+
+        let shaken = shake.apply(&transform);
+        assert!((shaken.position - transform.position - shake.offset()).norm() < 1e-5);
+        assert!((shaken.angle() - shake.angle()).abs() < 1e-3);
+
+        // trauma runs out after 1.5 seconds, the shake goes back to none.
+        for _ in 0..40 {
+            shake.update(0.05);
+        }
+        assert_eq!(shake.trauma, 0.0);
+
+        let shaken = shake.apply(&transform);
+        assert!((shaken.position - Vector2::new(10.0, 20.0)).norm() < 1e-3);
+        assert!(shaken.angle().abs() < 1e-3);
+    }
+}
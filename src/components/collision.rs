@@ -0,0 +1,838 @@
+use crate::{
+    components::{
+        geometry::{closest_on_line, contains_point, Assembly, Geometry, GeometryType},
+        transform::Transform2D,
+    },
+    legion::{system, world::SubWorld, Entity, Query},
+    nalgebra::{Point2, Vector2},
+};
+
+use std::{cmp::Ordering, collections::HashMap};
+
+/// The axis-aligned bounding box in `world space`.
+///
+/// Add it to an entity with `Transform2D` and `Geometry`(or `Assembly`), then `compute_aabb_system`
+/// keeps it up to date.
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AABB2D {
+    pub min: Vector2<f32>,
+    pub max: Vector2<f32>,
+}
+
+impl AABB2D {
+    pub fn new(min: Vector2<f32>, max: Vector2<f32>) -> Self {
+        Self { min, max }
+    }
+
+    pub fn from_center(center: Vector2<f32>, half_size: Vector2<f32>) -> Self {
+        Self {
+            min: center - half_size,
+            max: center + half_size,
+        }
+    }
+
+    pub fn center(&self) -> Vector2<f32> {
+        (self.min + self.max) / 2.0
+    }
+
+    pub fn size(&self) -> Vector2<f32> {
+        self.max - self.min
+    }
+
+    /// Whether the box contains nothing, the `AABB2D::default()` is empty.
+    pub fn is_empty(&self) -> bool {
+        self.min.x > self.max.x || self.min.y > self.max.y
+    }
+
+    pub fn overlaps(&self, other: &AABB2D) -> bool {
+        self.min.x <= other.max.x
+            && self.max.x >= other.min.x
+            && self.min.y <= other.max.y
+            && self.max.y >= other.min.y
+    }
+
+    pub fn contains_point(&self, point: &Vector2<f32>) -> bool {
+        self.min.x <= point.x
+            && point.x <= self.max.x
+            && self.min.y <= point.y
+            && point.y <= self.max.y
+    }
+
+    pub fn overlaps_circle(&self, center: &Vector2<f32>, radius: f32) -> bool {
+        let closest = Vector2::new(
+            center.x.max(self.min.x).min(self.max.x),
+            center.y.max(self.min.y).min(self.max.y),
+        );
+
+        (closest - center).norm_squared() <= radius * radius
+    }
+
+    /// The smallest box contains both boxes.
+    pub fn union(&self, other: &AABB2D) -> AABB2D {
+        AABB2D {
+            min: Vector2::new(self.min.x.min(other.min.x), self.min.y.min(other.min.y)),
+            max: Vector2::new(self.max.x.max(other.max.x), self.max.y.max(other.max.y)),
+        }
+    }
+
+    /// Expand the box to contain the point.
+    pub fn extend(&mut self, point: &Vector2<f32>) {
+        self.min = Vector2::new(self.min.x.min(point.x), self.min.y.min(point.y));
+        self.max = Vector2::new(self.max.x.max(point.x), self.max.y.max(point.y));
+    }
+
+    /// The bounding box of the geometry in `local space`.
+    ///
+    /// Returns `None` for `Line` and `Ray`, they are infinite.
+    pub fn from_geometry(geometry: &Geometry) -> Option<AABB2D> {
+        match geometry.geometry_type() {
+            GeometryType::Line | GeometryType::Ray => None,
+            GeometryType::Segment => {
+                let mut aabb = AABB2D::default();
+                aabb.extend(&geometry.start_point_uncheck());
+                aabb.extend(&geometry.end_point_uncheck());
+
+                Some(aabb)
+            }
+            _ => {
+                // NOTE: 2d geometry is drawn in a square of side `size`, the bound is independent of the angle.
+                let half = geometry.size_uncheck() * std::f32::consts::FRAC_1_SQRT_2;
+
+                Some(AABB2D::from_center(
+                    geometry.position_uncheck(),
+                    Vector2::new(half, half),
+                ))
+            }
+        }
+    }
+
+    /// Transform the box from `local space` to `world space`, the result contains the transformed box.
+    pub fn transformed(&self, transform: &Transform2D) -> AABB2D {
+        let mut aabb = AABB2D::default();
+
+        for corner in [
+            Point2::new(self.min.x, self.min.y),
+            Point2::new(self.max.x, self.min.y),
+            Point2::new(self.max.x, self.max.y),
+            Point2::new(self.min.x, self.max.y),
+        ]
+        .iter()
+        {
+            aabb.extend(&transform.transform_point2(corner).coords);
+        }
+
+        aabb
+    }
+}
+
+impl Default for AABB2D {
+    fn default() -> Self {
+        Self {
+            min: Vector2::new(f32::INFINITY, f32::INFINITY),
+            max: Vector2::new(f32::NEG_INFINITY, f32::NEG_INFINITY),
+        }
+    }
+}
+
+/// Update `AABB2D` of entities from their `Transform2D` and `Geometry` or `Assembly`.
+#[system(for_each)]
+pub fn compute_aabb(
+    transform: &Transform2D,
+    geometry: Option<&Geometry>,
+    assembly: Option<&Assembly>,
+    aabb: &mut AABB2D,
+) {
+    let local = geometry
+        .into_iter()
+        .chain(assembly.into_iter().flatten())
+        .filter_map(AABB2D::from_geometry)
+        .fold(AABB2D::default(), |acc, aabb| acc.union(&aabb));
+
+    *aabb = if local.is_empty() {
+        local
+    } else {
+        local.transformed(transform)
+    };
+}
+
+/// A uniform grid which buckets entities by their `AABB2D`, used to query entities in an area quickly.
+///
+/// The boxes spanning more than `SpatialHash2D::MAX_CELL_SPAN` cells on an axis are kept in an overflow
+/// list and tested against every query, so a huge box doesn't fill the grid.
+#[derive(Debug, Clone)]
+pub struct SpatialHash2D {
+    cell_size: f32,
+
+    cells: HashMap<(i32, i32), Vec<usize>>,
+    overflow: Vec<usize>,
+    items: Vec<(Entity, AABB2D)>,
+}
+
+impl SpatialHash2D {
+    /// The max count of cells a box spans on an axis, the larger boxes go to the overflow list.
+    pub const MAX_CELL_SPAN: i64 = 16;
+
+    /// Create a `SpatialHash2D`, `cell_size` should be close to the size of common entities.
+    pub fn new(cell_size: f32) -> Self {
+        assert!(
+            cell_size.is_finite() && cell_size > 0.0,
+            "ERR: the cell size must be positive"
+        );
+
+        Self {
+            cell_size,
+
+            cells: Default::default(),
+            overflow: Default::default(),
+            items: Default::default(),
+        }
+    }
+
+    pub fn cell_size(&self) -> f32 {
+        self.cell_size
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.clear();
+        self.overflow.clear();
+        self.items.clear();
+    }
+
+    /// Insert the entity with its box, return `false` if the box is empty or not finite, it's not inserted.
+    pub fn insert(&mut self, entity: Entity, aabb: AABB2D) -> bool {
+        let finite = aabb
+            .min
+            .iter()
+            .chain(aabb.max.iter())
+            .all(|v| v.is_finite());
+        if !finite || aabb.is_empty() {
+            return false;
+        }
+
+        let index = self.items.len();
+        self.items.push((entity, aabb));
+
+        match self.cell_range(&aabb) {
+            Some((min, max)) => {
+                for x in min.0..=max.0 {
+                    for y in min.1..=max.1 {
+                        self.cells.entry((x, y)).or_default().push(index);
+                    }
+                }
+            }
+            None => self.overflow.push(index),
+        }
+
+        true
+    }
+
+    /// The entities whose `AABB2D` overlaps the rectangle.
+    pub fn query_rect(&self, min: Vector2<f32>, max: Vector2<f32>) -> Vec<Entity> {
+        let rect = AABB2D::new(min, max);
+
+        self.query(&rect, |aabb| aabb.overlaps(&rect))
+    }
+
+    /// The entities whose `AABB2D` overlaps the circle.
+    pub fn query_radius(&self, center: Vector2<f32>, radius: f32) -> Vec<Entity> {
+        let rect = AABB2D::from_center(center, Vector2::new(radius, radius));
+
+        self.query(&rect, |aabb| aabb.overlaps_circle(&center, radius))
+    }
+
+    /// All pairs of entities whose `AABB2D`s overlap, each pair is reported once.
+    pub fn overlapping_pairs(&self) -> Vec<(Entity, Entity)> {
+        let mut pairs = Vec::new();
+
+        for (cell, indices) in self.cells.iter() {
+            for (i, &a) in indices.iter().enumerate() {
+                for &b in indices[i + 1..].iter() {
+                    let (ea, aa) = &self.items[a];
+                    let (eb, ab) = &self.items[b];
+
+                    // NOTE: report the pair only in the cell which contains the min corner of the intersection.
+                    let corner = Vector2::new(aa.min.x.max(ab.min.x), aa.min.y.max(ab.min.y));
+                    if aa.overlaps(ab) && self.cell_of(&corner) == *cell {
+                        pairs.push((*ea, *eb));
+                    }
+                }
+            }
+        }
+
+        // the overflowed boxes are tested against all boxes, the pairs of two overflowed boxes only once.
+        for (i, &a) in self.overflow.iter().enumerate() {
+            let (ea, aa) = &self.items[a];
+
+            for (b, (eb, ab)) in self.items.iter().enumerate() {
+                let tested = b == a || self.overflow[..i].contains(&b);
+                if !tested && aa.overlaps(ab) {
+                    pairs.push((*ea, *eb));
+                }
+            }
+        }
+
+        pairs
+    }
+
+    fn query(&self, rect: &AABB2D, filter: impl Fn(&AABB2D) -> bool) -> Vec<Entity> {
+        // NOTE: a huge or invalid query area is cheaper to test item by item.
+        let (min, max) = match self.cell_range(rect) {
+            Some(range) => range,
+            None => {
+                return self
+                    .items
+                    .iter()
+                    .filter(|(_, aabb)| filter(aabb))
+                    .map(|(entity, _)| *entity)
+                    .collect()
+            }
+        };
+
+        let mut visited = vec![false; self.items.len()];
+        let mut entities = Vec::new();
+
+        let cells = (min.0..=max.0).flat_map(|x| (min.1..=max.1).map(move |y| (x, y)));
+        let indices = cells
+            .flat_map(|cell| self.cells.get(&cell).into_iter().flatten())
+            .chain(self.overflow.iter());
+
+        for &index in indices {
+            let (entity, aabb) = &self.items[index];
+
+            if !visited[index] && filter(aabb) {
+                entities.push(*entity);
+            }
+            visited[index] = true;
+        }
+
+        entities
+    }
+
+    // The cells covered by the box, `None` if the box spans more than `MAX_CELL_SPAN` cells on an axis.
+    fn cell_range(&self, aabb: &AABB2D) -> Option<((i32, i32), (i32, i32))> {
+        let (min, max) = (self.cell_of(&aabb.min), self.cell_of(&aabb.max));
+        let span = |lo: i32, hi: i32| hi as i64 - lo as i64 + 1;
+
+        if span(min.0, max.0) > Self::MAX_CELL_SPAN || span(min.1, max.1) > Self::MAX_CELL_SPAN {
+            None
+        } else {
+            Some((min, max))
+        }
+    }
+
+    fn cell_of(&self, point: &Vector2<f32>) -> (i32, i32) {
+        (
+            (point.x / self.cell_size).floor() as i32,
+            (point.y / self.cell_size).floor() as i32,
+        )
+    }
+}
+
+impl Default for SpatialHash2D {
+    fn default() -> Self {
+        Self::new(64.0)
+    }
+}
+
+/// The pairs of entities whose `AABB2D`s overlap, updated by `broad_phase_system` every frame.
+#[derive(Debug, Clone, Default)]
+pub struct BroadPhaseResult {
+    pub pairs: Vec<(Entity, Entity)>,
+}
+
+/// Rebuild `SpatialHash2D` from all `AABB2D`s and find the overlapping pairs.
+///
+/// Needs the `SpatialHash2D` and `BroadPhaseResult` resources, run it after `compute_aabb_system`.
+#[system]
+pub fn broad_phase(
+    world: &mut SubWorld,
+    query: &mut Query<(Entity, &AABB2D)>,
+    #[resource] spatial_hash: &mut SpatialHash2D,
+    #[resource] result: &mut BroadPhaseResult,
+) {
+    spatial_hash.clear();
+
+    for (entity, aabb) in query.iter(world) {
+        spatial_hash.insert(*entity, *aabb);
+    }
+
+    result.pairs = spatial_hash.overlapping_pairs();
+}
+
+/// The contact of two overlapping geometries, found by `narrow_phase_collision`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContactManifold {
+    /// How deep the geometries overlap along `normal`.
+    pub depth: f32,
+    /// The unit direction from the first geometry to the second, moving the second by `normal * depth`
+    /// separates them.
+    pub normal: Vector2<f32>,
+    /// The contact points in `world space`.
+    pub contact_points: Vec<Vector2<f32>>,
+}
+
+impl ContactManifold {
+    fn flipped(mut self) -> Self {
+        self.normal = -self.normal;
+        self
+    }
+}
+
+/// The count of points sampled on a circle when it's tested against a concave geometry.
+const CIRCLE_SAMPLES: usize = 32;
+
+/// Find the contact of two geometries in `world space`, `None` if they don't overlap.
+///
+/// Circles and convex polygons are tested exactly(SAT for polygons), `Segment` is a polygon of two points.
+/// `Hexagram`, `StarFive` and `Heart` are concave, the pairs with them fall back to sampling the outline of
+/// one geometry in the distance field of the other, so crossing edges without a sampled point inside are
+/// missed. `Line` and `Ray` are infinite and never collide.
+pub fn narrow_phase_collision(
+    a_geometry: &Geometry,
+    a_transform: &Transform2D,
+    b_geometry: &Geometry,
+    b_transform: &Transform2D,
+) -> Option<ContactManifold> {
+    let a = Shape::of(a_geometry, a_transform)?;
+    let b = Shape::of(b_geometry, b_transform)?;
+
+    match (&a, &b) {
+        (Shape::Circle(ca, ra), Shape::Circle(cb, rb)) => circle_circle(*ca, *ra, *cb, *rb),
+        (Shape::Convex(polygon), Shape::Circle(center, radius)) => {
+            polygon_circle(polygon, *center, *radius)
+        }
+        (Shape::Circle(center, radius), Shape::Convex(polygon)) => {
+            polygon_circle(polygon, *center, *radius).map(ContactManifold::flipped)
+        }
+        (Shape::Convex(a_polygon), Shape::Convex(b_polygon)) => {
+            polygon_polygon(a_polygon, b_polygon)
+        }
+        _ => sample_distance_fields(
+            (a_geometry, a_transform, &a.samples()),
+            (b_geometry, b_transform, &b.samples()),
+        ),
+    }
+}
+
+// A geometry in `world space` for the narrow phase.
+enum Shape {
+    Circle(Vector2<f32>, f32),
+    // the vertices of a convex polygon or a segment.
+    Convex(Vec<Vector2<f32>>),
+    Concave(Vec<Vector2<f32>>),
+}
+
+impl Shape {
+    fn of(geometry: &Geometry, transform: &Transform2D) -> Option<Self> {
+        let to_world = |points: Vec<Vector2<f32>>| {
+            points
+                .into_iter()
+                .map(|p| transform.transform_point2(&Point2::from(p)).coords)
+                .collect::<Vec<_>>()
+        };
+        let (sx, sy) = (transform.scale.x.abs(), transform.scale.y.abs());
+
+        match geometry.geometry_type() {
+            GeometryType::Line | GeometryType::Ray => None,
+            GeometryType::Segment => Some(Shape::Convex(to_world(vec![
+                geometry.start_point_uncheck(),
+                geometry.end_point_uncheck(),
+            ]))),
+            // NOTE: a circle scaled non-uniformly is an ellipse, it's tested as the polygon of its outline.
+            GeometryType::Circle if (sx - sy).abs() <= f32::EPSILON * sx.max(sy) => {
+                let center = transform.transform_point2(&Point2::from(geometry.position_uncheck()));
+
+                Some(Shape::Circle(
+                    center.coords,
+                    geometry.size_uncheck() / 2.0 * sx,
+                ))
+            }
+            GeometryType::Hexagram | GeometryType::StarFive | GeometryType::Heart => {
+                Some(Shape::Concave(to_world(geometry.local_outline())))
+            }
+            _ => Some(Shape::Convex(to_world(geometry.local_outline()))),
+        }
+    }
+
+    // The points on the outline tested against the distance field of the other geometry.
+    fn samples(&self) -> Vec<Vector2<f32>> {
+        match self {
+            Shape::Circle(center, radius) => (0..CIRCLE_SAMPLES)
+                .map(|i| {
+                    let angle = std::f32::consts::PI * 2.0 * i as f32 / CIRCLE_SAMPLES as f32;
+                    center + Vector2::new(angle.cos(), angle.sin()) * *radius
+                })
+                .collect(),
+            Shape::Convex(points) | Shape::Concave(points) => points.clone(),
+        }
+    }
+}
+
+fn circle_circle(ca: Vector2<f32>, ra: f32, cb: Vector2<f32>, rb: f32) -> Option<ContactManifold> {
+    let offset = cb - ca;
+    let distance = offset.norm();
+
+    if distance >= ra + rb {
+        return None;
+    }
+
+    // NOTE: the circles at the same center are separated upward.
+    let normal = if distance > f32::EPSILON {
+        offset / distance
+    } else {
+        Vector2::new(0.0, 1.0)
+    };
+    let depth = ra + rb - distance;
+
+    Some(ContactManifold {
+        depth,
+        normal,
+        contact_points: vec![ca + normal * (ra - depth / 2.0)],
+    })
+}
+
+// The normal points from the polygon to the circle.
+fn polygon_circle(
+    polygon: &[Vector2<f32>],
+    center: Vector2<f32>,
+    radius: f32,
+) -> Option<ContactManifold> {
+    let closest = polygon
+        .iter()
+        .zip(polygon.iter().cycle().skip(1))
+        .map(|(&p, &q)| closest_on_line(p, q, center, 0.0, 1.0))
+        .min_by(|p, q| {
+            (p - center)
+                .norm_squared()
+                .partial_cmp(&(q - center).norm_squared())
+                .unwrap_or(Ordering::Equal)
+        })?;
+
+    let inside = polygon.len() > 2 && contains_point(polygon, center);
+    let offset = center - closest;
+    let distance = offset.norm();
+
+    if !inside && distance >= radius {
+        return None;
+    }
+
+    let normal = if distance > f32::EPSILON {
+        if inside {
+            -offset / distance
+        } else {
+            offset / distance
+        }
+    } else {
+        // the center is on the boundary, push it away from the center of polygon.
+        let centroid = polygon.iter().sum::<Vector2<f32>>() / polygon.len() as f32;
+        (center - centroid)
+            .try_normalize(f32::EPSILON)
+            .unwrap_or_else(|| Vector2::new(0.0, 1.0))
+    };
+
+    Some(ContactManifold {
+        depth: if inside {
+            radius + distance
+        } else {
+            radius - distance
+        },
+        normal,
+        contact_points: vec![closest],
+    })
+}
+
+// The separating axis theorem, the axes are the edge normals of both polygons.
+fn polygon_polygon(a: &[Vector2<f32>], b: &[Vector2<f32>]) -> Option<ContactManifold> {
+    let project = |polygon: &[Vector2<f32>], axis: &Vector2<f32>| {
+        polygon
+            .iter()
+            .map(|p| p.dot(axis))
+            .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), d| {
+                (min.min(d), max.max(d))
+            })
+    };
+
+    let mut best: Option<(f32, Vector2<f32>)> = None;
+    for polygon in [a, b].iter() {
+        for (p, q) in polygon.iter().zip(polygon.iter().cycle().skip(1)) {
+            let axis = match Vector2::new(p.y - q.y, q.x - p.x).try_normalize(f32::EPSILON) {
+                Some(axis) => axis,
+                None => continue,
+            };
+
+            let (a_min, a_max) = project(a, &axis);
+            let (b_min, b_max) = project(b, &axis);
+
+            // the distance to push `b` out along `axis` or against it.
+            let (forward, backward) = (a_max - b_min, b_max - a_min);
+            if forward <= 0.0 || backward <= 0.0 {
+                return None;
+            }
+
+            let (depth, normal) = if forward < backward {
+                (forward, axis)
+            } else {
+                (backward, -axis)
+            };
+            if best.map_or(true, |(best_depth, _)| depth < best_depth) {
+                best = Some((depth, normal));
+            }
+        }
+    }
+
+    let (depth, normal) = best?;
+
+    let mut contact_points = b
+        .iter()
+        .filter(|p| a.len() > 2 && contains_point(a, **p))
+        .chain(a.iter().filter(|p| b.len() > 2 && contains_point(b, **p)))
+        .copied()
+        .collect::<Vec<_>>();
+
+    // NOTE: the edges cross without any vertex inside, take the deepest vertex of `b`.
+    if contact_points.is_empty() {
+        contact_points.extend(b.iter().copied().min_by(|p, q| {
+            p.dot(&normal)
+                .partial_cmp(&q.dot(&normal))
+                .unwrap_or(Ordering::Equal)
+        }));
+    }
+
+    Some(ContactManifold {
+        depth,
+        normal,
+        contact_points,
+    })
+}
+
+// Sample the outline of each geometry in the distance field of the other, the normal is the gradient of the
+// distance field at the deepest point.
+fn sample_distance_fields(
+    a: (&Geometry, &Transform2D, &[Vector2<f32>]),
+    b: (&Geometry, &Transform2D, &[Vector2<f32>]),
+) -> Option<ContactManifold> {
+    const STEP: f32 = 1e-2;
+
+    let gradient = |geometry: &Geometry, transform: &Transform2D, p: Vector2<f32>| {
+        let distance = |p: Vector2<f32>| geometry.distance_to_boundary(p, transform);
+        let (dx, dy) = (Vector2::new(STEP, 0.0), Vector2::new(0.0, STEP));
+
+        Vector2::new(
+            distance(p + dx) - distance(p - dx),
+            distance(p + dy) - distance(p - dy),
+        )
+        .try_normalize(f32::EPSILON)
+        .unwrap_or_else(|| Vector2::new(0.0, 1.0))
+    };
+
+    let mut contact_points = Vec::new();
+    let mut deepest: Option<(f32, Vector2<f32>)> = None;
+
+    // the points of `b` inside `a` are pushed along the gradient of `a`, the points of `a` inside `b` are
+    // pushed against the gradient of `b`.
+    for &(inner, outer, sign) in [(b, a, 1.0), (a, b, -1.0)].iter() {
+        let (geometry, transform, _) = outer;
+
+        for &p in inner.2.iter() {
+            let distance = geometry.distance_to_boundary(p, transform);
+            if distance >= 0.0 {
+                continue;
+            }
+
+            contact_points.push(p);
+            if deepest.map_or(true, |(depth, _)| -distance > depth) {
+                deepest = Some((-distance, gradient(geometry, transform, p) * sign));
+            }
+        }
+    }
+
+    let (depth, normal) = deepest?;
+
+    Some(ContactManifold {
+        depth,
+        normal,
+        contact_points,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        components::geometry::{
+            BorderDecoration, BorderThickness, Geometry2DType, InnerDecoration,
+        },
+        legion::World,
+        misc::color::Rgba,
+    };
+
+    #[test]
+    fn spatial_hash_queries() {
+        let mut world = World::default();
+        let (e0, e1, e2) = (world.push(()), world.push(()), world.push(()));
+
+        let mut spatial_hash = SpatialHash2D::new(10.0);
+        spatial_hash.insert(
+            e0,
+            AABB2D::new(Vector2::new(0.0, 0.0), Vector2::new(15.0, 15.0)),
+        );
+        spatial_hash.insert(
+            e1,
+            AABB2D::new(Vector2::new(12.0, 12.0), Vector2::new(20.0, 20.0)),
+        );
+        spatial_hash.insert(
+            e2,
+            AABB2D::new(Vector2::new(50.0, 50.0), Vector2::new(55.0, 55.0)),
+        );
+
+        assert_eq!(spatial_hash.overlapping_pairs(), vec![(e0, e1)]);
+
+        let found = spatial_hash.query_rect(Vector2::new(-5.0, -5.0), Vector2::new(13.0, 13.0));
+        assert_eq!(found.len(), 2);
+        assert!(found.contains(&e0) && found.contains(&e1));
+
+        assert_eq!(
+            spatial_hash.query_radius(Vector2::new(60.0, 60.0), 8.0),
+            vec![e2]
+        );
+        assert!(spatial_hash
+            .query_radius(Vector2::new(60.0, 60.0), 6.0)
+            .is_empty());
+    }
+
+    #[test]
+    fn spatial_hash_overflow() {
+        let mut world = World::default();
+        let (e0, e1, e2) = (world.push(()), world.push(()), world.push(()));
+
+        let mut spatial_hash = SpatialHash2D::new(1.0);
+        assert!(!spatial_hash.insert(
+            e0,
+            AABB2D::new(Vector2::new(0.0, 0.0), Vector2::new(f32::NAN, 1.0))
+        ));
+        assert!(!spatial_hash.insert(
+            e0,
+            AABB2D::new(Vector2::new(0.0, 0.0), Vector2::new(f32::INFINITY, 1.0))
+        ));
+        assert!(spatial_hash.is_empty());
+
+        // spans 1e6 cells, it goes to the overflow list instead of the grid.
+        assert!(spatial_hash.insert(
+            e0,
+            AABB2D::new(Vector2::new(-5e5, -5e5), Vector2::new(5e5, 5e5))
+        ));
+        assert!(spatial_hash.cells.is_empty());
+        assert!(spatial_hash.insert(
+            e1,
+            AABB2D::new(Vector2::new(10.0, 10.0), Vector2::new(11.0, 11.0))
+        ));
+        assert!(spatial_hash.insert(
+            e2,
+            AABB2D::new(Vector2::new(-1e6, 0.0), Vector2::new(1e6, 0.5))
+        ));
+
+        let pairs = spatial_hash.overlapping_pairs();
+        assert_eq!(pairs.len(), 2);
+        assert!(pairs.contains(&(e0, e1)) && pairs.contains(&(e0, e2)));
+
+        let found = spatial_hash.query_rect(Vector2::new(10.5, 10.5), Vector2::new(10.6, 10.6));
+        assert_eq!(found.len(), 2);
+        assert!(found.contains(&e0) && found.contains(&e1));
+        assert_eq!(
+            spatial_hash
+                .query_rect(Vector2::new(-1e7, -1e7), Vector2::new(1e7, 1e7))
+                .len(),
+            3
+        );
+    }
+
+    fn shape(gtype: Geometry2DType, size: f32) -> Geometry {
+        Geometry::new_2d(
+            gtype,
+            BorderDecoration::Solid,
+            Rgba::WHITE,
+            BorderThickness::LocalSpace(0.0),
+            InnerDecoration::Solid,
+            Rgba::WHITE,
+            0,
+            Vector2::new(0.0, 0.0),
+            0.0,
+            size,
+        )
+    }
+
+    #[test]
+    fn narrow_phase_pairs() {
+        let approx = |a: Vector2<f32>, b: Vector2<f32>| (a - b).norm() < 1e-3;
+        let (circle, square) = (
+            shape(Geometry2DType::Circle, 2.0),
+            shape(Geometry2DType::Square, 2.0),
+        );
+
+        let contact = narrow_phase_collision(
+            &circle,
+            &Transform2D::at(0.0, 0.0),
+            &circle,
+            &Transform2D::at(1.5, 0.0),
+        )
+        .unwrap();
+        assert!((contact.depth - 0.5).abs() < 1e-4);
+        assert!(approx(contact.normal, Vector2::new(1.0, 0.0)));
+        assert!(narrow_phase_collision(
+            &circle,
+            &Transform2D::default(),
+            &circle,
+            &Transform2D::at(2.5, 0.0)
+        )
+        .is_none());
+
+        let contact = narrow_phase_collision(
+            &square,
+            &Transform2D::default(),
+            &square,
+            &Transform2D::at(0.5, 1.75),
+        )
+        .unwrap();
+        assert!((contact.depth - 0.25).abs() < 1e-4);
+        assert!(approx(contact.normal, Vector2::new(0.0, 1.0)));
+        assert!(!contact.contact_points.is_empty());
+
+        let contact = narrow_phase_collision(
+            &circle,
+            &Transform2D::at(-1.5, 0.0),
+            &square,
+            &Transform2D::default(),
+        )
+        .unwrap();
+        assert!((contact.depth - 0.5).abs() < 1e-4);
+        assert!(approx(contact.normal, Vector2::new(1.0, 0.0)));
+
+        // the star is concave, the tip of the star pokes into the square.
+        let star = shape(Geometry2DType::StarFive, 2.0);
+        let contact = narrow_phase_collision(
+            &square,
+            &Transform2D::default(),
+            &star,
+            &Transform2D::at(0.0, 1.75),
+        )
+        .unwrap();
+        assert!(contact.depth > 0.0);
+        assert!(contact.normal.y > 0.0);
+        assert!(narrow_phase_collision(
+            &square,
+            &Transform2D::default(),
+            &star,
+            &Transform2D::at(0.0, 5.0)
+        )
+        .is_none());
+    }
+}
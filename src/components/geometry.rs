@@ -1,9 +1,11 @@
 use crate::{
+    components::{hierarchy::global_to_local, transform::Transform2D},
+    math::polygon::{Polygon2D, PolygonError},
     misc::color::{Hex, Rgba},
-    nalgebra::Vector2,
+    nalgebra::{Point2, UnitComplex, Vector2},
 };
 
-use std::convert::Into;
+use std::{cmp::Ordering, convert::Into};
 
 pub type Assembly = Vec<Geometry>;
 
@@ -218,6 +220,394 @@ impl Geometry {
         self.extras[2] = end_point.x;
         self.extras[3] = end_point.y;
     }
+
+    /// The area of the 2d geometry in `local space`, 0.0 for 1d geometries.
+    pub fn area(&self) -> f32 {
+        self.geometry_2d_type()
+            .map_or(0.0, |gtype| gtype.area(self.size_uncheck()))
+    }
+
+    /// The perimeter of the 2d geometry in `local space`, the length of segment for `Segment` and infinity
+    /// for `Line` and `Ray`.
+    pub fn perimeter(&self) -> f32 {
+        match self.geometry_type() {
+            GeometryType::Line | GeometryType::Ray => f32::INFINITY,
+            GeometryType::Segment => (self.end_point_uncheck() - self.start_point_uncheck()).norm(),
+            _ => self
+                .geometry_2d_type()
+                .map_or(0.0, |gtype| gtype.perimeter(self.size_uncheck())),
+        }
+    }
+
+    /// The outline of the 2d geometry in `local space`, with the position, angle and size applied, empty for
+    /// 1d geometries.
+    pub fn local_outline(&self) -> Vec<Vector2<f32>> {
+        let rotation = UnitComplex::new(self.angle_uncheck().to_radians());
+        let (position, size) = (self.position_uncheck(), self.size_uncheck());
+
+        self.geometry_2d_type()
+            .map(|gtype| gtype.outline())
+            .unwrap_or_default()
+            .into_iter()
+            .map(|v| position + rotation * (v * size))
+            .collect()
+    }
+
+    /// The axis-aligned bounding box of the quad the geometry is drawn in, in `local space`, (min, max).
+    ///
+    /// The border of 2d geometries is drawn inside them, the border of `Segment` in `local space` is
+    /// included. The box of `Line` and `Ray` is infinite, they are never culled.
+    pub fn local_aabb(&self) -> (Vector2<f32>, Vector2<f32>) {
+        match self.geometry_type() {
+            GeometryType::Line | GeometryType::Ray => (
+                Vector2::new(f32::NEG_INFINITY, f32::NEG_INFINITY),
+                Vector2::new(f32::INFINITY, f32::INFINITY),
+            ),
+            GeometryType::Segment => {
+                let (a, b) = (self.start_point_uncheck(), self.end_point_uncheck());
+                let half = match self.border_thickness() {
+                    BorderThickness::LocalSpace(th) => th / 2.0,
+                    BorderThickness::ScreenSpace(_) => 0.0,
+                };
+                let normal = (b - a)
+                    .try_normalize(f32::EPSILON)
+                    .map_or(Vector2::zeros(), |dir| Vector2::new(-dir.y, dir.x) * half);
+
+                bounds_of(
+                    [a + normal, a - normal, b + normal, b - normal]
+                        .iter()
+                        .copied(),
+                )
+            }
+            GeometryType::Circle => {
+                let radius = self.size_uncheck() / 2.0;
+                let position = self.position_uncheck();
+                (
+                    position - Vector2::new(radius, radius),
+                    position + Vector2::new(radius, radius),
+                )
+            }
+            _ => {
+                let rotation = UnitComplex::new(self.angle_uncheck().to_radians());
+                let (position, half) = (self.position_uncheck(), self.size_uncheck() / 2.0);
+
+                bounds_of(
+                    [(-half, -half), (half, -half), (half, half), (-half, half)]
+                        .iter()
+                        .map(|&(x, y)| position + rotation * Vector2::new(x, y)),
+                )
+            }
+        }
+    }
+
+    /// The axis-aligned bounding box in `world space`, (min, max), see `Geometry::world_aabb_of`.
+    pub fn world_aabb(
+        &self,
+        transform: &Transform2D,
+        pixel_size: f32,
+    ) -> (Vector2<f32>, Vector2<f32>) {
+        self.world_aabb_of(self.local_aabb(), transform, pixel_size)
+    }
+
+    /// The axis-aligned bounding box in `world space` which contains the transformed corners of
+    /// `local_aabb`, pass the `local_aabb` computed once if the geometry is shared by many transforms.
+    ///
+    /// `pixel_size` is the world units of a screen pixel, the border of `Segment` in `screen space` is
+    /// included by it, see `Camera2D::pixel_size`.
+    pub fn world_aabb_of(
+        &self,
+        local_aabb: (Vector2<f32>, Vector2<f32>),
+        transform: &Transform2D,
+        pixel_size: f32,
+    ) -> (Vector2<f32>, Vector2<f32>) {
+        let (min, max) = local_aabb;
+        if !(min.x.is_finite() && min.y.is_finite() && max.x.is_finite() && max.y.is_finite()) {
+            return (min, max);
+        }
+
+        let (min, max) = bounds_of(
+            [
+                Vector2::new(min.x, min.y),
+                Vector2::new(max.x, min.y),
+                Vector2::new(max.x, max.y),
+                Vector2::new(min.x, max.y),
+            ]
+            .iter()
+            .map(|corner| transform.transform_point2(&Point2::from(*corner)).coords),
+        );
+
+        match (self.geometry_type(), self.border_thickness()) {
+            (GeometryType::Segment, BorderThickness::ScreenSpace(th)) => {
+                let margin = Vector2::new(th, th) * (pixel_size / 2.0);
+                (min - margin, max + margin)
+            }
+            _ => (min, max),
+        }
+    }
+
+    /// The triangles of the 2d geometry in `local space`, see `Geometry2DType::tessellate`.
+    pub fn tessellate(&self) -> (Vec<Vector2<f32>>, Vec<u32>) {
+        match self.geometry_2d_type() {
+            Some(gtype) => {
+                let (_, indices) = gtype.tessellate(1.0);
+                (self.local_outline(), indices)
+            }
+            None => (Vec::new(), Vec::new()),
+        }
+    }
+
+    /// The outline of the 2d geometry in `world space` as a polygon, `None` for 1d geometries and the
+    /// degenerate ones, e.g. of zero scale.
+    pub fn to_polygon(&self, transform: &Transform2D) -> Option<Polygon2D> {
+        self.geometry_2d_type()?;
+
+        let mut vertices = self
+            .local_outline()
+            .into_iter()
+            .map(|v| transform.transform_point2(&Point2::from(v)).coords)
+            .collect::<Vec<_>>();
+
+        // NOTE: a mirrored outline is clockwise.
+        if transform.scale.x * transform.scale.y < 0.0 {
+            vertices.reverse();
+        }
+
+        Polygon2D::new(vertices).ok()
+    }
+
+    /// The union of two 2d geometries in `world space`, see `Polygon2D::union`.
+    ///
+    /// Empty if any of them is a 1d geometry, fails if the boundaries touch or overlap.
+    pub fn union(
+        &self,
+        transform: &Transform2D,
+        other: &Geometry,
+        other_transform: &Transform2D,
+    ) -> Result<Vec<Polygon2D>, PolygonError> {
+        self.boolean(transform, other, other_transform, Polygon2D::union)
+    }
+
+    /// The intersection of two 2d geometries in `world space`, see `Polygon2D::intersection`.
+    pub fn intersection(
+        &self,
+        transform: &Transform2D,
+        other: &Geometry,
+        other_transform: &Transform2D,
+    ) -> Result<Vec<Polygon2D>, PolygonError> {
+        self.boolean(transform, other, other_transform, Polygon2D::intersection)
+    }
+
+    /// `self` with `other` cut off in `world space`, see `Polygon2D::difference`.
+    pub fn difference(
+        &self,
+        transform: &Transform2D,
+        other: &Geometry,
+        other_transform: &Transform2D,
+    ) -> Result<Vec<Polygon2D>, PolygonError> {
+        self.boolean(transform, other, other_transform, Polygon2D::difference)
+    }
+
+    /// The closest point on the boundary of the geometry to `world_point`, the geometry is placed in
+    /// `world space` by `transform`.
+    ///
+    /// NOTE: the point is found in `local space`, so it's approximate if the scale is not uniform.
+    pub fn closest_point(
+        &self,
+        world_point: Vector2<f32>,
+        transform: &Transform2D,
+    ) -> Vector2<f32> {
+        let local = global_to_local(transform, &Transform2D::default(), world_point);
+        let closest = self.closest_local_point(local);
+
+        transform.transform_point2(&Point2::from(closest)).coords
+    }
+
+    /// The distance from `world_point` to the boundary of the geometry, negative inside 2d geometries.
+    pub fn distance_to_boundary(&self, world_point: Vector2<f32>, transform: &Transform2D) -> f32 {
+        let distance = (world_point - self.closest_point(world_point, transform)).norm();
+
+        let local = global_to_local(transform, &Transform2D::default(), world_point);
+        let inside = match self.geometry_type() {
+            GeometryType::Line | GeometryType::Ray | GeometryType::Segment => false,
+            GeometryType::Circle => {
+                (local - self.position_uncheck()).norm() < self.size_uncheck() / 2.0
+            }
+            _ => contains_point(&self.local_outline(), local),
+        };
+
+        if inside {
+            -distance
+        } else {
+            distance
+        }
+    }
+
+    /// The SVG `<path>` element of the geometry with `transform`, empty for `Line` and `Ray`.
+    ///
+    /// The y axis is flipped, since it points down in SVG. `Circle` and `Heart` are approximated by
+    /// segments, see `Geometry2DType::outline`.
+    pub fn to_svg_path(&self, transform: &Transform2D) -> String {
+        let points = self.svg_points(transform);
+        if points.is_empty() {
+            return String::new();
+        }
+
+        let mut d = points
+            .iter()
+            .enumerate()
+            .map(|(i, p)| format!("{} {} {}", if i == 0 { "M" } else { "L" }, p.x, p.y))
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        let fill = match self.geometry_2d_type() {
+            Some(_) => {
+                d.push_str(" Z");
+
+                match self.inner_decoration() {
+                    InnerDecoration::None => None,
+                    _ => Some(Rgba::from_hex(self.icolor)),
+                }
+            }
+            None => None,
+        };
+
+        let stroke = match self.border_decoration() {
+            BorderDecoration::None => None,
+            _ => Some(Rgba::from_hex(self.bcolor)),
+        };
+        let stroke_width = match self.border_thickness() {
+            BorderThickness::LocalSpace(th) => {
+                th * (transform.scale.x.abs() + transform.scale.y.abs()) / 2.0
+            }
+            BorderThickness::ScreenSpace(th) => th,
+        };
+
+        format!(
+            "<path d=\"{}\" {} {} stroke-width=\"{}\"/>",
+            d,
+            svg_paint("fill", fill),
+            svg_paint("stroke", stroke),
+            stroke_width
+        )
+    }
+
+    /// The outline of the geometry in SVG space(`world space` with the y axis flipped).
+    pub(crate) fn svg_points(&self, transform: &Transform2D) -> Vec<Vector2<f32>> {
+        let local = match self.geometry_type() {
+            GeometryType::Line | GeometryType::Ray => Vec::new(),
+            GeometryType::Segment => vec![self.start_point_uncheck(), self.end_point_uncheck()],
+            _ => self.local_outline(),
+        };
+
+        local
+            .into_iter()
+            .map(|v| {
+                let p = transform.transform_point2(&Point2::from(v));
+                Vector2::new(p.x, -p.y)
+            })
+            .collect()
+    }
+
+    fn boolean<F>(
+        &self,
+        transform: &Transform2D,
+        other: &Geometry,
+        other_transform: &Transform2D,
+        op: F,
+    ) -> Result<Vec<Polygon2D>, PolygonError>
+    where
+        F: Fn(&Polygon2D, &Polygon2D) -> Result<Vec<Polygon2D>, PolygonError>,
+    {
+        match (
+            self.to_polygon(transform),
+            other.to_polygon(other_transform),
+        ) {
+            (Some(a), Some(b)) => op(&a, &b),
+            _ => Ok(Vec::new()),
+        }
+    }
+
+    fn closest_local_point(&self, p: Vector2<f32>) -> Vector2<f32> {
+        let (start, end) = (self.start_point_uncheck(), self.end_point_uncheck());
+
+        match self.geometry_type() {
+            GeometryType::Line => closest_on_line(start, end, p, f32::NEG_INFINITY, f32::INFINITY),
+            GeometryType::Ray => closest_on_line(start, end, p, 0.0, f32::INFINITY),
+            GeometryType::Segment => closest_on_line(start, end, p, 0.0, 1.0),
+            GeometryType::Circle => {
+                let (center, radius) = (self.position_uncheck(), self.size_uncheck() / 2.0);
+                let offset = p - center;
+
+                // NOTE: every point on the circle is the closest to the center, take the top one.
+                if offset.norm() > f32::EPSILON {
+                    center + offset.normalize() * radius
+                } else {
+                    center + Vector2::new(0.0, radius)
+                }
+            }
+            _ => {
+                let outline = self.local_outline();
+
+                outline
+                    .iter()
+                    .zip(outline.iter().cycle().skip(1))
+                    .map(|(&a, &b)| closest_on_line(a, b, p, 0.0, 1.0))
+                    .min_by(|a, b| {
+                        (a - p)
+                            .norm_squared()
+                            .partial_cmp(&(b - p).norm_squared())
+                            .unwrap_or(Ordering::Equal)
+                    })
+                    .unwrap_or(p)
+            }
+        }
+    }
+
+    fn geometry_2d_type(&self) -> Option<Geometry2DType> {
+        match self.geometry_type() {
+            GeometryType::Circle => Some(Geometry2DType::Circle),
+            GeometryType::ETriangle => Some(Geometry2DType::ETriangle),
+            GeometryType::Square => Some(Geometry2DType::Square),
+            GeometryType::Pentagon => Some(Geometry2DType::Pentagon),
+            GeometryType::Hexagon => Some(Geometry2DType::Hexagon),
+            GeometryType::Octogon => Some(Geometry2DType::Octogon),
+            GeometryType::Hexagram => Some(Geometry2DType::Hexagram),
+            GeometryType::StarFive => Some(Geometry2DType::StarFive),
+            GeometryType::Heart => Some(Geometry2DType::Heart),
+            GeometryType::Line | GeometryType::Ray | GeometryType::Segment => None,
+        }
+    }
+
+    /// The raw 32 bytes of geometry, used to save the geometry to scene files.
+    pub(crate) fn to_words(&self) -> [u32; 8] {
+        let extras = self.extras;
+
+        [
+            self.datas,
+            self.bcolor,
+            self.icolor,
+            self.thickness.to_bits(),
+            extras[0].to_bits(),
+            extras[1].to_bits(),
+            extras[2].to_bits(),
+            extras[3].to_bits(),
+        ]
+    }
+
+    pub(crate) fn from_words(words: [u32; 8]) -> Self {
+        Self {
+            datas: words[0],
+            bcolor: words[1],
+            icolor: words[2],
+            thickness: f32::from_bits(words[3]),
+            extras: [
+                f32::from_bits(words[4]),
+                f32::from_bits(words[5]),
+                f32::from_bits(words[6]),
+                f32::from_bits(words[7]),
+            ],
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -303,6 +693,198 @@ impl Into<GeometryType> for Geometry2DType {
     }
 }
 
+impl Geometry2DType {
+    /// The vertices of the outline in `geometry space`(the shape of size 1.0 at the origin), same as the
+    /// shape drawn by the render stage.
+    ///
+    /// Vertices are counter-clockwise, starting from the top middle. `Circle` and the
+    /// arcs of `Heart` are approximated by segments.
+    pub fn outline(&self) -> Vec<Vector2<f32>> {
+        // `count` vertices on the circle of `radius`, starting from `start` in degrees.
+        let regular = |count: usize, radius: f32, start: f32| -> Vec<Vector2<f32>> {
+            (0..count)
+                .map(|i| {
+                    let angle = (start + 360.0 * i as f32 / count as f32).to_radians();
+                    Vector2::new(angle.cos(), angle.sin()) * radius
+                })
+                .collect()
+        };
+        // the star with `count` tips on `outer` radius and the inner vertices on `inner` radius.
+        let star = |count: usize, outer: f32, inner: f32| -> Vec<Vector2<f32>> {
+            let tips = regular(count, outer, 90.0);
+            let valleys = regular(count, inner, 90.0 + 180.0 / count as f32);
+
+            tips.into_iter()
+                .zip(valleys)
+                .flat_map(|(tip, valley)| vec![tip, valley])
+                .collect()
+        };
+
+        match self {
+            Self::Circle => regular(CIRCLE_SEGMENTS, 0.5, 90.0),
+            Self::ETriangle => regular(3, 0.5, 90.0),
+            Self::Square => regular(4, std::f32::consts::FRAC_1_SQRT_2, 45.0),
+            // NOTE: pentagon, hexagon and octogon have a flat top edge.
+            Self::Pentagon => regular(5, 0.5, 54.0),
+            Self::Hexagon => regular(6, 0.5, 60.0),
+            Self::Octogon => regular(8, 0.5, 67.5),
+            Self::Hexagram => star(6, 0.5, 0.5 / 3f32.sqrt()),
+            Self::StarFive => {
+                let (an, en) = (36f32.to_radians(), 60f32.to_radians());
+                star(5, 0.5, 0.5 * (an.cos() - an.sin() / en.tan()))
+            }
+            Self::Heart => heart_outline(),
+        }
+    }
+
+    /// The area of the shape of `size`, same as the shape drawn by the render stage.
+    ///
+    /// `Circle` is exact, the others are computed from `outline` by the shoelace formula.
+    pub fn area(&self, size: f32) -> f32 {
+        match self {
+            Self::Circle => std::f32::consts::PI * (size / 2.0).powi(2),
+            _ => {
+                let outline = self.outline();
+                let twice_area = outline
+                    .iter()
+                    .zip(outline.iter().cycle().skip(1))
+                    .map(|(a, b)| a.perp(b))
+                    .sum::<f32>();
+
+                twice_area.abs() / 2.0 * size * size
+            }
+        }
+    }
+
+    /// Triangulate the shape of `size`, return the vertices of `outline` and the indices of the
+    /// counter-clockwise triangles, 3 indices a triangle.
+    ///
+    /// Useful for colliders, hit testing and custom meshes, the render stage draws the shapes by distance
+    /// functions instead.
+    pub fn tessellate(&self, size: f32) -> (Vec<Vector2<f32>>, Vec<u32>) {
+        let vertices = self
+            .outline()
+            .into_iter()
+            .map(|v| v * size)
+            .collect::<Vec<_>>();
+
+        // NOTE: the outline of unit size is triangulated, so the triangles don't change with the size.
+        let indices = Polygon2D::new(self.outline())
+            .map(|polygon| polygon.triangulate())
+            .unwrap_or_default()
+            .iter()
+            .flat_map(|triangle| triangle.iter().map(|&index| index as u32))
+            .collect();
+
+        (vertices, indices)
+    }
+
+    /// The perimeter of the shape of `size`, same as the shape drawn by the render stage.
+    ///
+    /// `Circle` is exact, the others are the length of `outline`.
+    pub fn perimeter(&self, size: f32) -> f32 {
+        match self {
+            Self::Circle => std::f32::consts::PI * size,
+            _ => {
+                let outline = self.outline();
+                let length = outline
+                    .iter()
+                    .zip(outline.iter().cycle().skip(1))
+                    .map(|(a, b)| (b - a).norm())
+                    .sum::<f32>();
+
+                length * size
+            }
+        }
+    }
+}
+
+// The SVG attributes of `fill` or `stroke`, the alpha goes to the opacity attribute.
+fn svg_paint(name: &str, color: Option<Rgba>) -> String {
+    match color {
+        Some(rgba) => format!(
+            "{0}=\"#{1:02x}{2:02x}{3:02x}\" {0}-opacity=\"{4}\"",
+            name,
+            rgba.r,
+            rgba.g,
+            rgba.b,
+            rgba.a as f32 / 255.0
+        ),
+        None => format!("{}=\"none\"", name),
+    }
+}
+
+// The (min, max) of the points.
+fn bounds_of<I: IntoIterator<Item = Vector2<f32>>>(points: I) -> (Vector2<f32>, Vector2<f32>) {
+    points.into_iter().fold(
+        (
+            Vector2::new(f32::INFINITY, f32::INFINITY),
+            Vector2::new(f32::NEG_INFINITY, f32::NEG_INFINITY),
+        ),
+        |(min, max), p| (min.inf(&p), max.sup(&p)),
+    )
+}
+
+// The closest point to `p` on the line through `a` and `b`, `a + (b - a) * t` with `t` clamped to `[min, max]`.
+pub(crate) fn closest_on_line(
+    a: Vector2<f32>,
+    b: Vector2<f32>,
+    p: Vector2<f32>,
+    min: f32,
+    max: f32,
+) -> Vector2<f32> {
+    let ab = b - a;
+    let len2 = ab.norm_squared();
+
+    if len2 <= f32::EPSILON {
+        return a;
+    }
+
+    a + ab * ((p - a).dot(&ab) / len2).max(min).min(max)
+}
+
+// Whether `p` is inside the polygon of `outline`, by the even-odd rule.
+pub(crate) fn contains_point(outline: &[Vector2<f32>], p: Vector2<f32>) -> bool {
+    outline
+        .iter()
+        .zip(outline.iter().cycle().skip(1))
+        .filter(|(a, b)| {
+            (a.y > p.y) != (b.y > p.y) && p.x < a.x + (p.y - a.y) / (b.y - a.y) * (b.x - a.x)
+        })
+        .count()
+        % 2
+        == 1
+}
+
+/// The count of segments approximating a circle.
+const CIRCLE_SEGMENTS: usize = 64;
+
+// NOTE: the heart in shader is two arcs on circles centered at (±0.25, 0.75) and tangent lines to the
+// bottom tip (0, 0), then mapped by `(p / 1.214) - (0, 0.5)`.
+fn heart_outline() -> Vec<Vector2<f32>> {
+    const SCALE: f32 = 1.0 / 1.214;
+    const ARC_SEGMENTS: usize = CIRCLE_SEGMENTS / 4;
+
+    let radius = std::f32::consts::SQRT_2 / 4.0;
+    let map = |x: f32, y: f32| Vector2::new(x * SCALE, y * SCALE - 0.5);
+
+    // the right arc from the notch (0, 1) clockwise to the tangent point (0.5, 0.5).
+    let arc = (0..=ARC_SEGMENTS * 3 / 2)
+        .map(|i| {
+            let angle = (135.0 - 180.0 * i as f32 / (ARC_SEGMENTS * 3 / 2) as f32).to_radians();
+            (0.25 + radius * angle.cos(), 0.75 + radius * angle.sin())
+        })
+        .collect::<Vec<_>>();
+
+    // counter-clockwise: notch -> left arc -> bottom tip -> right arc.
+    let mut outline = vec![map(0.0, 1.0)];
+    outline.extend(arc[1..].iter().map(|&(x, y)| map(-x, y)));
+    outline.push(map(0.0, 0.0));
+    outline.extend(arc[..arc.len() - 1].iter().rev().map(|&(x, y)| map(x, y)));
+
+    outline
+}
+
 #[repr(u8)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum BorderDecoration {
@@ -340,4 +922,199 @@ mod test {
         assert_eq!(align_of::<BorderDecoration>(), 1);
         assert_eq!(size_of::<BorderDecoration>(), 1);
     }
+
+    #[test]
+    fn test_tessellate() {
+        let shapes = [
+            (Geometry2DType::Circle, CIRCLE_SEGMENTS),
+            (Geometry2DType::ETriangle, 3),
+            (Geometry2DType::Square, 4),
+            (Geometry2DType::Pentagon, 5),
+            (Geometry2DType::Hexagon, 6),
+            (Geometry2DType::Octogon, 8),
+            (Geometry2DType::Hexagram, 12),
+            (Geometry2DType::StarFive, 10),
+        ];
+
+        for &(gtype, count) in shapes.iter() {
+            let (vertices, indices) = gtype.tessellate(2.0);
+
+            assert_eq!(vertices.len(), count, "{:?}", gtype);
+            assert_eq!(indices.len(), (count - 2) * 3, "{:?}", gtype);
+
+            let mut area = 0.0;
+            for triangle in indices.chunks(3) {
+                let (a, b, c) = (
+                    vertices[triangle[0] as usize],
+                    vertices[triangle[1] as usize],
+                    vertices[triangle[2] as usize],
+                );
+                let twice_area = (b - a).perp(&(c - a));
+
+                assert!(twice_area > 0.0, "{:?} has a clockwise triangle", gtype);
+                area += twice_area / 2.0;
+            }
+            assert!((area - gtype.area(2.0)).abs() < 1e-2 * area, "{:?}", gtype);
+        }
+
+        let (vertices, indices) = Geometry2DType::Heart.tessellate(1.0);
+        assert_eq!(indices.len(), (vertices.len() - 2) * 3);
+    }
+
+    #[test]
+    fn test_area_and_perimeter() {
+        let approx = |a: f32, b: f32| (a - b).abs() < 1e-3;
+
+        assert!(approx(
+            Geometry2DType::Circle.area(2.0),
+            std::f32::consts::PI
+        ));
+        assert!(approx(
+            Geometry2DType::Circle.perimeter(2.0),
+            2.0 * std::f32::consts::PI
+        ));
+
+        assert!(approx(Geometry2DType::Square.area(2.0), 4.0));
+        assert!(approx(Geometry2DType::Square.perimeter(2.0), 8.0));
+
+        // the equilateral triangle on the circle of radius 0.5.
+        let side = 3f32.sqrt() / 2.0;
+        assert!(approx(
+            Geometry2DType::ETriangle.area(1.0),
+            3f32.sqrt() / 4.0 * side * side
+        ));
+        assert!(approx(Geometry2DType::ETriangle.perimeter(1.0), 3.0 * side));
+
+        let segment = Geometry::new_1d(
+            Geometry1DType::Segment,
+            BorderDecoration::Solid,
+            Rgba::WHITE,
+            BorderThickness::LocalSpace(1.0),
+            0,
+            Vector2::new(0.0, 0.0),
+            Vector2::new(3.0, 4.0),
+        );
+        assert_eq!(segment.area(), 0.0);
+        assert_eq!(segment.perimeter(), 5.0);
+    }
+
+    #[test]
+    fn test_closest_point() {
+        let approx = |a: Vector2<f32>, b: Vector2<f32>| (a - b).norm() < 1e-3;
+        let shape = |gtype: Geometry2DType| {
+            Geometry::new_2d(
+                gtype,
+                BorderDecoration::Solid,
+                Rgba::WHITE,
+                BorderThickness::LocalSpace(0.0),
+                InnerDecoration::Solid,
+                Rgba::WHITE,
+                0,
+                Vector2::new(0.0, 0.0),
+                0.0,
+                2.0,
+            )
+        };
+        let transform = Transform2D::new(10.0, 0.0, 0.0, 2.0, 2.0);
+
+        // the circle of radius 2.0 at (10.0, 0.0) in world space.
+        let circle = shape(Geometry2DType::Circle);
+        assert!(approx(
+            circle.closest_point(Vector2::new(15.0, 0.0), &transform),
+            Vector2::new(12.0, 0.0)
+        ));
+        assert!(
+            (circle.distance_to_boundary(Vector2::new(15.0, 0.0), &transform) - 3.0).abs() < 1e-3
+        );
+        assert!(
+            (circle.distance_to_boundary(Vector2::new(11.0, 0.0), &transform) + 1.0).abs() < 1e-3
+        );
+
+        // the square of side 4.0 at (10.0, 0.0) in world space.
+        let square = shape(Geometry2DType::Square);
+        assert!(approx(
+            square.closest_point(Vector2::new(10.5, 5.0), &transform),
+            Vector2::new(10.5, 2.0)
+        ));
+        assert!(
+            (square.distance_to_boundary(Vector2::new(10.0, 0.5), &transform) + 1.5).abs() < 1e-3
+        );
+
+        // the bottom edge of the triangle is at y = -0.5 in world space.
+        let triangle = shape(Geometry2DType::ETriangle);
+        let transform = Transform2D::default();
+        assert!(approx(
+            triangle.closest_point(Vector2::new(0.0, -3.0), &transform),
+            Vector2::new(0.0, -0.5)
+        ));
+        assert!(
+            (triangle.distance_to_boundary(Vector2::new(0.0, -3.0), &transform) - 2.5).abs() < 1e-3
+        );
+        assert!(triangle.distance_to_boundary(Vector2::new(0.0, 0.0), &transform) < 0.0);
+    }
+
+    #[test]
+    fn test_aabb() {
+        let approx = |a: Vector2<f32>, b: Vector2<f32>| (a - b).norm() < 1e-3;
+        let square = Geometry::new_2d(
+            Geometry2DType::Square,
+            BorderDecoration::Solid,
+            Rgba::WHITE,
+            BorderThickness::LocalSpace(0.0),
+            InnerDecoration::Solid,
+            Rgba::WHITE,
+            0,
+            Vector2::new(1.0, 0.0),
+            0.0,
+            2.0,
+        );
+
+        let (min, max) = square.local_aabb();
+        assert!(approx(min, Vector2::new(0.0, -1.0)));
+        assert!(approx(max, Vector2::new(2.0, 1.0)));
+
+        // the rotated box is larger than the box of the rotated square.
+        let (min, max) = square.world_aabb(&Transform2D::new(0.0, 0.0, 45.0, 1.0, 1.0), 1.0);
+        let half = std::f32::consts::FRAC_1_SQRT_2;
+        assert!(approx(min, Vector2::new(-half, -half)));
+        assert!(approx(max, Vector2::new(3.0 * half, 3.0 * half)));
+
+        let line = Geometry::new_1d(
+            Geometry1DType::Line,
+            BorderDecoration::Solid,
+            Rgba::WHITE,
+            BorderThickness::LocalSpace(1.0),
+            0,
+            Vector2::new(0.0, 0.0),
+            Vector2::new(1.0, 1.0),
+        );
+        let (min, max) = line.world_aabb(&Transform2D::rotated(30.0), 1.0);
+        assert_eq!(min.x, f32::NEG_INFINITY);
+        assert_eq!(max.y, f32::INFINITY);
+
+        // the border of segments sticks out of the end points.
+        let mut segment = Geometry::new_1d(
+            Geometry1DType::Segment,
+            BorderDecoration::Solid,
+            Rgba::WHITE,
+            BorderThickness::LocalSpace(2.0),
+            0,
+            Vector2::new(-1.0, 0.0),
+            Vector2::new(1.0, 0.0),
+        );
+        let (min, max) = segment.local_aabb();
+        assert!(approx(min, Vector2::new(-1.0, -1.0)));
+        assert!(approx(max, Vector2::new(1.0, 1.0)));
+
+        let transform = Transform2D::new(0.0, 0.0, 0.0, 2.0, 2.0);
+        let (min, max) = segment.world_aabb(&transform, 1.0);
+        assert!(approx(min, Vector2::new(-2.0, -2.0)));
+        assert!(approx(max, Vector2::new(2.0, 2.0)));
+
+        // 4 pixels of 0.5 world units.
+        segment.set_border_thickness(BorderThickness::ScreenSpace(4.0));
+        let (min, max) = segment.world_aabb_of(segment.local_aabb(), &transform, 0.5);
+        assert!(approx(min, Vector2::new(-3.0, -1.0)));
+        assert!(approx(max, Vector2::new(3.0, 1.0)));
+    }
 }
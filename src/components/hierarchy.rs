@@ -0,0 +1,203 @@
+use crate::{
+    components::transform::Transform2D,
+    legion::{
+        query::{component, IntoQuery},
+        systems::{ParallelRunnable, SystemBuilder},
+        Entity, EntityStore,
+    },
+    nalgebra::Vector2,
+};
+
+use std::collections::{HashMap, HashSet};
+
+/// The parent of the entity, the `Transform2D` of the entity is relative to the parent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Parent(pub Entity);
+
+/// The transformation from the local space of the entity to `world space`, with all parents applied.
+///
+/// It's cached by `propagate_global_transforms_system`, which adds it to the entities with `Transform2D`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GlobalTransform2D(pub Transform2D);
+
+/// Append the `child` transform to the `parent` transform.
+///
+/// NOTE: a rotated child of a parent with non-uniform scale is skewed, which can't be represented by
+/// `Transform2D`, the scales are multiplied per axis instead.
+pub fn compose_transforms(parent: &Transform2D, child: &Transform2D) -> Transform2D {
+    Transform2D {
+        position: parent.position + parent.rotation * parent.scale.component_mul(&child.position),
+        rotation: parent.rotation * child.rotation,
+        scale: parent.scale.component_mul(&child.scale),
+    }
+}
+
+/// Return the transform of the entity in `world space` by walking up the `Parent` chain.
+///
+/// Missing entities and `Transform2D`s are treated as identity, the walk stops at a cycle.
+pub fn global_transform<W: EntityStore>(entity: Entity, world: &W) -> Transform2D {
+    resolve(entity, &mut Scratch::default(), |entity| {
+        let entry = world.entry_ref(entity).ok()?;
+        let transform = entry
+            .get_component::<Transform2D>()
+            .map(|transform| *transform)
+            .unwrap_or_default();
+        let parent = entry.get_component::<Parent>().ok().map(|parent| parent.0);
+
+        Some((transform, parent))
+    })
+}
+
+/// Convert `point` from the space of `local_root_transform` to the local space of the entity with
+/// `global_transform`, pass `Transform2D::default()` as the root for a point in `world space`.
+///
+/// The axis of a zero scale collapses, so the point is at 0.0 in that axis.
+pub fn global_to_local(
+    global_transform: &Transform2D,
+    local_root_transform: &Transform2D,
+    point: Vector2<f32>,
+) -> Vector2<f32> {
+    let point = local_root_transform.position
+        + local_root_transform.rotation * local_root_transform.scale.component_mul(&point);
+    let unrotated = global_transform.rotation.inverse() * (point - global_transform.position);
+
+    let divide = |v: f32, scale: f32| {
+        if scale.abs() > f32::EPSILON {
+            v / scale
+        } else {
+            0.0
+        }
+    };
+
+    Vector2::new(
+        divide(unrotated.x, global_transform.scale.x),
+        divide(unrotated.y, global_transform.scale.y),
+    )
+}
+
+/// Create a system which updates `GlobalTransform2D` of all entities with `Transform2D`, the hierarchy can
+/// be arbitrarily deep.
+///
+/// The missing `GlobalTransform2D`s are added by `CommandBuffer`, so they are visible after the system.
+pub fn propagate_global_transforms_system() -> impl ParallelRunnable {
+    SystemBuilder::new("propagate_global_transforms")
+        .with_query(<(Entity, &Transform2D, Option<&Parent>)>::query())
+        .with_query(<(Entity, &mut GlobalTransform2D)>::query())
+        .with_query(
+            <Entity>::query()
+                .filter(component::<Transform2D>() & !component::<GlobalTransform2D>()),
+        )
+        .build({
+            // NOTE: the buffers are reused across frames.
+            let mut nodes = HashMap::new();
+            let mut scratch = Scratch::default();
+
+            move |cmd, world, _, (locals, globals, missing)| {
+                nodes.clear();
+                nodes.extend(locals.iter(world).map(|(entity, transform, parent)| {
+                    (*entity, (*transform, parent.map(|parent| parent.0)))
+                }));
+
+                let lookup = |entity: Entity| nodes.get(&entity).copied();
+
+                globals.for_each_mut(world, |(entity, global)| {
+                    global.0 = resolve(*entity, &mut scratch, lookup);
+                });
+
+                for entity in missing.iter(world) {
+                    cmd.add_component(
+                        *entity,
+                        GlobalTransform2D(resolve(*entity, &mut scratch, lookup)),
+                    );
+                }
+            }
+        })
+}
+
+// The buffers of `resolve`, reused to avoid allocating per entity.
+#[derive(Default)]
+struct Scratch {
+    chain: Vec<Transform2D>,
+    visited: HashSet<Entity>,
+}
+
+// Walk up from `entity` and compose the transforms from the root, `lookup` returns the local transform
+// and the parent of an entity.
+fn resolve<F>(entity: Entity, scratch: &mut Scratch, lookup: F) -> Transform2D
+where
+    F: Fn(Entity) -> Option<(Transform2D, Option<Entity>)>,
+{
+    let Scratch { chain, visited } = scratch;
+    chain.clear();
+    visited.clear();
+
+    let mut next = Some(entity);
+    while let Some(entity) = next {
+        // NOTE: a cycle of parents would walk forever.
+        if !visited.insert(entity) {
+            break;
+        }
+
+        match lookup(entity) {
+            Some((transform, parent)) => {
+                chain.push(transform);
+                next = parent;
+            }
+            None => break,
+        }
+    }
+
+    chain
+        .iter()
+        .rev()
+        .fold(Transform2D::default(), |global, local| {
+            compose_transforms(&global, local)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::legion::World;
+
+    fn approx_eq(a: Vector2<f32>, b: Vector2<f32>) -> bool {
+        (a - b).norm() < 1e-4
+    }
+
+    #[test]
+    fn global_transform_walks_up_parents() {
+        let mut world = World::default();
+
+        let root = world.push((Transform2D::new(10.0, 0.0, 90.0, 2.0, 2.0),));
+        let child = world.push((Transform2D::at(5.0, 0.0), Parent(root)));
+        let grandchild = world.push((Transform2D::at(1.0, 0.0), Parent(child)));
+
+        let global = global_transform(grandchild, &world);
+        // (6.0, 0.0) in the root, rotated by 90 degrees and scaled by 2.0.
+        assert!(approx_eq(global.position, Vector2::new(10.0, 12.0)));
+        assert!((global.angle() - 90.0).abs() < 1e-4);
+
+        let local = global_to_local(&global, &Transform2D::default(), global.position);
+        assert!(approx_eq(local, Vector2::new(0.0, 0.0)));
+
+        let point = global_to_local(
+            &global_transform(child, &world),
+            &Transform2D::default(),
+            Vector2::new(10.0, 14.0),
+        );
+        assert!(approx_eq(point, Vector2::new(2.0, 0.0)));
+    }
+
+    #[test]
+    fn zero_scale_and_cycles_are_handled() {
+        let mut world = World::default();
+
+        let a = world.push((Transform2D::with_scale(0.0, 1.0),));
+        let b = world.push((Transform2D::at(1.0, 1.0), Parent(a)));
+        world.entry(a).unwrap().add_component(Parent(b));
+
+        let global = global_transform(b, &world);
+        let local = global_to_local(&global, &Transform2D::default(), Vector2::new(3.0, 3.0));
+        assert!(local.x.is_finite() && local.y.is_finite());
+    }
+}
@@ -1,7 +1,24 @@
+pub mod animation;
+pub mod blend;
 pub mod camera;
+pub mod camera_shake;
+pub mod collision;
 pub mod geometry;
+pub mod hierarchy;
+pub mod morph;
+pub mod parallax;
+pub mod particle;
+pub mod path;
+pub mod pathfinding;
+pub mod post_process;
+pub mod rigid_body;
 pub mod sprite;
+pub mod state_machine;
+pub mod stencil;
+pub mod text;
+pub mod tilemap;
 pub mod time;
 pub mod transform;
+pub mod tween;
 
 pub type Instance<T> = Vec<T>;
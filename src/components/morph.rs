@@ -0,0 +1,191 @@
+use crate::{
+    components::geometry::{
+        Assembly, BorderDecoration, BorderThickness, Geometry, Geometry1DType, Geometry2DType,
+    },
+    legion::system,
+    misc::color::Rgba,
+    nalgebra::Vector2,
+};
+
+/// The default count of vertices both shapes are resampled to.
+const DEFAULT_RESOLUTION: usize = 64;
+
+/// Morph the outline of `from` into the outline of `to`, `t` in [0, 1].
+///
+/// `geometry_morph_system` writes the interpolated outline as segments to the `Assembly` of the entity,
+/// so add an `Assembly`(can be empty) and a `Transform2D` together with it.
+///
+/// NOTE: the geometry renderer draws shapes by sdf, only the outline of the morph is drawn.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeometryMorph {
+    pub from: Geometry2DType,
+    pub to: Geometry2DType,
+    pub t: f32,
+
+    /// The size of shapes in `local space`.
+    pub size: f32,
+    pub color: Rgba,
+    pub thickness: BorderThickness,
+    pub order: u8,
+    /// The count of vertices both outlines are resampled to.
+    pub resolution: usize,
+}
+
+impl GeometryMorph {
+    pub fn new(from: Geometry2DType, to: Geometry2DType, size: f32) -> Self {
+        Self {
+            from,
+            to,
+            t: 0.0,
+
+            size,
+            color: Rgba::SOFT_BLACK,
+            thickness: BorderThickness::LocalSpace(2.0),
+            order: 100,
+            resolution: DEFAULT_RESOLUTION,
+        }
+    }
+
+    pub fn with_t(mut self, t: f32) -> Self {
+        self.t = t;
+        self
+    }
+
+    pub fn with_color(mut self, color: Rgba) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn with_thickness(mut self, thickness: BorderThickness) -> Self {
+        self.thickness = thickness;
+        self
+    }
+
+    pub fn with_order(mut self, order: u8) -> Self {
+        self.order = order;
+        self
+    }
+
+    pub fn with_resolution(mut self, resolution: usize) -> Self {
+        self.resolution = resolution;
+        self
+    }
+
+    /// The interpolated outline in `local space`, `t` is clamped to [0, 1].
+    pub fn vertices(&self) -> Vec<Vector2<f32>> {
+        let t = self.t.max(0.0).min(1.0);
+        let count = self.resolution.max(3);
+
+        let from = resample_vertices(&self.from.outline(), count);
+        let to = resample_vertices(&self.to.outline(), count);
+
+        from.iter()
+            .zip(to.iter())
+            .map(|(a, b)| (a + (b - a) * t) * self.size)
+            .collect()
+    }
+
+    /// The outline as closed segments.
+    pub fn to_assembly(&self) -> Assembly {
+        let vertices = self.vertices();
+
+        (0..vertices.len())
+            .map(|i| {
+                Geometry::new_1d(
+                    Geometry1DType::Segment,
+                    BorderDecoration::Solid,
+                    self.color,
+                    self.thickness,
+                    self.order,
+                    vertices[i],
+                    vertices[(i + 1) % vertices.len()],
+                )
+            })
+            .collect()
+    }
+}
+
+/// Resample the closed polygon to `target_count` vertices evenly spaced along its perimeter.
+///
+/// The first vertex is kept, so outlines starting from the same direction stay aligned.
+pub fn resample_vertices(verts: &[Vector2<f32>], target_count: usize) -> Vec<Vector2<f32>> {
+    if verts.len() < 2 || target_count == 0 {
+        return verts.iter().cloned().cycle().take(target_count).collect();
+    }
+
+    let edges = verts
+        .iter()
+        .zip(verts.iter().cycle().skip(1))
+        .map(|(a, b)| (*a, *b, (b - a).norm()))
+        .collect::<Vec<_>>();
+    let perimeter: f32 = edges.iter().map(|(_, _, len)| len).sum();
+    let step = perimeter / target_count as f32;
+
+    let mut resampled = Vec::with_capacity(target_count);
+    let mut edge = 0;
+    // the length of edges before `edge`.
+    let mut passed = 0.0;
+
+    for i in 0..target_count {
+        let distance = step * i as f32;
+
+        while edge + 1 < edges.len() && passed + edges[edge].2 < distance {
+            passed += edges[edge].2;
+            edge += 1;
+        }
+
+        let (a, b, len) = edges[edge];
+        let k = if len > 0.0 {
+            ((distance - passed) / len).min(1.0)
+        } else {
+            0.0
+        };
+
+        resampled.push(a + (b - a) * k);
+    }
+
+    resampled
+}
+
+#[system(for_each)]
+pub fn geometry_morph(morph: &GeometryMorph, assembly: &mut Assembly) {
+    *assembly = morph.to_assembly();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resample_keeps_start_and_spacing() {
+        let square = vec![
+            Vector2::new(0.0, 0.0),
+            Vector2::new(1.0, 0.0),
+            Vector2::new(1.0, 1.0),
+            Vector2::new(0.0, 1.0),
+        ];
+
+        let resampled = resample_vertices(&square, 8);
+        assert_eq!(resampled.len(), 8);
+        assert_eq!(resampled[0], square[0]);
+        assert!((resampled[1] - Vector2::new(0.5, 0.0)).norm() < 1e-5);
+        assert!((resampled[3] - Vector2::new(1.0, 0.5)).norm() < 1e-5);
+        assert!((resampled[7] - Vector2::new(0.0, 0.5)).norm() < 1e-5);
+    }
+
+    #[test]
+    fn morph_ends_are_the_shapes() {
+        let morph = GeometryMorph::new(Geometry2DType::Circle, Geometry2DType::StarFive, 1.0)
+            .with_resolution(10);
+
+        let star = resample_vertices(&Geometry2DType::StarFive.outline(), 10);
+        for (a, b) in morph.clone().with_t(1.0).vertices().iter().zip(star.iter()) {
+            assert!((a - b).norm() < 1e-5);
+        }
+
+        let circle = morph.with_t(0.0).vertices();
+        for v in circle {
+            assert!((v.norm() - 0.5).abs() < 1e-2);
+        }
+    }
+}
@@ -0,0 +1,132 @@
+use crate::{
+    components::{
+        camera::{Camera2D, RenderTarget},
+        transform::Transform2D,
+    },
+    legion::{
+        query::{component, IntoQuery},
+        systems::{ParallelRunnable, SystemBuilder},
+    },
+    nalgebra::Vector2,
+};
+
+/// Scroll the entity with the camera to fake depth, processed by `parallax_system`.
+///
+/// The entity follows `scroll_factor` of the camera movement: (0.0, 0.0) stays in the world like the
+/// other entities, (1.0, 1.0) sticks to the camera as if it's infinitely far away.
+///
+/// The offset is applied when rendering, `Transform2D::position` stays the position of the layer when the
+/// camera is at the origin, move the layer by it as usual.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Parallax2D {
+    pub scroll_factor: Vector2<f32>,
+    /// Repeat the layer every `tile_size`, the entity is moved by whole tiles to stay around the camera.
+    ///
+    /// NOTE: the content of the layer should cover at least one tile plus the view on each side.
+    pub tile: bool,
+    pub tile_size: Vector2<f32>,
+
+    // The offset from `Transform2D::position` to the rendered position, updated by `parallax_system`.
+    offset: Vector2<f32>,
+}
+
+impl Parallax2D {
+    pub fn new(scroll_factor: Vector2<f32>) -> Self {
+        Self {
+            scroll_factor,
+            tile: false,
+            tile_size: Vector2::new(0.0, 0.0),
+
+            offset: Vector2::new(0.0, 0.0),
+        }
+    }
+
+    /// Repeat the layer every `tile_size`, a non-positive component doesn't repeat in that axis.
+    pub fn tiled(mut self, tile_size: Vector2<f32>) -> Self {
+        self.tile = true;
+        self.tile_size = tile_size;
+        self
+    }
+
+    /// The offset from `Transform2D::position` to the rendered position in this frame.
+    pub fn offset(&self) -> Vector2<f32> {
+        self.offset
+    }
+
+    /// The rendered position of the layer at `origin` for the camera at `camera_position`.
+    pub fn position(&self, origin: Vector2<f32>, camera_position: Vector2<f32>) -> Vector2<f32> {
+        let mut position = origin + camera_position.component_mul(&self.scroll_factor);
+
+        if self.tile {
+            for axis in 0..2 {
+                let size = self.tile_size[axis];
+                if size > 0.0 {
+                    position[axis] +=
+                        ((camera_position[axis] - position[axis]) / size).round() * size;
+                }
+            }
+        }
+
+        position
+    }
+
+    /// The transform of the entity with the offset of this frame, used by the render stage.
+    pub fn apply(&self, transform: &Transform2D) -> Transform2D {
+        Transform2D {
+            position: transform.position + self.offset,
+            ..*transform
+        }
+    }
+
+    pub fn update(&mut self, camera_position: Vector2<f32>, transform: &Transform2D) {
+        self.offset = self.position(transform.position, camera_position) - transform.position;
+    }
+}
+
+/// Create a system which offsets the entities with `Parallax2D` by the screen camera of the highest
+/// priority.
+pub fn parallax_system() -> impl ParallelRunnable {
+    SystemBuilder::new("parallax")
+        .with_query(<(&Transform2D, &Camera2D)>::query().filter(!component::<Parallax2D>()))
+        .with_query(<(&mut Parallax2D, &Transform2D)>::query().filter(!component::<Camera2D>()))
+        .build(|_, world, _, (cameras, layers)| {
+            let camera_position = cameras
+                .iter(world)
+                .filter(|(_, camera)| camera.render_target == RenderTarget::Screen)
+                .max_by_key(|(_, camera)| camera.priority)
+                .map(|(transform, _)| transform.position);
+
+            if let Some(camera_position) = camera_position {
+                layers.for_each_mut(world, |(parallax, transform)| {
+                    parallax.update(camera_position, transform)
+                });
+            }
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parallax_follows_camera_by_factor() {
+        let mut parallax = Parallax2D::new(Vector2::new(0.5, 0.0));
+        let transform = Transform2D::at(10.0, 10.0);
+
+        parallax.update(Vector2::new(100.0, 100.0), &transform);
+        assert_eq!(parallax.offset(), Vector2::new(50.0, 0.0));
+        assert_eq!(
+            parallax.apply(&transform).position,
+            Vector2::new(60.0, 10.0)
+        );
+        // the transform isn't touched, it can be moved as usual.
+        assert_eq!(transform.position, Vector2::new(10.0, 10.0));
+
+        let tiled = Parallax2D::new(Vector2::new(0.5, 0.0)).tiled(Vector2::new(40.0, 0.0));
+        // 0.0 + 0.5 * 100.0 = 50.0, moved by a whole tile towards the camera.
+        assert_eq!(
+            tiled.position(Vector2::new(0.0, 0.0), Vector2::new(100.0, 0.0)),
+            Vector2::new(90.0, 0.0)
+        );
+    }
+}
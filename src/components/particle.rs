@@ -0,0 +1,411 @@
+use crate::{
+    components::{
+        geometry::{BorderDecoration, BorderThickness, Geometry, Geometry2DType, InnerDecoration},
+        time::Time,
+        transform::Transform2D,
+    },
+    legion::system,
+    misc::color::Rgba,
+    nalgebra::Vector2,
+};
+
+use std::ops::RangeInclusive;
+
+/// Emit circle particles in `world space`.
+///
+/// Particles live in a fixed-size ring buffer, when the buffer is full the oldest particle is
+/// replaced. All particles are rendered by the geometry renderer in the same instanced draw call
+/// as `Geometry`.
+#[derive(Debug, Clone)]
+pub struct ParticleEmitter2D {
+    /// Particles emitted per second.
+    pub emission_rate: f32,
+    /// The lifetime of particle in seconds.
+    pub lifetime: RangeInclusive<f32>,
+    pub initial_velocity: Vector2<f32>,
+    /// The velocity of particle is in `initial_velocity ± velocity_variance`.
+    pub velocity_variance: Vector2<f32>,
+    pub gravity: Vector2<f32>,
+    pub start_color: Rgba,
+    pub end_color: Rgba,
+    pub start_size: f32,
+    pub end_size: f32,
+    /// The render order of particles.
+    pub order: u8,
+
+    particles: Vec<Particle>,
+    next: usize,
+    emit_accumulator: f32,
+    pending_burst: u32,
+    seed: u32,
+}
+
+impl ParticleEmitter2D {
+    pub fn builder() -> ParticleEmitter2DBuilder {
+        ParticleEmitter2DBuilder::new()
+    }
+
+    /// Emit `count` particles at once in next update.
+    pub fn burst(&mut self, count: u32) {
+        self.pending_burst += count;
+    }
+
+    /// The max count of alive particles.
+    pub fn capacity(&self) -> usize {
+        self.particles.len()
+    }
+
+    pub fn alive_count(&self) -> usize {
+        self.particles.iter().filter(|p| p.is_alive()).count()
+    }
+
+    /// Kill all alive particles.
+    pub fn clear(&mut self) {
+        for particle in self.particles.iter_mut() {
+            particle.age = particle.lifetime;
+        }
+    }
+
+    /// Simulate particles, new particles are emitted at `origin`.
+    pub fn update(&mut self, dt: f32, origin: Vector2<f32>) {
+        for particle in self.particles.iter_mut().filter(|p| p.is_alive()) {
+            particle.velocity += self.gravity * dt;
+            particle.position += particle.velocity * dt;
+            particle.age += dt;
+        }
+
+        self.emit_accumulator += self.emission_rate.max(0.0) * dt;
+        let count = self.emit_accumulator as u32 + self.pending_burst;
+        self.emit_accumulator = self.emit_accumulator.fract();
+        self.pending_burst = 0;
+
+        for _ in 0..count {
+            self.emit(origin);
+        }
+    }
+
+    /// Iterate alive particles as the pair of `Transform2D` and `Geometry` to render.
+    pub(crate) fn renderables(&self) -> impl Iterator<Item = (Transform2D, Geometry)> + '_ {
+        self.particles
+            .iter()
+            .filter(|p| p.is_alive())
+            .map(move |p| {
+                let t = p.age / p.lifetime;
+                let size = self.start_size + (self.end_size - self.start_size) * t;
+                let color = lerp_rgba(&self.start_color, &self.end_color, t);
+
+                (
+                    Transform2D::at(p.position.x, p.position.y),
+                    Geometry::new_2d(
+                        Geometry2DType::Circle,
+                        BorderDecoration::None,
+                        color,
+                        BorderThickness::LocalSpace(0.0),
+                        InnerDecoration::Solid,
+                        color,
+                        self.order,
+                        Vector2::new(0.0, 0.0),
+                        0.0,
+                        size,
+                    ),
+                )
+            })
+    }
+
+    fn emit(&mut self, origin: Vector2<f32>) {
+        if self.particles.is_empty() {
+            return;
+        }
+
+        let (lt_min, lt_max) = (*self.lifetime.start(), *self.lifetime.end());
+        let lifetime = lt_min + (lt_max - lt_min) * self.random();
+        let variance = Vector2::new(
+            self.velocity_variance.x * (self.random() * 2.0 - 1.0),
+            self.velocity_variance.y * (self.random() * 2.0 - 1.0),
+        );
+
+        self.particles[self.next] = Particle {
+            position: origin,
+            velocity: self.initial_velocity + variance,
+            age: 0.0,
+            lifetime: lifetime.max(f32::EPSILON),
+        };
+        self.next = (self.next + 1) % self.particles.len();
+    }
+
+    /// Xorshift32, return a number in [0, 1].
+    fn random(&mut self) -> f32 {
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 17;
+        self.seed ^= self.seed << 5;
+
+        self.seed as f32 / u32::MAX as f32
+    }
+}
+
+/// Build `ParticleEmitter2D` with sane defaults.
+#[derive(Debug, Clone)]
+pub struct ParticleEmitter2DBuilder {
+    emitter: ParticleEmitter2D,
+    capacity: usize,
+}
+
+impl ParticleEmitter2DBuilder {
+    pub fn new() -> Self {
+        Self {
+            emitter: ParticleEmitter2D {
+                emission_rate: 32.0,
+                lifetime: 1.0..=2.0,
+                initial_velocity: Vector2::new(0.0, 64.0),
+                velocity_variance: Vector2::new(16.0, 16.0),
+                gravity: Vector2::new(0.0, -32.0),
+                start_color: Rgba::WHITE,
+                end_color: Rgba::new(255, 255, 255, 0),
+                start_size: 8.0,
+                end_size: 2.0,
+                order: 0,
+
+                particles: Vec::new(),
+                next: 0,
+                emit_accumulator: 0.0,
+                pending_burst: 0,
+                seed: 0x9E37_79B9,
+            },
+            capacity: 1024,
+        }
+    }
+
+    pub fn emission_rate(mut self, emission_rate: f32) -> Self {
+        self.emitter.emission_rate = emission_rate;
+        self
+    }
+
+    pub fn lifetime(mut self, lifetime: RangeInclusive<f32>) -> Self {
+        self.emitter.lifetime = lifetime;
+        self
+    }
+
+    pub fn initial_velocity(mut self, initial_velocity: Vector2<f32>) -> Self {
+        self.emitter.initial_velocity = initial_velocity;
+        self
+    }
+
+    pub fn velocity_variance(mut self, velocity_variance: Vector2<f32>) -> Self {
+        self.emitter.velocity_variance = velocity_variance;
+        self
+    }
+
+    pub fn gravity(mut self, gravity: Vector2<f32>) -> Self {
+        self.emitter.gravity = gravity;
+        self
+    }
+
+    pub fn color(mut self, start_color: Rgba, end_color: Rgba) -> Self {
+        self.emitter.start_color = start_color;
+        self.emitter.end_color = end_color;
+        self
+    }
+
+    pub fn size(mut self, start_size: f32, end_size: f32) -> Self {
+        self.emitter.start_size = start_size;
+        self.emitter.end_size = end_size;
+        self
+    }
+
+    pub fn order(mut self, order: u8) -> Self {
+        self.emitter.order = order;
+        self
+    }
+
+    /// The max count of alive particles.
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// The seed of the random generator which randomizes lifetime and velocity.
+    pub fn seed(mut self, seed: u32) -> Self {
+        // NOTE: xorshift gets stuck at 0.
+        self.emitter.seed = seed.max(1);
+        self
+    }
+
+    pub fn build(mut self) -> ParticleEmitter2D {
+        self.emitter.particles = vec![Particle::default(); self.capacity];
+        self.emitter
+    }
+}
+
+impl Default for ParticleEmitter2DBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Particle {
+    position: Vector2<f32>,
+    velocity: Vector2<f32>,
+    age: f32,
+    lifetime: f32,
+}
+
+impl Default for Particle {
+    fn default() -> Self {
+        Self {
+            position: Vector2::zeros(),
+            velocity: Vector2::zeros(),
+            age: 0.0,
+            lifetime: 0.0,
+        }
+    }
+}
+
+impl Particle {
+    fn is_alive(&self) -> bool {
+        self.age < self.lifetime
+    }
+}
+
+fn lerp_rgba(from: &Rgba, to: &Rgba, t: f32) -> Rgba {
+    let lerp = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+
+    Rgba::new(
+        lerp(from.r, to.r),
+        lerp(from.g, to.g),
+        lerp(from.b, to.b),
+        lerp(from.a, to.a),
+    )
+}
+
+/// Simulate all `ParticleEmitter2D`s, particles are emitted at the position of the entity.
+#[system(for_each)]
+pub fn update_particles(
+    emitter: &mut ParticleEmitter2D,
+    transform: &Transform2D,
+    #[resource] time: &Time,
+) {
+    emitter.update(time.delta().as_secs_f32(), transform.position);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // An emitter whose particles stay where they are emitted and live for 10 seconds.
+    fn still_emitter() -> ParticleEmitter2DBuilder {
+        ParticleEmitter2D::builder()
+            .seed(7)
+            .emission_rate(0.0)
+            .lifetime(10.0..=10.0)
+            .initial_velocity(Vector2::zeros())
+            .velocity_variance(Vector2::zeros())
+            .gravity(Vector2::zeros())
+    }
+
+    fn positions(emitter: &ParticleEmitter2D) -> Vec<Vector2<f32>> {
+        emitter
+            .renderables()
+            .map(|(transform, _)| transform.position)
+            .collect()
+    }
+
+    #[test]
+    fn carry_fractional_emission() {
+        let mut emitter = still_emitter().emission_rate(6.0).build();
+
+        // 1.5 particles per update.
+        emitter.update(0.25, Vector2::zeros());
+        assert_eq!(emitter.alive_count(), 1);
+        emitter.update(0.25, Vector2::zeros());
+        assert_eq!(emitter.alive_count(), 3);
+        emitter.update(0.25, Vector2::zeros());
+        assert_eq!(emitter.alive_count(), 4);
+    }
+
+    #[test]
+    fn burst_with_emission() {
+        let mut emitter = still_emitter().emission_rate(6.0).build();
+
+        emitter.burst(2);
+        emitter.burst(1);
+        emitter.update(0.25, Vector2::zeros());
+        assert_eq!(emitter.alive_count(), 4);
+
+        // the burst is emitted once.
+        emitter.update(0.25, Vector2::zeros());
+        assert_eq!(emitter.alive_count(), 6);
+    }
+
+    #[test]
+    fn replace_oldest_particle() {
+        let mut emitter = still_emitter().capacity(2).build();
+        assert_eq!(emitter.capacity(), 2);
+
+        for x in 1..=3 {
+            emitter.burst(1);
+            emitter.update(0.1, Vector2::new(x as f32, 0.0));
+        }
+
+        assert_eq!(emitter.alive_count(), 2);
+        assert_eq!(
+            positions(&emitter),
+            vec![Vector2::new(3.0, 0.0), Vector2::new(2.0, 0.0)]
+        );
+    }
+
+    #[test]
+    fn emit_without_capacity() {
+        let mut emitter = still_emitter().capacity(0).emission_rate(100.0).build();
+
+        emitter.burst(8);
+        emitter.update(1.0, Vector2::zeros());
+        assert_eq!(emitter.capacity(), 0);
+        assert_eq!(emitter.alive_count(), 0);
+        assert_eq!(emitter.renderables().count(), 0);
+    }
+
+    #[test]
+    fn clear_particles() {
+        let mut emitter = still_emitter().build();
+
+        emitter.burst(5);
+        emitter.update(0.1, Vector2::zeros());
+        assert_eq!(emitter.alive_count(), 5);
+
+        emitter.clear();
+        assert_eq!(emitter.alive_count(), 0);
+        assert_eq!(emitter.renderables().count(), 0);
+
+        // the particles die by age too.
+        emitter.burst(1);
+        emitter.update(0.1, Vector2::zeros());
+        emitter.update(10.0, Vector2::zeros());
+        assert_eq!(emitter.alive_count(), 0);
+    }
+
+    #[test]
+    fn interpolate_size_and_color() {
+        let mut emitter = still_emitter()
+            .size(8.0, 2.0)
+            .color(Rgba::new(0, 0, 0, 255), Rgba::new(200, 100, 50, 55))
+            .order(3)
+            .build();
+
+        emitter.burst(1);
+        emitter.update(0.1, Vector2::new(1.0, 2.0));
+
+        let (transform, geometry) = emitter.renderables().next().unwrap();
+        assert_eq!(transform.position, Vector2::new(1.0, 2.0));
+        assert_eq!(geometry.size_uncheck(), 8.0);
+        assert_eq!(geometry.inner_color_rgba(), Rgba::new(0, 0, 0, 255));
+        assert_eq!(geometry.order(), 3);
+
+        // half of the lifetime.
+        emitter.update(5.0, Vector2::zeros());
+        let (_, geometry) = emitter.renderables().next().unwrap();
+        assert_eq!(geometry.size_uncheck(), 5.0);
+        assert_eq!(geometry.inner_color_rgba(), Rgba::new(100, 50, 25, 155));
+        assert_eq!(geometry.border_color_rgba(), Rgba::new(100, 50, 25, 155));
+    }
+}
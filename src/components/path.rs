@@ -0,0 +1,388 @@
+use crate::{
+    assets::{Assets, Handle},
+    components::{
+        geometry::{Assembly, BorderDecoration, BorderThickness, Geometry, Geometry1DType},
+        time::Time,
+        transform::Transform2D,
+    },
+    legion::{system, systems::CommandBuffer, Entity},
+    misc::color::Rgba,
+    nalgebra::Vector2,
+};
+
+/// The count of subdivisions of a curved span when measuring or drawing it.
+const CURVE_SUBDIVISION: usize = 16;
+
+/// How the points of `Path2D` are connected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathInterpolation {
+    /// Straight segments between points.
+    Linear,
+    /// A smooth curve passing through all points.
+    CatmullRom,
+    /// A chain of cubic bezier curves, `[p0, c0, c1, p1, c2, c3, p2, ...]`.
+    Bezier,
+}
+
+/// A path in `world space`, followed by `PathFollower`.
+///
+/// The total length is cached, so the path is modified by the setters only.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Path2D {
+    points: Vec<Vector2<f32>>,
+    closed: bool,
+    interpolation: PathInterpolation,
+
+    total_length: f32,
+}
+
+impl Path2D {
+    pub fn new(points: Vec<Vector2<f32>>, closed: bool, interpolation: PathInterpolation) -> Self {
+        let mut path = Self {
+            points,
+            closed,
+            interpolation,
+
+            total_length: 0.0,
+        };
+        path.total_length = path.measure();
+
+        path
+    }
+
+    pub fn points(&self) -> &[Vector2<f32>] {
+        &self.points
+    }
+
+    pub fn set_points(&mut self, points: Vec<Vector2<f32>>) {
+        self.points = points;
+        self.total_length = self.measure();
+    }
+
+    pub fn closed(&self) -> bool {
+        self.closed
+    }
+
+    pub fn set_closed(&mut self, closed: bool) {
+        self.closed = closed;
+        self.total_length = self.measure();
+    }
+
+    pub fn interpolation(&self) -> PathInterpolation {
+        self.interpolation
+    }
+
+    pub fn set_interpolation(&mut self, interpolation: PathInterpolation) {
+        self.interpolation = interpolation;
+        self.total_length = self.measure();
+    }
+
+    /// Return the point at `t` ∈ [0, 1] of the path, `t` is spread evenly over the spans of the path.
+    pub fn sample(&self, t: f32) -> Vector2<f32> {
+        let span_count = self.span_count();
+
+        if span_count == 0 {
+            return self.points.first().copied().unwrap_or_else(Vector2::zeros);
+        }
+
+        let s = t.max(0.0).min(1.0) * span_count as f32;
+        let index = usize::min(s as usize, span_count - 1);
+
+        self.sample_span(index, s - index as f32)
+    }
+
+    /// The length of the whole path, curves are measured by subdivision.
+    pub fn total_length(&self) -> f32 {
+        self.total_length
+    }
+
+    fn measure(&self) -> f32 {
+        let subdivision = match self.interpolation {
+            PathInterpolation::Linear => 1,
+            _ => CURVE_SUBDIVISION,
+        };
+
+        let points = self.polyline(subdivision);
+
+        points.windows(2).map(|w| (w[1] - w[0]).norm()).sum()
+    }
+
+    /// Build segments which visualize the path, useful for debugging.
+    pub fn to_assembly(&self, color: Rgba, thickness: BorderThickness, order: u8) -> Assembly {
+        let subdivision = match self.interpolation {
+            PathInterpolation::Linear => 1,
+            _ => CURVE_SUBDIVISION,
+        };
+
+        self.polyline(subdivision)
+            .windows(2)
+            .map(|w| {
+                Geometry::new_1d(
+                    Geometry1DType::Segment,
+                    BorderDecoration::Solid,
+                    color,
+                    thickness,
+                    order,
+                    w[0],
+                    w[1],
+                )
+            })
+            .collect()
+    }
+
+    fn span_count(&self) -> usize {
+        let n = self.points.len();
+
+        match self.interpolation {
+            _ if n < 2 => 0,
+            PathInterpolation::Linear | PathInterpolation::CatmullRom => {
+                if self.closed {
+                    n
+                } else {
+                    n - 1
+                }
+            }
+            PathInterpolation::Bezier => {
+                if self.closed {
+                    n / 3
+                } else {
+                    (n - 1) / 3
+                }
+            }
+        }
+    }
+
+    fn point(&self, index: isize) -> Vector2<f32> {
+        let n = self.points.len() as isize;
+
+        let index = if self.closed {
+            index.rem_euclid(n)
+        } else {
+            index.max(0).min(n - 1)
+        };
+
+        self.points[index as usize]
+    }
+
+    fn sample_span(&self, index: usize, t: f32) -> Vector2<f32> {
+        let i = index as isize;
+
+        match self.interpolation {
+            PathInterpolation::Linear => {
+                let (p0, p1) = (self.point(i), self.point(i + 1));
+
+                p0 + (p1 - p0) * t
+            }
+            PathInterpolation::CatmullRom => {
+                let (p0, p1, p2, p3) = (
+                    self.point(i - 1),
+                    self.point(i),
+                    self.point(i + 1),
+                    self.point(i + 2),
+                );
+                let (t2, t3) = (t * t, t * t * t);
+
+                (p1 * 2.0
+                    + (p2 - p0) * t
+                    + (p0 * 2.0 - p1 * 5.0 + p2 * 4.0 - p3) * t2
+                    + (p1 * 3.0 - p0 - p2 * 3.0 + p3) * t3)
+                    * 0.5
+            }
+            PathInterpolation::Bezier => {
+                let (p0, c0, c1, p1) = (
+                    self.point(3 * i),
+                    self.point(3 * i + 1),
+                    self.point(3 * i + 2),
+                    self.point(3 * i + 3),
+                );
+                let u = 1.0 - t;
+
+                p0 * (u * u * u)
+                    + c0 * (3.0 * u * u * t)
+                    + c1 * (3.0 * u * t * t)
+                    + p1 * (t * t * t)
+            }
+        }
+    }
+
+    fn polyline(&self, subdivision: usize) -> Vec<Vector2<f32>> {
+        let span_count = self.span_count();
+
+        if span_count == 0 {
+            return self.points.clone();
+        }
+
+        let mut points = Vec::with_capacity(span_count * subdivision + 1);
+
+        for index in 0..span_count {
+            for step in 0..subdivision {
+                points.push(self.sample_span(index, step as f32 / subdivision as f32));
+            }
+        }
+        points.push(self.sample_span(span_count - 1, 1.0));
+
+        points
+    }
+}
+
+/// What to do when a `PathFollower` reaches the end of the path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathEnd {
+    Stop,
+    Loop,
+    PingPong,
+    Despawn,
+}
+
+/// Move the `Transform2D` of the entity along a `Path2D` stored in `Assets<Path2D>`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PathFollower {
+    pub path: Handle<Path2D>,
+    /// The speed in world units per second.
+    pub speed: f32,
+    /// The normalized progress in [0, 1].
+    pub progress: f32,
+    pub on_end: PathEnd,
+
+    reversed: bool,
+}
+
+impl PathFollower {
+    pub fn new(path: Handle<Path2D>, speed: f32, on_end: PathEnd) -> Self {
+        Self {
+            path,
+            speed,
+            progress: 0.0,
+            on_end,
+
+            reversed: false,
+        }
+    }
+
+    /// Whether the follower has stopped at the end of the path.
+    pub fn is_finished(&self) -> bool {
+        self.on_end == PathEnd::Stop && self.progress >= 1.0
+    }
+}
+
+/// Move all `PathFollower`s along their paths.
+///
+/// The speed is converted to progress by the total length of the path, so it is exactly
+/// constant only when the spans have similar lengths.
+#[system(for_each)]
+pub fn follow_path(
+    entity: &Entity,
+    follower: &mut PathFollower,
+    transform: &mut Transform2D,
+    cmd: &mut CommandBuffer,
+    #[resource] paths: &Assets<Path2D>,
+    #[resource] time: &Time,
+) {
+    let path = match paths.get(follower.path) {
+        Some(path) => path,
+        None => return,
+    };
+
+    let length = path.total_length();
+    let delta = if length > 0.0 {
+        follower.speed * time.delta().as_secs_f32() / length
+    } else {
+        1.0
+    };
+
+    if follower.reversed {
+        follower.progress -= delta;
+    } else {
+        follower.progress += delta;
+    }
+
+    // NOTE: only the end the follower moves towards counts, a still follower doesn't bounce at the start.
+    let reached_end = if follower.reversed {
+        follower.progress <= 0.0
+    } else {
+        follower.progress >= 1.0
+    };
+
+    if reached_end {
+        match follower.on_end {
+            PathEnd::Stop => follower.progress = follower.progress.max(0.0).min(1.0),
+            PathEnd::Loop => follower.progress = follower.progress.rem_euclid(1.0),
+            PathEnd::PingPong => {
+                follower.progress = follower.progress.max(0.0).min(1.0);
+                follower.reversed = !follower.reversed;
+            }
+            PathEnd::Despawn if follower.progress >= 1.0 => {
+                follower.progress = 1.0;
+                cmd.remove(*entity);
+            }
+            PathEnd::Despawn => {}
+        }
+    }
+
+    transform.position = path.sample(follower.progress);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::legion::{IntoQuery, Resources, Schedule, World};
+
+    #[test]
+    fn cache_total_length() {
+        let mut path = Path2D::new(
+            vec![Vector2::new(0.0, 0.0), Vector2::new(3.0, 4.0)],
+            false,
+            PathInterpolation::Linear,
+        );
+        assert_eq!(path.total_length(), 5.0);
+
+        path.set_points(vec![
+            Vector2::new(0.0, 0.0),
+            Vector2::new(3.0, 0.0),
+            Vector2::new(3.0, 4.0),
+        ]);
+        assert_eq!(path.total_length(), 7.0);
+
+        path.set_closed(true);
+        assert_eq!(path.total_length(), 12.0);
+    }
+
+    #[test]
+    fn still_follower_does_not_bounce() {
+        let mut world = World::default();
+        let mut resources = Resources::default();
+
+        let mut paths = Assets::new();
+        let path = paths.add(Path2D::new(
+            vec![Vector2::new(0.0, 0.0), Vector2::new(10.0, 0.0)],
+            false,
+            PathInterpolation::Linear,
+        ));
+
+        let at_start = world.push((
+            PathFollower::new(path, 0.0, PathEnd::PingPong),
+            Transform2D::default(),
+        ));
+        let mut follower = PathFollower::new(path, 0.0, PathEnd::PingPong);
+        follower.progress = 1.0;
+        let at_end = world.push((follower, Transform2D::default()));
+
+        resources.insert(paths);
+        resources.insert(Time::now());
+
+        let mut schedule = Schedule::builder().add_system(follow_path_system()).build();
+
+        for _ in 0..3 {
+            schedule.execute(&mut world, &mut resources);
+        }
+
+        let mut query = <&PathFollower>::query();
+        // the follower at the start never moves towards the end.
+        assert!(!query.get(&world, at_start).unwrap().reversed);
+        // the follower at the end turns back only once.
+        assert!(query.get(&world, at_end).unwrap().reversed);
+
+        let transform = <&Transform2D>::query().get(&world, at_end).unwrap();
+        assert_eq!(transform.position, Vector2::new(10.0, 0.0));
+    }
+}
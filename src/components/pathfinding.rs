@@ -0,0 +1,66 @@
+use crate::{
+    legion::{
+        systems::{ParallelRunnable, SystemBuilder},
+        Entity, IntoQuery,
+    },
+    math::{
+        grid::Grid2D,
+        pathfinding::{astar_with, Connectivity},
+    },
+};
+
+/// Ask `pathfinding_system` to find a path on the `Grid2D<T>` resource.
+///
+/// The request is replaced with a `PathfindingResult` when it is serviced.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PathfindingRequest {
+    pub start: (usize, usize),
+    pub goal: (usize, usize),
+    pub connectivity: Connectivity,
+}
+
+impl PathfindingRequest {
+    pub fn new(start: (usize, usize), goal: (usize, usize), connectivity: Connectivity) -> Self {
+        Self {
+            start,
+            goal,
+            connectivity,
+        }
+    }
+}
+
+/// The path found for a `PathfindingRequest`, `None` if the goal is unreachable.
+///
+/// Convert the cells to world positions by `Grid2D::cell_to_world`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathfindingResult {
+    pub path: Option<Vec<(usize, usize)>>,
+}
+
+/// Create a system which services all `PathfindingRequest`s on the `Grid2D<T>` resource.
+///
+/// `cost` returns the cost to enter a cell, `None` means the cell is impassable. The results are written
+/// by `CommandBuffer`, so they are visible after the system.
+pub fn pathfinding_system<T, F>(cost: F) -> impl ParallelRunnable
+where
+    T: Send + Sync + 'static,
+    F: Fn(&T) -> Option<f32> + Send + Sync + 'static,
+{
+    SystemBuilder::new(format!("pathfinding<{}>", std::any::type_name::<T>()))
+        .read_resource::<Grid2D<T>>()
+        .with_query(<(Entity, &PathfindingRequest)>::query())
+        .build(move |cmd, world, grid, query| {
+            for (entity, request) in query.iter(world) {
+                let path = astar_with(
+                    grid,
+                    request.start,
+                    request.goal,
+                    &cost,
+                    request.connectivity,
+                );
+
+                cmd.remove_component::<PathfindingRequest>(*entity);
+                cmd.add_component(*entity, PathfindingResult { path });
+            }
+        })
+}
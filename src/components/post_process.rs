@@ -0,0 +1,312 @@
+use crate::misc::color::Rgba;
+
+/// A full-screen pass applied to what a camera has rendered.
+///
+/// The fragment shader reads the source texture at binding 0 and 1, and the uniform at binding 2:
+/// `Params { vec4 params0; vec4 params1; vec2 texel_size; float t_total; }`, the vertex shader is
+/// `assets/shaders/post_process/fullscreen.vert`.
+pub trait PostEffect: Send + Sync + 'static {
+    /// The unique name of the effect, the render pipeline is cached by it.
+    fn name(&self) -> &'static str;
+
+    /// The fragment shader in SPIR-V.
+    fn fragment_shader(&self) -> &'static [u8];
+
+    /// The parameters written to `params0` and `params1` of the shader.
+    fn params(&self) -> [f32; 8];
+
+    fn enabled(&self) -> bool {
+        true
+    }
+}
+
+/// The post-processing effects of a camera, add it to the entity with `Camera2D`.
+///
+/// Effects are composed in order, the output of an effect is the input of the next one.
+///
+/// NOTE: it's a component instead of a field of `Camera2D`, because `Camera2D` is `Copy` and the effects
+/// are boxed, and the cameras without effects pay nothing for it.
+#[derive(Default)]
+pub struct PostProcessStack {
+    pub effects: Vec<Box<dyn PostEffect>>,
+}
+
+impl PostProcessStack {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with<E: PostEffect>(mut self, effect: E) -> Self {
+        self.push(effect);
+        self
+    }
+
+    pub fn push<E: PostEffect>(&mut self, effect: E) {
+        self.effects.push(Box::new(effect));
+    }
+
+    pub fn clear(&mut self) {
+        self.effects.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.effects.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.effects.is_empty()
+    }
+
+    /// The effects will be applied.
+    pub fn enabled_effects(&self) -> impl Iterator<Item = &dyn PostEffect> {
+        self.effects
+            .iter()
+            .map(|effect| effect.as_ref())
+            .filter(|effect| effect.enabled())
+    }
+}
+
+impl std::fmt::Debug for PostProcessStack {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list()
+            .entries(self.effects.iter().map(|effect| effect.name()))
+            .finish()
+    }
+}
+
+/// Blur the part of image brighter than `threshold` and add it back.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BloomEffect {
+    /// The brightness in [0, 1] above which the color blooms.
+    pub threshold: f32,
+    pub intensity: f32,
+    /// The radius of blur in pixels.
+    pub radius: f32,
+}
+
+impl Default for BloomEffect {
+    fn default() -> Self {
+        Self {
+            threshold: 0.8,
+            intensity: 1.0,
+            radius: 8.0,
+        }
+    }
+}
+
+impl PostEffect for BloomEffect {
+    fn name(&self) -> &'static str {
+        "bloom"
+    }
+
+    fn fragment_shader(&self) -> &'static [u8] {
+        include_bytes!("../../assets/shaders/post_process/bloom.frag.spv")
+    }
+
+    fn params(&self) -> [f32; 8] {
+        [
+            self.threshold,
+            self.intensity,
+            self.radius,
+            0.0,
+            0.0,
+            0.0,
+            0.0,
+            0.0,
+        ]
+    }
+}
+
+/// Darken(or tint) the edge of image.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VignetteEffect {
+    /// The distance from the center where the vignette starts, 1.0 is the corner.
+    pub radius: f32,
+    /// The distance over which the vignette fades in.
+    pub softness: f32,
+    /// The color of the edge, the alpha controls the strength.
+    pub color: Rgba,
+}
+
+impl Default for VignetteEffect {
+    fn default() -> Self {
+        Self {
+            radius: 0.75,
+            softness: 0.45,
+            color: Rgba::BLACK,
+        }
+    }
+}
+
+impl PostEffect for VignetteEffect {
+    fn name(&self) -> &'static str {
+        "vignette"
+    }
+
+    fn fragment_shader(&self) -> &'static [u8] {
+        include_bytes!("../../assets/shaders/post_process/vignette.frag.spv")
+    }
+
+    fn params(&self) -> [f32; 8] {
+        let Rgba { r, g, b, a } = self.color;
+
+        [
+            self.radius,
+            self.softness,
+            0.0,
+            0.0,
+            r as f32 / 255.0,
+            g as f32 / 255.0,
+            b as f32 / 255.0,
+            a as f32 / 255.0,
+        ]
+    }
+}
+
+/// Adjust the color of image, the default value changes nothing.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColorGradeEffect {
+    /// 0.0 is grayscale, 1.0 keeps the color.
+    pub saturation: f32,
+    /// Scale the distance from the middle gray.
+    pub contrast: f32,
+    /// Multiply the color.
+    pub brightness: f32,
+}
+
+impl Default for ColorGradeEffect {
+    fn default() -> Self {
+        Self {
+            saturation: 1.0,
+            contrast: 1.0,
+            brightness: 1.0,
+        }
+    }
+}
+
+impl PostEffect for ColorGradeEffect {
+    fn name(&self) -> &'static str {
+        "color_grade"
+    }
+
+    fn fragment_shader(&self) -> &'static [u8] {
+        include_bytes!("../../assets/shaders/post_process/color_grade.frag.spv")
+    }
+
+    fn params(&self) -> [f32; 8] {
+        [
+            self.saturation,
+            self.contrast,
+            self.brightness,
+            0.0,
+            0.0,
+            0.0,
+            0.0,
+            0.0,
+        ]
+    }
+}
+
+/// Split the red and blue channels towards the edge of image.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChromaticAberration {
+    /// The offset of channels at the edge, in uv.
+    pub strength: f32,
+}
+
+impl Default for ChromaticAberration {
+    fn default() -> Self {
+        Self { strength: 0.01 }
+    }
+}
+
+impl PostEffect for ChromaticAberration {
+    fn name(&self) -> &'static str {
+        "chromatic_aberration"
+    }
+
+    fn fragment_shader(&self) -> &'static [u8] {
+        include_bytes!("../../assets/shaders/post_process/chromatic_aberration.frag.spv")
+    }
+
+    fn params(&self) -> [f32; 8] {
+        [self.strength, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Toggle(&'static str, bool);
+
+    impl PostEffect for Toggle {
+        fn name(&self) -> &'static str {
+            self.0
+        }
+
+        fn fragment_shader(&self) -> &'static [u8] {
+            &[]
+        }
+
+        fn params(&self) -> [f32; 8] {
+            [0.0; 8]
+        }
+
+        fn enabled(&self) -> bool {
+            self.1
+        }
+    }
+
+    #[test]
+    fn pack_params() {
+        let bloom = BloomEffect {
+            threshold: 0.5,
+            intensity: 2.0,
+            radius: 4.0,
+        };
+        assert_eq!(bloom.params(), [0.5, 2.0, 4.0, 0.0, 0.0, 0.0, 0.0, 0.0]);
+
+        // the color is in [0, 1].
+        let vignette = VignetteEffect {
+            radius: 0.5,
+            softness: 0.25,
+            color: Rgba::new(255, 0, 51, 102),
+        };
+        assert_eq!(vignette.params(), [0.5, 0.25, 0.0, 0.0, 1.0, 0.0, 0.2, 0.4]);
+
+        let grade = ColorGradeEffect {
+            saturation: 0.0,
+            contrast: 1.5,
+            brightness: 0.75,
+        };
+        assert_eq!(grade.params(), [0.0, 1.5, 0.75, 0.0, 0.0, 0.0, 0.0, 0.0]);
+
+        let aberration = ChromaticAberration { strength: 0.02 };
+        assert_eq!(
+            aberration.params(),
+            [0.02, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0, 0.0]
+        );
+    }
+
+    #[test]
+    fn skip_disabled_effects() {
+        let mut stack = PostProcessStack::new()
+            .with(Toggle("a", true))
+            .with(Toggle("b", false))
+            .with(Toggle("c", true));
+        stack.push(Toggle("d", false));
+        stack.push(Toggle("e", true));
+        assert_eq!(stack.len(), 5);
+
+        let names: Vec<_> = stack
+            .enabled_effects()
+            .map(|effect| effect.name())
+            .collect();
+        assert_eq!(names, vec!["a", "c", "e"]);
+
+        stack.clear();
+        assert!(stack.is_empty());
+        assert_eq!(stack.enabled_effects().count(), 0);
+    }
+}
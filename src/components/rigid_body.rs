@@ -0,0 +1,401 @@
+use crate::{
+    components::{
+        collision::{narrow_phase_collision, ContactManifold, SpatialHash2D, AABB2D},
+        geometry::Geometry,
+        time::Time,
+        transform::Transform2D,
+    },
+    legion::{systems::ParallelRunnable, Entity, IntoQuery, SystemBuilder},
+    nalgebra::Vector2,
+};
+
+use std::collections::HashMap;
+
+/// How a `RigidBody2D` is moved by `integrate_rigid_bodies_system`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BodyType {
+    /// Never moves, other bodies bounce off it.
+    Static,
+    /// Moved only by its velocity, ignores gravity, forces and collisions.
+    Kinematic,
+    /// Moved by gravity, forces and collisions.
+    Dynamic,
+}
+
+/// A simple body for arcade physics, it moves the `Transform2D` of the entity.
+///
+/// The bodies with a `Geometry` collide with each other by `narrow_phase_collision`, there's no rotation
+/// from collisions.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RigidBody2D {
+    pub body_type: BodyType,
+    pub velocity: Vector2<f32>,
+    /// Degrees per second, counter-clockwise.
+    pub angular_velocity: f32,
+    /// The mass is infinite if it's not positive.
+    pub mass: f32,
+    /// How much speed is kept after a collision, 0 stops and 1 bounces back fully.
+    pub restitution: f32,
+    pub friction: f32,
+
+    // the forces applied since the last integration.
+    force: Vector2<f32>,
+}
+
+impl RigidBody2D {
+    pub fn new(body_type: BodyType) -> Self {
+        Self {
+            body_type,
+            velocity: Vector2::new(0.0, 0.0),
+            angular_velocity: 0.0,
+            mass: 1.0,
+            restitution: 0.0,
+            friction: 0.0,
+            force: Vector2::new(0.0, 0.0),
+        }
+    }
+
+    pub fn new_static() -> Self {
+        Self::new(BodyType::Static)
+    }
+
+    pub fn new_kinematic() -> Self {
+        Self::new(BodyType::Kinematic)
+    }
+
+    pub fn new_dynamic(mass: f32) -> Self {
+        Self::new(BodyType::Dynamic).with_mass(mass)
+    }
+
+    pub fn with_velocity(mut self, velocity: Vector2<f32>) -> Self {
+        self.velocity = velocity;
+        self
+    }
+
+    pub fn with_mass(mut self, mass: f32) -> Self {
+        self.mass = mass;
+        self
+    }
+
+    pub fn with_restitution(mut self, restitution: f32) -> Self {
+        self.restitution = restitution;
+        self
+    }
+
+    pub fn with_friction(mut self, friction: f32) -> Self {
+        self.friction = friction;
+        self
+    }
+
+    /// The inverse of mass, 0 for the bodies not moved by forces.
+    pub fn inverse_mass(&self) -> f32 {
+        if self.body_type == BodyType::Dynamic && self.mass > 0.0 {
+            1.0 / self.mass
+        } else {
+            0.0
+        }
+    }
+
+    // Apply gravity and forces to velocity, then move the transform by velocity.
+    fn integrate(&mut self, transform: &mut Transform2D, gravity: Vector2<f32>, dt: f32) {
+        match self.body_type {
+            BodyType::Static => {
+                self.velocity = Vector2::new(0.0, 0.0);
+                self.angular_velocity = 0.0;
+            }
+            BodyType::Kinematic => (),
+            BodyType::Dynamic => self.velocity += (gravity + self.force * self.inverse_mass()) * dt,
+        }
+
+        self.force = Vector2::new(0.0, 0.0);
+
+        transform.position += self.velocity * dt;
+        transform.rotate(self.angular_velocity * dt);
+    }
+}
+
+impl Default for RigidBody2D {
+    fn default() -> Self {
+        Self::new_dynamic(1.0)
+    }
+}
+
+/// Apply the force to the body in the next integration, it's cleared after that.
+pub fn apply_force(body: &mut RigidBody2D, force: Vector2<f32>) {
+    if body.body_type == BodyType::Dynamic {
+        body.force += force;
+    }
+}
+
+/// Change the velocity of the body at once by `impulse / mass`.
+pub fn apply_impulse(body: &mut RigidBody2D, impulse: Vector2<f32>) {
+    body.velocity += impulse * body.inverse_mass();
+}
+
+/// Create a system which moves the `Transform2D`s by `RigidBody2D`s and bounces the colliding bodies.
+///
+/// The `gravity` is an acceleration applied to `Dynamic` bodies, e.g. `Vector2::new(0.0, -980.0)`.
+pub fn integrate_rigid_bodies_system(gravity: Vector2<f32>) -> impl ParallelRunnable {
+    // NOTE: the system keeps its own spatial hash, the `SpatialHash2D` resource belongs to `broad_phase_system`.
+    let mut spatial_hash = SpatialHash2D::default();
+
+    SystemBuilder::new("integrate_rigid_bodies")
+        .read_resource::<Time>()
+        .with_query(<(
+            Entity,
+            &mut RigidBody2D,
+            &mut Transform2D,
+            Option<&Geometry>,
+        )>::query())
+        .build(move |_, world, time, query| {
+            let dt = time.delta().as_secs_f32();
+
+            let mut bodies = query.iter_mut(world).collect::<Vec<_>>();
+            for (_, body, transform, _) in bodies.iter_mut() {
+                body.integrate(transform, gravity, dt);
+            }
+
+            let mut colliders = bodies
+                .into_iter()
+                .filter_map(|(entity, body, transform, geometry)| {
+                    Some((*entity, body, transform, geometry?))
+                })
+                .collect::<Vec<_>>();
+
+            resolve_collisions(&mut colliders, &mut spatial_hash);
+        })
+}
+
+// A body which collides by its geometry.
+type Collider<'a> = (
+    Entity,
+    &'a mut RigidBody2D,
+    &'a mut Transform2D,
+    &'a Geometry,
+);
+
+// Find the overlapping colliders by the spatial hash, then resolve the contacts found by the narrow phase.
+fn resolve_collisions(colliders: &mut [Collider], spatial_hash: &mut SpatialHash2D) {
+    spatial_hash.clear();
+
+    let mut indices = HashMap::with_capacity(colliders.len());
+    for (i, (entity, _, transform, geometry)) in colliders.iter().enumerate() {
+        if let Some(aabb) = AABB2D::from_geometry(geometry) {
+            if spatial_hash.insert(*entity, aabb.transformed(transform)) {
+                indices.insert(*entity, i);
+            }
+        }
+    }
+
+    // NOTE: the pairs are resolved in the order of colliders, the cells of spatial hash are not ordered.
+    let mut pairs = spatial_hash
+        .overlapping_pairs()
+        .into_iter()
+        .map(|(a, b)| {
+            let (a, b) = (indices[&a], indices[&b]);
+            (a.min(b), a.max(b))
+        })
+        .collect::<Vec<_>>();
+    pairs.sort_unstable();
+
+    for (i, j) in pairs {
+        let (left, right) = colliders.split_at_mut(j);
+        let (_, a_body, a_transform, a_geometry) = &mut left[i];
+        let (_, b_body, b_transform, b_geometry) = &mut right[0];
+
+        let contact =
+            narrow_phase_collision(*a_geometry, &**a_transform, *b_geometry, &**b_transform);
+        if let Some(contact) = contact {
+            resolve_contact(
+                (&mut **a_body, &mut **a_transform),
+                (&mut **b_body, &mut **b_transform),
+                &contact,
+            );
+        }
+    }
+}
+
+// Separate the overlapping bodies and bounce their velocities along the contact normal.
+fn resolve_contact(
+    a: (&mut RigidBody2D, &mut Transform2D),
+    b: (&mut RigidBody2D, &mut Transform2D),
+    contact: &ContactManifold,
+) {
+    let (a_body, a_transform) = a;
+    let (b_body, b_transform) = b;
+
+    let (inv_a, inv_b) = (a_body.inverse_mass(), b_body.inverse_mass());
+    if inv_a + inv_b <= 0.0 || contact.depth <= 0.0 {
+        return;
+    }
+
+    let normal = contact.normal;
+
+    // push the bodies apart by their share of the inverse mass.
+    let correction = normal * (contact.depth / (inv_a + inv_b));
+    a_transform.position -= correction * inv_a;
+    b_transform.position += correction * inv_b;
+
+    let relative = b_body.velocity - a_body.velocity;
+    let approaching = relative.dot(&normal);
+    if approaching >= 0.0 {
+        return;
+    }
+
+    let restitution = a_body.restitution.max(b_body.restitution);
+    let jn = -(1.0 + restitution) * approaching / (inv_a + inv_b);
+    a_body.velocity -= normal * jn * inv_a;
+    b_body.velocity += normal * jn * inv_b;
+
+    // the friction slows the sliding along the tangent, at most by `friction * jn`.
+    let relative = b_body.velocity - a_body.velocity;
+    let tangent = relative - normal * relative.dot(&normal);
+    if let Some(tangent) = tangent.try_normalize(f32::EPSILON) {
+        let friction = (a_body.friction * b_body.friction).sqrt();
+        let jt = (-relative.dot(&tangent) / (inv_a + inv_b)).max(-friction * jn);
+
+        a_body.velocity -= tangent * jt * inv_a;
+        b_body.velocity += tangent * jt * inv_b;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        components::geometry::{
+            BorderDecoration, BorderThickness, Geometry2DType, InnerDecoration,
+        },
+        legion::World,
+        misc::color::Rgba,
+    };
+
+    fn shape(gtype: Geometry2DType, size: f32) -> Geometry {
+        Geometry::new_2d(
+            gtype,
+            BorderDecoration::None,
+            Rgba::WHITE,
+            BorderThickness::LocalSpace(0.0),
+            InnerDecoration::Solid,
+            Rgba::WHITE,
+            0,
+            Vector2::new(0.0, 0.0),
+            0.0,
+            size,
+        )
+    }
+
+    // Resolve the collisions of the bodies like `integrate_rigid_bodies_system`.
+    fn collide(bodies: &mut [(&mut RigidBody2D, &mut Transform2D, &Geometry)]) {
+        let mut world = World::default();
+        let mut colliders = bodies
+            .iter_mut()
+            .map(|(body, transform, geometry)| {
+                (
+                    world.push((0u32,)),
+                    &mut **body,
+                    &mut **transform,
+                    *geometry,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        resolve_collisions(&mut colliders, &mut SpatialHash2D::new(1.0));
+    }
+
+    #[test]
+    fn integrate_bodies() {
+        let gravity = Vector2::new(0.0, -10.0);
+
+        let mut body = RigidBody2D::new_dynamic(2.0);
+        let mut transform = Transform2D::default();
+        apply_force(&mut body, Vector2::new(4.0, 0.0));
+        body.integrate(&mut transform, gravity, 0.5);
+        assert_eq!(body.velocity, Vector2::new(1.0, -5.0));
+        assert_eq!(transform.position, Vector2::new(0.5, -2.5));
+
+        // the force is cleared after the integration.
+        body.integrate(&mut transform, Vector2::new(0.0, 0.0), 0.5);
+        assert_eq!(body.velocity, Vector2::new(1.0, -5.0));
+
+        apply_impulse(&mut body, Vector2::new(-2.0, 10.0));
+        assert_eq!(body.velocity, Vector2::new(0.0, 0.0));
+
+        let mut wall = RigidBody2D::new_static().with_velocity(Vector2::new(1.0, 0.0));
+        let mut transform = Transform2D::default();
+        apply_impulse(&mut wall, Vector2::new(1.0, 1.0));
+        wall.integrate(&mut transform, gravity, 1.0);
+        assert_eq!(transform.position, Vector2::new(0.0, 0.0));
+
+        let mut paddle = RigidBody2D::new_kinematic().with_velocity(Vector2::new(3.0, 0.0));
+        paddle.integrate(&mut transform, gravity, 1.0);
+        assert_eq!(transform.position, Vector2::new(3.0, 0.0));
+    }
+
+    #[test]
+    fn bounce_circles() {
+        let ball = shape(Geometry2DType::Circle, 2.0);
+
+        // the equal masses swap their velocities in an elastic collision.
+        let mut a = RigidBody2D::new_dynamic(1.0)
+            .with_velocity(Vector2::new(1.0, 0.0))
+            .with_restitution(1.0);
+        let mut b = RigidBody2D::new_dynamic(1.0).with_velocity(Vector2::new(-1.0, 0.0));
+        let (mut ta, mut tb) = (Transform2D::at(0.0, 0.0), Transform2D::at(1.5, 0.0));
+
+        collide(&mut [(&mut a, &mut ta, &ball), (&mut b, &mut tb, &ball)]);
+        assert!((a.velocity - Vector2::new(-1.0, 0.0)).norm() < 1e-5);
+        assert!((b.velocity - Vector2::new(1.0, 0.0)).norm() < 1e-5);
+        assert!(((tb.position - ta.position).norm() - 2.0).abs() < 1e-5);
+
+        // the static body doesn't move, the ball stops without restitution.
+        let mut wall = RigidBody2D::new_static();
+        let mut ball_body = RigidBody2D::new_dynamic(1.0).with_velocity(Vector2::new(0.0, -2.0));
+        let (mut tw, mut tball) = (Transform2D::at(0.0, 0.0), Transform2D::at(0.0, 1.0));
+
+        collide(&mut [
+            (&mut wall, &mut tw, &ball),
+            (&mut ball_body, &mut tball, &ball),
+        ]);
+        assert_eq!(tw.position, Vector2::new(0.0, 0.0));
+        assert!((tball.position - Vector2::new(0.0, 2.0)).norm() < 1e-5);
+        assert!(ball_body.velocity.norm() < 1e-5);
+    }
+
+    #[test]
+    fn bounce_off_boxes() {
+        let floor = shape(Geometry2DType::Square, 2.0);
+        let ball = shape(Geometry2DType::Circle, 1.0);
+
+        let mut floor_body = RigidBody2D::new_static();
+        let mut ball_body = RigidBody2D::new_dynamic(1.0).with_velocity(Vector2::new(0.0, -2.0));
+        let mut far_body = RigidBody2D::new_dynamic(1.0).with_velocity(Vector2::new(0.0, -2.0));
+        let mut tfloor = Transform2D::at(0.0, 0.0);
+        let mut tball = Transform2D::at(0.0, 1.4);
+        let mut tfar = Transform2D::at(10.0, 10.0);
+
+        // the circle on the box stops, the far one is untouched.
+        collide(&mut [
+            (&mut floor_body, &mut tfloor, &floor),
+            (&mut ball_body, &mut tball, &ball),
+            (&mut far_body, &mut tfar, &ball),
+        ]);
+        assert!((tball.position - Vector2::new(0.0, 1.5)).norm() < 1e-4);
+        assert!(ball_body.velocity.norm() < 1e-4);
+        assert_eq!(tfar.position, Vector2::new(10.0, 10.0));
+        assert_eq!(far_body.velocity, Vector2::new(0.0, -2.0));
+
+        // the box bounces back from the box.
+        let mut crate_body = RigidBody2D::new_dynamic(1.0)
+            .with_velocity(Vector2::new(0.0, -1.0))
+            .with_restitution(1.0);
+        let mut tcrate = Transform2D::at(0.0, 1.9);
+
+        collide(&mut [
+            (&mut floor_body, &mut tfloor, &floor),
+            (&mut crate_body, &mut tcrate, &floor),
+        ]);
+        assert!((tcrate.position - Vector2::new(0.0, 2.0)).norm() < 1e-4);
+        assert!((crate_body.velocity - Vector2::new(0.0, 1.0)).norm() < 1e-4);
+    }
+}
@@ -1,5 +1,421 @@
-use crate::misc::color::Rgba;
+use crate::{
+    assets::AssetError,
+    components::transform::Transform2D,
+    misc::color::Rgba,
+    nalgebra::{Point2, Vector2},
+};
 
 pub struct Sprite {
     pub color: Rgba,
 }
+
+/// A texture-mapped quad, rendered with the texture in `TextureAtlas` resource.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sprite2D {
+    pub texture_handle: TextureHandle,
+    /// The region of the texture in pixels, `None` means the whole texture.
+    pub region: Option<Rect>,
+    /// The size of the quad in `local space`.
+    pub size: Vector2<f32>,
+    /// The normalized pivot of the quad, (0.5, 0.5) is center.
+    pub pivot: Vector2<f32>,
+    pub color_tint: Rgba,
+    pub flip: SpriteFlip,
+}
+
+impl Sprite2D {
+    pub fn new(texture_handle: TextureHandle, size: Vector2<f32>) -> Self {
+        Self {
+            texture_handle,
+            region: None,
+            size,
+            pivot: Vector2::new(0.5, 0.5),
+            color_tint: Rgba::WHITE,
+            flip: SpriteFlip::None,
+        }
+    }
+
+    /// Create a `Sprite2D` which shows the `region` of the texture, the size of the quad is the size of the `region`.
+    pub fn from_atlas_region(texture_handle: TextureHandle, region: Rect) -> Self {
+        Self {
+            region: Some(region),
+            ..Self::new(texture_handle, region.size())
+        }
+    }
+
+    /// The quad in `local space`, (min, max), the pivot is at the origin.
+    pub fn local_aabb(&self) -> (Vector2<f32>, Vector2<f32>) {
+        let min = -self.pivot.component_mul(&self.size);
+
+        (min, min + self.size)
+    }
+
+    /// The axis-aligned bounding box of the quad in `world space`, (min, max).
+    pub fn world_aabb(&self, transform: &Transform2D) -> (Vector2<f32>, Vector2<f32>) {
+        let (min, max) = self.local_aabb();
+
+        [
+            Point2::new(min.x, min.y),
+            Point2::new(max.x, min.y),
+            Point2::new(max.x, max.y),
+            Point2::new(min.x, max.y),
+        ]
+        .iter()
+        .map(|corner| transform.transform_point2(corner).coords)
+        .fold(
+            (
+                Vector2::new(f32::INFINITY, f32::INFINITY),
+                Vector2::new(f32::NEG_INFINITY, f32::NEG_INFINITY),
+            ),
+            |(min, max), p| (min.inf(&p), max.sup(&p)),
+        )
+    }
+
+    /// The (min, max) uv of the quad in the texture of `texture_size`, (0, 0) is the left-top corner.
+    ///
+    /// The min is greater than the max on the flipped axis.
+    pub fn uv_rect(&self, texture_size: (u32, u32)) -> (Vector2<f32>, Vector2<f32>) {
+        let (mut min, mut max) = match self.region {
+            Some(region) => {
+                let size = Vector2::new(texture_size.0 as f32, texture_size.1 as f32);
+                (
+                    region.min.component_div(&size),
+                    region.max.component_div(&size),
+                )
+            }
+            None => (Vector2::new(0.0, 0.0), Vector2::new(1.0, 1.0)),
+        };
+
+        if matches!(self.flip, SpriteFlip::Horizontal | SpriteFlip::Both) {
+            std::mem::swap(&mut min.x, &mut max.x);
+        }
+        if matches!(self.flip, SpriteFlip::Vertical | SpriteFlip::Both) {
+            std::mem::swap(&mut min.y, &mut max.y);
+        }
+
+        (min, max)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpriteFlip {
+    None,
+    Horizontal,
+    Vertical,
+    Both,
+}
+
+impl Default for SpriteFlip {
+    fn default() -> Self {
+        Self::None
+    }
+}
+
+/// Axis-aligned rectangle.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rect {
+    pub min: Vector2<f32>,
+    pub max: Vector2<f32>,
+}
+
+impl Rect {
+    pub fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Self {
+            min: Vector2::new(x, y),
+            max: Vector2::new(x + width, y + height),
+        }
+    }
+
+    pub fn width(&self) -> f32 {
+        self.max.x - self.min.x
+    }
+
+    pub fn height(&self) -> f32 {
+        self.max.y - self.min.y
+    }
+
+    pub fn size(&self) -> Vector2<f32> {
+        self.max - self.min
+    }
+}
+
+/// The opaque id of a texture in `TextureAtlas`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TextureHandle(u32);
+
+/// Store the textures used by `Sprite2D`.
+#[derive(Debug, Default)]
+pub struct TextureAtlas {
+    textures: Vec<Texture>,
+}
+
+impl TextureAtlas {
+    pub fn new() -> Self {
+        Self {
+            textures: Default::default(),
+        }
+    }
+
+    /// Insert a texture consists of RGBA8 pixels.
+    ///
+    /// Return `AssetError::Decode` if the length of `pixels` is not `width * height * 4`.
+    pub fn insert_rgba(
+        &mut self,
+        width: u32,
+        height: u32,
+        pixels: Vec<u8>,
+    ) -> Result<TextureHandle, AssetError> {
+        Ok(self.insert(Texture::from_rgba(width, height, pixels)?))
+    }
+
+    /// NOTE: the textures can't be modified or removed after inserted, the renderer uploads each texture
+    /// only once.
+    pub fn insert(&mut self, texture: Texture) -> TextureHandle {
+        self.textures.push(texture);
+
+        TextureHandle(self.textures.len() as u32 - 1)
+    }
+
+    /// Return the size of texture in pixels.
+    pub fn size(&self, handle: TextureHandle) -> Option<(u32, u32)> {
+        self.textures
+            .get(handle.0 as usize)
+            .map(|texture| (texture.width, texture.height))
+    }
+
+    pub fn get(&self, handle: TextureHandle) -> Option<&Texture> {
+        self.textures.get(handle.0 as usize)
+    }
+
+    /// Return the RGBA8 pixels of texture.
+    pub fn pixels(&self, handle: TextureHandle) -> Option<&[u8]> {
+        self.textures
+            .get(handle.0 as usize)
+            .map(|texture| texture.pixels.as_slice())
+    }
+
+    pub fn len(&self) -> usize {
+        self.textures.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.textures.is_empty()
+    }
+}
+
+/// An image of RGBA8 pixels, row by row from top to bottom.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Texture {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl Texture {
+    /// Return `AssetError::Decode` if the length of `pixels` is not `width * height * 4`.
+    pub fn from_rgba(width: u32, height: u32, pixels: Vec<u8>) -> Result<Self, AssetError> {
+        let len = (width as usize)
+            .checked_mul(height as usize)
+            .and_then(|len| len.checked_mul(4))
+            .ok_or_else(|| {
+                AssetError::Decode(String::from("ERR: The size of texture overflows."))
+            })?;
+
+        if pixels.len() != len {
+            return Err(AssetError::Decode(String::from(
+                "ERR: The length of pixels doesn't match the size of texture.",
+            )));
+        }
+
+        Ok(Self {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    /// Decode the PNG image, the pixels of any color type and bit depth are converted to RGBA8.
+    pub fn from_png(bytes: &[u8]) -> Result<Self, AssetError> {
+        let err = |msg: String| AssetError::Decode(format!("ERR: invalid png, {}", msg));
+
+        let mut decoder = png::Decoder::new(bytes);
+        // expand the palette and the channels less than 8-bit, strip the 16-bit channels to 8-bit.
+        decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+
+        let (info, mut reader) = decoder.read_info().map_err(|e| err(e.to_string()))?;
+
+        let mut buf = vec![0; info.buffer_size()];
+        reader
+            .next_frame(&mut buf)
+            .map_err(|e| err(e.to_string()))?;
+
+        let pixels = match info.color_type {
+            png::ColorType::RGBA => buf,
+            png::ColorType::RGB => buf
+                .chunks_exact(3)
+                .flat_map(|p| [p[0], p[1], p[2], 255].to_vec())
+                .collect(),
+            png::ColorType::GrayscaleAlpha => buf
+                .chunks_exact(2)
+                .flat_map(|p| [p[0], p[0], p[0], p[1]].to_vec())
+                .collect(),
+            png::ColorType::Grayscale => buf
+                .iter()
+                .flat_map(|g| [*g, *g, *g, 255].to_vec())
+                .collect(),
+            png::ColorType::Indexed => return Err(err(String::from("unexpanded palette"))),
+        };
+
+        Self::from_rgba(info.width, info.height, pixels)
+    }
+
+    /// Whether the bytes start with the signature of PNG.
+    pub fn is_png(bytes: &[u8]) -> bool {
+        bytes.starts_with(&[0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1A, b'\n'])
+    }
+
+    /// Decode the binary PPM(P6) image with 8-bit channels.
+    pub fn from_ppm(bytes: &[u8]) -> Result<Self, AssetError> {
+        let err = |msg: &str| AssetError::Decode(format!("ERR: invalid ppm, {}", msg));
+
+        // header: "P6" width height maxval, separated by whitespace, comments start with '#'.
+        let mut fields = Vec::with_capacity(4);
+        let mut cursor = 0;
+
+        while fields.len() < 4 {
+            while cursor < bytes.len() && (bytes[cursor] as char).is_ascii_whitespace() {
+                cursor += 1;
+            }
+
+            if cursor < bytes.len() && bytes[cursor] == b'#' {
+                while cursor < bytes.len() && bytes[cursor] != b'\n' {
+                    cursor += 1;
+                }
+                continue;
+            }
+
+            let start = cursor;
+            while cursor < bytes.len() && !(bytes[cursor] as char).is_ascii_whitespace() {
+                cursor += 1;
+            }
+
+            if start == cursor {
+                return Err(err("unexpected end of header"));
+            }
+            fields.push(String::from_utf8_lossy(&bytes[start..cursor]).into_owned());
+        }
+        // exactly one whitespace after maxval.
+        cursor += 1;
+
+        if fields[0] != "P6" {
+            return Err(err("only binary ppm(P6) is supported"));
+        }
+
+        let parse = |field: &str| field.parse::<u32>().map_err(|_| err("invalid number"));
+        let (width, height, maxval) = (parse(&fields[1])?, parse(&fields[2])?, parse(&fields[3])?);
+
+        if maxval != 255 {
+            return Err(err("only 8-bit channels are supported"));
+        }
+
+        let len = (width as usize)
+            .checked_mul(height as usize)
+            .and_then(|len| len.checked_mul(3))
+            .ok_or_else(|| err("the image is too large"))?;
+        let rgb = cursor
+            .checked_add(len)
+            .and_then(|end| bytes.get(cursor..end))
+            .ok_or_else(|| err("not enough pixels"))?;
+
+        let pixels = rgb
+            .chunks_exact(3)
+            .flat_map(|p| [p[0], p[1], p[2], 255].to_vec())
+            .collect();
+
+        Self::from_rgba(width, height, pixels)
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    pub fn pixels(&self) -> &[u8] {
+        &self.pixels
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Rect, Sprite2D, SpriteFlip, Texture, TextureAtlas};
+    use crate::{components::transform::Transform2D, nalgebra::Vector2};
+
+    #[test]
+    fn decode_ppm() {
+        let mut bytes = b"P6\n# comment\n2 1\n255\n".to_vec();
+        bytes.extend_from_slice(&[255, 0, 0, 0, 0, 255]);
+
+        let texture = Texture::from_ppm(&bytes).unwrap();
+        assert_eq!((texture.width(), texture.height()), (2, 1));
+        assert_eq!(texture.pixels(), &[255, 0, 0, 255, 0, 0, 255, 255]);
+
+        assert!(Texture::from_ppm(b"P3\n1 1\n255\n0 0 0").is_err());
+    }
+
+    #[test]
+    fn decode_png() {
+        let bytes = include_bytes!("../../assets/images/sprites/atlas.png");
+        assert!(Texture::is_png(bytes));
+
+        let texture = Texture::from_png(bytes).unwrap();
+        assert_eq!((texture.width(), texture.height()), (64, 32));
+        assert_eq!(texture.pixels().len(), 64 * 32 * 4);
+        // the border of cells is white.
+        assert_eq!(&texture.pixels()[0..4], &[255, 255, 255, 255]);
+
+        assert!(Texture::from_png(&bytes[..32]).is_err());
+    }
+
+    #[test]
+    fn reject_invalid_rgba() {
+        assert!(Texture::from_rgba(2, 2, vec![0; 16]).is_ok());
+        assert!(Texture::from_rgba(2, 2, vec![0; 12]).is_err());
+        // `width * height * 4` overflows u32.
+        assert!(Texture::from_rgba(u32::MAX, 2, vec![0; 16]).is_err());
+
+        let mut atlas = TextureAtlas::new();
+        assert!(atlas.insert_rgba(1 << 16, 1 << 16, Vec::new()).is_err());
+        assert!(atlas.is_empty());
+    }
+
+    #[test]
+    fn sprite_bounds_and_uv() {
+        let mut atlas = TextureAtlas::new();
+        let handle = atlas.insert_rgba(4, 2, vec![0; 32]).unwrap();
+
+        let mut sprite = Sprite2D::from_atlas_region(handle, Rect::new(1.0, 0.0, 2.0, 1.0));
+        assert_eq!(
+            sprite.local_aabb(),
+            (Vector2::new(-1.0, -0.5), Vector2::new(1.0, 0.5))
+        );
+        assert_eq!(
+            sprite.world_aabb(&Transform2D::at(10.0, 0.0)),
+            (Vector2::new(9.0, -0.5), Vector2::new(11.0, 0.5))
+        );
+
+        let size = atlas.size(handle).unwrap();
+        assert_eq!(
+            sprite.uv_rect(size),
+            (Vector2::new(0.25, 0.0), Vector2::new(0.75, 0.5))
+        );
+
+        sprite.flip = SpriteFlip::Horizontal;
+        assert_eq!(
+            sprite.uv_rect(size),
+            (Vector2::new(0.75, 0.0), Vector2::new(0.25, 0.5))
+        );
+    }
+}
@@ -0,0 +1,235 @@
+use crate::{
+    events::Events,
+    legion::{systems::CommandBuffer, Entity, IntoQuery, Resources, World},
+};
+
+use std::{fmt, hash::Hash};
+
+/// The condition of an automatic transition, evaluated against the `World` and the entity owns
+/// the `StateMachine`.
+pub type TransitionCondition = Box<dyn Fn(&World, Entity) -> bool + Send + Sync>;
+/// The callback runs when the entity enters or exits a state.
+pub type StateCallback = Box<dyn Fn(&mut CommandBuffer, Entity) + Send + Sync>;
+
+/// The behavioral state of an entity, driven by `state_machine_system::<S>`.
+///
+/// Automatic transitions are evaluated in the order they are added, the first satisfied one
+/// wins. A manual transition by `Self::transition_to` takes precedence over automatic ones.
+pub struct StateMachine<S: Clone + Eq + Hash + Send + Sync + 'static> {
+    current: S,
+    previous: Option<S>,
+    pending: Option<S>,
+    just_changed: bool,
+
+    transitions: Vec<(S, S, TransitionCondition)>,
+    on_enter: Vec<(S, StateCallback)>,
+    on_exit: Vec<(S, StateCallback)>,
+}
+
+impl<S: Clone + Eq + Hash + Send + Sync + 'static> StateMachine<S> {
+    pub fn new(initial: S) -> Self {
+        Self {
+            current: initial,
+            previous: None,
+            pending: None,
+            just_changed: false,
+
+            transitions: Default::default(),
+            on_enter: Default::default(),
+            on_exit: Default::default(),
+        }
+    }
+
+    /// Add an automatic transition from `from` to `to` which fires when `condition` returns `true`.
+    pub fn with_transition<F>(mut self, from: S, to: S, condition: F) -> Self
+    where
+        F: Fn(&World, Entity) -> bool + Send + Sync + 'static,
+    {
+        self.transitions.push((from, to, Box::new(condition)));
+
+        self
+    }
+
+    pub fn on_enter<F>(mut self, state: S, callback: F) -> Self
+    where
+        F: Fn(&mut CommandBuffer, Entity) + Send + Sync + 'static,
+    {
+        self.on_enter.push((state, Box::new(callback)));
+
+        self
+    }
+
+    pub fn on_exit<F>(mut self, state: S, callback: F) -> Self
+    where
+        F: Fn(&mut CommandBuffer, Entity) + Send + Sync + 'static,
+    {
+        self.on_exit.push((state, Box::new(callback)));
+
+        self
+    }
+
+    pub fn current(&self) -> &S {
+        &self.current
+    }
+
+    /// The state before the last transition.
+    pub fn previous(&self) -> Option<&S> {
+        self.previous.as_ref()
+    }
+
+    /// Whether the state changed in the last run of `state_machine_system::<S>`.
+    pub fn just_changed(&self) -> bool {
+        self.just_changed
+    }
+
+    /// Transition to `next` in next run of `state_machine_system::<S>`.
+    pub fn transition_to(&mut self, next: S) {
+        self.pending = Some(next);
+    }
+
+    fn next_state(&self, world: &World, entity: Entity) -> Option<S> {
+        if self.pending.is_some() {
+            return self.pending.clone();
+        }
+
+        self.transitions
+            .iter()
+            .find(|(from, _, condition)| *from == self.current && condition(world, entity))
+            .map(|(_, to, _)| to.clone())
+    }
+
+    /// Return the old state if the state changed.
+    fn apply(&mut self, next: S, cmd: &mut CommandBuffer, entity: Entity) -> Option<S> {
+        self.pending = None;
+
+        if next == self.current {
+            return None;
+        }
+
+        for (_, callback) in self.on_exit.iter().filter(|(s, _)| *s == self.current) {
+            callback(cmd, entity);
+        }
+
+        self.previous = Some(std::mem::replace(&mut self.current, next));
+        self.just_changed = true;
+
+        for (_, callback) in self.on_enter.iter().filter(|(s, _)| *s == self.current) {
+            callback(cmd, entity);
+        }
+
+        self.previous.clone()
+    }
+}
+
+impl<S: Clone + Eq + Hash + Send + Sync + fmt::Debug + 'static> fmt::Debug for StateMachine<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StateMachine")
+            .field("current", &self.current)
+            .field("previous", &self.previous)
+            .field("pending", &self.pending)
+            .field("transitions", &self.transitions.len())
+            .finish()
+    }
+}
+
+/// Sent when the `StateMachine<S>` of the entity changes from the old state to the new state, by
+/// `StateMachine::transition_to` or an automatic transition.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StateChanged<S>(pub Entity, pub S, pub S);
+
+/// Evaluate the transitions of all `StateMachine<S>`s and run the entry/exit callbacks.
+///
+/// Add it by `AppStageBuilder::add_thread_local_fn_process(state_machine_system::<S>)`, it writes
+/// `Events<StateChanged<S>>`, add it by `AppStageBuilder::add_event::<StateChanged<S>>()`.
+pub fn state_machine_system<S: Clone + Eq + Hash + Send + Sync + 'static>(
+    world: &mut World,
+    resources: &mut Resources,
+) {
+    <&mut StateMachine<S>>::query().for_each_mut(world, |sm| sm.just_changed = false);
+
+    let nexts: Vec<(Entity, S)> = {
+        let world: &World = world;
+
+        <(Entity, &StateMachine<S>)>::query()
+            .iter(world)
+            .filter_map(|(entity, sm)| sm.next_state(world, *entity).map(|next| (*entity, next)))
+            .collect()
+    };
+
+    if nexts.is_empty() {
+        return;
+    }
+
+    let mut cmd = CommandBuffer::new(world);
+    let mut changes = Vec::with_capacity(nexts.len());
+
+    for (entity, next) in nexts {
+        if let Some(mut entry) = world.entry(entity) {
+            if let Ok(sm) = entry.get_component_mut::<StateMachine<S>>() {
+                if let Some(old) = sm.apply(next.clone(), &mut cmd, entity) {
+                    changes.push(StateChanged(entity, old, next));
+                }
+            }
+        }
+    }
+
+    if !changes.is_empty() {
+        let mut events = resources
+            .get_mut::<Events<StateChanged<S>>>()
+            .expect("ERR: Not find Events<StateChanged<S>>, add it by AppStageBuilder::add_event.");
+
+        for change in changes {
+            events.send(change);
+        }
+    }
+
+    cmd.flush(world, resources);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+    enum Player {
+        Idle,
+        Running,
+        Dead,
+    }
+
+    #[test]
+    fn send_state_changed() {
+        let mut world = World::default();
+        let mut resources = Resources::default();
+        resources.insert(Events::<StateChanged<Player>>::new());
+
+        let entity = world.push((StateMachine::new(Player::Idle).with_transition(
+            Player::Idle,
+            Player::Running,
+            |_, _| true,
+        ),));
+
+        // the automatic transition.
+        state_machine_system::<Player>(&mut world, &mut resources);
+        // no transition from `Running`.
+        state_machine_system::<Player>(&mut world, &mut resources);
+
+        // the manual transition.
+        world
+            .entry(entity)
+            .unwrap()
+            .get_component_mut::<StateMachine<Player>>()
+            .unwrap()
+            .transition_to(Player::Dead);
+        state_machine_system::<Player>(&mut world, &mut resources);
+
+        let events = resources.get::<Events<StateChanged<Player>>>().unwrap();
+        assert_eq!(
+            events.iter().cloned().collect::<Vec<_>>(),
+            vec![
+                StateChanged(entity, Player::Idle, Player::Running),
+                StateChanged(entity, Player::Running, Player::Dead),
+            ]
+        );
+    }
+}
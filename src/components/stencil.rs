@@ -0,0 +1,118 @@
+/// Mark the entity as the mask of its `RenderGroup`, its shape is written to the stencil buffer instead of
+/// being drawn, the same as `StencilMode::Write`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct StencilMask;
+
+/// The group of masks and the entities clipped by them, e.g. a minimap or a clipped UI panel.
+///
+/// The entities in a group are clipped by `StencilMode::ReadInside` unless they have another `StencilMode`.
+/// The groups are rendered after the other entities, group by group from low to high.
+///
+/// NOTE: the stencil buffer has 8 bits, the groups whose ids are equal modulo 255 share the same mask.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+pub struct RenderGroup(pub u32);
+
+impl RenderGroup {
+    /// The value written to the stencil buffer by the masks of group, never 0 which is the cleared value.
+    pub fn stencil_reference(&self) -> u32 {
+        self.0 % 255 + 1
+    }
+}
+
+/// How the entity in a `RenderGroup` uses the stencil buffer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum StencilMode {
+    /// Write the shape to the mask of group, it's not drawn.
+    Write,
+    /// Only draw inside the masks of group.
+    #[default]
+    ReadInside,
+    /// Only draw outside the masks of group.
+    ReadOutside,
+}
+
+impl StencilMode {
+    /// All modes in the order they are rendered in a group.
+    pub const ALL: [StencilMode; 3] = [
+        StencilMode::Write,
+        StencilMode::ReadInside,
+        StencilMode::ReadOutside,
+    ];
+
+    /// The index of mode in `StencilMode::ALL`.
+    pub fn index(&self) -> usize {
+        *self as usize
+    }
+
+    /// The mode of the entity, `None` if it's not in a `RenderGroup` and has neither `StencilMask` nor
+    /// `StencilMode`.
+    ///
+    /// The entities with `StencilMask` or `StencilMode` but without `RenderGroup` are in `RenderGroup(0)`.
+    pub(crate) fn of(
+        group: Option<&RenderGroup>,
+        mask: Option<&StencilMask>,
+        mode: Option<&StencilMode>,
+    ) -> Option<(RenderGroup, StencilMode)> {
+        if group.is_none() && mask.is_none() && mode.is_none() {
+            return None;
+        }
+
+        let mode = match (mask, mode) {
+            (Some(_), _) => StencilMode::Write,
+            (None, Some(mode)) => *mode,
+            (None, None) => StencilMode::ReadInside,
+        };
+
+        Some((group.copied().unwrap_or_default(), mode))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stencil_mode_of_entity() {
+        assert_eq!(StencilMode::of(None, None, None), None);
+
+        // the entities in a group are clipped by the masks by default.
+        assert_eq!(
+            StencilMode::of(Some(&RenderGroup(2)), None, None),
+            Some((RenderGroup(2), StencilMode::ReadInside))
+        );
+        assert_eq!(
+            StencilMode::of(Some(&RenderGroup(2)), None, Some(&StencilMode::ReadOutside)),
+            Some((RenderGroup(2), StencilMode::ReadOutside))
+        );
+
+        // `StencilMask` overrides `StencilMode`.
+        assert_eq!(
+            StencilMode::of(
+                Some(&RenderGroup(2)),
+                Some(&StencilMask),
+                Some(&StencilMode::ReadOutside)
+            ),
+            Some((RenderGroup(2), StencilMode::Write))
+        );
+
+        // the entities without `RenderGroup` are in `RenderGroup(0)`.
+        assert_eq!(
+            StencilMode::of(None, Some(&StencilMask), None),
+            Some((RenderGroup(0), StencilMode::Write))
+        );
+        assert_eq!(
+            StencilMode::of(None, None, Some(&StencilMode::ReadOutside)),
+            Some((RenderGroup(0), StencilMode::ReadOutside))
+        );
+    }
+
+    #[test]
+    fn stencil_reference_wraps() {
+        assert_eq!(RenderGroup(0).stencil_reference(), 1);
+        assert_eq!(RenderGroup(254).stencil_reference(), 255);
+        // never 0, the cleared value.
+        assert_eq!(RenderGroup(255).stencil_reference(), 1);
+        assert_eq!(RenderGroup(u32::MAX).stencil_reference(), 1);
+        assert!((0..1024).all(|id| RenderGroup(id).stencil_reference() != 0));
+    }
+}
@@ -0,0 +1,396 @@
+use crate::{
+    components::sprite::{Rect, Sprite2D, TextureHandle},
+    misc::color::Rgba,
+    nalgebra::Vector2,
+};
+
+use std::collections::HashMap;
+
+/// A string rendered at the position of the entity in `world space`, with the font in `FontAtlas` resource.
+///
+/// The glyphs are rendered as sprites, so the texture of font must be in `TextureAtlas` resource.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Text2D {
+    pub text: String,
+    pub font: FontHandle,
+    /// The height of a line in `local space`.
+    pub size: f32,
+    pub color: Rgba,
+    pub alignment: TextAlign,
+    pub outline: Option<TextOutline>,
+}
+
+impl Text2D {
+    pub fn new(text: impl Into<String>, font: FontHandle, size: f32, color: Rgba) -> Self {
+        Self {
+            text: text.into(),
+            font,
+            size,
+            color,
+            alignment: TextAlign::default(),
+            outline: None,
+        }
+    }
+
+    pub fn with_alignment(mut self, alignment: TextAlign) -> Self {
+        self.alignment = alignment;
+
+        self
+    }
+
+    pub fn with_outline(mut self, color: Rgba, thickness: f32) -> Self {
+        self.outline = Some(TextOutline { color, thickness });
+
+        self
+    }
+
+    /// Calculate the quads of glyphs in `local space`.
+    ///
+    /// Characters not in the font are skipped, return an empty `Vec` if the font doesn't exist.
+    pub fn layout(&self, fonts: &FontAtlas) -> Vec<GlyphQuad> {
+        let font = match fonts.get(self.font) {
+            Some(font) if font.line_height > 0.0 => font,
+            _ => return Vec::new(),
+        };
+
+        let scale = self.size / font.line_height;
+        let line_count = self.text.lines().count().max(1);
+        let total_height = line_count as f32 * self.size;
+
+        let top = match self.alignment.vertical {
+            VAlign::Top => 0.0,
+            VAlign::Center => total_height / 2.0,
+            VAlign::Bottom => total_height,
+        };
+
+        let mut quads = Vec::with_capacity(self.text.len());
+
+        for (row, line) in self.text.lines().enumerate() {
+            let width: f32 = line
+                .chars()
+                .filter_map(|ch| font.glyph(ch))
+                .map(|glyph| glyph.advance * scale)
+                .sum();
+
+            let mut x = match self.alignment.horizontal {
+                HAlign::Left => 0.0,
+                HAlign::Center => -width / 2.0,
+                HAlign::Right => -width,
+            };
+            let y = top - (row as f32 + 0.5) * self.size;
+
+            for glyph in line.chars().filter_map(|ch| font.glyph(ch)) {
+                let size = glyph.region.size() * scale;
+
+                quads.push(GlyphQuad {
+                    position: Vector2::new(x + size.x / 2.0, y),
+                    size,
+                    region: glyph.region,
+                });
+
+                x += glyph.advance * scale;
+            }
+        }
+
+        quads
+    }
+
+    /// The glyphs as `Sprite2D`s centered at the positions in `local space`.
+    ///
+    /// The outline is made of 8 copies of each glyph shifted by the thickness, they come before the
+    /// glyphs, so they are drawn under the glyphs.
+    pub fn sprites(&self, fonts: &FontAtlas) -> Vec<(Vector2<f32>, Sprite2D)> {
+        let texture = match fonts.get(self.font) {
+            Some(font) => font.texture,
+            None => return Vec::new(),
+        };
+
+        let quads = self.layout(fonts);
+        let glyph = |quad: &GlyphQuad, color: Rgba| Sprite2D {
+            size: quad.size,
+            color_tint: color,
+            ..Sprite2D::from_atlas_region(texture, quad.region)
+        };
+
+        let mut sprites = Vec::with_capacity(quads.len());
+
+        if let Some(TextOutline { color, thickness }) = self.outline {
+            for quad in quads.iter() {
+                for (dx, dy) in OUTLINE_DIRECTIONS.iter() {
+                    let offset = Vector2::new(*dx, *dy) * thickness;
+                    sprites.push((quad.position + offset, glyph(quad, color)));
+                }
+            }
+        }
+
+        sprites.extend(
+            quads
+                .iter()
+                .map(|quad| (quad.position, glyph(quad, self.color))),
+        );
+
+        sprites
+    }
+}
+
+#[cfg_attr(rustfmt, rustfmt_skip)]
+const OUTLINE_DIRECTIONS: [(f32, f32); 8] = [
+    (-1.0, 1.0),    (0.0, 1.0),     (1.0, 1.0),
+    (-1.0, 0.0),                    (1.0, 0.0),
+    (-1.0, -1.0),   (0.0, -1.0),    (1.0, -1.0),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextOutline {
+    pub color: Rgba,
+    /// The thickness in `local space`.
+    pub thickness: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TextAlign {
+    pub horizontal: HAlign,
+    pub vertical: VAlign,
+}
+
+impl TextAlign {
+    pub fn new(horizontal: HAlign, vertical: VAlign) -> Self {
+        Self {
+            horizontal,
+            vertical,
+        }
+    }
+
+    pub fn center() -> Self {
+        Self::new(HAlign::Center, VAlign::Center)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HAlign {
+    Left,
+    Center,
+    Right,
+}
+
+impl Default for HAlign {
+    fn default() -> Self {
+        Self::Left
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VAlign {
+    Top,
+    Center,
+    Bottom,
+}
+
+impl Default for VAlign {
+    fn default() -> Self {
+        Self::Top
+    }
+}
+
+/// A glyph quad generated by `Text2D::layout`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GlyphQuad {
+    /// The center of the quad in `local space`.
+    pub position: Vector2<f32>,
+    pub size: Vector2<f32>,
+    /// The region of the font texture in pixels.
+    pub region: Rect,
+}
+
+/// A pre-rasterized glyph of `Font`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Glyph {
+    /// The region of the font texture in pixels.
+    pub region: Rect,
+    /// The horizontal distance to the next glyph in pixels.
+    pub advance: f32,
+}
+
+/// A bitmap font whose glyphs are pre-rasterized into a texture of `TextureAtlas`.
+///
+/// Glyphs are keyed by `char`, so a font is not limited to ASCII.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Font {
+    pub texture: TextureHandle,
+    /// The height of a line in pixels.
+    pub line_height: f32,
+
+    glyphs: HashMap<char, Glyph>,
+}
+
+impl Font {
+    pub fn new(texture: TextureHandle, line_height: f32) -> Self {
+        Self {
+            texture,
+            line_height,
+            glyphs: Default::default(),
+        }
+    }
+
+    /// Create a font from a texture whose ASCII glyphs(from ' ' to '~') are arranged in a
+    /// monospaced grid, row by row.
+    pub fn from_ascii_grid(
+        texture: TextureHandle,
+        glyph_width: f32,
+        glyph_height: f32,
+        columns: u32,
+    ) -> Self {
+        let mut font = Self::new(texture, glyph_height);
+
+        for (index, ch) in (' '..='~').enumerate() {
+            let col = index as u32 % columns.max(1);
+            let row = index as u32 / columns.max(1);
+
+            font.add_glyph(
+                ch,
+                Glyph {
+                    region: Rect::new(
+                        col as f32 * glyph_width,
+                        row as f32 * glyph_height,
+                        glyph_width,
+                        glyph_height,
+                    ),
+                    advance: glyph_width,
+                },
+            );
+        }
+
+        font
+    }
+
+    pub fn add_glyph(&mut self, ch: char, glyph: Glyph) {
+        self.glyphs.insert(ch, glyph);
+    }
+
+    pub fn glyph(&self, ch: char) -> Option<&Glyph> {
+        self.glyphs.get(&ch)
+    }
+}
+
+/// The opaque id of a font in `FontAtlas`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct FontHandle(u32);
+
+/// Store the fonts used by `Text2D`.
+#[derive(Debug, Default)]
+pub struct FontAtlas {
+    fonts: Vec<Font>,
+}
+
+impl FontAtlas {
+    pub fn new() -> Self {
+        Self {
+            fonts: Default::default(),
+        }
+    }
+
+    pub fn insert(&mut self, font: Font) -> FontHandle {
+        self.fonts.push(font);
+
+        FontHandle(self.fonts.len() as u32 - 1)
+    }
+
+    pub fn get(&self, handle: FontHandle) -> Option<&Font> {
+        self.fonts.get(handle.0 as usize)
+    }
+
+    /// The handle of the first inserted font.
+    pub(crate) fn first(&self) -> Option<FontHandle> {
+        if self.fonts.is_empty() {
+            None
+        } else {
+            Some(FontHandle(0))
+        }
+    }
+
+    pub fn get_mut(&mut self, handle: FontHandle) -> Option<&mut Font> {
+        self.fonts.get_mut(handle.0 as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::sprite::TextureAtlas;
+
+    // A font of 8 x 16 pixels glyphs, 16 glyphs per row.
+    fn fonts() -> (FontAtlas, FontHandle) {
+        let texture = TextureAtlas::new()
+            .insert_rgba(128, 96, vec![0; 128 * 96 * 4])
+            .unwrap();
+
+        let mut fonts = FontAtlas::new();
+        let handle = fonts.insert(Font::from_ascii_grid(texture, 8.0, 16.0, 16));
+
+        (fonts, handle)
+    }
+
+    #[test]
+    fn layout_aligned_lines() {
+        let (fonts, font) = fonts();
+
+        // the size is twice the line height of font.
+        let text = Text2D::new("ab\nc", font, 32.0, Rgba::WHITE);
+        let quads = text.layout(&fonts);
+
+        assert_eq!(quads.len(), 3);
+        assert_eq!(quads[0].size, Vector2::new(16.0, 32.0));
+        assert_eq!(quads[0].position, Vector2::new(8.0, -16.0));
+        assert_eq!(quads[1].position, Vector2::new(24.0, -16.0));
+        assert_eq!(quads[2].position, Vector2::new(8.0, -48.0));
+        // 'b' is the 67th glyph from ' ', at column 2 of row 4.
+        assert_eq!(quads[1].region, Rect::new(16.0, 64.0, 8.0, 16.0));
+
+        let centered = text
+            .clone()
+            .with_alignment(TextAlign::center())
+            .layout(&fonts);
+        assert_eq!(centered[0].position, Vector2::new(-8.0, 16.0));
+        assert_eq!(centered[2].position, Vector2::new(0.0, -16.0));
+
+        let right = text
+            .with_alignment(TextAlign::new(HAlign::Right, VAlign::Bottom))
+            .layout(&fonts);
+        assert_eq!(right[1].position, Vector2::new(-8.0, 48.0));
+        assert_eq!(right[2].position, Vector2::new(-8.0, 16.0));
+    }
+
+    #[test]
+    fn layout_skips_unknown_glyphs() {
+        let (fonts, font) = fonts();
+
+        let quads = Text2D::new("a\u{4e2d}b", font, 16.0, Rgba::WHITE).layout(&fonts);
+        assert_eq!(quads.len(), 2);
+        assert_eq!(quads[1].position, Vector2::new(12.0, -8.0));
+
+        // a handle of the other `FontAtlas`.
+        let mut others = FontAtlas::new();
+        others.insert(fonts.get(font).unwrap().clone());
+        let missing = others.insert(fonts.get(font).unwrap().clone());
+        assert!(Text2D::new("a", missing, 16.0, Rgba::WHITE)
+            .layout(&fonts)
+            .is_empty());
+    }
+
+    #[test]
+    fn outline_sprites_come_first() {
+        let (fonts, font) = fonts();
+
+        let text = Text2D::new("ab", font, 16.0, Rgba::WHITE).with_outline(Rgba::BLACK, 1.0);
+        let sprites = text.sprites(&fonts);
+
+        assert_eq!(sprites.len(), 2 * 9);
+        assert!(sprites[..16]
+            .iter()
+            .all(|(_, sprite)| sprite.color_tint == Rgba::BLACK));
+        assert_eq!(sprites[0].0, Vector2::new(3.0, -7.0));
+        assert_eq!(sprites[16].0, Vector2::new(4.0, -8.0));
+        assert_eq!(sprites[17].1.color_tint, Rgba::WHITE);
+        assert_eq!(sprites[17].1.size, Vector2::new(8.0, 16.0));
+    }
+}
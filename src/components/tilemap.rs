@@ -0,0 +1,237 @@
+use crate::{
+    assets::{Assets, Handle},
+    components::{
+        camera::{Camera2D, RenderTarget},
+        sprite::{Rect, Texture},
+        transform::Transform2D,
+    },
+    legion::{
+        systems::{ParallelRunnable, SystemBuilder},
+        IntoQuery,
+    },
+    math::grid::Grid2D,
+    nalgebra::Vector2,
+};
+
+use std::ops::Range;
+
+/// The index of a tile in the tileset, counted row by row from the top-left tile.
+pub type TileId = u16;
+
+/// A visible tile prepared by `update_tilemaps_system`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TileInstance {
+    /// The center of the tile in `world space`.
+    pub position: Vector2<f32>,
+    /// The size of the tile in `world space`.
+    pub size: Vector2<f32>,
+    /// The normalized region of the tile in the tileset.
+    pub uv: Rect,
+}
+
+/// Render a `Grid2D<TileId>` with a tileset texture, the tiles are in the cells of grid.
+///
+/// Only the tiles seen by the screen camera are prepared every frame, all of them share the tileset so
+/// they are drawn in a single instanced draw call.
+#[derive(Debug, Clone)]
+pub struct TilemapRenderer2D {
+    pub grid: Handle<Grid2D<TileId>>,
+    pub tileset: Handle<Texture>,
+    /// The size of a tile in the tileset in pixels.
+    pub tile_size: Vector2<f32>,
+
+    batch: Vec<TileInstance>,
+}
+
+impl TilemapRenderer2D {
+    /// The cells with this id are not rendered.
+    pub const EMPTY: TileId = TileId::MAX;
+
+    pub fn new(
+        grid: Handle<Grid2D<TileId>>,
+        tileset: Handle<Texture>,
+        tile_size: Vector2<f32>,
+    ) -> Self {
+        assert!(
+            tile_size.x > 0.0 && tile_size.y > 0.0,
+            "ERR: the tile size must be positive"
+        );
+
+        Self {
+            grid,
+            tileset,
+            tile_size,
+
+            batch: Vec::new(),
+        }
+    }
+
+    /// Change the tile of the cell, return the old tile or `None` if the cell is out of the grid.
+    pub fn set_tile(
+        &self,
+        grids: &mut Assets<Grid2D<TileId>>,
+        col: usize,
+        row: usize,
+        tile_id: TileId,
+    ) -> Option<TileId> {
+        grids.get_mut(self.grid)?.set(col, row, tile_id)
+    }
+
+    /// The normalized region of the tile in the tileset, `None` if the tile is out of the tileset.
+    pub fn tile_uv(&self, tile_id: TileId, tileset_size: (u32, u32)) -> Option<Rect> {
+        let columns = (tileset_size.0 as f32 / self.tile_size.x) as u32;
+        let rows = (tileset_size.1 as f32 / self.tile_size.y) as u32;
+
+        let (col, row) = (
+            tile_id as u32 % columns.max(1),
+            tile_id as u32 / columns.max(1),
+        );
+        if columns == 0 || row >= rows {
+            return None;
+        }
+
+        let (w, h) = (tileset_size.0 as f32, tileset_size.1 as f32);
+        Some(Rect::new(
+            col as f32 * self.tile_size.x / w,
+            row as f32 * self.tile_size.y / h,
+            self.tile_size.x / w,
+            self.tile_size.y / h,
+        ))
+    }
+
+    /// The columns and rows of the grid in the view of camera.
+    pub fn visible_cells(
+        grid: &Grid2D<TileId>,
+        camera_transform: &Transform2D,
+        camera: &Camera2D,
+    ) -> (Range<usize>, Range<usize>) {
+        let (min, max) = camera.world_bounds(camera_transform);
+
+        let min = (min - grid.origin()) / grid.cell_size();
+        let max = (max - grid.origin()) / grid.cell_size();
+        let range = |min: f32, max: f32, len: usize| {
+            let start = min.floor().max(0.0).min(len as f32) as usize;
+            let end = max.ceil().max(0.0).min(len as f32) as usize;
+            start..end.max(start)
+        };
+
+        (
+            range(min.x, max.x, grid.width()),
+            range(min.y, max.y, grid.height()),
+        )
+    }
+
+    /// The visible tiles prepared in this frame.
+    pub fn batch(&self) -> &[TileInstance] {
+        &self.batch
+    }
+
+    /// Prepare the tiles in the view of camera, the cost only depends on the count of visible tiles.
+    pub fn prepare(
+        &mut self,
+        grid: &Grid2D<TileId>,
+        tileset_size: (u32, u32),
+        camera_transform: &Transform2D,
+        camera: &Camera2D,
+    ) {
+        self.batch.clear();
+
+        let (cols, rows) = Self::visible_cells(grid, camera_transform, camera);
+        let size = Vector2::new(grid.cell_size(), grid.cell_size());
+        for row in rows {
+            for col in cols.clone() {
+                let tile_id = match grid.get(col, row) {
+                    Some(&tile_id) if tile_id != Self::EMPTY => tile_id,
+                    _ => continue,
+                };
+
+                if let Some(uv) = self.tile_uv(tile_id, tileset_size) {
+                    self.batch.push(TileInstance {
+                        position: grid.cell_to_world(col, row),
+                        size,
+                        uv,
+                    });
+                }
+            }
+        }
+    }
+}
+
+/// Create a system which prepares the visible tiles of `TilemapRenderer2D`s for the screen camera of the
+/// highest priority.
+///
+/// It reads `Assets<Grid2D<TileId>>` and `Assets<Texture>`.
+pub fn update_tilemaps_system() -> impl ParallelRunnable {
+    SystemBuilder::new("update_tilemaps")
+        .read_resource::<Assets<Grid2D<TileId>>>()
+        .read_resource::<Assets<Texture>>()
+        .with_query(<(&Transform2D, &Camera2D)>::query())
+        .with_query(<&mut TilemapRenderer2D>::query())
+        .build(|_, world, (grids, textures), (cameras, tilemaps)| {
+            let camera = cameras
+                .iter(world)
+                .filter(|(_, camera)| camera.render_target == RenderTarget::Screen)
+                .max_by_key(|(_, camera)| camera.priority)
+                .map(|(transform, camera)| (*transform, *camera));
+
+            let (camera_transform, camera) = match camera {
+                Some(camera) => camera,
+                None => return,
+            };
+
+            tilemaps.for_each_mut(world, |tilemap| {
+                let grid = grids.get(tilemap.grid);
+                let tileset = textures.get(tilemap.tileset);
+
+                if let (Some(grid), Some(tileset)) = (grid, tileset) {
+                    let tileset_size = (tileset.width(), tileset.height());
+                    tilemap.prepare(grid, tileset_size, &camera_transform, &camera);
+                } else {
+                    tilemap.batch.clear();
+                }
+            });
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prepare_culls_invisible_tiles() {
+        let mut grids = Assets::new();
+        let mut textures = Assets::new();
+
+        let grid = grids.add(Grid2D::new(
+            1000,
+            1000,
+            16.0,
+            Vector2::new(0.0, 0.0),
+            TilemapRenderer2D::EMPTY,
+        ));
+        let tileset = textures.add(Texture::from_rgba(64, 32, vec![0; 64 * 32 * 4]).unwrap());
+
+        let mut tilemap = TilemapRenderer2D::new(grid, tileset, Vector2::new(16.0, 16.0));
+        assert_eq!(
+            tilemap.set_tile(&mut grids, 0, 0, 5),
+            Some(TilemapRenderer2D::EMPTY)
+        );
+        tilemap.set_tile(&mut grids, 999, 999, 1);
+
+        // the view covers the cells in [0, 4) x [0, 4).
+        let camera_transform = Transform2D::at(32.0, 32.0);
+        let camera = Camera2D::new(64, 64);
+
+        tilemap.prepare(
+            grids.get(grid).unwrap(),
+            (64, 32),
+            &camera_transform,
+            &camera,
+        );
+
+        assert_eq!(tilemap.batch().len(), 1);
+        assert_eq!(tilemap.batch()[0].position, Vector2::new(8.0, 8.0));
+        assert_eq!(tilemap.batch()[0].size, Vector2::new(16.0, 16.0));
+        assert_eq!(tilemap.batch()[0].uv, Rect::new(0.25, 0.5, 0.25, 0.5));
+    }
+}
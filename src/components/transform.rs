@@ -1,9 +1,14 @@
-use crate::nalgebra::{Matrix3, Matrix4, Point2, UnitComplex, UnitQuaternion, Vector2, Vector3};
+use crate::{
+    legion::system,
+    nalgebra::{Matrix3, Matrix4, Point2, UnitComplex, UnitQuaternion, Vector2, Vector3},
+};
+
+use std::ops::RangeInclusive;
 
 /// Transformation from local space to world space.
 ///
 /// Position, rotation and scale of an entity in world space.
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Transform2D {
     pub position: Vector2<f32>,
     pub rotation: UnitComplex<f32>,
@@ -19,7 +24,13 @@ impl Transform2D {
         }
     }
 
-    pub fn with_position(tx: f32, ty: f32) -> Self {
+    /// No translation, rotation and scaling, the same as `Transform2D::default()`.
+    pub fn identity() -> Self {
+        Self::new(0.0, 0.0, 0.0, 1.0, 1.0)
+    }
+
+    /// Only translated to `(tx, ty)`.
+    pub fn at(tx: f32, ty: f32) -> Self {
         Self {
             position: Vector2::new(tx, ty),
             rotation: UnitComplex::new(0.0),
@@ -27,6 +38,21 @@ impl Transform2D {
         }
     }
 
+    /// Short for `Transform2D::with_rotation`.
+    pub fn rotated(angle: f32) -> Self {
+        Self::with_rotation(angle)
+    }
+
+    /// Short for `Transform2D::with_scale`.
+    pub fn scaled(sx: f32, sy: f32) -> Self {
+        Self::with_scale(sx, sy)
+    }
+
+    #[deprecated(note = "use `Transform2D::at` instead")]
+    pub fn with_position(tx: f32, ty: f32) -> Self {
+        Self::at(tx, ty)
+    }
+
     pub fn with_rotation(angle: f32) -> Self {
         Self {
             position: Vector2::new(0.0, 0.0),
@@ -55,6 +81,27 @@ impl Transform2D {
         self.rotation *= UnitComplex::new(f32::to_radians(delta_angle));
     }
 
+    /// Rotate the position around `pivot` in `world space` by `delta_angle`, and rotate by `delta_angle`
+    /// too, so the transform orbits the pivot.
+    pub fn rotate_around(&mut self, pivot: Vector2<f32>, delta_angle: f32) {
+        let rotation = UnitComplex::new(f32::to_radians(delta_angle));
+
+        self.position = pivot + rotation * (self.position - pivot);
+        self.rotation = rotation * self.rotation;
+    }
+
+    /// Scale the distance to `pivot` in `world space` by `(sx, sy)`, and scale by `(sx, sy)` too, e.g.
+    /// zoom a camera to the cursor.
+    ///
+    /// NOTE: a rotated transform scaled non-uniformly is skewed, which can't be represented by `Transform2D`,
+    /// the scales are multiplied per axis instead.
+    pub fn scale_around(&mut self, pivot: Vector2<f32>, sx: f32, sy: f32) {
+        let factor = Vector2::new(sx, sy);
+
+        self.position = pivot + (self.position - pivot).component_mul(&factor);
+        self.scale = self.scale.component_mul(&factor);
+    }
+
     pub fn heading_x(&self) -> Vector2<f32> {
         Vector2::new(self.rotation.re, self.rotation.im)
     }
@@ -137,18 +184,121 @@ impl Transform2D {
 
 impl Default for Transform2D {
     fn default() -> Self {
-        Self::new(0.0, 0.0, 0.0, 1.0, 1.0)
+        Self::identity()
     }
 }
 
 unsafe impl bytemuck::Zeroable for Transform2D {}
 unsafe impl bytemuck::Pod for Transform2D {}
 
+/// Restrict the `Transform2D` of the entity, enforced by `apply_transform_constraints_system`.
+///
+/// The locked parts keep the value of `Transform2D` when the constraints are applied the first time.
+#[derive(Debug, Clone, Default)]
+pub struct Transform2DConstraints {
+    pub lock_x: bool,
+    pub lock_y: bool,
+    pub lock_rotation: bool,
+    pub lock_scale: bool,
+    pub clamp_x: Option<RangeInclusive<f32>>,
+    pub clamp_y: Option<RangeInclusive<f32>>,
+
+    // The transform when the constraints are applied the first time.
+    anchor: Option<Transform2D>,
+}
+
+impl Transform2DConstraints {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Lock both axes of position, the entity can still rotate and scale.
+    pub fn position_locked() -> Self {
+        Self::new().lock_x().lock_y()
+    }
+
+    pub fn rotation_locked() -> Self {
+        Self::new().lock_rotation()
+    }
+
+    pub fn scale_locked() -> Self {
+        Self::new().lock_scale()
+    }
+
+    pub fn lock_x(mut self) -> Self {
+        self.lock_x = true;
+        self
+    }
+
+    pub fn lock_y(mut self) -> Self {
+        self.lock_y = true;
+        self
+    }
+
+    pub fn lock_rotation(mut self) -> Self {
+        self.lock_rotation = true;
+        self
+    }
+
+    pub fn lock_scale(mut self) -> Self {
+        self.lock_scale = true;
+        self
+    }
+
+    pub fn clamp_x(mut self, range: RangeInclusive<f32>) -> Self {
+        self.clamp_x = Some(range);
+        self
+    }
+
+    pub fn clamp_y(mut self, range: RangeInclusive<f32>) -> Self {
+        self.clamp_y = Some(range);
+        self
+    }
+
+    /// Forget the locked values, the next `apply` locks the current transform.
+    pub fn reset_anchor(&mut self) {
+        self.anchor = None;
+    }
+
+    pub fn apply(&mut self, transform: &mut Transform2D) {
+        let anchor = *self.anchor.get_or_insert(*transform);
+
+        if self.lock_x {
+            transform.position.x = anchor.position.x;
+        }
+        if self.lock_y {
+            transform.position.y = anchor.position.y;
+        }
+        if self.lock_rotation {
+            transform.rotation = anchor.rotation;
+        }
+        if self.lock_scale {
+            transform.scale = anchor.scale;
+        }
+
+        if let Some(range) = &self.clamp_x {
+            transform.position.x = transform.position.x.max(*range.start()).min(*range.end());
+        }
+        if let Some(range) = &self.clamp_y {
+            transform.position.y = transform.position.y.max(*range.start()).min(*range.end());
+        }
+    }
+}
+
+/// Enforce `Transform2DConstraints`, run it after all systems which move entities.
+#[system(for_each)]
+pub fn apply_transform_constraints(
+    constraints: &mut Transform2DConstraints,
+    transform: &mut Transform2D,
+) {
+    constraints.apply(transform);
+}
+
 #[cfg(test)]
 mod tests {
-    use crate::nalgebra::Matrix3;
+    use crate::nalgebra::{Matrix3, Vector2};
 
-    use super::Transform2D;
+    use super::{Transform2D, Transform2DConstraints};
     use std::assert_eq;
 
     #[test]
@@ -178,4 +328,38 @@ mod tests {
 
         assert_eq!(t.to_homogeneous(), m_t * m_r * m_s);
     }
+
+    #[test]
+    fn constraints_lock_and_clamp() {
+        let mut constraints = Transform2DConstraints::new()
+            .lock_y()
+            .lock_rotation()
+            .clamp_x(-10.0..=10.0);
+        let mut t = Transform2D::new(0.0, 5.0, 30.0, 1.0, 1.0);
+        constraints.apply(&mut t);
+
+        t.position.x = 20.0;
+        t.position.y = 8.0;
+        t.set_angle(90.0);
+        t.scale.x = 2.0;
+        constraints.apply(&mut t);
+
+        assert_eq!(t.position.x, 10.0);
+        assert_eq!(t.position.y, 5.0);
+        assert!((t.angle() - 30.0).abs() < 1e-4);
+        assert_eq!(t.scale.x, 2.0);
+    }
+
+    #[test]
+    fn rotate_and_scale_around_pivot() {
+        let mut t = Transform2D::at(2.0, 0.0);
+        t.rotate_around(Vector2::new(1.0, 0.0), 90.0);
+
+        assert!((t.position - Vector2::new(1.0, 1.0)).norm() < 1e-5);
+        assert!((t.angle() - 90.0).abs() < 1e-4);
+
+        t.scale_around(Vector2::new(1.0, 0.0), 2.0, 3.0);
+        assert!((t.position - Vector2::new(1.0, 3.0)).norm() < 1e-5);
+        assert_eq!(t.scale, Vector2::new(2.0, 3.0));
+    }
 }
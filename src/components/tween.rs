@@ -0,0 +1,328 @@
+use crate::{
+    components::{geometry::Geometry, time::Time, transform::Transform2D},
+    events::Events,
+    legion::{
+        systems::{ParallelRunnable, SystemBuilder},
+        Entity, IntoQuery,
+    },
+    math::easing::{self, EasingFn},
+    misc::color::Rgba,
+    nalgebra::Vector2,
+};
+
+/// The value which can be interpolated by `Tween`.
+pub trait Lerpable: Copy + Send + Sync + 'static {
+    fn lerp(&self, to: &Self, t: f32) -> Self;
+
+    /// Write the value to the `target` of the entity, do nothing if `Self` doesn't fit the `target`.
+    fn write_to(
+        &self,
+        target: TweenTarget,
+        transform: Option<&mut Transform2D>,
+        geometry: Option<&mut Geometry>,
+    );
+}
+
+impl Lerpable for f32 {
+    fn lerp(&self, to: &Self, t: f32) -> Self {
+        self + (to - self) * t
+    }
+
+    fn write_to(
+        &self,
+        target: TweenTarget,
+        transform: Option<&mut Transform2D>,
+        _geometry: Option<&mut Geometry>,
+    ) {
+        match (target, transform) {
+            (TweenTarget::Angle, Some(transform)) => transform.set_angle(*self),
+            (TweenTarget::Scale, Some(transform)) => transform.scale = Vector2::new(*self, *self),
+            _ => {}
+        }
+    }
+}
+
+impl Lerpable for Vector2<f32> {
+    fn lerp(&self, to: &Self, t: f32) -> Self {
+        self + (to - self) * t
+    }
+
+    fn write_to(
+        &self,
+        target: TweenTarget,
+        transform: Option<&mut Transform2D>,
+        _geometry: Option<&mut Geometry>,
+    ) {
+        match (target, transform) {
+            (TweenTarget::Position, Some(transform)) => transform.position = *self,
+            (TweenTarget::Scale, Some(transform)) => transform.scale = *self,
+            _ => {}
+        }
+    }
+}
+
+impl Lerpable for Rgba {
+    fn lerp(&self, to: &Self, t: f32) -> Self {
+        let lerp = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * t).round() as u8;
+
+        Rgba::new(
+            lerp(self.r, to.r),
+            lerp(self.g, to.g),
+            lerp(self.b, to.b),
+            lerp(self.a, to.a),
+        )
+    }
+
+    fn write_to(
+        &self,
+        target: TweenTarget,
+        _transform: Option<&mut Transform2D>,
+        geometry: Option<&mut Geometry>,
+    ) {
+        match (target, geometry) {
+            (TweenTarget::BorderColor, Some(geometry)) => geometry.set_border_color_rgba(self),
+            (TweenTarget::InnerColor, Some(geometry)) => geometry.set_inner_color_rgba(self),
+            _ => {}
+        }
+    }
+}
+
+impl Lerpable for Transform2D {
+    fn lerp(&self, to: &Self, t: f32) -> Self {
+        Self {
+            position: Lerpable::lerp(&self.position, &to.position, t),
+            rotation: self.rotation.slerp(&to.rotation, t),
+            scale: Lerpable::lerp(&self.scale, &to.scale, t),
+        }
+    }
+
+    fn write_to(
+        &self,
+        target: TweenTarget,
+        transform: Option<&mut Transform2D>,
+        _geometry: Option<&mut Geometry>,
+    ) {
+        if let (TweenTarget::Transform, Some(transform)) = (target, transform) {
+            *transform = *self;
+        }
+    }
+}
+
+/// Which component(or field of component) the interpolated value is written to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TweenTarget {
+    /// The whole `Transform2D`, for `Tween<Transform2D>`.
+    Transform,
+    /// `Transform2D::position`, for `Tween<Vector2<f32>>`.
+    Position,
+    /// The angle(in degrees) of `Transform2D`, for `Tween<f32>`.
+    Angle,
+    /// `Transform2D::scale`, for `Tween<Vector2<f32>>`, or uniform scale for `Tween<f32>`.
+    Scale,
+    /// The border color of `Geometry`, for `Tween<Rgba>`.
+    BorderColor,
+    /// The inner color of `Geometry`, for `Tween<Rgba>`.
+    InnerColor,
+}
+
+/// What to do when a `Tween` reaches its end.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TweenComplete {
+    /// Restart from `from`.
+    Loop,
+    /// Go back and forth between `from` and `to`.
+    PingPong,
+    /// Stay at `to`.
+    Stop,
+    /// Stay at `to` and despawn the entity.
+    Despawn,
+}
+
+/// Interpolate a value from `from` to `to` in `duration` seconds, the value is written to the
+/// `target` of the entity by `update_tweens_system::<T>()`.
+#[derive(Debug, Clone, Copy)]
+pub struct Tween<T: Lerpable> {
+    pub from: T,
+    pub to: T,
+    /// The duration in seconds.
+    pub duration: f32,
+    /// The elapsed time in seconds.
+    pub elapsed: f32,
+    pub easing: EasingFn,
+    pub on_complete: TweenComplete,
+    pub target: TweenTarget,
+
+    reversed: bool,
+    finished: bool,
+}
+
+impl<T: Lerpable> Tween<T> {
+    pub fn new(from: T, to: T, duration: f32, target: TweenTarget) -> Self {
+        Self {
+            from,
+            to,
+            duration,
+            elapsed: 0.0,
+            easing: easing::linear,
+            on_complete: TweenComplete::Stop,
+            target,
+
+            reversed: false,
+            finished: false,
+        }
+    }
+
+    pub fn with_easing(mut self, easing: EasingFn) -> Self {
+        self.easing = easing;
+
+        self
+    }
+
+    pub fn with_on_complete(mut self, on_complete: TweenComplete) -> Self {
+        self.on_complete = on_complete;
+
+        self
+    }
+
+    /// The normalized progress in [0, 1], before easing.
+    pub fn progress(&self) -> f32 {
+        let t = if self.duration > 0.0 {
+            (self.elapsed / self.duration).min(1.0).max(0.0)
+        } else {
+            1.0
+        };
+
+        if self.reversed {
+            1.0 - t
+        } else {
+            t
+        }
+    }
+
+    /// The interpolated value at current progress.
+    pub fn value(&self) -> T {
+        self.from.lerp(&self.to, (self.easing)(self.progress()))
+    }
+
+    /// Whether a `Stop` or `Despawn` tween has reached the end.
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    /// Advance the tween by `dt` seconds and return the interpolated value.
+    pub fn advance(&mut self, dt: f32) -> T {
+        if !self.finished {
+            self.elapsed += dt;
+
+            if self.elapsed >= self.duration {
+                match self.on_complete {
+                    TweenComplete::Loop if self.duration > 0.0 => {
+                        self.elapsed %= self.duration;
+                    }
+                    TweenComplete::PingPong if self.duration > 0.0 => {
+                        self.elapsed %= self.duration;
+                        self.reversed = !self.reversed;
+                    }
+                    _ => {
+                        self.elapsed = self.duration;
+                        self.finished = true;
+                    }
+                }
+            }
+        }
+
+        self.value()
+    }
+}
+
+/// Sent when a `Stop` or `Despawn` tween on the entity reaches the end.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TweenCompleted(pub Entity);
+
+/// Create a system which advances all `Tween<T>`s and writes the values to their targets.
+///
+/// It writes `Events<TweenCompleted>`, add it by `AppStageBuilder::add_event::<TweenCompleted>()`.
+pub fn update_tweens_system<T: Lerpable>() -> impl ParallelRunnable {
+    SystemBuilder::new(format!("update_tweens<{}>", std::any::type_name::<T>()))
+        .read_resource::<Time>()
+        .write_resource::<Events<TweenCompleted>>()
+        .with_query(<(
+            Entity,
+            &mut Tween<T>,
+            Option<&mut Transform2D>,
+            Option<&mut Geometry>,
+        )>::query())
+        .build(|cmd, world, (time, completed), query| {
+            let dt = time.delta().as_secs_f32();
+
+            for (entity, tween, transform, geometry) in query.iter_mut(world) {
+                if tween.is_finished() {
+                    continue;
+                }
+
+                let value = tween.advance(dt);
+                value.write_to(tween.target, transform, geometry);
+
+                if tween.is_finished() {
+                    completed.send(TweenCompleted(*entity));
+
+                    if tween.on_complete == TweenComplete::Despawn {
+                        cmd.remove(*entity);
+                    }
+                }
+            }
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::legion::{Resources, Schedule, World};
+
+    #[test]
+    fn send_tween_completed() {
+        let mut world = World::default();
+        let mut resources = Resources::default();
+
+        let position = |on_complete, duration| {
+            Tween::new(
+                Vector2::new(0.0, 0.0),
+                Vector2::new(1.0, 0.0),
+                duration,
+                TweenTarget::Position,
+            )
+            .with_on_complete(on_complete)
+        };
+
+        // the tweens of zero duration reach the end at the first update.
+        let stop = world.push((Transform2D::default(), position(TweenComplete::Stop, 0.0)));
+        let despawn = world.push((
+            Transform2D::default(),
+            position(TweenComplete::Despawn, 0.0),
+        ));
+        let looping = world.push((Transform2D::default(), position(TweenComplete::Loop, 60.0)));
+
+        resources.insert(Time::now());
+        resources.insert(Events::<TweenCompleted>::new());
+
+        let mut schedule = Schedule::builder()
+            .add_system(update_tweens_system::<Vector2<f32>>())
+            .build();
+
+        schedule.execute(&mut world, &mut resources);
+        schedule.execute(&mut world, &mut resources);
+
+        let events = resources.get::<Events<TweenCompleted>>().unwrap();
+        assert_eq!(
+            events.iter().copied().collect::<Vec<_>>(),
+            vec![TweenCompleted(stop), TweenCompleted(despawn)]
+        );
+
+        assert_eq!(
+            <&Transform2D>::query().get(&world, stop).unwrap().position,
+            Vector2::new(1.0, 0.0)
+        );
+        assert!(world.entry(despawn).is_none());
+        assert!(world.entry(looping).is_some());
+    }
+}
@@ -0,0 +1,174 @@
+use serde::{Deserialize, Serialize};
+
+use std::{error::Error, fmt, fs, io, path::Path};
+
+/// The setup of an `App` loaded from a file, build it by `AppBuilder::from_config`.
+///
+/// # File format
+///
+/// TOML, every `[[stage]]` table is an `AppStage` made of the stages of its plugins:
+///
+/// ```text
+/// [[stage]]
+/// name = "logic"
+/// frequency = 0
+/// plugins = ["physics", "audio"]
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AppConfig {
+    #[serde(rename = "stage", default)]
+    pub stages: Vec<StageConfig>,
+}
+
+/// An `AppStage` named `name`, which has the systems of all stages added by the plugins, the plugin names
+/// are resolved by `PluginRegistry`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct StageConfig {
+    pub name: String,
+    /// How many times the stage plays per second, 0 means every frame.
+    ///
+    /// NOTE: `AppStage` plays every frame for now, other frequencies are rejected by `AppBuilder::from_config`.
+    #[serde(default)]
+    pub frequency: u32,
+    #[serde(default)]
+    pub plugins: Vec<String>,
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(io::Error),
+    /// The file isn't valid TOML or doesn't match `AppConfig`.
+    Parse(toml::de::Error),
+    /// No plugin is registered with the name.
+    UnknownPlugin(String),
+    /// More than one stage has the name.
+    DuplicateStage(String),
+    /// The stage plays at the frequency other than 0(every frame), with the stage name.
+    UnsupportedFrequency(String, u32),
+    /// The plugins add the stages of different `AppStageTag`s to the stage, with the stage name.
+    MixedTags(String),
+}
+
+impl From<io::Error> for ConfigError {
+    fn from(err: io::Error) -> Self {
+        ConfigError::Io(err)
+    }
+}
+
+impl From<toml::de::Error> for ConfigError {
+    fn from(err: toml::de::Error) -> Self {
+        ConfigError::Parse(err)
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Io(err) => write!(f, "{}", err),
+            ConfigError::Parse(err) => write!(f, "{}", err),
+            ConfigError::UnknownPlugin(name) => write!(f, "unknown plugin `{}`", name),
+            ConfigError::DuplicateStage(name) => write!(f, "duplicate stage `{}`", name),
+            ConfigError::UnsupportedFrequency(name, frequency) => write!(
+                f,
+                "stage `{}` can't play {} times a second, only every frame",
+                name, frequency
+            ),
+            ConfigError::MixedTags(name) => {
+                write!(f, "the plugins of stage `{}` have different tags", name)
+            }
+        }
+    }
+}
+
+impl Error for ConfigError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ConfigError::Io(err) => Some(err),
+            ConfigError::Parse(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl AppConfig {
+    pub fn load_toml(path: &Path) -> Result<AppConfig, ConfigError> {
+        Self::from_toml_str(&fs::read_to_string(path)?)
+    }
+
+    pub fn from_toml_str(text: &str) -> Result<AppConfig, ConfigError> {
+        Ok(toml::from_str(text)?)
+    }
+
+    pub fn to_toml_string(&self) -> Result<String, toml::ser::Error> {
+        toml::to_string(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_stages() {
+        let config = AppConfig::from_toml_str(
+            r##"
+            # the game
+            [[stage]]
+            name = 'logic'   # main logic
+            frequency = 60
+            plugins = [
+                "physics",
+                "audio, \"3d\"",
+            ]
+
+            [[stage]]
+            name = "#ui"
+            "##,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.stages,
+            vec![
+                StageConfig {
+                    name: String::from("logic"),
+                    frequency: 60,
+                    plugins: vec![String::from("physics"), String::from("audio, \"3d\"")],
+                },
+                StageConfig {
+                    name: String::from("#ui"),
+                    frequency: 0,
+                    plugins: Vec::new(),
+                },
+            ]
+        );
+
+        assert_eq!(
+            AppConfig::from_toml_str(&config.to_toml_string().unwrap()).unwrap(),
+            config
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        // the key is outside of `[[stage]]`.
+        assert!(matches!(
+            AppConfig::from_toml_str("name = \"logic\""),
+            Err(ConfigError::Parse(_))
+        ));
+        assert!(matches!(
+            AppConfig::from_toml_str("[[stage]]\nname = \"logic\"\nfrequncy = 60"),
+            Err(ConfigError::Parse(_))
+        ));
+        assert!(matches!(
+            AppConfig::from_toml_str("[[stage]]\nname = \"logic\"\nfrequency = -1"),
+            Err(ConfigError::Parse(_))
+        ));
+        assert!(matches!(
+            AppConfig::from_toml_str("[[stage]]\nplugins = []"),
+            Err(ConfigError::Parse(_))
+        ));
+    }
+}
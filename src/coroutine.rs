@@ -0,0 +1,325 @@
+use crate::{
+    components::time::Time,
+    legion::{Resources, World},
+};
+
+use std::{collections::VecDeque, fmt};
+
+/// The condition of `Yield::Until`, evaluated once per frame.
+pub type WaitCondition = Box<dyn Fn(&World) -> bool + Send + Sync>;
+/// A step of `Coroutine`, runs once and returns how long to wait before the next step.
+pub type CoroutineStep = Box<dyn FnMut(&mut World, &mut Resources) -> Yield + Send + Sync>;
+
+/// How long a `Coroutine` waits before the next step.
+pub enum Yield {
+    /// Wait the count of frames, 0 goes on in the same frame.
+    Frames(u32),
+    /// Wait the seconds measured by `Time::delta`.
+    Seconds(f32),
+    /// Wait until the condition returns `true`.
+    Until(WaitCondition),
+}
+
+impl fmt::Debug for Yield {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Yield::Frames(n) => f.debug_tuple("Frames").field(n).finish(),
+            Yield::Seconds(s) => f.debug_tuple("Seconds").field(s).finish(),
+            Yield::Until(_) => f.write_str("Until"),
+        }
+    }
+}
+
+pub fn wait_frames(n: u32) -> Yield {
+    Yield::Frames(n)
+}
+
+pub fn wait_seconds(s: f32) -> Yield {
+    Yield::Seconds(s)
+}
+
+pub fn wait_until<F: Fn(&World) -> bool + Send + Sync + 'static>(cond: F) -> Yield {
+    Yield::Until(Box::new(cond))
+}
+
+/// A multi-frame task made of steps, started by `CoroutineRunner::start`.
+///
+/// ```ignore
+/// let battle = Coroutine::new()
+///     .then(show_dialog)
+///     .wait(wait_seconds(2.0))
+///     .then(start_battle);
+/// ```
+#[derive(Default)]
+pub struct Coroutine {
+    steps: VecDeque<CoroutineStep>,
+    waiting: Option<Yield>,
+}
+
+impl Coroutine {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run `f` once, the next step runs right after it.
+    pub fn then<F>(self, mut f: F) -> Self
+    where
+        F: FnMut(&mut World, &mut Resources) + Send + Sync + 'static,
+    {
+        self.step(move |world, resources| {
+            f(world, resources);
+            Yield::Frames(0)
+        })
+    }
+
+    /// Run `f` once, the next step waits for the returned `Yield`.
+    pub fn step<F>(mut self, f: F) -> Self
+    where
+        F: FnMut(&mut World, &mut Resources) -> Yield + Send + Sync + 'static,
+    {
+        self.steps.push_back(Box::new(f));
+
+        self
+    }
+
+    /// Wait before the next step.
+    pub fn wait(self, wait: Yield) -> Self {
+        let mut wait = Some(wait);
+
+        self.step(move |_, _| wait.take().unwrap_or(Yield::Frames(0)))
+    }
+
+    /// The count of steps not run yet.
+    pub fn remaining_steps(&self) -> usize {
+        self.steps.len()
+    }
+
+    // Run the steps until a wait is not finished, return whether the coroutine is still alive.
+    fn advance(&mut self, world: &mut World, resources: &mut Resources, delta: f32) -> bool {
+        // count the frame and time passed since the last advance.
+        match &mut self.waiting {
+            Some(Yield::Frames(n)) => *n = n.saturating_sub(1),
+            Some(Yield::Seconds(s)) => *s -= delta,
+            _ => (),
+        }
+
+        loop {
+            let ready = match &self.waiting {
+                None => true,
+                Some(Yield::Frames(n)) => *n == 0,
+                Some(Yield::Seconds(s)) => *s <= 0.0,
+                Some(Yield::Until(condition)) => condition(world),
+            };
+            if !ready {
+                return true;
+            }
+
+            match self.steps.pop_front() {
+                Some(mut step) => self.waiting = Some(step(world, resources)),
+                None => {
+                    self.waiting = None;
+                    return false;
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Debug for Coroutine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Coroutine")
+            .field("steps", &self.steps.len())
+            .field("waiting", &self.waiting)
+            .finish()
+    }
+}
+
+/// The id of a coroutine started by `CoroutineRunner`, use it to cancel the coroutine.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CoroutineHandle(u64);
+
+/// The active coroutines, insert it to `Resources` and add `coroutine_system` to advance them each frame.
+#[derive(Debug, Default)]
+pub struct CoroutineRunner {
+    next_id: u64,
+    active: Vec<(CoroutineHandle, Coroutine)>,
+
+    // the coroutines taken out by `coroutine_system` and the ones cancelled meanwhile.
+    advancing: Vec<CoroutineHandle>,
+    cancelled: Vec<CoroutineHandle>,
+}
+
+impl CoroutineRunner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start the coroutine, the first step runs in the next run of `coroutine_system`.
+    pub fn start(&mut self, coroutine: Coroutine) -> CoroutineHandle {
+        let handle = CoroutineHandle(self.next_id);
+        self.next_id += 1;
+
+        self.active.push((handle, coroutine));
+
+        handle
+    }
+
+    /// Stop the coroutine, the remaining steps never run. Return `false` if it's finished or cancelled.
+    pub fn cancel(&mut self, handle: CoroutineHandle) -> bool {
+        if let Some(index) = self.active.iter().position(|(h, _)| *h == handle) {
+            self.active.remove(index);
+            true
+        } else if self.advancing.contains(&handle) && !self.cancelled.contains(&handle) {
+            self.cancelled.push(handle);
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn cancel_all(&mut self) {
+        self.active.clear();
+
+        for handle in self.advancing.iter() {
+            if !self.cancelled.contains(handle) {
+                self.cancelled.push(*handle);
+            }
+        }
+    }
+
+    pub fn is_running(&self, handle: CoroutineHandle) -> bool {
+        self.active.iter().any(|(h, _)| *h == handle)
+            || (self.advancing.contains(&handle) && !self.cancelled.contains(&handle))
+    }
+
+    /// The count of active coroutines.
+    pub fn len(&self) -> usize {
+        self.active.len() + self.advancing.len() - self.cancelled.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Advance the coroutines of `CoroutineRunner`, the steps can start or cancel coroutines by the runner.
+///
+/// Add it by `AppStageBuilder::add_thread_local_fn_process(coroutine_system)`.
+pub fn coroutine_system(world: &mut World, resources: &mut Resources) {
+    let delta = resources
+        .get::<Time>()
+        .map(|time| time.delta().as_secs_f32())
+        .unwrap_or_default();
+
+    let active = match resources.get_mut::<CoroutineRunner>() {
+        Some(mut runner) => {
+            let active = std::mem::take(&mut runner.active);
+            runner.advancing = active.iter().map(|(handle, _)| *handle).collect();
+            active
+        }
+        None => return,
+    };
+
+    let mut alive = Vec::with_capacity(active.len());
+    for (handle, mut coroutine) in active {
+        // NOTE: a step may cancel the coroutines after it.
+        let cancelled = resources
+            .get::<CoroutineRunner>()
+            .map_or(true, |runner| runner.cancelled.contains(&handle));
+
+        if !cancelled && coroutine.advance(world, resources, delta) {
+            alive.push((handle, coroutine));
+        }
+    }
+
+    if let Some(mut runner) = resources.get_mut::<CoroutineRunner>() {
+        let runner = &mut *runner;
+
+        alive.retain(|(handle, _)| !runner.cancelled.contains(handle));
+        // the coroutines started by the steps run after the others.
+        alive.append(&mut runner.active);
+
+        runner.active = alive;
+        runner.advancing.clear();
+        runner.cancelled.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Default)]
+    struct Log(Vec<&'static str>);
+
+    fn log(message: &'static str) -> impl FnMut(&mut World, &mut Resources) + Send + Sync {
+        move |_, resources| resources.get_mut::<Log>().unwrap().0.push(message)
+    }
+
+    #[test]
+    fn steps_wait_for_yields() {
+        let mut world = World::default();
+        let mut resources = Resources::default();
+        resources.insert(Log::default());
+        resources.insert(CoroutineRunner::new());
+
+        let handle = resources.get_mut::<CoroutineRunner>().unwrap().start(
+            Coroutine::new()
+                .then(log("dialog"))
+                .wait(wait_frames(2))
+                .then(log("battle"))
+                .wait(wait_until(|world| world.len() > 0))
+                .then(log("spawned")),
+        );
+
+        coroutine_system(&mut world, &mut resources);
+        assert_eq!(resources.get::<Log>().unwrap().0, vec!["dialog"]);
+
+        coroutine_system(&mut world, &mut resources);
+        coroutine_system(&mut world, &mut resources);
+        coroutine_system(&mut world, &mut resources);
+        assert_eq!(resources.get::<Log>().unwrap().0, vec!["dialog", "battle"]);
+        assert!(resources
+            .get::<CoroutineRunner>()
+            .unwrap()
+            .is_running(handle));
+
+        world.push((0u32,));
+        coroutine_system(&mut world, &mut resources);
+        assert_eq!(
+            resources.get::<Log>().unwrap().0,
+            vec!["dialog", "battle", "spawned"]
+        );
+        assert!(resources.get::<CoroutineRunner>().unwrap().is_empty());
+    }
+
+    #[test]
+    fn cancel_coroutines() {
+        let mut world = World::default();
+        let mut resources = Resources::default();
+        resources.insert(Log::default());
+        resources.insert(CoroutineRunner::new());
+
+        let (first, second) = {
+            let mut runner = resources.get_mut::<CoroutineRunner>().unwrap();
+            let second = CoroutineHandle(1);
+
+            let first = runner.start(Coroutine::new().then(move |_, resources| {
+                resources
+                    .get_mut::<CoroutineRunner>()
+                    .unwrap()
+                    .cancel(second);
+            }));
+            assert_eq!(runner.start(Coroutine::new().then(log("second"))), second);
+
+            (first, second)
+        };
+
+        coroutine_system(&mut world, &mut resources);
+        assert!(resources.get::<Log>().unwrap().0.is_empty());
+
+        let runner = resources.get::<CoroutineRunner>().unwrap();
+        assert!(!runner.is_running(first) && !runner.is_running(second));
+        assert!(runner.is_empty());
+    }
+}
@@ -0,0 +1,199 @@
+use crate::legion::systems::{ParallelRunnable, SystemBuilder};
+
+use std::{fmt, marker::PhantomData};
+
+/// A double-buffered queue of events of type `T`, insert it to `Resources` to send events between systems.
+///
+/// Events live for two updates, so every system running in a frame can see the events sent in the
+/// previous frame, no matter of the order of systems.
+pub struct Events<T> {
+    previous: Vec<T>,
+    current: Vec<T>,
+
+    // the id of first event of `previous`.
+    previous_start: usize,
+    // the id of first event of `current`.
+    current_start: usize,
+}
+
+impl<T: Send + Sync + 'static> Events<T> {
+    pub fn new() -> Self {
+        Self {
+            previous: Default::default(),
+            current: Default::default(),
+
+            previous_start: 0,
+            current_start: 0,
+        }
+    }
+
+    pub fn send(&mut self, event: T) {
+        self.current.push(event);
+    }
+
+    /// Iterate over the events of last update and this update.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.previous.iter().chain(self.current.iter())
+    }
+
+    /// Take all events of last update and this update out.
+    pub fn drain(&mut self) -> impl Iterator<Item = T> + '_ {
+        self.previous_start = self.event_count();
+        self.current_start = self.previous_start;
+
+        self.previous.drain(..).chain(self.current.drain(..))
+    }
+
+    /// Swap the buffers, the events sent before last update will be dropped.
+    pub fn update(&mut self) {
+        self.previous = std::mem::take(&mut self.current);
+        self.previous_start = self.current_start;
+        self.current_start = self.previous_start + self.previous.len();
+    }
+
+    pub fn len(&self) -> usize {
+        self.previous.len() + self.current.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.previous.is_empty() && self.current.is_empty()
+    }
+
+    /// The count of events sent since creation.
+    fn event_count(&self) -> usize {
+        self.current_start + self.current.len()
+    }
+}
+
+impl<T: Send + Sync + 'static> Default for Events<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> fmt::Debug for Events<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Events")
+            .field("previous", &self.previous.len())
+            .field("current", &self.current.len())
+            .finish()
+    }
+}
+
+/// Read the events which haven't been read by this reader.
+///
+/// Keep it as the `#[state]` of a system, so each system reads every event only once.
+pub struct EventReader<T> {
+    last_event_count: usize,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T: Send + Sync + 'static> EventReader<T> {
+    pub fn new() -> Self {
+        Self {
+            last_event_count: 0,
+            _marker: PhantomData,
+        }
+    }
+
+    pub fn read<'a>(&mut self, events: &'a Events<T>) -> impl Iterator<Item = &'a T> {
+        // NOTE: skip the events have been dropped if the reader falls behind.
+        let skip_previous = self.last_event_count.saturating_sub(events.previous_start);
+        let skip_current = self.last_event_count.saturating_sub(events.current_start);
+
+        self.last_event_count = events.event_count();
+
+        events
+            .previous
+            .iter()
+            .skip(skip_previous)
+            .chain(events.current.iter().skip(skip_current))
+    }
+}
+
+impl<T: Send + Sync + 'static> Default for EventReader<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> fmt::Debug for EventReader<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EventReader")
+            .field("last_event_count", &self.last_event_count)
+            .finish()
+    }
+}
+
+/// Send events of type `T`, a thin wrapper of the `Events<T>` resource.
+pub struct EventWriter<'a, T> {
+    events: &'a mut Events<T>,
+}
+
+impl<'a, T: Send + Sync + 'static> EventWriter<'a, T> {
+    pub fn new(events: &'a mut Events<T>) -> Self {
+        Self { events }
+    }
+
+    pub fn send(&mut self, event: T) {
+        self.events.send(event);
+    }
+
+    pub fn send_batch(&mut self, events: impl IntoIterator<Item = T>) {
+        for event in events {
+            self.events.send(event);
+        }
+    }
+}
+
+/// Swap the buffers of `Events<T>` every frame, added by `AppStageBuilder::add_event`.
+pub fn update_events_system<T: Send + Sync + 'static>() -> impl ParallelRunnable {
+    SystemBuilder::new(format!("update_events<{}>", std::any::type_name::<T>()))
+        .write_resource::<Events<T>>()
+        .build(|_, _, events, _| events.update())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn events_live_for_two_updates() {
+        let mut events = Events::<u32>::new();
+
+        events.send(0);
+        events.update();
+        events.send(1);
+        assert_eq!(events.iter().copied().collect::<Vec<_>>(), vec![0, 1]);
+
+        events.update();
+        assert_eq!(events.iter().copied().collect::<Vec<_>>(), vec![1]);
+
+        events.update();
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn reader_reads_each_event_once() {
+        let mut events = Events::<u32>::new();
+        let mut reader = EventReader::<u32>::new();
+
+        events.send(0);
+        events.send(1);
+        assert_eq!(
+            reader.read(&events).copied().collect::<Vec<_>>(),
+            vec![0, 1]
+        );
+        assert_eq!(reader.read(&events).count(), 0);
+
+        events.update();
+        events.send(2);
+        assert_eq!(reader.read(&events).copied().collect::<Vec<_>>(), vec![2]);
+
+        // the reader falls behind, events dropped are skipped.
+        events.update();
+        events.update();
+        events.send(3);
+        assert_eq!(reader.read(&events).copied().collect::<Vec<_>>(), vec![3]);
+    }
+}
@@ -2,7 +2,13 @@ use winit::event::{
     DeviceEvent, ElementState, Event, KeyboardInput, MouseScrollDelta, WindowEvent,
 };
 
-use crate::{misc::coordinates::Transformation, nalgebra::Vector4};
+use crate::{
+    components::{camera::Camera2D, time::Time, transform::Transform2D},
+    legion::systems::{ParallelRunnable, SystemBuilder},
+    misc::coordinates::Transformation,
+    nalgebra::{Point2, Vector2, Vector4},
+    window::Window,
+};
 
 use std::collections::HashMap;
 
@@ -12,6 +18,7 @@ pub type MouseButton = winit::event::MouseButton;
 pub struct Input {
     pub mouse: Mouse,
     pub keyboard: Keyboard,
+    pub gamepad: Gamepad,
 }
 
 impl Input {
@@ -19,19 +26,43 @@ impl Input {
         Self {
             mouse: Mouse::new(),
             keyboard: Keyboard::new(),
+            gamepad: Gamepad::new(),
         }
     }
 
-    pub(crate) fn apply(&mut self, evts: &mut Vec<Event<()>>, trf: &Transformation) {
+    /// `trf` must be built with the viewport in logical pixels, cursor positions are converted with
+    /// `scale_factor`.
+    pub(crate) fn apply(
+        &mut self,
+        evts: &mut Vec<Event<()>>,
+        trf: &Transformation,
+        scale_factor: f32,
+        queue: &mut InputEventQueue,
+    ) {
         self.mouse.before_apply();
         self.keyboard.before_apply();
+        queue.events.clear();
 
         self.mouse.trf = *trf;
+        self.mouse.scale_factor = scale_factor;
 
         for evt in evts.drain(..) {
             match evt {
                 Event::WindowEvent { event, .. } => match event {
                     WindowEvent::MouseInput { button, state, .. } => {
+                        let position = self.mouse.cursor_vector_in_ss();
+                        queue.events.push(match state {
+                            ElementState::Pressed => InputEvent::MouseButtonDown(button, position),
+                            ElementState::Released => InputEvent::MouseButtonUp(button, position),
+                        });
+
+                        match state {
+                            ElementState::Pressed => self.mouse.begin_drag(button),
+                            ElementState::Released => {
+                                self.mouse.drags.remove(&button);
+                            }
+                        }
+
                         if let Some(bs) = self.mouse.mouse_button_state.get_mut(&button) {
                             match state {
                                 ElementState::Pressed => *bs = ButtonState::JustPressed,
@@ -52,6 +83,7 @@ impl Input {
                         ..
                     } => {
                         self.mouse.mouse_wheel_motion = (x, y);
+                        queue.events.push(InputEvent::MouseWheel(y));
                     }
 
                     WindowEvent::CursorLeft { .. } => {
@@ -61,7 +93,11 @@ impl Input {
                         self.mouse.cursor_state = CursorState::JustEntered;
                     }
                     WindowEvent::CursorMoved { position, .. } => {
-                        self.mouse.cursor_position_ss = (position.x as f32, position.y as f32);
+                        self.mouse.cursor_position_physical =
+                            (position.x as f32, position.y as f32);
+                        queue
+                            .events
+                            .push(InputEvent::MouseMove(self.mouse.cursor_vector_in_ss()));
                     }
 
                     WindowEvent::KeyboardInput {
@@ -73,6 +109,14 @@ impl Input {
                             },
                         ..
                     } => {
+                        match state {
+                            ElementState::Pressed if !self.keyboard.held(keycode) => {
+                                queue.events.push(InputEvent::KeyDown(keycode))
+                            }
+                            ElementState::Released => queue.events.push(InputEvent::KeyUp(keycode)),
+                            _ => {}
+                        }
+
                         if let Some(bs) = self.keyboard.key_button_state.get_mut(&keycode) {
                             match state {
                                 ElementState::Pressed if *bs != ButtonState::Pressed => {
@@ -92,6 +136,12 @@ impl Input {
                         }
                     }
 
+                    // NOTE: the composed text of IME is received as characters too.
+                    WindowEvent::ReceivedCharacter(c) if !c.is_control() => {
+                        self.keyboard.text_input.push(c);
+                        queue.events.push(InputEvent::TextInput(c));
+                    }
+
                     _ => {}
                 },
                 Event::DeviceEvent { event, .. } => match event {
@@ -103,6 +153,8 @@ impl Input {
                 _ => {}
             }
         }
+
+        self.mouse.update_drags();
     }
 
     pub(crate) fn release_all(&mut self) {
@@ -111,16 +163,113 @@ impl Input {
     }
 }
 
+/// An input event, in the order they happen in the frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InputEvent {
+    /// The key goes down, the repeats of a held key are not included.
+    KeyDown(KeyCode),
+    KeyUp(KeyCode),
+    /// The button and the cursor position in `screen space`.
+    MouseButtonDown(MouseButton, Vector2<f32>),
+    MouseButtonUp(MouseButton, Vector2<f32>),
+    /// The cursor position in `screen space`.
+    MouseMove(Vector2<f32>),
+    /// The vertical scroll in lines.
+    MouseWheel(f32),
+    /// A typed character, see `Keyboard::text_input`.
+    TextInput(char),
+}
+
+/// All input events of this frame, cleared when the next frame begins.
+///
+/// `Input` only tells the states at the end of frame, e.g. a key pressed and released in the same frame is
+/// lost, iterate the queue if every event matters.
+#[derive(Debug, Default)]
+pub struct InputEventQueue {
+    events: Vec<InputEvent>,
+}
+
+impl InputEventQueue {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<InputEvent> {
+        self.events.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+}
+
+/// The position of the cursor in `world space` seen by the camera.
+///
+/// Unlike `Mouse::cursor_position_in_ws`, which uses the screen camera of the highest priority, it works with
+/// any camera on the window. The cursor outside the letterboxed viewport maps beyond the view bounds.
+pub fn mouse_world_position(
+    input: &Input,
+    camera: &Camera2D,
+    camera_transform: &Transform2D,
+    window: &Window,
+) -> Vector2<f32> {
+    screen_to_world(
+        input.mouse.physical_position(),
+        window.scale_factor(),
+        window.logical_size(),
+        camera,
+        camera_transform,
+    )
+}
+
+// The position in physical pixels on a window of `logical_size` to `world space`, see `mouse_world_position`.
+fn screen_to_world(
+    (x, y): (f32, f32),
+    scale_factor: f32,
+    (width, height): (f32, f32),
+    camera: &Camera2D,
+    camera_transform: &Transform2D,
+) -> Vector2<f32> {
+    let viewport = camera.viewport_in(width, height);
+    let (half_width, half_height) = camera.half_extents();
+
+    // NOTE: the y axis of `screen space` points down.
+    let ndc = Vector2::new(
+        (x / scale_factor - viewport.x) / viewport.w * 2.0 - 1.0,
+        1.0 - (y / scale_factor - viewport.y) / viewport.h * 2.0,
+    );
+    let view = Point2::new(ndc.x * half_width, ndc.y * half_height);
+
+    camera_transform.transform_point2(&view).coords
+}
+
 pub struct Mouse {
     mouse_motion: (f32, f32),
     mouse_wheel_motion: (f32, f32),
     mouse_button_state: HashMap<MouseButton, ButtonState>,
 
     cursor_state: CursorState,
-    // cursor position in `screen space`.
-    cursor_position_ss: (f32, f32),
+    // cursor position in `screen space`, in physical pixels.
+    cursor_position_physical: (f32, f32),
 
     trf: Transformation,
+    scale_factor: f32,
+
+    drags: HashMap<MouseButton, Drag>,
+    drag_threshold: f32,
+}
+
+// The drag of a held button, positions are in `screen space`, in logical pixels.
+#[derive(Debug, Clone, Copy)]
+struct Drag {
+    start: Vector2<f32>,
+    last: Vector2<f32>,
+    current: Vector2<f32>,
+    dragging: bool,
 }
 
 impl Mouse {
@@ -131,9 +280,13 @@ impl Mouse {
             mouse_button_state: HashMap::with_capacity(4),
 
             cursor_state: CursorState::Left,
-            cursor_position_ss: (0f32, 0f32),
+            cursor_position_physical: (0f32, 0f32),
 
             trf: Transformation::default(),
+            scale_factor: 1.0,
+
+            drags: HashMap::with_capacity(4),
+            drag_threshold: 4.0,
         }
     }
 
@@ -189,33 +342,31 @@ impl Mouse {
         self.cursor_state == CursorState::JustEntered
     }
 
-    /// Return the position of the cursor in `screen space`.
+    /// Return the position of the cursor in `screen space`, in logical pixels.
     pub fn cursor_position_in_ss(&self) -> (f32, f32) {
-        self.cursor_position_ss
+        (
+            self.cursor_position_physical.0 / self.scale_factor,
+            self.cursor_position_physical.1 / self.scale_factor,
+        )
+    }
+
+    /// Return the position of the cursor in `screen space`, in physical pixels.
+    pub fn physical_position(&self) -> (f32, f32) {
+        self.cursor_position_physical
     }
 
     /// Return the position of the cursor in `view space`.
     pub fn cursor_position_in_vs(&self) -> (f32, f32) {
-        let mp_vs = self.trf.mx_s2v()
-            * Vector4::new(
-                self.cursor_position_ss.0,
-                self.cursor_position_ss.1,
-                0.0,
-                1.0,
-            );
+        let (x, y) = self.cursor_position_in_ss();
+        let mp_vs = self.trf.mx_s2v() * Vector4::new(x, y, 0.0, 1.0);
 
         (mp_vs.x, mp_vs.y)
     }
 
     /// Return the position of the cursor in `world space`.
     pub fn cursor_position_in_ws(&self) -> (f32, f32) {
-        let mp_ws = self.trf.mx_s2w()
-            * Vector4::new(
-                self.cursor_position_ss.0,
-                self.cursor_position_ss.1,
-                0.0,
-                1.0,
-            );
+        let (x, y) = self.cursor_position_in_ss();
+        let mp_ws = self.trf.mx_s2w() * Vector4::new(x, y, 0.0, 1.0);
 
         (mp_ws.x, mp_ws.y)
     }
@@ -246,6 +397,89 @@ impl Mouse {
         self.mouse_wheel_motion
     }
 
+    /// How far the cursor must move with the button held before a drag starts, in logical pixels.
+    pub fn drag_threshold(&self) -> f32 {
+        self.drag_threshold
+    }
+
+    pub fn set_drag_threshold(&mut self, threshold: f32) {
+        self.drag_threshold = threshold.max(0.0);
+    }
+
+    /// Detect whether the mouse button is dragging.
+    ///
+    /// A drag starts once the cursor has moved further than `drag_threshold` from where the button was
+    /// pressed, and ends when the button is released.
+    pub fn is_dragging(&self, button: MouseButton) -> bool {
+        self.drags.get(&button).map_or(false, |drag| drag.dragging)
+    }
+
+    /// Return the position where the drag was pressed in `screen space`, in logical pixels, `None` if the
+    /// button is not dragging.
+    pub fn drag_start(&self, button: MouseButton) -> Option<Vector2<f32>> {
+        self.drags
+            .get(&button)
+            .filter(|drag| drag.dragging)
+            .map(|drag| drag.start)
+    }
+
+    /// Return the movement of the drag between two frames(in screen space), zero if the button is not
+    /// dragging.
+    ///
+    /// In the frame the drag starts, it's the movement since the button was pressed, so the deltas always
+    /// add up to `drag_total_displacement`.
+    pub fn drag_delta(&self, button: MouseButton) -> Vector2<f32> {
+        self.drags
+            .get(&button)
+            .filter(|drag| drag.dragging)
+            .map_or_else(Vector2::zeros, |drag| drag.current - drag.last)
+    }
+
+    /// Return the movement of the drag since the button was pressed(in screen space), zero if the button is
+    /// not dragging.
+    pub fn drag_total_displacement(&self, button: MouseButton) -> Vector2<f32> {
+        self.drags
+            .get(&button)
+            .filter(|drag| drag.dragging)
+            .map_or_else(Vector2::zeros, |drag| drag.current - drag.start)
+    }
+
+    fn cursor_vector_in_ss(&self) -> Vector2<f32> {
+        let (x, y) = self.cursor_position_in_ss();
+        Vector2::new(x, y)
+    }
+
+    fn begin_drag(&mut self, button: MouseButton) {
+        let position = self.cursor_vector_in_ss();
+
+        self.drags.insert(
+            button,
+            Drag {
+                start: position,
+                last: position,
+                current: position,
+                dragging: false,
+            },
+        );
+    }
+
+    fn update_drags(&mut self) {
+        let position = self.cursor_vector_in_ss();
+        let threshold = self.drag_threshold;
+
+        for drag in self.drags.values_mut() {
+            // NOTE: `last` stays at the start until the drag starts, see `drag_delta`.
+            if drag.dragging {
+                drag.last = drag.current;
+            }
+            drag.current = position;
+
+            if !drag.dragging && (drag.current - drag.start).norm() > threshold {
+                drag.dragging = true;
+            }
+        }
+    }
+
     fn before_apply(&mut self) {
         self.mouse_motion = (0f32, 0f32);
         self.mouse_wheel_motion = (0f32, 0f32);
@@ -269,20 +503,35 @@ impl Mouse {
         for (_, bs) in self.mouse_button_state.iter_mut() {
             *bs = ButtonState::Released;
         }
+        self.drags.clear();
     }
 }
 
 pub struct Keyboard {
     key_button_state: HashMap<KeyCode, ButtonState>,
+    text_input: String,
 }
 
 impl Keyboard {
     fn new() -> Self {
         Self {
             key_button_state: HashMap::with_capacity(16),
+            text_input: String::new(),
         }
     }
 
+    /// The characters typed in this frame, with the keyboard layout, dead keys and IME applied.
+    ///
+    /// NOTE: control characters like backspace and enter are not included, detect them by `KeyCode`.
+    pub fn text_input(&self) -> &str {
+        &self.text_input
+    }
+
+    /// Consume the typed characters, so the other systems in this frame see no text.
+    pub fn clear_text_input(&mut self) {
+        self.text_input.clear();
+    }
+
     /// Detect whether the keyboard button has been pressed.
     pub fn just_pressed(&self, keycode: KeyCode) -> bool {
         match self.key_button_state.get(&keycode) {
@@ -315,7 +564,26 @@ impl Keyboard {
         }
     }
 
+    /// Detect whether all keys of the chord are held, e.g. `&[KeyCode::LControl, KeyCode::S]`.
+    ///
+    /// NOTE: other keys are not checked, so Ctrl+Z is also held while Ctrl+Shift+Z is held.
+    pub fn chord_pressed(&self, keys: &[KeyCode]) -> bool {
+        !keys.is_empty() && keys.iter().all(|key| self.held(*key))
+    }
+
+    /// Detect whether the chord has just been completed, the last key of chord goes down in this frame while
+    /// the others are held.
+    pub fn chord_just_pressed(&self, keys: &[KeyCode]) -> bool {
+        self.chord_pressed(keys) && keys.iter().any(|key| self.just_pressed(*key))
+    }
+
+    fn held(&self, keycode: KeyCode) -> bool {
+        self.just_pressed(keycode) || self.pressed(keycode)
+    }
+
     fn before_apply(&mut self) {
+        self.text_input.clear();
+
         for bs in self.key_button_state.values_mut() {
             match *bs {
                 ButtonState::JustPressed => *bs = ButtonState::Pressed,
@@ -329,9 +597,254 @@ impl Keyboard {
         for (_, bs) in self.key_button_state.iter_mut() {
             *bs = ButtonState::Released;
         }
+        self.text_input.clear();
+    }
+}
+
+/// A stick axis of gamepads, up and right are positive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GamepadAxis {
+    LeftStickX,
+    LeftStickY,
+    RightStickX,
+    RightStickY,
+}
+
+/// The sticks of the connected gamepads, they are seen as a single device, the last moved stick wins.
+///
+/// The gamepads are read with the `gamepad` feature, or injected by `SyntheticWindowEvent::GamepadAxis`.
+#[derive(Debug, Default)]
+pub struct Gamepad {
+    axes: HashMap<GamepadAxis, f32>,
+}
+
+impl Gamepad {
+    fn new() -> Self {
+        Default::default()
+    }
+
+    /// The value of the axis in [-1, 1], 0 if it never moved.
+    pub fn axis(&self, axis: GamepadAxis) -> f32 {
+        self.axes.get(&axis).copied().unwrap_or(0.0)
+    }
+
+    pub(crate) fn set_axis(&mut self, axis: GamepadAxis, value: f32) {
+        self.axes.insert(axis, value.max(-1.0).min(1.0));
+    }
+
+    /// Center all sticks, e.g. when a gamepad is disconnected.
+    pub(crate) fn release_all(&mut self) {
+        self.axes.clear();
+    }
+
+    /// Apply the stick events of the connected gamepads.
+    #[cfg(feature = "gamepad")]
+    pub(crate) fn poll(&mut self, gilrs: &mut gilrs::Gilrs) {
+        use gilrs::{Axis, EventType};
+
+        while let Some(event) = gilrs.next_event() {
+            match event.event {
+                EventType::AxisChanged(axis, value, _) => {
+                    let axis = match axis {
+                        Axis::LeftStickX => GamepadAxis::LeftStickX,
+                        Axis::LeftStickY => GamepadAxis::LeftStickY,
+                        Axis::RightStickX => GamepadAxis::RightStickX,
+                        Axis::RightStickY => GamepadAxis::RightStickY,
+                        _ => continue,
+                    };
+                    self.set_axis(axis, value);
+                }
+                EventType::Disconnected => self.release_all(),
+                _ => {}
+            }
+        }
     }
 }
 
+/// A key combination like Ctrl+S, call `InputChord::update` every frame.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InputChord {
+    pub keys: Vec<KeyCode>,
+    /// Whether the chord was completed in the last update.
+    pub just_completed: bool,
+}
+
+impl InputChord {
+    pub fn new(keys: &[KeyCode]) -> Self {
+        Self {
+            keys: keys.to_vec(),
+            just_completed: false,
+        }
+    }
+
+    pub fn update(&mut self, keyboard: &Keyboard) {
+        self.just_completed = keyboard.chord_just_pressed(&self.keys);
+    }
+
+    /// Whether all keys of the chord are held.
+    pub fn pressed(&self, keyboard: &Keyboard) -> bool {
+        keyboard.chord_pressed(&self.keys)
+    }
+}
+
+/// An analog axis in [-1, 1] driven by a pair of keys or a stick of gamepad, the keys move it smoothly
+/// toward the held key and the stick drives it directly.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VirtualAxis {
+    pub positive_key: KeyCode,
+    pub negative_key: KeyCode,
+    /// The held keys win over the stick.
+    pub gamepad_axis: Option<GamepadAxis>,
+    /// The value reads 0 while its magnitude is under the deadzone.
+    pub deadzone: f32,
+    /// How fast the value moves in units per second, it takes `1 / sensitivity` seconds from 0 to 1.
+    pub sensitivity: f32,
+
+    value: f32,
+}
+
+impl VirtualAxis {
+    pub fn new(positive_key: KeyCode, negative_key: KeyCode) -> Self {
+        Self {
+            positive_key,
+            negative_key,
+            gamepad_axis: None,
+            deadzone: 0.001,
+            sensitivity: 3.0,
+            value: 0.0,
+        }
+    }
+
+    pub fn with_gamepad_axis(mut self, axis: GamepadAxis) -> Self {
+        self.gamepad_axis = Some(axis);
+        self
+    }
+
+    pub fn with_deadzone(mut self, deadzone: f32) -> Self {
+        self.deadzone = deadzone;
+        self
+    }
+
+    pub fn with_sensitivity(mut self, sensitivity: f32) -> Self {
+        self.sensitivity = sensitivity;
+        self
+    }
+
+    pub fn value(&self) -> f32 {
+        if self.value.abs() < self.deadzone {
+            0.0
+        } else {
+            self.value
+        }
+    }
+
+    /// Move the value toward the held key, or follow the stick out of the deadzone if no key is held, or
+    /// back to 0 if none or both keys are held.
+    pub fn update(&mut self, input: &Input, dt: f32) {
+        let (keyboard, positive, negative) =
+            (&input.keyboard, self.positive_key, self.negative_key);
+        let stick = self
+            .gamepad_axis
+            .map_or(0.0, |axis| input.gamepad.axis(axis));
+
+        if !keyboard.held(positive) && !keyboard.held(negative) && stick.abs() >= self.deadzone {
+            self.value = stick;
+            return;
+        }
+
+        let mut target = 0.0;
+        if keyboard.held(positive) {
+            target += 1.0;
+        }
+        if keyboard.held(negative) {
+            target -= 1.0;
+        }
+
+        // NOTE: a non-positive sensitivity snaps to the target.
+        let step = if self.sensitivity > 0.0 {
+            self.sensitivity * dt
+        } else {
+            f32::INFINITY
+        };
+        let delta = target - self.value;
+
+        self.value = if delta.abs() <= step {
+            target
+        } else {
+            self.value + step * delta.signum()
+        };
+    }
+}
+
+/// The named `VirtualAxis`es, e.g. "horizontal" and "vertical", updated by `virtual_axes_system`.
+#[derive(Debug, Clone, Default)]
+pub struct VirtualAxes {
+    axes: HashMap<String, VirtualAxis>,
+}
+
+impl VirtualAxes {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The axes "horizontal" on D/A and "vertical" on W/S, both on the left stick of gamepad too.
+    pub fn wasd() -> Self {
+        Self::new()
+            .with_axis(
+                "horizontal",
+                VirtualAxis::new(KeyCode::D, KeyCode::A).with_gamepad_axis(GamepadAxis::LeftStickX),
+            )
+            .with_axis(
+                "vertical",
+                VirtualAxis::new(KeyCode::W, KeyCode::S).with_gamepad_axis(GamepadAxis::LeftStickY),
+            )
+    }
+
+    pub fn with_axis(mut self, name: &str, axis: VirtualAxis) -> Self {
+        self.insert(name, axis);
+        self
+    }
+
+    /// Add the axis, the axis with the same name is replaced.
+    pub fn insert(&mut self, name: &str, axis: VirtualAxis) -> Option<VirtualAxis> {
+        self.axes.insert(String::from(name), axis)
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<VirtualAxis> {
+        self.axes.remove(name)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&VirtualAxis> {
+        self.axes.get(name)
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut VirtualAxis> {
+        self.axes.get_mut(name)
+    }
+
+    /// The value of the axis, 0 if there's no axis of the name.
+    pub fn value(&self, name: &str) -> f32 {
+        self.axes.get(name).map_or(0.0, VirtualAxis::value)
+    }
+
+    pub fn update(&mut self, input: &Input, dt: f32) {
+        for axis in self.axes.values_mut() {
+            axis.update(input, dt);
+        }
+    }
+}
+
+/// Create a system which updates the `VirtualAxes` resource from the keyboard and gamepads every frame.
+pub fn virtual_axes_system() -> impl ParallelRunnable {
+    SystemBuilder::new("virtual_axes")
+        .read_resource::<Input>()
+        .read_resource::<Time>()
+        .write_resource::<VirtualAxes>()
+        .build(|_, _, (input, time, axes), _| {
+            axes.update(input, time.delta().as_secs_f32());
+        })
+}
+
 #[derive(Clone, Copy, PartialEq)]
 enum ButtonState {
     Pressed,
@@ -347,3 +860,387 @@ enum CursorState {
     JustLeft,
     JustEntered,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::window::SyntheticWindowEvent;
+
+    // Apply the synthetic events as a frame with the default transformation.
+    fn apply(input: &mut Input, queue: &mut InputEventQueue, events: &[SyntheticWindowEvent]) {
+        let mut evts = events
+            .iter()
+            .filter_map(|event| event.to_input_event())
+            .collect::<Vec<_>>();
+
+        input.apply(&mut evts, &Transformation::default(), 1.0, queue);
+    }
+
+    #[test]
+    fn receive_characters_and_backspace() {
+        let mut input = Input::new();
+        let mut queue = InputEventQueue::new();
+
+        apply(
+            &mut input,
+            &mut queue,
+            &[
+                SyntheticWindowEvent::Character('a'),
+                SyntheticWindowEvent::Character('好'),
+                // backspace is received as a control character as well as a key.
+                SyntheticWindowEvent::KeyDown(KeyCode::Back),
+                SyntheticWindowEvent::Character('\u{8}'),
+            ],
+        );
+
+        assert_eq!(input.keyboard.text_input(), "a好");
+        assert!(input.keyboard.just_pressed(KeyCode::Back));
+        assert_eq!(
+            queue.iter().copied().collect::<Vec<_>>(),
+            vec![
+                InputEvent::TextInput('a'),
+                InputEvent::TextInput('好'),
+                InputEvent::KeyDown(KeyCode::Back),
+            ]
+        );
+
+        // the text is cleared in the next frame.
+        apply(&mut input, &mut queue, &[]);
+        assert_eq!(input.keyboard.text_input(), "");
+        assert!(queue.is_empty());
+        assert!(input.keyboard.pressed(KeyCode::Back));
+    }
+
+    #[test]
+    fn mouse_world_position_in_viewport() {
+        let approx = |a: Vector2<f32>, b: Vector2<f32>| (a - b).norm() < 1e-3;
+
+        // the right half of a 1600 x 900 window, one world unit a pixel.
+        let camera = Camera2D::new(1600, 900).with_viewport(0.5, 0.0, 0.5, 1.0);
+        let transform = Transform2D::at(10.0, 0.0);
+        let to_world = |x, y| screen_to_world((x, y), 1.0, (1600.0, 900.0), &camera, &transform);
+
+        assert!(approx(to_world(1200.0, 450.0), Vector2::new(10.0, 0.0)));
+        assert!(approx(to_world(1600.0, 0.0), Vector2::new(410.0, 450.0)));
+        assert!(approx(to_world(800.0, 900.0), Vector2::new(-390.0, -450.0)));
+        // the left half is beyond the view.
+        assert!(to_world(400.0, 450.0).x < -390.0);
+
+        // the view of 9 units a half height in the same viewport.
+        let camera = camera.with_orthographic_size(9.0);
+        let to_world = |x, y| screen_to_world((x, y), 1.0, (1600.0, 900.0), &camera, &transform);
+        assert!(approx(to_world(1600.0, 0.0), Vector2::new(18.0, 9.0)));
+    }
+
+    #[test]
+    fn mouse_world_position_letterboxed() {
+        let approx = |a: Vector2<f32>, b: Vector2<f32>| (a - b).norm() < 1e-3;
+
+        // a 2:1 view in a 800 x 600 window has bars of 100 pixels at the top and bottom, the cursor
+        // positions are in physical pixels of scale factor 2.
+        let camera = Camera2D::new(800, 600).with_fixed_aspect(2.0);
+        let transform = Transform2D::default();
+        let to_world = |x, y| screen_to_world((x, y), 2.0, (800.0, 600.0), &camera, &transform);
+
+        assert_eq!(camera.half_extents(), (400.0, 200.0));
+        assert!(approx(to_world(1600.0, 200.0), Vector2::new(400.0, 200.0)));
+        assert!(approx(to_world(800.0, 600.0), Vector2::new(0.0, 0.0)));
+        assert!(approx(to_world(0.0, 1000.0), Vector2::new(-400.0, -200.0)));
+        // the cursor in the top bar maps above the view.
+        assert!(approx(to_world(800.0, 100.0), Vector2::new(0.0, 250.0)));
+    }
+
+    #[test]
+    fn ramp_virtual_axis() {
+        let mut input = Input::new();
+        let mut queue = InputEventQueue::new();
+        let mut axis = VirtualAxis::new(KeyCode::D, KeyCode::A).with_sensitivity(2.0);
+
+        apply(
+            &mut input,
+            &mut queue,
+            &[SyntheticWindowEvent::KeyDown(KeyCode::D)],
+        );
+        axis.update(&input, 0.25);
+        assert_eq!(axis.value(), 0.5);
+        axis.update(&input, 1.0);
+        assert_eq!(axis.value(), 1.0);
+
+        // it goes back to 0 at the same speed after the key is released.
+        apply(
+            &mut input,
+            &mut queue,
+            &[SyntheticWindowEvent::KeyUp(KeyCode::D)],
+        );
+        axis.update(&input, 0.25);
+        assert_eq!(axis.value(), 0.5);
+
+        apply(
+            &mut input,
+            &mut queue,
+            &[SyntheticWindowEvent::KeyDown(KeyCode::A)],
+        );
+        axis.update(&input, 0.5);
+        assert_eq!(axis.value(), -0.5);
+
+        // the opposing keys cancel out.
+        apply(
+            &mut input,
+            &mut queue,
+            &[SyntheticWindowEvent::KeyDown(KeyCode::D)],
+        );
+        axis.update(&input, 0.125);
+        assert_eq!(axis.value(), -0.25);
+        axis.update(&input, 1.0);
+        assert_eq!(axis.value(), 0.0);
+    }
+
+    #[test]
+    fn virtual_axis_deadzone() {
+        let mut input = Input::new();
+        let mut queue = InputEventQueue::new();
+        let mut axis = VirtualAxis::new(KeyCode::W, KeyCode::S)
+            .with_sensitivity(1.0)
+            .with_deadzone(0.3);
+
+        apply(
+            &mut input,
+            &mut queue,
+            &[SyntheticWindowEvent::KeyDown(KeyCode::W)],
+        );
+        axis.update(&input, 0.25);
+        assert_eq!(axis.value(), 0.0);
+        axis.update(&input, 0.25);
+        assert_eq!(axis.value(), 0.5);
+
+        // a non-positive sensitivity snaps to the target.
+        axis.sensitivity = 0.0;
+        apply(
+            &mut input,
+            &mut queue,
+            &[SyntheticWindowEvent::KeyUp(KeyCode::W)],
+        );
+        axis.update(&input, 0.01);
+        assert_eq!(axis.value(), 0.0);
+    }
+
+    #[test]
+    fn drive_virtual_axis_by_gamepad() {
+        let mut input = Input::new();
+        let mut queue = InputEventQueue::new();
+        let mut axes = VirtualAxes::wasd();
+
+        // the stick moves the value at once, the stick in the deadzone is ignored.
+        input.gamepad.set_axis(GamepadAxis::LeftStickX, -0.75);
+        input.gamepad.set_axis(GamepadAxis::LeftStickY, 0.0001);
+        axes.update(&input, 0.01);
+        assert_eq!(axes.value("horizontal"), -0.75);
+        assert_eq!(axes.value("vertical"), 0.0);
+
+        // the held key wins over the stick, it ramps from the value of stick.
+        apply(
+            &mut input,
+            &mut queue,
+            &[SyntheticWindowEvent::KeyDown(KeyCode::D)],
+        );
+        axes.update(&input, 0.25);
+        assert_eq!(axes.value("horizontal"), 0.0);
+
+        input.gamepad.set_axis(GamepadAxis::LeftStickY, 2.0);
+        axes.update(&input, 0.01);
+        assert_eq!(axes.value("vertical"), 1.0);
+
+        input.gamepad.release_all();
+        assert_eq!(input.gamepad.axis(GamepadAxis::LeftStickX), 0.0);
+    }
+
+    #[test]
+    fn queue_events_in_order() {
+        let mut input = Input::new();
+        let mut queue = InputEventQueue::new();
+
+        // the key pressed and released in the same frame is only seen in the queue.
+        apply(
+            &mut input,
+            &mut queue,
+            &[
+                SyntheticWindowEvent::KeyDown(KeyCode::A),
+                SyntheticWindowEvent::MouseWheel(0.0, 2.0),
+                SyntheticWindowEvent::KeyUp(KeyCode::A),
+                SyntheticWindowEvent::KeyDown(KeyCode::B),
+            ],
+        );
+
+        assert_eq!(queue.len(), 4);
+        assert_eq!(
+            queue.iter().copied().collect::<Vec<_>>(),
+            vec![
+                InputEvent::KeyDown(KeyCode::A),
+                InputEvent::MouseWheel(2.0),
+                InputEvent::KeyUp(KeyCode::A),
+                InputEvent::KeyDown(KeyCode::B),
+            ]
+        );
+        assert!(!input.keyboard.pressed(KeyCode::A));
+
+        // the repeats of the held key are not queued.
+        apply(
+            &mut input,
+            &mut queue,
+            &[SyntheticWindowEvent::KeyDown(KeyCode::B)],
+        );
+        assert!(queue.is_empty());
+
+        apply(
+            &mut input,
+            &mut queue,
+            &[SyntheticWindowEvent::KeyUp(KeyCode::B)],
+        );
+        assert_eq!(
+            queue.iter().copied().collect::<Vec<_>>(),
+            vec![InputEvent::KeyUp(KeyCode::B)]
+        );
+    }
+
+    #[test]
+    fn drag_mouse() {
+        let mut input = Input::new();
+        let mut queue = InputEventQueue::new();
+        let left = MouseButton::Left;
+
+        apply(
+            &mut input,
+            &mut queue,
+            &[
+                SyntheticWindowEvent::CursorMoved(10.0, 10.0),
+                SyntheticWindowEvent::MouseDown(left),
+            ],
+        );
+        assert!(!input.mouse.is_dragging(left));
+
+        // no drag before the cursor moves further than the threshold.
+        apply(
+            &mut input,
+            &mut queue,
+            &[SyntheticWindowEvent::CursorMoved(12.0, 11.0)],
+        );
+        assert!(!input.mouse.is_dragging(left));
+        assert_eq!(input.mouse.drag_start(left), None);
+        assert_eq!(input.mouse.drag_delta(left), Vector2::zeros());
+        assert_eq!(input.mouse.drag_total_displacement(left), Vector2::zeros());
+
+        // the first delta is measured from the press point.
+        let mut deltas = Vector2::zeros();
+        apply(
+            &mut input,
+            &mut queue,
+            &[SyntheticWindowEvent::CursorMoved(16.0, 10.0)],
+        );
+        assert!(input.mouse.is_dragging(left));
+        assert_eq!(input.mouse.drag_start(left), Some(Vector2::new(10.0, 10.0)));
+        assert_eq!(input.mouse.drag_delta(left), Vector2::new(6.0, 0.0));
+        deltas += input.mouse.drag_delta(left);
+
+        apply(
+            &mut input,
+            &mut queue,
+            &[SyntheticWindowEvent::CursorMoved(20.0, 14.0)],
+        );
+        assert_eq!(input.mouse.drag_delta(left), Vector2::new(4.0, 4.0));
+        deltas += input.mouse.drag_delta(left);
+
+        // the drag goes on without moving.
+        apply(&mut input, &mut queue, &[]);
+        assert!(input.mouse.is_dragging(left));
+        assert_eq!(input.mouse.drag_delta(left), Vector2::zeros());
+
+        assert_eq!(deltas, Vector2::new(10.0, 4.0));
+        assert_eq!(input.mouse.drag_total_displacement(left), deltas);
+
+        // the drag is cleared on release.
+        apply(
+            &mut input,
+            &mut queue,
+            &[SyntheticWindowEvent::MouseUp(left)],
+        );
+        assert!(!input.mouse.is_dragging(left));
+        assert_eq!(input.mouse.drag_start(left), None);
+        assert_eq!(input.mouse.drag_total_displacement(left), Vector2::zeros());
+
+        // the drag is cleared by `release_all`, e.g. when the window is resized.
+        input.mouse.set_drag_threshold(-1.0);
+        assert_eq!(input.mouse.drag_threshold(), 0.0);
+        apply(
+            &mut input,
+            &mut queue,
+            &[SyntheticWindowEvent::MouseDown(left)],
+        );
+        apply(
+            &mut input,
+            &mut queue,
+            &[SyntheticWindowEvent::CursorMoved(21.0, 14.0)],
+        );
+        assert!(input.mouse.is_dragging(left));
+
+        input.release_all();
+        assert!(!input.mouse.is_dragging(left));
+        assert_eq!(input.mouse.drag_delta(left), Vector2::zeros());
+    }
+
+    #[test]
+    fn complete_chord() {
+        let mut input = Input::new();
+        let mut queue = InputEventQueue::new();
+        let mut save = InputChord::new(&[KeyCode::LControl, KeyCode::S]);
+
+        // the chord is completed by its last key only.
+        apply(
+            &mut input,
+            &mut queue,
+            &[SyntheticWindowEvent::KeyDown(KeyCode::LControl)],
+        );
+        save.update(&input.keyboard);
+        assert!(!save.just_completed);
+        assert!(!save.pressed(&input.keyboard));
+
+        apply(
+            &mut input,
+            &mut queue,
+            &[SyntheticWindowEvent::KeyDown(KeyCode::S)],
+        );
+        save.update(&input.keyboard);
+        assert!(save.just_completed);
+        assert!(save.pressed(&input.keyboard));
+
+        // `just_completed` resets in the next update while the keys are held.
+        apply(&mut input, &mut queue, &[]);
+        save.update(&input.keyboard);
+        assert!(!save.just_completed);
+        assert!(save.pressed(&input.keyboard));
+
+        // releasing a key breaks the chord, pressing it again completes the chord again.
+        apply(
+            &mut input,
+            &mut queue,
+            &[SyntheticWindowEvent::KeyUp(KeyCode::LControl)],
+        );
+        save.update(&input.keyboard);
+        assert!(!save.just_completed);
+        assert!(!save.pressed(&input.keyboard));
+
+        apply(
+            &mut input,
+            &mut queue,
+            &[SyntheticWindowEvent::KeyDown(KeyCode::LControl)],
+        );
+        save.update(&input.keyboard);
+        assert!(save.just_completed);
+
+        // the empty chord is never pressed.
+        let mut empty = InputChord::new(&[]);
+        empty.update(&input.keyboard);
+        assert!(!empty.just_completed);
+        assert!(!empty.pressed(&input.keyboard));
+        assert!(!input.keyboard.chord_just_pressed(&[]));
+    }
+}
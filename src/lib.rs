@@ -1,9 +1,22 @@
 pub mod app;
+pub mod assets;
+pub mod audio;
 pub mod components;
+pub mod config;
+pub mod coroutine;
+pub mod events;
 pub mod input;
+pub mod math;
 pub mod misc;
+pub mod plugin;
+pub mod prefab;
+#[cfg(not(feature = "headless"))]
 pub mod render;
+pub mod scene;
+pub mod snapshot;
+pub mod task;
 pub mod window;
+pub mod world;
 
 // Use crate `legion` as the ecs framework of yam engine.
 //
@@ -15,20 +28,101 @@ pub extern crate legion;
 pub extern crate nalgebra;
 
 pub use app::*;
+pub use assets::{Asset, AssetError, AssetServer, Assets, Handle};
+pub use audio::{
+    spatial_audio_system, Audio, AudioClip, AudioCommand, AudioEmitter, AudioError, AudioInstance,
+    AudioPlayback, BackgroundMusic, RolloffModel, SpatialAudioConfig, SpatialAudioListener,
+    SpatialPlayback,
+};
 pub use components::{
-    camera::Camera2D,
+    animation::{animate_sprites_system, AnimationClip, AnimationFinished, AnimationPlayer},
+    blend::BlendMode,
+    camera::{
+        camera_zoom_system, window_resize_system, Camera2D, RenderLayer, RenderTarget,
+        RenderTexture, RenderTextureHandle,
+    },
+    camera_shake::{camera_shake_system, CameraShake, ShakeEvent},
+    collision::{
+        broad_phase_system, compute_aabb_system, narrow_phase_collision, BroadPhaseResult,
+        ContactManifold, SpatialHash2D, AABB2D,
+    },
     geometry::{
         Assembly, BorderDecoration, BorderThickness, Geometry, Geometry1DType, Geometry2DType,
         GeometryType, InnerDecoration,
     },
-    sprite::Sprite,
+    hierarchy::{
+        compose_transforms, global_to_local, global_transform, propagate_global_transforms_system,
+        GlobalTransform2D, Parent,
+    },
+    morph::{geometry_morph_system, resample_vertices, GeometryMorph},
+    parallax::{parallax_system, Parallax2D},
+    particle::{update_particles_system, ParticleEmitter2D, ParticleEmitter2DBuilder},
+    path::{follow_path_system, Path2D, PathEnd, PathFollower, PathInterpolation},
+    pathfinding::{pathfinding_system, PathfindingRequest, PathfindingResult},
+    post_process::{
+        BloomEffect, ChromaticAberration, ColorGradeEffect, PostEffect, PostProcessStack,
+        VignetteEffect,
+    },
+    rigid_body::{
+        apply_force, apply_impulse, integrate_rigid_bodies_system, BodyType, RigidBody2D,
+    },
+    sprite::{Rect, Sprite, Sprite2D, SpriteFlip, Texture, TextureAtlas, TextureHandle},
+    state_machine::{
+        state_machine_system, StateCallback, StateChanged, StateMachine, TransitionCondition,
+    },
+    stencil::{RenderGroup, StencilMask, StencilMode},
+    text::{
+        Font, FontAtlas, FontHandle, Glyph, GlyphQuad, HAlign, Text2D, TextAlign, TextOutline,
+        VAlign,
+    },
+    tilemap::{update_tilemaps_system, TileId, TileInstance, TilemapRenderer2D},
     time::{DiagnosticTimer, Time},
-    transform::Transform2D,
+    transform::{apply_transform_constraints_system, Transform2D, Transform2DConstraints},
+    tween::{update_tweens_system, Lerpable, Tween, TweenComplete, TweenCompleted, TweenTarget},
     Instance,
 };
-pub use input::{Input, KeyCode, MouseButton};
-pub use misc::color::{Hex, Rgba};
-pub use window::{Fullscreen, MonitorHandle, VideoMode, Window};
+pub use config::{AppConfig, ConfigError, StageConfig};
+pub use coroutine::{
+    coroutine_system, wait_frames, wait_seconds, wait_until, Coroutine, CoroutineHandle,
+    CoroutineRunner, CoroutineStep, WaitCondition, Yield,
+};
+pub use events::{update_events_system, EventReader, EventWriter, Events};
+pub use input::{
+    mouse_world_position, virtual_axes_system, Gamepad, GamepadAxis, Input, InputChord, InputEvent,
+    InputEventQueue, KeyCode, MouseButton, VirtualAxes, VirtualAxis,
+};
+pub use math::{
+    easing::EasingFn,
+    grid::Grid2D,
+    noise::{Noise2D, Noise2DBuilder},
+    pathfinding::{astar, astar_8, Connectivity},
+    polygon::{Polygon2D, PolygonError},
+};
+pub use misc::{
+    color::{Hex, Rgba},
+    debug_draw::{DebugDraw, DebugText},
+    frame_stats::{
+        print_frame_stats_system, print_stage_metrics_system, FrameStats, StageMetrics, StageTiming,
+    },
+    rng::RngResource,
+    svg::{parse_svg_path, svg_path_to_assembly, SvgParseError, SvgSubpath},
+};
+pub use plugin::{Plugin, PluginGroup, PluginRegistry};
+pub use prefab::{AnyComponent, Prefab, PrefabBuilder, PrefabCache, PrefabInstance};
+pub use scene::{
+    export_scene_to_svg, instantiate_scene, load_scene, save_scene, scene_to_svg, unload_scene,
+    Scene, SceneComponent, SceneEntities, SceneEntity, SceneError, SceneInstance,
+};
+pub use snapshot::{
+    restore_world, snapshot_world, transfer_entity, ComponentRegistry, EntityTransfers,
+    TransferError, WorldSnapshot,
+};
+pub use task::{poll_tasks_system, Task, TaskPool};
+pub use window::{
+    CursorIcon, CursorImage, CursorSettings, Fullscreen, FullscreenMode, MonitorHandle,
+    SyntheticWindowEvent, VideoMode, Window, WindowResized,
+};
+pub use world::{despawn_all_with, despawn_all_with_filter, QueryExt};
 
 const DEFAULT_WIDTH: u32 = 1280;
 const DEFAULT_HEIGHT: u32 = 720;
@@ -0,0 +1,285 @@
+//! Easing functions, the standard Penner set.
+//!
+//! Every function maps the normalized time in [0, 1] to the normalized progress, f(0) = 0 and
+//! f(1) = 1. Click [this](https://easings.net) to see how they look like.
+
+use std::f32::consts::PI;
+
+/// Map the normalized time in [0, 1] to the normalized progress.
+pub type EasingFn = fn(f32) -> f32;
+
+const C1: f32 = 1.70158;
+const C2: f32 = C1 * 1.525;
+const C3: f32 = C1 + 1.0;
+const C4: f32 = (2.0 * PI) / 3.0;
+const C5: f32 = (2.0 * PI) / 4.5;
+
+/// Clamp `t` to [0, 1] and apply `easing`.
+pub fn sample(easing: EasingFn, t: f32) -> f32 {
+    easing(t.max(0.0).min(1.0))
+}
+
+pub const fn linear(t: f32) -> f32 {
+    t
+}
+
+pub const fn ease_in_quad(t: f32) -> f32 {
+    t * t
+}
+
+pub const fn ease_out_quad(t: f32) -> f32 {
+    1.0 - (1.0 - t) * (1.0 - t)
+}
+
+pub fn ease_in_out_quad(t: f32) -> f32 {
+    if t < 0.5 {
+        2.0 * t * t
+    } else {
+        1.0 - (-2.0 * t + 2.0).powi(2) / 2.0
+    }
+}
+
+pub const fn ease_in_cubic(t: f32) -> f32 {
+    t * t * t
+}
+
+pub fn ease_out_cubic(t: f32) -> f32 {
+    1.0 - (1.0 - t).powi(3)
+}
+
+pub fn ease_in_out_cubic(t: f32) -> f32 {
+    if t < 0.5 {
+        4.0 * t * t * t
+    } else {
+        1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+    }
+}
+
+pub const fn ease_in_quart(t: f32) -> f32 {
+    t * t * t * t
+}
+
+pub fn ease_out_quart(t: f32) -> f32 {
+    1.0 - (1.0 - t).powi(4)
+}
+
+pub fn ease_in_out_quart(t: f32) -> f32 {
+    if t < 0.5 {
+        8.0 * t * t * t * t
+    } else {
+        1.0 - (-2.0 * t + 2.0).powi(4) / 2.0
+    }
+}
+
+pub const fn ease_in_quint(t: f32) -> f32 {
+    t * t * t * t * t
+}
+
+pub fn ease_out_quint(t: f32) -> f32 {
+    1.0 - (1.0 - t).powi(5)
+}
+
+pub fn ease_in_out_quint(t: f32) -> f32 {
+    if t < 0.5 {
+        16.0 * t * t * t * t * t
+    } else {
+        1.0 - (-2.0 * t + 2.0).powi(5) / 2.0
+    }
+}
+
+pub fn ease_in_sine(t: f32) -> f32 {
+    1.0 - f32::cos(t * PI / 2.0)
+}
+
+pub fn ease_out_sine(t: f32) -> f32 {
+    f32::sin(t * PI / 2.0)
+}
+
+pub fn ease_in_out_sine(t: f32) -> f32 {
+    -(f32::cos(PI * t) - 1.0) / 2.0
+}
+
+pub fn ease_in_expo(t: f32) -> f32 {
+    if t <= 0.0 {
+        0.0
+    } else {
+        f32::powf(2.0, 10.0 * t - 10.0)
+    }
+}
+
+pub fn ease_out_expo(t: f32) -> f32 {
+    if t >= 1.0 {
+        1.0
+    } else {
+        1.0 - f32::powf(2.0, -10.0 * t)
+    }
+}
+
+pub fn ease_in_out_expo(t: f32) -> f32 {
+    if t <= 0.0 {
+        0.0
+    } else if t >= 1.0 {
+        1.0
+    } else if t < 0.5 {
+        f32::powf(2.0, 20.0 * t - 10.0) / 2.0
+    } else {
+        (2.0 - f32::powf(2.0, -20.0 * t + 10.0)) / 2.0
+    }
+}
+
+pub fn ease_in_circ(t: f32) -> f32 {
+    1.0 - f32::sqrt(1.0 - t * t)
+}
+
+pub fn ease_out_circ(t: f32) -> f32 {
+    f32::sqrt(1.0 - (t - 1.0) * (t - 1.0))
+}
+
+pub fn ease_in_out_circ(t: f32) -> f32 {
+    if t < 0.5 {
+        (1.0 - f32::sqrt(1.0 - (2.0 * t).powi(2))) / 2.0
+    } else {
+        (f32::sqrt(1.0 - (-2.0 * t + 2.0).powi(2)) + 1.0) / 2.0
+    }
+}
+
+pub fn ease_in_back(t: f32) -> f32 {
+    C3 * t * t * t - C1 * t * t
+}
+
+pub fn ease_out_back(t: f32) -> f32 {
+    1.0 + C3 * (t - 1.0).powi(3) + C1 * (t - 1.0).powi(2)
+}
+
+pub fn ease_in_out_back(t: f32) -> f32 {
+    if t < 0.5 {
+        ((2.0 * t).powi(2) * ((C2 + 1.0) * 2.0 * t - C2)) / 2.0
+    } else {
+        ((2.0 * t - 2.0).powi(2) * ((C2 + 1.0) * (t * 2.0 - 2.0) + C2) + 2.0) / 2.0
+    }
+}
+
+pub fn ease_in_elastic(t: f32) -> f32 {
+    if t <= 0.0 {
+        0.0
+    } else if t >= 1.0 {
+        1.0
+    } else {
+        -f32::powf(2.0, 10.0 * t - 10.0) * f32::sin((t * 10.0 - 10.75) * C4)
+    }
+}
+
+pub fn ease_out_elastic(t: f32) -> f32 {
+    if t <= 0.0 {
+        0.0
+    } else if t >= 1.0 {
+        1.0
+    } else {
+        f32::powf(2.0, -10.0 * t) * f32::sin((t * 10.0 - 0.75) * C4) + 1.0
+    }
+}
+
+pub fn ease_in_out_elastic(t: f32) -> f32 {
+    if t <= 0.0 {
+        0.0
+    } else if t >= 1.0 {
+        1.0
+    } else if t < 0.5 {
+        -(f32::powf(2.0, 20.0 * t - 10.0) * f32::sin((20.0 * t - 11.125) * C5)) / 2.0
+    } else {
+        (f32::powf(2.0, -20.0 * t + 10.0) * f32::sin((20.0 * t - 11.125) * C5)) / 2.0 + 1.0
+    }
+}
+
+pub fn ease_in_bounce(t: f32) -> f32 {
+    1.0 - ease_out_bounce(1.0 - t)
+}
+
+pub fn ease_out_bounce(t: f32) -> f32 {
+    const N1: f32 = 7.5625;
+    const D1: f32 = 2.75;
+
+    if t < 1.0 / D1 {
+        N1 * t * t
+    } else if t < 2.0 / D1 {
+        let t = t - 1.5 / D1;
+        N1 * t * t + 0.75
+    } else if t < 2.5 / D1 {
+        let t = t - 2.25 / D1;
+        N1 * t * t + 0.9375
+    } else {
+        let t = t - 2.625 / D1;
+        N1 * t * t + 0.984375
+    }
+}
+
+pub fn ease_in_out_bounce(t: f32) -> f32 {
+    if t < 0.5 {
+        (1.0 - ease_out_bounce(1.0 - 2.0 * t)) / 2.0
+    } else {
+        (1.0 + ease_out_bounce(2.0 * t - 1.0)) / 2.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPSILON: f32 = 1e-4;
+
+    #[rustfmt::skip]
+    const ALL: [(&str, EasingFn); 31] = [
+        ("linear", linear),
+        ("ease_in_quad", ease_in_quad),         ("ease_out_quad", ease_out_quad),       ("ease_in_out_quad", ease_in_out_quad),
+        ("ease_in_cubic", ease_in_cubic),       ("ease_out_cubic", ease_out_cubic),     ("ease_in_out_cubic", ease_in_out_cubic),
+        ("ease_in_quart", ease_in_quart),       ("ease_out_quart", ease_out_quart),     ("ease_in_out_quart", ease_in_out_quart),
+        ("ease_in_quint", ease_in_quint),       ("ease_out_quint", ease_out_quint),     ("ease_in_out_quint", ease_in_out_quint),
+        ("ease_in_sine", ease_in_sine),         ("ease_out_sine", ease_out_sine),       ("ease_in_out_sine", ease_in_out_sine),
+        ("ease_in_expo", ease_in_expo),         ("ease_out_expo", ease_out_expo),       ("ease_in_out_expo", ease_in_out_expo),
+        ("ease_in_circ", ease_in_circ),         ("ease_out_circ", ease_out_circ),       ("ease_in_out_circ", ease_in_out_circ),
+        ("ease_in_back", ease_in_back),         ("ease_out_back", ease_out_back),       ("ease_in_out_back", ease_in_out_back),
+        ("ease_in_elastic", ease_in_elastic),   ("ease_out_elastic", ease_out_elastic), ("ease_in_out_elastic", ease_in_out_elastic),
+        ("ease_in_bounce", ease_in_bounce),     ("ease_out_bounce", ease_out_bounce),   ("ease_in_out_bounce", ease_in_out_bounce),
+    ];
+
+    #[test]
+    fn check_endpoints() {
+        for (name, easing) in ALL.iter() {
+            assert!(f32::abs(easing(0.0)) < EPSILON, "{}(0) != 0", name);
+            assert!(f32::abs(easing(1.0) - 1.0) < EPSILON, "{}(1) != 1", name);
+        }
+    }
+
+    #[test]
+    fn check_monotonicity() {
+        let monotonic: [EasingFn; 10] = [
+            linear,
+            ease_in_quad,
+            ease_out_quad,
+            ease_in_out_quad,
+            ease_in_cubic,
+            ease_out_cubic,
+            ease_in_out_cubic,
+            ease_in_sine,
+            ease_out_sine,
+            ease_in_out_sine,
+        ];
+
+        for easing in monotonic.iter() {
+            let mut last = easing(0.0);
+
+            for i in 1..=100 {
+                let current = easing(i as f32 / 100.0);
+                assert!(current >= last);
+                last = current;
+            }
+        }
+    }
+
+    #[test]
+    fn check_sample_clamp() {
+        assert_eq!(sample(linear, -1.0), 0.0);
+        assert_eq!(sample(linear, 2.0), 1.0);
+        assert_eq!(sample(ease_in_quad, 0.5), 0.25);
+    }
+}
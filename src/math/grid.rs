@@ -0,0 +1,214 @@
+use crate::{
+    components::geometry::{Assembly, BorderDecoration, BorderThickness, Geometry, Geometry1DType},
+    misc::color::Rgba,
+    nalgebra::Vector2,
+};
+
+/// A fixed-size grid of cells in `world space`, the cell (0, 0) is at the `origin`.
+///
+/// Cells are addressed by `(col, row)`, column grows along x-axis and row grows along y-axis.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Grid2D<T> {
+    cells: Vec<T>,
+    width: usize,
+    height: usize,
+    cell_size: f32,
+    origin: Vector2<f32>,
+}
+
+impl<T: Clone> Grid2D<T> {
+    /// Create a grid with all cells set to `value`.
+    pub fn new(
+        width: usize,
+        height: usize,
+        cell_size: f32,
+        origin: Vector2<f32>,
+        value: T,
+    ) -> Self {
+        assert!(cell_size > 0.0, "ERR: the cell size must be positive");
+
+        Self {
+            cells: vec![value; width * height],
+            width,
+            height,
+            cell_size,
+            origin,
+        }
+    }
+
+    /// Reset all cells to `value`.
+    pub fn fill(&mut self, value: T) {
+        for cell in self.cells.iter_mut() {
+            *cell = value.clone();
+        }
+    }
+}
+
+impl<T> Grid2D<T> {
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn cell_size(&self) -> f32 {
+        self.cell_size
+    }
+
+    pub fn origin(&self) -> Vector2<f32> {
+        self.origin
+    }
+
+    pub fn in_bounds(&self, col: usize, row: usize) -> bool {
+        col < self.width && row < self.height
+    }
+
+    pub fn get(&self, col: usize, row: usize) -> Option<&T> {
+        if self.in_bounds(col, row) {
+            self.cells.get(row * self.width + col)
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, col: usize, row: usize) -> Option<&mut T> {
+        if self.in_bounds(col, row) {
+            self.cells.get_mut(row * self.width + col)
+        } else {
+            None
+        }
+    }
+
+    /// Set the cell and return the old value, return `None` if the cell is out of bounds.
+    pub fn set(&mut self, col: usize, row: usize, value: T) -> Option<T> {
+        self.get_mut(col, row)
+            .map(|cell| std::mem::replace(cell, value))
+    }
+
+    /// Iterate over all cells with their coordinates.
+    pub fn iter(&self) -> impl Iterator<Item = ((usize, usize), &T)> {
+        let width = self.width;
+
+        self.cells
+            .iter()
+            .enumerate()
+            .map(move |(index, cell)| ((index % width, index / width), cell))
+    }
+
+    /// The cell containing the position, return `None` if the position is out of the grid.
+    pub fn world_to_cell(&self, pos: Vector2<f32>) -> Option<(usize, usize)> {
+        let local = (pos - self.origin) / self.cell_size;
+
+        if local.x < 0.0 || local.y < 0.0 {
+            return None;
+        }
+
+        let (col, row) = (local.x as usize, local.y as usize);
+
+        if self.in_bounds(col, row) {
+            Some((col, row))
+        } else {
+            None
+        }
+    }
+
+    /// The center of the cell in `world space`.
+    pub fn cell_to_world(&self, col: usize, row: usize) -> Vector2<f32> {
+        self.origin + Vector2::new(col as f32 + 0.5, row as f32 + 0.5) * self.cell_size
+    }
+
+    /// The neighbors sharing an edge with the cell, in order of right, up, left and down.
+    pub fn neighbors_4(&self, col: usize, row: usize) -> Vec<(usize, usize)> {
+        self.neighbors(col, row, &[(1, 0), (0, 1), (-1, 0), (0, -1)])
+    }
+
+    /// The neighbors sharing an edge or a corner with the cell.
+    pub fn neighbors_8(&self, col: usize, row: usize) -> Vec<(usize, usize)> {
+        self.neighbors(
+            col,
+            row,
+            &[
+                (1, 0),
+                (1, 1),
+                (0, 1),
+                (-1, 1),
+                (-1, 0),
+                (-1, -1),
+                (0, -1),
+                (1, -1),
+            ],
+        )
+    }
+
+    /// Build the grid lines in `world space` as debug overlay, attach it to an entity with
+    /// `Transform2D::default()`.
+    pub fn render_debug(&self, color: Rgba, thickness: BorderThickness, order: u8) -> Assembly {
+        let size = Vector2::new(self.width as f32, self.height as f32) * self.cell_size;
+
+        let segment = |start: Vector2<f32>, end: Vector2<f32>| {
+            Geometry::new_1d(
+                Geometry1DType::Segment,
+                BorderDecoration::Solid,
+                color,
+                thickness,
+                order,
+                self.origin + start,
+                self.origin + end,
+            )
+        };
+
+        let vertical = (0..=self.width).map(|col| {
+            let x = col as f32 * self.cell_size;
+            segment(Vector2::new(x, 0.0), Vector2::new(x, size.y))
+        });
+        let horizontal = (0..=self.height).map(|row| {
+            let y = row as f32 * self.cell_size;
+            segment(Vector2::new(0.0, y), Vector2::new(size.x, y))
+        });
+
+        vertical.chain(horizontal).collect()
+    }
+
+    fn neighbors(&self, col: usize, row: usize, offsets: &[(isize, isize)]) -> Vec<(usize, usize)> {
+        offsets
+            .iter()
+            .filter_map(|(dx, dy)| {
+                let col = col as isize + dx;
+                let row = row as isize + dy;
+
+                if col >= 0 && row >= 0 && self.in_bounds(col as usize, row as usize) {
+                    Some((col as usize, row as usize))
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Grid2D;
+    use crate::nalgebra::Vector2;
+
+    #[test]
+    fn cell_and_world_conversion() {
+        let mut grid = Grid2D::new(4, 3, 2.0, Vector2::new(-4.0, -3.0), 0u8);
+
+        assert_eq!(grid.world_to_cell(Vector2::new(-4.0, -3.0)), Some((0, 0)));
+        assert_eq!(grid.world_to_cell(Vector2::new(3.9, 2.9)), Some((3, 2)));
+        assert_eq!(grid.world_to_cell(Vector2::new(4.1, 0.0)), None);
+        assert_eq!(grid.world_to_cell(Vector2::new(-4.1, 0.0)), None);
+        assert_eq!(grid.cell_to_world(1, 1), Vector2::new(-1.0, 0.0));
+
+        assert_eq!(grid.set(3, 2, 7), Some(0));
+        assert_eq!(grid.get(3, 2), Some(&7));
+        assert_eq!(grid.set(4, 2, 7), None);
+
+        assert_eq!(grid.neighbors_4(0, 0), vec![(1, 0), (0, 1)]);
+        assert_eq!(grid.neighbors_8(1, 1).len(), 8);
+        assert_eq!(grid.neighbors_8(3, 2).len(), 3);
+    }
+}
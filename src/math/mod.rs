@@ -0,0 +1,5 @@
+pub mod easing;
+pub mod grid;
+pub mod noise;
+pub mod pathfinding;
+pub mod polygon;
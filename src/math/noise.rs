@@ -0,0 +1,240 @@
+//! Gradient noise for procedural generation.
+
+use rand::{rngs::SmallRng, seq::SliceRandom, SeedableRng};
+
+use std::fmt;
+
+// NOTE: the gradients of perlin and simplex noise, 8 directions on the unit circle.
+const GRADIENTS: [(f32, f32); 8] = [
+    (1.0, 0.0),
+    (-1.0, 0.0),
+    (0.0, 1.0),
+    (0.0, -1.0),
+    (
+        std::f32::consts::FRAC_1_SQRT_2,
+        std::f32::consts::FRAC_1_SQRT_2,
+    ),
+    (
+        -std::f32::consts::FRAC_1_SQRT_2,
+        std::f32::consts::FRAC_1_SQRT_2,
+    ),
+    (
+        std::f32::consts::FRAC_1_SQRT_2,
+        -std::f32::consts::FRAC_1_SQRT_2,
+    ),
+    (
+        -std::f32::consts::FRAC_1_SQRT_2,
+        -std::f32::consts::FRAC_1_SQRT_2,
+    ),
+];
+
+// Skew and unskew factors of 2d simplex noise: (sqrt(3) - 1) / 2 and (3 - sqrt(3)) / 6.
+const F2: f32 = 0.366_025_42;
+const G2: f32 = 0.211_324_87;
+
+/// The 2d noise generator, all noise functions return values in [-1, 1].
+///
+/// Insert it to `Resources` to share it between systems.
+#[derive(Clone)]
+pub struct Noise2D {
+    seed: u64,
+    // the permutation of 0..256 repeated twice, so that `perm[i + perm[j]]` never overflows.
+    perm: [u8; 512],
+}
+
+impl Noise2D {
+    pub fn new(seed: u64) -> Self {
+        let mut table: Vec<u8> = (0..=255).collect();
+        table.shuffle(&mut SmallRng::seed_from_u64(seed));
+
+        let mut perm = [0; 512];
+        for (i, p) in perm.iter_mut().enumerate() {
+            *p = table[i & 255];
+        }
+
+        Self { seed, perm }
+    }
+
+    pub fn builder() -> Noise2DBuilder {
+        Noise2DBuilder::new()
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Classic perlin noise, it is 0 at every integer coordinate.
+    pub fn perlin(&self, x: f32, y: f32) -> f32 {
+        let (xf, yf) = (x.floor(), y.floor());
+        let (xi, yi) = (xf as i32 & 255, yf as i32 & 255);
+        let (x, y) = (x - xf, y - yf);
+
+        let n00 = self.gradient_dot(xi, yi, x, y);
+        let n10 = self.gradient_dot(xi + 1, yi, x - 1.0, y);
+        let n01 = self.gradient_dot(xi, yi + 1, x, y - 1.0);
+        let n11 = self.gradient_dot(xi + 1, yi + 1, x - 1.0, y - 1.0);
+
+        let (u, v) = (fade(x), fade(y));
+        let n0 = lerp(n00, n10, u);
+        let n1 = lerp(n01, n11, u);
+
+        // NOTE: the range of 2d perlin noise with unit gradients is [-sqrt(2)/2, sqrt(2)/2].
+        (lerp(n0, n1, v) * std::f32::consts::SQRT_2)
+            .max(-1.0)
+            .min(1.0)
+    }
+
+    /// Simplex noise, faster than perlin noise and has less directional artifacts.
+    pub fn simplex(&self, x: f32, y: f32) -> f32 {
+        let s = (x + y) * F2;
+        let (i, j) = ((x + s).floor(), (y + s).floor());
+
+        let t = (i + j) * G2;
+        let (x0, y0) = (x - (i - t), y - (j - t));
+
+        // the second corner of the triangle the point is in.
+        let (i1, j1) = if x0 > y0 { (1, 0) } else { (0, 1) };
+
+        let (x1, y1) = (x0 - i1 as f32 + G2, y0 - j1 as f32 + G2);
+        let (x2, y2) = (x0 - 1.0 + 2.0 * G2, y0 - 1.0 + 2.0 * G2);
+
+        let (ii, jj) = (i as i32 & 255, j as i32 & 255);
+
+        let n0 = self.simplex_corner(ii, jj, x0, y0);
+        let n1 = self.simplex_corner(ii + i1, jj + j1, x1, y1);
+        let n2 = self.simplex_corner(ii + 1, jj + 1, x2, y2);
+
+        // NOTE: scale the result to about [-1, 1].
+        (70.0 * (n0 + n1 + n2)).max(-1.0).min(1.0)
+    }
+
+    /// Sum `octaves` layers of perlin noise, each layer scales the amplitude by `persistence` and the
+    /// frequency by `lacunarity`.
+    pub fn fractal_brownian_motion(
+        &self,
+        x: f32,
+        y: f32,
+        octaves: u32,
+        persistence: f32,
+        lacunarity: f32,
+    ) -> f32 {
+        let mut sum = 0.0;
+        let mut amplitude = 1.0;
+        let mut frequency = 1.0;
+        let mut max_amplitude = 0.0;
+
+        for _ in 0..octaves {
+            sum += self.perlin(x * frequency, y * frequency) * amplitude;
+            max_amplitude += amplitude;
+
+            amplitude *= persistence;
+            frequency *= lacunarity;
+        }
+
+        if max_amplitude > 0.0 {
+            sum / max_amplitude
+        } else {
+            0.0
+        }
+    }
+
+    fn hash(&self, x: i32, y: i32) -> usize {
+        self.perm[x as usize + self.perm[y as usize] as usize] as usize
+    }
+
+    fn gradient_dot(&self, xi: i32, yi: i32, x: f32, y: f32) -> f32 {
+        let (gx, gy) = GRADIENTS[self.hash(xi, yi) & 7];
+
+        gx * x + gy * y
+    }
+
+    fn simplex_corner(&self, xi: i32, yi: i32, x: f32, y: f32) -> f32 {
+        let t = 0.5 - x * x - y * y;
+
+        if t < 0.0 {
+            0.0
+        } else {
+            let t2 = t * t;
+            t2 * t2 * self.gradient_dot(xi, yi, x, y)
+        }
+    }
+}
+
+impl Default for Noise2D {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl fmt::Debug for Noise2D {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Noise2D").field("seed", &self.seed).finish()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Noise2DBuilder {
+    seed: u64,
+}
+
+impl Noise2DBuilder {
+    pub fn new() -> Self {
+        Self { seed: 0 }
+    }
+
+    /// Set the seed to shuffle the permutation table.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+
+        self
+    }
+
+    pub fn build(self) -> Noise2D {
+        Noise2D::new(self.seed)
+    }
+}
+
+// quintic smooth step: 6t^5 - 15t^4 + 10t^3.
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(a: f32, b: f32, t: f32) -> f32 {
+    a + (b - a) * t
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Noise2D;
+
+    #[test]
+    fn noise_in_range() {
+        let noise = Noise2D::builder().seed(7).build();
+
+        for i in 0..64 {
+            for j in 0..64 {
+                let (x, y) = (i as f32 * 0.37 - 10.0, j as f32 * 0.53 - 10.0);
+
+                for value in [
+                    noise.perlin(x, y),
+                    noise.simplex(x, y),
+                    noise.fractal_brownian_motion(x, y, 4, 0.5, 2.0),
+                ]
+                .iter()
+                {
+                    assert!((-1.0..=1.0).contains(value));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn noise_is_deterministic() {
+        let a = Noise2D::new(1);
+        let b = Noise2D::new(1);
+
+        assert_eq!(a.perlin(1.5, 2.5), b.perlin(1.5, 2.5));
+        assert_eq!(a.simplex(1.5, 2.5), b.simplex(1.5, 2.5));
+        assert_eq!(a.perlin(3.0, 4.0), 0.0);
+    }
+}
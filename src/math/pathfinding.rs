@@ -0,0 +1,205 @@
+//! A* pathfinding on `Grid2D`.
+
+use crate::math::grid::Grid2D;
+
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, HashMap},
+};
+
+/// Which neighbors of a cell can be walked to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Connectivity {
+    /// Walk to the neighbors sharing an edge.
+    Four,
+    /// Walk to the neighbors sharing an edge or a corner, diagonal moves cost `sqrt(2)` times more.
+    Eight,
+}
+
+/// Find the cheapest 4-directional path from `start` to `goal`, both ends are included.
+///
+/// `cost` returns the cost to enter a cell, `None` means the cell is impassable. The cost should be
+/// at least 1.0, otherwise the path found may be not the cheapest.
+pub fn astar<T, F>(
+    grid: &Grid2D<T>,
+    start: (usize, usize),
+    goal: (usize, usize),
+    cost: F,
+) -> Option<Vec<(usize, usize)>>
+where
+    F: Fn(&T) -> Option<f32>,
+{
+    astar_with(grid, start, goal, cost, Connectivity::Four)
+}
+
+/// Find the cheapest 8-directional path from `start` to `goal`, see `astar`.
+pub fn astar_8<T, F>(
+    grid: &Grid2D<T>,
+    start: (usize, usize),
+    goal: (usize, usize),
+    cost: F,
+) -> Option<Vec<(usize, usize)>>
+where
+    F: Fn(&T) -> Option<f32>,
+{
+    astar_with(grid, start, goal, cost, Connectivity::Eight)
+}
+
+pub fn astar_with<T, F>(
+    grid: &Grid2D<T>,
+    start: (usize, usize),
+    goal: (usize, usize),
+    cost: F,
+    connectivity: Connectivity,
+) -> Option<Vec<(usize, usize)>>
+where
+    F: Fn(&T) -> Option<f32>,
+{
+    let passable = |cell: (usize, usize)| grid.get(cell.0, cell.1).and_then(&cost);
+
+    passable(start)?;
+    passable(goal)?;
+
+    let heuristic = |cell: (usize, usize)| {
+        let dx = (cell.0 as f32 - goal.0 as f32).abs();
+        let dy = (cell.1 as f32 - goal.1 as f32).abs();
+
+        match connectivity {
+            Connectivity::Four => dx + dy,
+            // octile distance
+            Connectivity::Eight => dx.max(dy) + (std::f32::consts::SQRT_2 - 1.0) * dx.min(dy),
+        }
+    };
+
+    let mut open = BinaryHeap::new();
+    let mut came_from: HashMap<(usize, usize), (usize, usize)> = HashMap::new();
+    let mut g_score: HashMap<(usize, usize), f32> = HashMap::new();
+
+    g_score.insert(start, 0.0);
+    open.push(OpenNode {
+        f: heuristic(start),
+        cell: start,
+    });
+
+    while let Some(OpenNode { f, cell }) = open.pop() {
+        if cell == goal {
+            let mut path = vec![goal];
+            let mut current = goal;
+
+            while let Some(&previous) = came_from.get(&current) {
+                path.push(previous);
+                current = previous;
+            }
+            path.reverse();
+
+            return Some(path);
+        }
+
+        let g = g_score[&cell];
+
+        // NOTE: skip the stale node, a cheaper one has been visited.
+        if f > g + heuristic(cell) {
+            continue;
+        }
+
+        let neighbors = match connectivity {
+            Connectivity::Four => grid.neighbors_4(cell.0, cell.1),
+            Connectivity::Eight => grid.neighbors_8(cell.0, cell.1),
+        };
+
+        for neighbor in neighbors {
+            let step = match passable(neighbor) {
+                Some(step) => step,
+                None => continue,
+            };
+
+            let diagonal = neighbor.0 != cell.0 && neighbor.1 != cell.1;
+            let tentative = if diagonal {
+                // NOTE: don't cut the corner of impassable cells.
+                if passable((neighbor.0, cell.1)).is_none()
+                    || passable((cell.0, neighbor.1)).is_none()
+                {
+                    continue;
+                }
+
+                g + step * std::f32::consts::SQRT_2
+            } else {
+                g + step
+            };
+
+            if g_score.get(&neighbor).map_or(true, |&old| tentative < old) {
+                g_score.insert(neighbor, tentative);
+                came_from.insert(neighbor, cell);
+                open.push(OpenNode {
+                    f: tentative + heuristic(neighbor),
+                    cell: neighbor,
+                });
+            }
+        }
+    }
+
+    None
+}
+
+/// The node in the open set, ordered by the lowest `f` first.
+struct OpenNode {
+    f: f32,
+    cell: (usize, usize),
+}
+
+impl PartialEq for OpenNode {
+    fn eq(&self, other: &Self) -> bool {
+        self.f == other.f
+    }
+}
+
+impl Eq for OpenNode {}
+
+impl PartialOrd for OpenNode {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for OpenNode {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.f.partial_cmp(&self.f).unwrap_or(Ordering::Equal)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nalgebra::Vector2;
+
+    #[test]
+    fn find_path_around_wall() {
+        // . . . .
+        // . # # .
+        // . . # .
+        let mut grid = Grid2D::new(4, 3, 1.0, Vector2::zeros(), true);
+        grid.set(1, 1, false);
+        grid.set(2, 1, false);
+        grid.set(2, 0, false);
+
+        let cost = |walkable: &bool| if *walkable { Some(1.0) } else { None };
+
+        let path = astar(&grid, (0, 0), (3, 0), cost).unwrap();
+        assert_eq!(path.first(), Some(&(0, 0)));
+        assert_eq!(path.last(), Some(&(3, 0)));
+        assert_eq!(path.len(), 8);
+
+        grid.set(3, 1, false);
+        grid.set(2, 2, false);
+        assert_eq!(astar(&grid, (0, 0), (3, 0), cost), None);
+    }
+
+    #[test]
+    fn diagonal_path_is_shorter() {
+        let grid = Grid2D::new(4, 3, 1.0, Vector2::zeros(), ());
+        let cost = |_: &()| Some(1.0);
+
+        assert_eq!(astar(&grid, (0, 0), (3, 2), cost).unwrap().len(), 6);
+        assert_eq!(astar_8(&grid, (0, 0), (3, 2), cost).unwrap().len(), 4);
+    }
+}
@@ -0,0 +1,688 @@
+use crate::{
+    components::geometry::{Assembly, BorderDecoration, BorderThickness, Geometry, Geometry1DType},
+    misc::color::Rgba,
+    nalgebra::Vector2,
+};
+
+use std::{error::Error, fmt};
+
+/// Why the vertices can't make a `Polygon2D`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PolygonError {
+    /// A polygon needs at least 3 vertices, with the count of vertices.
+    TooFewVertices(usize),
+    /// The vertices are clockwise.
+    Clockwise,
+    /// Two edges which are not adjacent intersect.
+    SelfIntersecting,
+    /// A boolean operation meets a vertex on the boundary of the other polygon or two overlapping edges,
+    /// where it can't tell whether the boundaries cross.
+    DegenerateBoolean,
+}
+
+impl fmt::Display for PolygonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PolygonError::TooFewVertices(count) => {
+                write!(f, "a polygon needs at least 3 vertices, got {}", count)
+            }
+            PolygonError::Clockwise => write!(f, "the vertices are not counter-clockwise"),
+            PolygonError::SelfIntersecting => write!(f, "the edges intersect"),
+            PolygonError::DegenerateBoolean => write!(
+                f,
+                "a vertex is on the boundary of the other polygon or the edges overlap"
+            ),
+        }
+    }
+}
+
+impl Error for PolygonError {}
+
+/// An arbitrary simple polygon in `local space`, for the shapes not covered by `Geometry2DType`.
+///
+/// The vertices should be counter-clockwise, the polygon may be concave.
+///
+/// NOTE: `Geometry` is drawn by the distance function of built-in shapes, so the polygon is rendered as
+/// its outline by `Polygon2D::to_assembly`, the triangles are for the systems which need the inner area.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Polygon2D {
+    vertices: Vec<Vector2<f32>>,
+}
+
+impl Polygon2D {
+    /// Create the polygon, fails if there are less than 3 vertices, the vertices are clockwise or the edges
+    /// intersect.
+    pub fn new(vertices: Vec<Vector2<f32>>) -> Result<Self, PolygonError> {
+        if vertices.len() < 3 {
+            return Err(PolygonError::TooFewVertices(vertices.len()));
+        }
+
+        let polygon = Self { vertices };
+
+        if !polygon.is_ccw() {
+            Err(PolygonError::Clockwise)
+        } else if polygon.is_self_intersecting() {
+            Err(PolygonError::SelfIntersecting)
+        } else {
+            Ok(polygon)
+        }
+    }
+
+    pub fn vertices(&self) -> &[Vector2<f32>] {
+        &self.vertices
+    }
+
+    /// Positive if the vertices are counter-clockwise.
+    pub fn signed_area(&self) -> f32 {
+        self.edges().map(|(a, b)| a.perp(&b)).sum::<f32>() / 2.0
+    }
+
+    pub fn area(&self) -> f32 {
+        self.signed_area().abs()
+    }
+
+    pub fn perimeter(&self) -> f32 {
+        self.edges().map(|(a, b)| (b - a).norm()).sum()
+    }
+
+    pub fn is_ccw(&self) -> bool {
+        self.signed_area() > 0.0
+    }
+
+    /// Whether all corners turn to the same side, collinear vertices are allowed.
+    pub fn is_convex(&self) -> bool {
+        let n = self.vertices.len();
+        let (mut left, mut right) = (false, false);
+
+        for i in 0..n {
+            let (a, b, c) = (
+                self.vertices[i],
+                self.vertices[(i + 1) % n],
+                self.vertices[(i + 2) % n],
+            );
+            let turn = (b - a).perp(&(c - b));
+
+            left |= turn > 0.0;
+            right |= turn < 0.0;
+        }
+
+        // NOTE: a star turns to the same side at all corners, but its edges intersect.
+        !(left && right) && !self.is_self_intersecting()
+    }
+
+    /// Whether any two edges which are not adjacent intersect.
+    pub fn is_self_intersecting(&self) -> bool {
+        let edges = self.edges().collect::<Vec<_>>();
+        let n = edges.len();
+
+        (0..n).any(|i| {
+            (i + 1..n)
+                .filter(|&j| j != i + 1 && (j + 1) % n != i)
+                .any(|j| segments_intersect(edges[i], edges[j]))
+        })
+    }
+
+    /// Triangulate the polygon by ear clipping, return the indices of the vertices of triangles.
+    ///
+    /// The triangles are counter-clockwise even if the vertices are not.
+    pub fn triangulate(&self) -> Vec<[usize; 3]> {
+        let mut indices = (0..self.vertices.len()).collect::<Vec<_>>();
+        if !self.is_ccw() {
+            indices.reverse();
+        }
+
+        let mut triangles = Vec::with_capacity(indices.len() - 2);
+        let (mut i, mut tries) = (0, 0);
+
+        while indices.len() > 3 {
+            let len = indices.len();
+            i %= len;
+
+            let (prev, curr, next) = (
+                indices[(i + len - 1) % len],
+                indices[i],
+                indices[(i + 1) % len],
+            );
+
+            // NOTE: a self-intersecting polygon may have no ear, clip the corner anyway to terminate.
+            if self.is_ear(prev, curr, next, &indices) || tries >= len {
+                triangles.push([prev, curr, next]);
+                indices.remove(i);
+                tries = 0;
+            } else {
+                i += 1;
+                tries += 1;
+            }
+        }
+        triangles.push([indices[0], indices[1], indices[2]]);
+
+        triangles
+    }
+
+    /// Whether the point is inside the polygon by the even-odd rule, the points on the edges may be either.
+    pub fn contains(&self, point: Vector2<f32>) -> bool {
+        self.edges()
+            .filter(|(a, b)| {
+                (a.y > point.y) != (b.y > point.y)
+                    && point.x < a.x + (point.y - a.y) / (b.y - a.y) * (b.x - a.x)
+            })
+            .count()
+            % 2
+            == 1
+    }
+
+    /// The parts inside both polygons.
+    ///
+    /// Fails with `PolygonError::DegenerateBoolean` if a vertex is on the boundary of the other polygon or
+    /// two edges overlap, the same for `Polygon2D::union` and `Polygon2D::difference`.
+    pub fn intersection(&self, other: &Polygon2D) -> Result<Vec<Polygon2D>, PolygonError> {
+        boolean(self, other, BooleanOp::Intersection)
+    }
+
+    /// The outlines of both polygons merged, the holes enclosed by them are clockwise.
+    pub fn union(&self, other: &Polygon2D) -> Result<Vec<Polygon2D>, PolygonError> {
+        boolean(self, other, BooleanOp::Union)
+    }
+
+    /// The parts of `self` outside `other`, the hole cut by `other` inside `self` is clockwise.
+    pub fn difference(&self, other: &Polygon2D) -> Result<Vec<Polygon2D>, PolygonError> {
+        boolean(self, other, BooleanOp::Difference)
+    }
+
+    /// The outline as closed segments.
+    pub fn to_assembly(&self, color: Rgba, thickness: BorderThickness, order: u8) -> Assembly {
+        self.edges()
+            .map(|(a, b)| {
+                Geometry::new_1d(
+                    Geometry1DType::Segment,
+                    BorderDecoration::Solid,
+                    color,
+                    thickness,
+                    order,
+                    a,
+                    b,
+                )
+            })
+            .collect()
+    }
+
+    fn to_ccw(&self) -> Self {
+        let mut vertices = self.vertices.clone();
+        if !self.is_ccw() {
+            vertices.reverse();
+        }
+
+        Self { vertices }
+    }
+
+    fn reversed(&self) -> Self {
+        Self {
+            vertices: self.vertices.iter().rev().copied().collect(),
+        }
+    }
+
+    fn edges(&self) -> impl Iterator<Item = (Vector2<f32>, Vector2<f32>)> + '_ {
+        let n = self.vertices.len();
+        (0..n).map(move |i| (self.vertices[i], self.vertices[(i + 1) % n]))
+    }
+
+    fn is_ear(&self, prev: usize, curr: usize, next: usize, indices: &[usize]) -> bool {
+        let (a, b, c) = (
+            self.vertices[prev],
+            self.vertices[curr],
+            self.vertices[next],
+        );
+
+        if (b - a).perp(&(c - b)) <= 0.0 {
+            return false;
+        }
+
+        indices
+            .iter()
+            .filter(|&&index| index != prev && index != curr && index != next)
+            .all(|&index| !point_in_triangle(self.vertices[index], a, b, c))
+    }
+}
+
+// The triangle `abc` is counter-clockwise, the points on the edges are inside.
+fn point_in_triangle(p: Vector2<f32>, a: Vector2<f32>, b: Vector2<f32>, c: Vector2<f32>) -> bool {
+    (b - a).perp(&(p - a)) >= 0.0 && (c - b).perp(&(p - b)) >= 0.0 && (a - c).perp(&(p - c)) >= 0.0
+}
+
+fn segments_intersect(
+    (a, b): (Vector2<f32>, Vector2<f32>),
+    (c, d): (Vector2<f32>, Vector2<f32>),
+) -> bool {
+    let side = |p: Vector2<f32>, q: Vector2<f32>, r: Vector2<f32>| (q - p).perp(&(r - p));
+
+    let (d1, d2) = (side(c, d, a), side(c, d, b));
+    let (d3, d4) = (side(a, b, c), side(a, b, d));
+
+    d1 * d2 < 0.0 && d3 * d4 < 0.0
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BooleanOp {
+    Intersection,
+    Union,
+    Difference,
+}
+
+/// The relative tolerance of intersections at the vertices.
+const DEGENERATE_EPSILON: f32 = 1e-5;
+
+// A crossing of the edge `s_edge` of subject and the edge `c_edge` of clip, at `alpha`s along the edges.
+#[derive(Debug, Clone, Copy)]
+struct Crossing {
+    s_edge: usize,
+    s_alpha: f32,
+    c_edge: usize,
+    c_alpha: f32,
+    point: Vector2<f32>,
+}
+
+// A vertex or an intersection of the linked lists of Greiner-Hormann.
+#[derive(Debug, Clone, Copy)]
+struct Node {
+    point: Vector2<f32>,
+    next: usize,
+    prev: usize,
+    // The same intersection in the list of the other polygon.
+    neighbor: Option<usize>,
+    entry: bool,
+    visited: bool,
+}
+
+// The Greiner-Hormann clipping, both polygons may be concave.
+fn boolean(
+    subject: &Polygon2D,
+    clip: &Polygon2D,
+    op: BooleanOp,
+) -> Result<Vec<Polygon2D>, PolygonError> {
+    let subject = subject.to_ccw();
+    let clip = clip.to_ccw();
+
+    let extent = subject
+        .vertices
+        .iter()
+        .chain(clip.vertices.iter())
+        .fold(0f32, |extent, v| extent.max(v.x.abs()).max(v.y.abs()))
+        .max(1.0);
+
+    // NOTE: the algorithm can't tell whether a vertex on the edge of the other polygon enters or exits.
+    let crossings = find_crossings(&subject, &clip).ok_or(PolygonError::DegenerateBoolean)?;
+
+    if crossings.is_empty() {
+        let subject_in_clip = clip.contains(subject.vertices[0]);
+        let clip_in_subject = subject.contains(clip.vertices[0]);
+
+        return Ok(match op {
+            BooleanOp::Intersection if subject_in_clip => vec![subject],
+            BooleanOp::Intersection if clip_in_subject => vec![clip],
+            BooleanOp::Intersection => vec![],
+            BooleanOp::Union if subject_in_clip => vec![clip],
+            BooleanOp::Union if clip_in_subject => vec![subject],
+            BooleanOp::Union => vec![subject, clip],
+            BooleanOp::Difference if subject_in_clip => vec![],
+            BooleanOp::Difference if clip_in_subject => vec![subject, clip.reversed()],
+            BooleanOp::Difference => vec![subject],
+        });
+    }
+
+    let mut nodes =
+        Vec::with_capacity(subject.vertices.len() + clip.vertices.len() + crossings.len() * 2);
+    let mut s_nodes = vec![0; crossings.len()];
+    let mut c_nodes = vec![0; crossings.len()];
+
+    let s_list = build_list(&subject, &crossings, &mut nodes, &mut s_nodes, |c| {
+        (c.s_edge, c.s_alpha)
+    });
+    let c_list = build_list(&clip, &crossings, &mut nodes, &mut c_nodes, |c| {
+        (c.c_edge, c.c_alpha)
+    });
+
+    for (&s, &c) in s_nodes.iter().zip(c_nodes.iter()) {
+        nodes[s].neighbor = Some(c);
+        nodes[c].neighbor = Some(s);
+    }
+
+    // NOTE: travel forward from the entries for intersection, the result of union and difference is the
+    // intersection with the outside of polygons, so the flags are inverted.
+    let (s_forward, c_forward) = match op {
+        BooleanOp::Intersection => (true, true),
+        BooleanOp::Union => (false, false),
+        BooleanOp::Difference => (false, true),
+    };
+    mark_entries(
+        &mut nodes,
+        &s_list,
+        clip.contains(subject.vertices[0]),
+        s_forward,
+    );
+    mark_entries(
+        &mut nodes,
+        &c_list,
+        subject.contains(clip.vertices[0]),
+        c_forward,
+    );
+
+    let mut polygons = Vec::new();
+
+    // NOTE: start from the subject travelling forward, so the results are counter-clockwise.
+    for &start in s_nodes.iter() {
+        if nodes[start].visited || !nodes[start].entry {
+            continue;
+        }
+
+        let mut current = start;
+        let mut vertices = vec![nodes[start].point];
+
+        // NOTE: the guard stops the walk if the lists are broken by the precision.
+        for _ in 0..nodes.len() {
+            let forward = nodes[current].entry;
+            nodes[current].visited = true;
+
+            loop {
+                current = if forward {
+                    nodes[current].next
+                } else {
+                    nodes[current].prev
+                };
+                vertices.push(nodes[current].point);
+
+                if nodes[current].neighbor.is_some() {
+                    break;
+                }
+            }
+
+            nodes[current].visited = true;
+            let neighbor = nodes[current].neighbor.unwrap();
+
+            if current == start || neighbor == start {
+                break;
+            }
+            current = neighbor;
+        }
+
+        // the walk ends at the start.
+        vertices.pop();
+        vertices.dedup_by(|a, b| (*a - *b).norm() <= f32::EPSILON * extent);
+
+        if vertices.len() >= 3 {
+            polygons.push(Polygon2D { vertices });
+        }
+    }
+
+    Ok(polygons)
+}
+
+// Find the crossings of edges, `None` if any intersection is at a vertex or any edges overlap.
+fn find_crossings(subject: &Polygon2D, clip: &Polygon2D) -> Option<Vec<Crossing>> {
+    let mut crossings = Vec::new();
+
+    for (s_edge, (a, b)) in subject.edges().enumerate() {
+        for (c_edge, (c, d)) in clip.edges().enumerate() {
+            let (r, q) = (b - a, d - c);
+            let denominator = r.perp(&q);
+
+            if denominator.abs() <= DEGENERATE_EPSILON * r.norm() * q.norm() {
+                // parallel edges, degenerate if they are on the same line and overlap.
+                let r2 = r.norm_squared().max(f32::EPSILON);
+                if (c - a).perp(&r).abs() <= DEGENERATE_EPSILON * r2 {
+                    let (t0, t1) = ((c - a).dot(&r) / r2, (d - a).dot(&r) / r2);
+                    if t0.min(t1) <= 1.0 && t0.max(t1) >= 0.0 {
+                        return None;
+                    }
+                }
+                continue;
+            }
+
+            let s_alpha = (c - a).perp(&q) / denominator;
+            let c_alpha = (c - a).perp(&r) / denominator;
+
+            let range = -DEGENERATE_EPSILON..=1.0 + DEGENERATE_EPSILON;
+            if !range.contains(&s_alpha) || !range.contains(&c_alpha) {
+                continue;
+            }
+
+            let at_vertex =
+                |alpha: f32| alpha <= DEGENERATE_EPSILON || alpha >= 1.0 - DEGENERATE_EPSILON;
+            if at_vertex(s_alpha) || at_vertex(c_alpha) {
+                return None;
+            }
+
+            crossings.push(Crossing {
+                s_edge,
+                s_alpha,
+                c_edge,
+                c_alpha,
+                point: a + r * s_alpha,
+            });
+        }
+    }
+
+    Some(crossings)
+}
+
+// Push the vertices and the crossings sorted along the edges to `nodes` as a ring, return the indices of
+// the ring in order, `crossing_nodes` maps the crossings to their nodes.
+fn build_list<F>(
+    polygon: &Polygon2D,
+    crossings: &[Crossing],
+    nodes: &mut Vec<Node>,
+    crossing_nodes: &mut [usize],
+    edge_alpha: F,
+) -> Vec<usize>
+where
+    F: Fn(&Crossing) -> (usize, f32),
+{
+    let mut list = Vec::new();
+    let node = |point| Node {
+        point,
+        next: 0,
+        prev: 0,
+        neighbor: None,
+        entry: false,
+        visited: false,
+    };
+
+    for (edge, &vertex) in polygon.vertices.iter().enumerate() {
+        list.push(nodes.len());
+        nodes.push(node(vertex));
+
+        let mut on_edge = (0..crossings.len())
+            .filter(|&i| edge_alpha(&crossings[i]).0 == edge)
+            .collect::<Vec<_>>();
+        on_edge.sort_by(|&i, &j| {
+            edge_alpha(&crossings[i])
+                .1
+                .partial_cmp(&edge_alpha(&crossings[j]).1)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+
+        for i in on_edge {
+            crossing_nodes[i] = nodes.len();
+            list.push(nodes.len());
+            nodes.push(node(crossings[i].point));
+        }
+    }
+
+    let len = list.len();
+    for (i, &index) in list.iter().enumerate() {
+        nodes[index].next = list[(i + 1) % len];
+        nodes[index].prev = list[(i + len - 1) % len];
+    }
+
+    list
+}
+
+// Mark the crossings where the polygon enters the other one, `inside` is whether the first vertex is
+// inside the other polygon.
+fn mark_entries(nodes: &mut [Node], list: &[usize], mut inside: bool, forward: bool) {
+    for &index in list {
+        if nodes[index].neighbor.is_some() {
+            nodes[index].entry = !inside ^ !forward;
+            inside = !inside;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn concave_polygon() {
+        // an arrow pointing up, the notch at the bottom makes it concave.
+        let polygon = Polygon2D::new(vec![
+            Vector2::new(0.0, 2.0),
+            Vector2::new(-1.0, 0.0),
+            Vector2::new(0.0, 1.0),
+            Vector2::new(1.0, 0.0),
+        ])
+        .unwrap();
+
+        assert!(polygon.is_ccw());
+        assert!(!polygon.is_convex());
+        assert!(!polygon.is_self_intersecting());
+        assert_eq!(polygon.area(), 1.0);
+
+        let triangles = polygon.triangulate();
+        assert_eq!(triangles.len(), 2);
+
+        let area = triangles
+            .iter()
+            .map(|&[a, b, c]| {
+                let v = polygon.vertices();
+                (v[b] - v[a]).perp(&(v[c] - v[a])) / 2.0
+            })
+            .sum::<f32>();
+        assert_eq!(area, polygon.area());
+    }
+
+    #[test]
+    fn self_intersecting_polygon() {
+        let vertices = vec![
+            Vector2::new(0.0, 0.0),
+            Vector2::new(1.0, 1.0),
+            Vector2::new(1.0, 0.0),
+            Vector2::new(0.0, 1.0),
+        ];
+        assert_eq!(
+            Polygon2D::new(vertices.clone()),
+            Err(PolygonError::SelfIntersecting)
+        );
+
+        let bowtie = Polygon2D { vertices };
+        assert!(bowtie.is_self_intersecting());
+        assert!(!bowtie.is_convex());
+        assert_eq!(bowtie.triangulate().len(), 2);
+
+        let square = Polygon2D::new(vec![
+            Vector2::new(0.0, 0.0),
+            Vector2::new(1.0, 0.0),
+            Vector2::new(1.0, 1.0),
+            Vector2::new(0.0, 1.0),
+        ])
+        .unwrap();
+        assert!(square.is_convex());
+    }
+
+    #[test]
+    fn reject_invalid_vertices() {
+        assert_eq!(
+            Polygon2D::new(vec![Vector2::new(0.0, 0.0), Vector2::new(1.0, 0.0)]),
+            Err(PolygonError::TooFewVertices(2))
+        );
+        assert_eq!(
+            Polygon2D::new(vec![
+                Vector2::new(0.0, 0.0),
+                Vector2::new(0.0, 1.0),
+                Vector2::new(1.0, 0.0),
+            ]),
+            Err(PolygonError::Clockwise)
+        );
+    }
+
+    fn square(x: f32, y: f32, side: f32) -> Polygon2D {
+        Polygon2D::new(vec![
+            Vector2::new(x, y),
+            Vector2::new(x + side, y),
+            Vector2::new(x + side, y + side),
+            Vector2::new(x, y + side),
+        ])
+        .unwrap()
+    }
+
+    // The area of the polygons, the clockwise holes are subtracted.
+    fn total_area(polygons: &[Polygon2D]) -> f32 {
+        polygons.iter().map(|polygon| polygon.signed_area()).sum()
+    }
+
+    #[test]
+    fn boolean_of_overlapping_squares() {
+        let (a, b) = (square(0.0, 0.0, 2.0), square(1.0, 1.0, 2.0));
+
+        let intersection = a.intersection(&b).unwrap();
+        assert_eq!(intersection.len(), 1);
+        assert!((total_area(&intersection) - 1.0).abs() < 1e-4);
+
+        let union = a.union(&b).unwrap();
+        assert_eq!(union.len(), 1);
+        assert!((total_area(&union) - 7.0).abs() < 1e-4);
+
+        let difference = a.difference(&b).unwrap();
+        assert_eq!(difference.len(), 1);
+        assert!((total_area(&difference) - 3.0).abs() < 1e-4);
+        assert_eq!(difference[0].vertices().len(), 6);
+    }
+
+    #[test]
+    fn boolean_of_disjoint_and_nested_squares() {
+        let (outer, inner, far) = (
+            square(0.0, 0.0, 4.0),
+            square(1.0, 1.0, 1.0),
+            square(10.0, 0.0, 1.0),
+        );
+
+        assert!(outer.intersection(&far).unwrap().is_empty());
+        assert_eq!(outer.union(&far).unwrap().len(), 2);
+        assert_eq!(outer.difference(&far), Ok(vec![outer.clone()]));
+
+        assert_eq!(outer.intersection(&inner), Ok(vec![inner.clone()]));
+        assert_eq!(inner.union(&outer), Ok(vec![outer.clone()]));
+        assert!(inner.difference(&outer).unwrap().is_empty());
+
+        let ring = outer.difference(&inner).unwrap();
+        assert_eq!(ring.len(), 2);
+        assert!(!ring[1].is_ccw());
+        assert!((total_area(&ring) - 15.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn reject_degenerate_boolean() {
+        let outer = square(0.0, 0.0, 4.0);
+
+        // the edges overlap.
+        assert_eq!(
+            outer.intersection(&outer),
+            Err(PolygonError::DegenerateBoolean)
+        );
+        assert_eq!(
+            outer.union(&square(4.0, 1.0, 1.0)),
+            Err(PolygonError::DegenerateBoolean)
+        );
+
+        // a vertex of the triangle is on the edge of the square.
+        let triangle = Polygon2D::new(vec![
+            Vector2::new(2.0, 4.0),
+            Vector2::new(3.0, 6.0),
+            Vector2::new(1.0, 6.0),
+        ])
+        .unwrap();
+        assert_eq!(
+            outer.difference(&triangle),
+            Err(PolygonError::DegenerateBoolean)
+        );
+    }
+}
@@ -1,7 +1,9 @@
+use std::fmt;
+
 pub type Hex = u32;
 
 #[repr(C)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Clone, Copy, PartialEq, Eq)]
 pub struct Rgba {
     pub r: u8,
     pub g: u8,
@@ -46,6 +48,32 @@ impl Rgba {
         u32::from_be_bytes([self.r, self.g, self.b, self.a])
     }
 
+    /// `[r, g, b, a]`, the layout of 8-bit RGBA images.
+    pub const fn to_u8_array(&self) -> [u8; 4] {
+        [self.r, self.g, self.b, self.a]
+    }
+
+    pub const fn from_u8_array(rgba: [u8; 4]) -> Self {
+        Self::new(rgba[0], rgba[1], rgba[2], rgba[3])
+    }
+
+    /// `0xAARRGGBB`, the layout used by most UI libraries.
+    pub const fn to_u32_argb(&self) -> u32 {
+        u32::from_be_bytes([self.a, self.r, self.g, self.b])
+    }
+
+    pub const fn from_u32_argb(argb: u32) -> Self {
+        let argb = argb.to_be_bytes();
+
+        Self::new(argb[1], argb[2], argb[3], argb[0])
+    }
+
+    /// `0xRRGGBBAA`, the same as `Rgba::to_hex`.
+    pub const fn to_u32_rgba(&self) -> u32 {
+        self.to_hex()
+    }
+
+    #[cfg(not(feature = "headless"))]
     pub(crate) fn to_wgpu_color(&self) -> wgpu::Color {
         wgpu::Color {
             r: self.r as f64 / 255.0,
@@ -56,6 +84,20 @@ impl Rgba {
     }
 }
 
+/// `#RRGGBBAA`.
+impl fmt::Display for Rgba {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{:08X}", self.to_hex())
+    }
+}
+
+/// `Rgba(#RRGGBBAA)`.
+impl fmt::Debug for Rgba {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Rgba({})", self)
+    }
+}
+
 impl Default for Rgba {
     fn default() -> Self {
         Self::SOFT_BLACK
@@ -78,4 +120,19 @@ mod test {
         assert_eq!(size, 4);
         assert_eq!(align, 1);
     }
+
+    #[test]
+    fn check_formats() {
+        let color = Rgba::new(0x12, 0x34, 0xAB, 0xFF);
+
+        assert_eq!(format!("{}", color), "#1234ABFF");
+        assert_eq!(format!("{:?}", color), "Rgba(#1234ABFF)");
+
+        assert_eq!(color.to_u8_array(), [0x12, 0x34, 0xAB, 0xFF]);
+        assert_eq!(Rgba::from_u8_array(color.to_u8_array()), color);
+
+        assert_eq!(color.to_u32_argb(), 0xFF1234AB);
+        assert_eq!(color.to_u32_rgba(), 0x1234ABFF);
+        assert_eq!(Rgba::from_u32_argb(0xFF1234AB), color);
+    }
 }
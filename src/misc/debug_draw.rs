@@ -0,0 +1,282 @@
+use crate::{
+    components::{
+        geometry::{
+            Assembly, BorderDecoration, BorderThickness, Geometry, Geometry1DType, Geometry2DType,
+            InnerDecoration,
+        },
+        sprite::Sprite2D,
+        text::{FontAtlas, FontHandle, Text2D, TextAlign},
+    },
+    misc::color::Rgba,
+    nalgebra::Vector2,
+};
+
+/// The thickness of debug shapes in `screen space`.
+const DEBUG_THICKNESS: f32 = 2.0;
+/// Debug shapes are drawn on top of everything.
+const DEBUG_ORDER: u8 = u8::MAX;
+/// The height of labels in `world space` if the size isn't set by `DebugDraw::set_font`.
+const DEBUG_TEXT_SIZE: f32 = 16.0;
+
+/// A label drawn by `DebugDraw::text`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DebugText {
+    /// The position in `world space`.
+    pub position: Vector2<f32>,
+    pub label: String,
+    pub color: Rgba,
+}
+
+/// Immediate-mode drawing of lines and shapes in `world space`, useful to visualize AABBs, raycasts,
+/// paths and colliders.
+///
+/// Commands live for one frame by default, the render stage draws them over all entities at the end
+/// of the frame then drops the expired ones.
+///
+/// At most `DebugDraw::MAX_SHAPE_COUNT` shapes and `DebugDraw::MAX_TEXT_COUNT` labels are kept, the
+/// commands over the limits are dropped.
+#[derive(Debug, Default)]
+pub struct DebugDraw {
+    // (geometry, frames left)
+    shapes: Vec<(Geometry, u32)>,
+    // (text, frames left)
+    texts: Vec<(DebugText, u32)>,
+    // The font and the size of labels.
+    font: Option<(FontHandle, f32)>,
+
+    // The duration of the next command.
+    next_duration: Option<u32>,
+}
+
+impl DebugDraw {
+    pub const MAX_SHAPE_COUNT: usize = 64 * 1024;
+    pub const MAX_TEXT_COUNT: usize = 1024;
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Draw the labels with the font in `FontAtlas`, `size` is the height of a line in `world space`.
+    ///
+    /// If not set, the first font in `FontAtlas` is used.
+    pub fn set_font(&mut self, font: FontHandle, size: f32) {
+        self.font = Some((font, size));
+    }
+
+    /// Keep the next command for `frames` frames, e.g. `debug_draw.with_duration(60).line(..)`.
+    pub fn with_duration(&mut self, frames: u32) -> &mut Self {
+        self.next_duration = Some(frames.max(1));
+        self
+    }
+
+    pub fn line(&mut self, start: Vector2<f32>, end: Vector2<f32>, color: Rgba) {
+        let segment = Self::segment(start, end, color);
+        let frames = self.take_duration();
+
+        self.push_shape(segment, frames);
+    }
+
+    pub fn rect(&mut self, min: Vector2<f32>, max: Vector2<f32>, color: Rgba) {
+        let frames = self.take_duration();
+
+        let corners = [
+            min,
+            Vector2::new(max.x, min.y),
+            max,
+            Vector2::new(min.x, max.y),
+        ];
+        for i in 0..corners.len() {
+            let segment = Self::segment(corners[i], corners[(i + 1) % corners.len()], color);
+            self.push_shape(segment, frames);
+        }
+    }
+
+    pub fn circle(&mut self, center: Vector2<f32>, radius: f32, color: Rgba) {
+        let circle = Geometry::new_2d(
+            Geometry2DType::Circle,
+            BorderDecoration::Solid,
+            color,
+            BorderThickness::ScreenSpace(DEBUG_THICKNESS),
+            InnerDecoration::None,
+            Rgba::default(),
+            DEBUG_ORDER,
+            center,
+            0.0,
+            radius * 2.0,
+        );
+        let frames = self.take_duration();
+
+        self.push_shape(circle, frames);
+    }
+
+    /// Draw the label centered at the position, see `DebugDraw::set_font` for the font.
+    ///
+    /// NOTE: the glyphs are drawn as sprites, so the label is skipped if there's no font in `FontAtlas` or
+    /// no `TextureAtlas`.
+    pub fn text(&mut self, position: Vector2<f32>, label: &str, color: Rgba) {
+        let frames = self.take_duration();
+
+        if self.texts.len() < Self::MAX_TEXT_COUNT {
+            let text = DebugText {
+                position,
+                label: label.to_owned(),
+                color,
+            };
+
+            self.texts.push((text, frames));
+        }
+    }
+
+    /// Remove all commands, including the persistent ones.
+    pub fn clear(&mut self) {
+        self.shapes.clear();
+        self.texts.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.shapes.is_empty() && self.texts.is_empty()
+    }
+
+    /// The shapes to draw in this frame, in `world space`.
+    pub fn shapes(&self) -> Assembly {
+        self.shapes.iter().map(|(shape, _)| *shape).collect()
+    }
+
+    /// The labels to draw in this frame.
+    pub fn texts(&self) -> impl Iterator<Item = &DebugText> {
+        self.texts.iter().map(|(text, _)| text)
+    }
+
+    /// The glyphs of the labels as `Sprite2D`s centered at the positions in `world space`.
+    pub(crate) fn text_sprites(&self, fonts: &FontAtlas) -> Vec<(Vector2<f32>, Sprite2D)> {
+        let (font, size) = match self
+            .font
+            .or_else(|| fonts.first().map(|font| (font, DEBUG_TEXT_SIZE)))
+        {
+            Some(font) => font,
+            None => return Vec::new(),
+        };
+
+        let mut sprites = Vec::new();
+        for text in self.texts() {
+            let text2d = Text2D::new(text.label.clone(), font, size, text.color)
+                .with_alignment(TextAlign::center());
+
+            sprites.extend(
+                text2d
+                    .sprites(fonts)
+                    .into_iter()
+                    .map(|(position, sprite)| (text.position + position, sprite)),
+            );
+        }
+
+        sprites
+    }
+
+    /// Count down the commands and drop the expired ones, called by the render stage every frame.
+    pub(crate) fn update(&mut self) {
+        self.shapes.retain(|(_, frames)| *frames > 1);
+        self.texts.retain(|(_, frames)| *frames > 1);
+
+        for (_, frames) in self.shapes.iter_mut() {
+            *frames -= 1;
+        }
+        for (_, frames) in self.texts.iter_mut() {
+            *frames -= 1;
+        }
+    }
+
+    fn push_shape(&mut self, shape: Geometry, frames: u32) {
+        if self.shapes.len() < Self::MAX_SHAPE_COUNT {
+            self.shapes.push((shape, frames));
+        }
+    }
+
+    fn take_duration(&mut self) -> u32 {
+        self.next_duration.take().unwrap_or(1)
+    }
+
+    fn segment(start: Vector2<f32>, end: Vector2<f32>, color: Rgba) -> Geometry {
+        Geometry::new_1d(
+            Geometry1DType::Segment,
+            BorderDecoration::Solid,
+            color,
+            BorderThickness::ScreenSpace(DEBUG_THICKNESS),
+            DEBUG_ORDER,
+            start,
+            end,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::{sprite::TextureAtlas, text::Font};
+
+    #[test]
+    fn commands_expire_after_duration() {
+        let mut debug_draw = DebugDraw::new();
+
+        debug_draw.line(Vector2::new(0.0, 0.0), Vector2::new(1.0, 1.0), Rgba::RED);
+        debug_draw.with_duration(3).rect(
+            Vector2::new(0.0, 0.0),
+            Vector2::new(1.0, 1.0),
+            Rgba::GREEN,
+        );
+        debug_draw.text(Vector2::new(0.0, 0.0), "label", Rgba::WHITE);
+        assert_eq!(debug_draw.shapes().len(), 5);
+        assert_eq!(debug_draw.texts().count(), 1);
+
+        debug_draw.update();
+        assert_eq!(debug_draw.shapes().len(), 4);
+        assert_eq!(debug_draw.texts().count(), 0);
+
+        debug_draw.update();
+        debug_draw.update();
+        assert!(debug_draw.is_empty());
+    }
+
+    #[test]
+    fn drop_commands_over_limit() {
+        let mut debug_draw = DebugDraw::new();
+
+        for _ in 0..DebugDraw::MAX_SHAPE_COUNT / 4 + 1 {
+            debug_draw.rect(Vector2::new(0.0, 0.0), Vector2::new(1.0, 1.0), Rgba::RED);
+        }
+        for _ in 0..DebugDraw::MAX_TEXT_COUNT + 1 {
+            debug_draw.text(Vector2::new(0.0, 0.0), "label", Rgba::WHITE);
+        }
+
+        assert_eq!(debug_draw.shapes().len(), DebugDraw::MAX_SHAPE_COUNT);
+        assert_eq!(debug_draw.texts().count(), DebugDraw::MAX_TEXT_COUNT);
+    }
+
+    #[test]
+    fn text_sprites_with_font() {
+        let mut debug_draw = DebugDraw::new();
+        debug_draw.text(Vector2::new(10.0, 20.0), "ab", Rgba::RED);
+
+        let mut fonts = FontAtlas::new();
+        assert!(debug_draw.text_sprites(&fonts).is_empty());
+
+        let texture = TextureAtlas::new()
+            .insert_rgba(128, 96, vec![0; 128 * 96 * 4])
+            .unwrap();
+        let first = fonts.insert(Font::from_ascii_grid(texture, 8.0, 16.0, 16));
+        let second = fonts.insert(Font::from_ascii_grid(texture, 8.0, 16.0, 16));
+
+        // the first font at the default size.
+        let sprites = debug_draw.text_sprites(&fonts);
+        assert_eq!(sprites.len(), 2);
+        assert_eq!(sprites[0].1.size.y, DEBUG_TEXT_SIZE);
+        assert_eq!(sprites[0].1.color_tint, Rgba::RED);
+        // the label is centered at the position.
+        assert!(((sprites[0].0.x + sprites[1].0.x) / 2.0 - 10.0).abs() < 1e-4);
+        assert!((sprites[0].0.y - 20.0).abs() < 1e-4);
+
+        debug_draw.set_font(second, 32.0);
+        assert_ne!(first, second);
+        assert_eq!(debug_draw.text_sprites(&fonts)[0].1.size.y, 32.0);
+    }
+}
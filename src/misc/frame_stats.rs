@@ -0,0 +1,205 @@
+use crate::legion::systems::{ParallelRunnable, SystemBuilder};
+
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+/// The time cost of an `AppStage` in the last frame.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct StageTiming {
+    /// The time cost of the process schedule in milliseconds.
+    pub cpu_ms: f32,
+    /// Whether the process schedule was skipped in the last frame.
+    pub skipped: bool,
+}
+
+/// The execution statistics of an `AppStage` since the app starts, updated every time the stage plays.
+///
+/// The metrics of all busy stages are in the `HashMap<String, StageMetrics>` resource, keyed by stage name.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StageMetrics {
+    pub name: String,
+    /// The count of frames the stage played, including the skipped ones.
+    pub total_ticks: u64,
+    /// The count of frames the process schedule was skipped for the budget.
+    pub skipped_ticks: u64,
+    /// The time cost of the process schedule in milliseconds in the last executed frame.
+    pub last_execution_ms: f32,
+    pub mean_execution_ms: f32,
+    pub max_execution_ms: f32,
+}
+
+impl StageMetrics {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            ..Default::default()
+        }
+    }
+
+    /// The count of frames the process schedule executed.
+    pub fn executed_ticks(&self) -> u64 {
+        self.total_ticks - self.skipped_ticks
+    }
+
+    pub(crate) fn record(&mut self, timing: StageTiming) {
+        self.total_ticks += 1;
+
+        if timing.skipped {
+            self.skipped_ticks += 1;
+            return;
+        }
+
+        let executed = self.executed_ticks() as f32;
+        self.last_execution_ms = timing.cpu_ms;
+        self.mean_execution_ms += (timing.cpu_ms - self.mean_execution_ms) / executed;
+        self.max_execution_ms = self.max_execution_ms.max(timing.cpu_ms);
+    }
+}
+
+/// The performance data of the last frame, updated by `App` every frame.
+///
+/// Only the `AppStage`s built with `AppStageBuilder::with_profiling(true)` are in `per_stage`.
+#[derive(Debug, Clone)]
+pub struct FrameStats {
+    pub fps: f32,
+    pub frame_time_ms: f32,
+    /// The draw calls submitted by the render stage.
+    pub draw_calls: usize,
+    pub per_stage: HashMap<String, StageTiming>,
+
+    last_frame: Option<Instant>,
+}
+
+impl FrameStats {
+    pub fn new() -> Self {
+        Self {
+            fps: 0.0,
+            frame_time_ms: 0.0,
+            draw_calls: 0,
+            per_stage: Default::default(),
+
+            last_frame: None,
+        }
+    }
+
+    /// The timing of the stage in the last frame, `None` if the stage isn't profiled.
+    pub fn stage(&self, stage_name: &str) -> Option<&StageTiming> {
+        self.per_stage.get(stage_name)
+    }
+
+    /// Measure the time since the last frame, called before the stages play.
+    pub(crate) fn begin_frame(&mut self) {
+        let now = Instant::now();
+
+        if let Some(last_frame) = self.last_frame {
+            self.frame_time_ms = (now - last_frame).as_secs_f32() * 1000.0;
+            self.fps = if self.frame_time_ms > 0.0 {
+                1000.0 / self.frame_time_ms
+            } else {
+                0.0
+            };
+        }
+
+        self.last_frame = Some(now);
+    }
+
+    pub(crate) fn record_stage(&mut self, stage_name: &str, timing: StageTiming) {
+        match self.per_stage.get_mut(stage_name) {
+            Some(old) => *old = timing,
+            None => {
+                self.per_stage.insert(stage_name.to_owned(), timing);
+            }
+        }
+    }
+}
+
+impl Default for FrameStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Print `FrameStats` to stdout every `interval`.
+pub fn print_frame_stats_system(interval: Duration) -> impl ParallelRunnable {
+    let mut last_print = Instant::now();
+
+    SystemBuilder::new("print_frame_stats")
+        .read_resource::<FrameStats>()
+        .build(move |_, _, stats, _| {
+            if last_print.elapsed() < interval {
+                return;
+            }
+            last_print = Instant::now();
+
+            println!(
+                "fps: {:.1}, frame time: {:.2}ms, draw calls: {}",
+                stats.fps, stats.frame_time_ms, stats.draw_calls
+            );
+
+            let mut stages = stats.per_stage.iter().collect::<Vec<_>>();
+            stages.sort_by(|a, b| a.0.cmp(b.0));
+            for (name, timing) in stages {
+                if timing.skipped {
+                    println!("    {}: skipped", name);
+                } else {
+                    println!("    {}: {:.2}ms", name, timing.cpu_ms);
+                }
+            }
+        })
+}
+
+/// Print the `HashMap<String, StageMetrics>` resource to stdout every `interval`, the stages are sorted by
+/// name.
+pub fn print_stage_metrics_system(interval: Duration) -> impl ParallelRunnable {
+    let mut last_print = Instant::now();
+
+    SystemBuilder::new("print_stage_metrics")
+        .read_resource::<HashMap<String, StageMetrics>>()
+        .build(move |_, _, metrics, _| {
+            if last_print.elapsed() < interval {
+                return;
+            }
+            last_print = Instant::now();
+
+            let mut stages = metrics.values().collect::<Vec<_>>();
+            stages.sort_by(|a, b| a.name.cmp(&b.name));
+
+            println!(
+                "{:<24}{:>10}{:>10}{:>12}{:>12}{:>12}",
+                "stage", "ticks", "skipped", "last(ms)", "mean(ms)", "max(ms)"
+            );
+            for stage in stages {
+                println!(
+                    "{:<24}{:>10}{:>10}{:>12.2}{:>12.2}{:>12.2}",
+                    stage.name,
+                    stage.total_ticks,
+                    stage.skipped_ticks,
+                    stage.last_execution_ms,
+                    stage.mean_execution_ms,
+                    stage.max_execution_ms
+                );
+            }
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stage_metrics_skip_the_skipped_ticks() {
+        let mut metrics = StageMetrics::new("physics");
+
+        for &(cpu_ms, skipped) in [(2.0, false), (0.0, true), (4.0, false), (3.0, false)].iter() {
+            metrics.record(StageTiming { cpu_ms, skipped });
+        }
+
+        assert_eq!(metrics.total_ticks, 4);
+        assert_eq!(metrics.skipped_ticks, 1);
+        assert_eq!(metrics.last_execution_ms, 3.0);
+        assert!((metrics.mean_execution_ms - 3.0).abs() < 1e-5);
+        assert_eq!(metrics.max_execution_ms, 4.0);
+    }
+}
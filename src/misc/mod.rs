@@ -1,3 +1,7 @@
 pub mod color;
 pub mod coordinates;
+pub mod debug_draw;
+pub mod frame_stats;
+pub mod rng;
+pub mod svg;
 pub mod viewport;
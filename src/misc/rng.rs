@@ -0,0 +1,100 @@
+use rand::{
+    distributions::{uniform::SampleRange, uniform::SampleUniform, Distribution, Standard},
+    rngs::SmallRng,
+    seq::SliceRandom,
+    Rng, SeedableRng,
+};
+
+/// A seeded random number generator, insert it to `Resources` to share it between systems.
+///
+/// The same seed always generates the same sequence, which makes procedural generation and tests reproducible.
+///
+/// NOTE: Systems accessing `RngResource` are ordered by the schedule, so the sequence each system gets
+/// depends on the order the systems are added.
+#[derive(Debug, Clone)]
+pub struct RngResource {
+    seed: u64,
+    rng: SmallRng,
+}
+
+impl RngResource {
+    pub fn from_seed(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: SmallRng::seed_from_u64(seed),
+        }
+    }
+
+    /// Create a `RngResource` seeded from the thread rng, for non-deterministic use.
+    pub fn new_random() -> Self {
+        Self::from_seed(rand::thread_rng().gen())
+    }
+
+    /// The seed this `RngResource` created from.
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Restart the sequence from the seed.
+    pub fn reset(&mut self) {
+        self.rng = SmallRng::seed_from_u64(self.seed);
+    }
+
+    /// Generate a random value of the standard distribution of `T`.
+    pub fn gen<T>(&mut self) -> T
+    where
+        Standard: Distribution<T>,
+    {
+        self.rng.gen()
+    }
+
+    /// Sample a random value from the distribution.
+    pub fn sample<T, D: Distribution<T>>(&mut self, distr: D) -> T {
+        self.rng.sample(distr)
+    }
+
+    /// Generate a random value in the range, panics if the range is empty.
+    pub fn gen_range<T: SampleUniform, R: SampleRange<T>>(&mut self, range: R) -> T {
+        self.rng.gen_range(range)
+    }
+
+    /// Return true with probability `p`.
+    pub fn gen_bool(&mut self, p: f64) -> bool {
+        self.rng.gen_bool(p)
+    }
+
+    pub fn shuffle<T>(&mut self, slice: &mut [T]) {
+        slice.shuffle(&mut self.rng);
+    }
+
+    pub fn choose<'a, T>(&mut self, slice: &'a [T]) -> Option<&'a T> {
+        slice.choose(&mut self.rng)
+    }
+}
+
+impl Default for RngResource {
+    fn default() -> Self {
+        Self::new_random()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RngResource;
+
+    #[test]
+    fn same_seed_same_sequence() {
+        let mut a = RngResource::from_seed(42);
+        let mut b = RngResource::from_seed(42);
+
+        let seq_a: Vec<u32> = (0..16).map(|_| a.gen()).collect();
+        let seq_b: Vec<u32> = (0..16).map(|_| b.gen()).collect();
+        assert_eq!(seq_a, seq_b);
+
+        a.reset();
+        assert_eq!(a.gen::<u32>(), seq_a[0]);
+
+        let x = a.gen_range(-1.0f32..1.0);
+        assert!((-1.0..1.0).contains(&x));
+    }
+}
@@ -0,0 +1,339 @@
+use crate::{
+    components::geometry::{Assembly, BorderDecoration, BorderThickness, Geometry, Geometry1DType},
+    misc::color::Rgba,
+    nalgebra::Vector2,
+};
+
+use std::{error::Error, fmt};
+
+/// The count of segments approximating a bezier curve.
+const CURVE_SEGMENTS: usize = 16;
+
+/// A subpath of SVG path data, the points are in SVG space(the y axis points down).
+#[derive(Debug, Clone, PartialEq)]
+pub struct SvgSubpath {
+    pub points: Vec<Vector2<f32>>,
+    pub closed: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SvgParseError {
+    /// The command letter is not supported, with the offset of the letter.
+    UnknownCommand(char, usize),
+    /// A number is expected at the offset.
+    ExpectedNumber(usize),
+    /// The path data doesn't start with `M` or `m`, with the offset of the first command.
+    MissingMoveTo(usize),
+}
+
+impl SvgParseError {
+    /// The character offset in the path data where the error occurs.
+    pub fn offset(&self) -> usize {
+        match self {
+            SvgParseError::UnknownCommand(_, offset)
+            | SvgParseError::ExpectedNumber(offset)
+            | SvgParseError::MissingMoveTo(offset) => *offset,
+        }
+    }
+}
+
+impl fmt::Display for SvgParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SvgParseError::UnknownCommand(command, offset) => write!(
+                f,
+                "unsupported path command `{}` at offset {}",
+                command, offset
+            ),
+            SvgParseError::ExpectedNumber(offset) => {
+                write!(f, "expected a number at offset {}", offset)
+            }
+            SvgParseError::MissingMoveTo(offset) => write!(
+                f,
+                "path data must start with a move-to command, found at offset {}",
+                offset
+            ),
+        }
+    }
+}
+
+impl Error for SvgParseError {}
+
+/// Parse the `d` attribute of a SVG `<path>`, the curves are flattened into segments.
+///
+/// `M`, `L`, `H`, `V`, `C`, `Q` and `Z` are supported in both absolute and relative forms, the smooth
+/// curves and arcs are not.
+pub fn parse_svg_path(data: &str) -> Result<Vec<SvgSubpath>, SvgParseError> {
+    let mut parser = Parser {
+        data: data.as_bytes(),
+        offset: 0,
+    };
+
+    let mut subpaths: Vec<SvgSubpath> = Vec::new();
+    let mut current = Vector2::new(0.0, 0.0);
+    let mut command = None;
+
+    loop {
+        parser.skip_separators();
+        let offset = parser.offset;
+
+        let letter = match parser.peek() {
+            None => break,
+            Some(c) if c.is_ascii_alphabetic() => {
+                parser.offset += 1;
+                c as char
+            }
+            // NOTE: the command repeats if there are more numbers, a repeated move-to is a line-to.
+            Some(_) => match command {
+                Some('M') => 'L',
+                Some('m') => 'l',
+                Some(c) if c != 'Z' && c != 'z' => c,
+                _ => return Err(SvgParseError::ExpectedNumber(offset)),
+            },
+        };
+
+        if subpaths.is_empty() && letter != 'M' && letter != 'm' {
+            return Err(SvgParseError::MissingMoveTo(offset));
+        }
+
+        let relative = letter.is_ascii_lowercase();
+        let origin = if relative {
+            current
+        } else {
+            Vector2::new(0.0, 0.0)
+        };
+
+        match letter.to_ascii_uppercase() {
+            'M' => {
+                current = origin + parser.point()?;
+                subpaths.push(SvgSubpath {
+                    points: vec![current],
+                    closed: false,
+                });
+            }
+            'L' => {
+                current = origin + parser.point()?;
+                subpaths.last_mut().unwrap().points.push(current);
+            }
+            'H' => {
+                current.x = origin.x + parser.number()?;
+                subpaths.last_mut().unwrap().points.push(current);
+            }
+            'V' => {
+                current.y = origin.y + parser.number()?;
+                subpaths.last_mut().unwrap().points.push(current);
+            }
+            'C' => {
+                let (c1, c2, end) = (
+                    origin + parser.point()?,
+                    origin + parser.point()?,
+                    origin + parser.point()?,
+                );
+                let start = current;
+
+                subpaths
+                    .last_mut()
+                    .unwrap()
+                    .points
+                    .extend((1..=CURVE_SEGMENTS).map(|i| {
+                        let t = i as f32 / CURVE_SEGMENTS as f32;
+                        let u = 1.0 - t;
+                        start * (u * u * u)
+                            + c1 * (3.0 * u * u * t)
+                            + c2 * (3.0 * u * t * t)
+                            + end * (t * t * t)
+                    }));
+                current = end;
+            }
+            'Q' => {
+                let (c, end) = (origin + parser.point()?, origin + parser.point()?);
+                let start = current;
+
+                subpaths
+                    .last_mut()
+                    .unwrap()
+                    .points
+                    .extend((1..=CURVE_SEGMENTS).map(|i| {
+                        let t = i as f32 / CURVE_SEGMENTS as f32;
+                        let u = 1.0 - t;
+                        start * (u * u) + c * (2.0 * u * t) + end * (t * t)
+                    }));
+                current = end;
+            }
+            'Z' => {
+                let subpath = subpaths.last_mut().unwrap();
+                subpath.closed = true;
+                current = subpath.points[0];
+
+                // NOTE: the drawing after `Z` starts a new subpath at the same point.
+                subpaths.push(SvgSubpath {
+                    points: vec![current],
+                    closed: false,
+                });
+            }
+            _ => return Err(SvgParseError::UnknownCommand(letter, offset)),
+        }
+
+        command = Some(letter);
+    }
+
+    // the subpaths started by `Z` but never drawn.
+    subpaths.retain(|subpath| subpath.points.len() > 1);
+
+    Ok(subpaths)
+}
+
+/// Parse the SVG path data into an `Assembly` of segments in `local space`, the y axis is flipped back.
+///
+/// NOTE: `Geometry` can only fill the built-in shapes, so the SVG shape is drawn as its outline.
+pub fn svg_path_to_assembly(
+    data: &str,
+    color: Rgba,
+    thickness: BorderThickness,
+    order: u8,
+) -> Result<Assembly, SvgParseError> {
+    let mut assembly = Assembly::new();
+
+    for subpath in parse_svg_path(data)? {
+        let points = subpath
+            .points
+            .iter()
+            .map(|p| Vector2::new(p.x, -p.y))
+            .collect::<Vec<_>>();
+
+        let count = if subpath.closed {
+            points.len()
+        } else {
+            points.len() - 1
+        };
+
+        assembly.extend((0..count).map(|i| {
+            Geometry::new_1d(
+                Geometry1DType::Segment,
+                BorderDecoration::Solid,
+                color,
+                thickness,
+                order,
+                points[i],
+                points[(i + 1) % points.len()],
+            )
+        }));
+    }
+
+    Ok(assembly)
+}
+
+struct Parser<'a> {
+    data: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<u8> {
+        self.data.get(self.offset).copied()
+    }
+
+    fn skip_separators(&mut self) {
+        while let Some(c) = self.peek() {
+            if c.is_ascii_whitespace() || c == b',' {
+                self.offset += 1;
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn point(&mut self) -> Result<Vector2<f32>, SvgParseError> {
+        Ok(Vector2::new(self.number()?, self.number()?))
+    }
+
+    fn number(&mut self) -> Result<f32, SvgParseError> {
+        self.skip_separators();
+        let start = self.offset;
+
+        if let Some(b'+') | Some(b'-') = self.peek() {
+            self.offset += 1;
+        }
+        let mut dot = false;
+        while let Some(c) = self.peek() {
+            match c {
+                b'0'..=b'9' => self.offset += 1,
+                // NOTE: "0.5.5" is two numbers in SVG.
+                b'.' if !dot => {
+                    dot = true;
+                    self.offset += 1;
+                }
+                _ => break,
+            }
+        }
+        if let Some(b'e') | Some(b'E') = self.peek() {
+            let mantissa_end = self.offset;
+            self.offset += 1;
+            if let Some(b'+') | Some(b'-') = self.peek() {
+                self.offset += 1;
+            }
+            let exponent_start = self.offset;
+            while let Some(b'0'..=b'9') = self.peek() {
+                self.offset += 1;
+            }
+            if self.offset == exponent_start {
+                self.offset = mantissa_end;
+            }
+        }
+
+        let number = std::str::from_utf8(&self.data[start..self.offset])
+            .ok()
+            .and_then(|number| number.parse::<f32>().ok());
+
+        match number {
+            Some(number) => Ok(number),
+            None => {
+                self.offset = start;
+                Err(SvgParseError::ExpectedNumber(start))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_commands() {
+        let subpaths = parse_svg_path("M 0,0 L10 0 v10 h-10 Z m 20 0 q 5 5 10 0").unwrap();
+        assert_eq!(subpaths.len(), 2);
+
+        assert!(subpaths[0].closed);
+        assert_eq!(
+            subpaths[0].points,
+            vec![
+                Vector2::new(0.0, 0.0),
+                Vector2::new(10.0, 0.0),
+                Vector2::new(10.0, 10.0),
+                Vector2::new(0.0, 10.0),
+            ]
+        );
+
+        // the quadratic curve is relative to (20, 0).
+        assert!(!subpaths[1].closed);
+        assert_eq!(subpaths[1].points.len(), CURVE_SEGMENTS + 1);
+        assert_eq!(*subpaths[1].points.last().unwrap(), Vector2::new(30.0, 0.0));
+    }
+
+    #[test]
+    fn parse_errors_have_offsets() {
+        assert_eq!(
+            parse_svg_path("L 0 0"),
+            Err(SvgParseError::MissingMoveTo(0))
+        );
+        assert_eq!(
+            parse_svg_path("M 0 0 A 1 1 0 0 0 1 1"),
+            Err(SvgParseError::UnknownCommand('A', 6))
+        );
+        assert_eq!(
+            parse_svg_path("M 0 x"),
+            Err(SvgParseError::ExpectedNumber(4))
+        );
+    }
+}
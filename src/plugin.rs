@@ -0,0 +1,68 @@
+use crate::app::AppBuilder;
+
+use std::collections::HashMap;
+
+/// A reusable piece of `App` setup.
+///
+/// A `Plugin` receives the `AppBuilder` and returns it with its own `AppStage`s added, so a
+/// subsystem(physics, audio, networking...) can be added to an `App` in one line.
+pub trait Plugin {
+    fn build(&self, app_builder: AppBuilder) -> AppBuilder;
+}
+
+/// Apply multiple `Plugin`s in the order they are added.
+#[derive(Default)]
+pub struct PluginGroup {
+    plugins: Vec<Box<dyn Plugin>>,
+}
+
+impl PluginGroup {
+    pub fn new() -> Self {
+        Self {
+            plugins: Default::default(),
+        }
+    }
+
+    pub fn add<P: Plugin + 'static>(mut self, plugin: P) -> Self {
+        self.plugins.push(Box::new(plugin));
+
+        self
+    }
+}
+
+impl Plugin for PluginGroup {
+    fn build(&self, app_builder: AppBuilder) -> AppBuilder {
+        self.plugins
+            .iter()
+            .fold(app_builder, |app_builder, plugin| plugin.build(app_builder))
+    }
+}
+
+/// The `Plugin`s by name, used by `AppBuilder::from_config` to resolve the plugin names in `AppConfig`.
+#[derive(Default)]
+pub struct PluginRegistry {
+    plugins: HashMap<String, Box<dyn Plugin>>,
+}
+
+impl PluginRegistry {
+    pub fn new() -> Self {
+        Self {
+            plugins: Default::default(),
+        }
+    }
+
+    /// Register the plugin, replace the old one if the name is registered.
+    pub fn register<P: Plugin + 'static>(mut self, name: impl Into<String>, plugin: P) -> Self {
+        self.plugins.insert(name.into(), Box::new(plugin));
+
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn Plugin> {
+        self.plugins.get(name).map(|plugin| plugin.as_ref())
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.plugins.contains_key(name)
+    }
+}
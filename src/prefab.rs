@@ -0,0 +1,355 @@
+use crate::{
+    legion::{storage::Component, systems::CommandBuffer, Entity, World},
+    scene::{find_scene_type, parse_scene, SceneEntities, SceneError},
+    snapshot::ComponentRegistry,
+};
+
+use std::{
+    any::{Any, TypeId},
+    collections::HashMap,
+    fmt, fs,
+    path::Path,
+};
+
+/// A type-erased component which can be cloned to entities.
+pub trait AnyComponent: Send + Sync + 'static {
+    fn component_type_id(&self) -> TypeId;
+
+    fn clone_box(&self) -> Box<dyn AnyComponent>;
+
+    fn as_any(&self) -> &dyn Any;
+
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+
+    /// Add a clone of the component to the entity by `CommandBuffer`.
+    fn add_to(&self, cmd: &mut CommandBuffer, entity: Entity);
+
+    /// Add a clone of the component to the entity in `World`.
+    fn add_to_world(&self, world: &mut World, entity: Entity);
+}
+
+impl<T: Component + Clone> AnyComponent for T {
+    fn component_type_id(&self) -> TypeId {
+        TypeId::of::<T>()
+    }
+
+    fn clone_box(&self) -> Box<dyn AnyComponent> {
+        Box::new(self.clone())
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    fn add_to(&self, cmd: &mut CommandBuffer, entity: Entity) {
+        cmd.add_component(entity, self.clone());
+    }
+
+    fn add_to_world(&self, world: &mut World, entity: Entity) {
+        if let Some(mut entry) = world.entry(entity) {
+            entry.add_component(self.clone());
+        }
+    }
+}
+
+/// An entity template, instantiate it to spawn entities with the same components.
+#[derive(Default)]
+pub struct Prefab {
+    components: Vec<Box<dyn AnyComponent>>,
+}
+
+impl Prefab {
+    pub fn builder() -> PrefabBuilder {
+        PrefabBuilder::new()
+    }
+
+    /// Load the prefab from the first entity of a scene file, see `save_scene` for the format.
+    pub fn load(path: &Path, registry: &ComponentRegistry) -> Result<Self, SceneError> {
+        Self::from_scene_str(&fs::read_to_string(path)?, registry)
+    }
+
+    /// Parse the prefab from the first entity of scene text.
+    ///
+    /// Entity references in the components are not resolved, the prefab is standalone.
+    pub fn from_scene_str(text: &str, registry: &ComponentRegistry) -> Result<Self, SceneError> {
+        let parsed = parse_scene(text)?;
+        let entities = SceneEntities::default();
+
+        let mut components = Vec::new();
+        if let Some((_, lines)) = parsed.first() {
+            for (_, name, data) in lines {
+                components.push(find_scene_type(registry, name)?.load_boxed(data, &entities)?);
+            }
+        }
+
+        Ok(Self { components })
+    }
+
+    pub fn len(&self) -> usize {
+        self.components.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.components.is_empty()
+    }
+
+    pub fn has<T: Component>(&self) -> bool {
+        self.get::<T>().is_some()
+    }
+
+    pub fn get<T: Component>(&self) -> Option<&T> {
+        self.components
+            .iter()
+            .find_map(|component| component.as_any().downcast_ref::<T>())
+    }
+
+    /// Spawn an entity with the components by `CommandBuffer`.
+    pub fn instantiate(&self, cmd: &mut CommandBuffer) -> Entity {
+        instantiate_components(&self.components, cmd)
+    }
+
+    /// Spawn an entity with the components modified by `overrides`, the prefab itself is not changed.
+    pub fn instantiate_with_override(
+        &self,
+        cmd: &mut CommandBuffer,
+        overrides: impl FnOnce(PrefabInstance) -> PrefabInstance,
+    ) -> Entity {
+        let instance = overrides(PrefabInstance {
+            components: clone_components(&self.components),
+        });
+
+        instantiate_components(&instance.components, cmd)
+    }
+
+    /// Spawn an entity with the components in `World` immediately.
+    pub fn spawn(&self, world: &mut World) -> Entity {
+        let entity = world.push(());
+
+        for component in self.components.iter() {
+            component.add_to_world(world, entity);
+        }
+
+        entity
+    }
+}
+
+impl Clone for Prefab {
+    fn clone(&self) -> Self {
+        Self {
+            components: clone_components(&self.components),
+        }
+    }
+}
+
+impl fmt::Debug for Prefab {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Prefab")
+            .field("len", &self.components.len())
+            .finish()
+    }
+}
+
+// NOTE: `Box<dyn AnyComponent>` is not `Clone` on purpose, otherwise the box itself would be an
+// `AnyComponent` and be added to entities as a component.
+fn clone_components(components: &[Box<dyn AnyComponent>]) -> Vec<Box<dyn AnyComponent>> {
+    components
+        .iter()
+        .map(|component| component.clone_box())
+        .collect()
+}
+
+fn instantiate_components(components: &[Box<dyn AnyComponent>], cmd: &mut CommandBuffer) -> Entity {
+    let entity = cmd.push(());
+
+    for component in components {
+        component.add_to(cmd, entity);
+    }
+
+    entity
+}
+
+#[derive(Default)]
+pub struct PrefabBuilder {
+    components: Vec<Box<dyn AnyComponent>>,
+}
+
+impl PrefabBuilder {
+    pub fn new() -> Self {
+        Self {
+            components: Default::default(),
+        }
+    }
+
+    /// Add the component, replace the old one if the prefab has a component of the same type.
+    pub fn with<T: Component + Clone>(mut self, component: T) -> Self {
+        replace_or_push(&mut self.components, component);
+
+        self
+    }
+
+    pub fn build(self) -> Prefab {
+        Prefab {
+            components: self.components,
+        }
+    }
+}
+
+impl fmt::Debug for PrefabBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PrefabBuilder")
+            .field("len", &self.components.len())
+            .finish()
+    }
+}
+
+/// The components of a prefab going to be instantiated, modify them in `Prefab::instantiate_with_override`.
+pub struct PrefabInstance {
+    components: Vec<Box<dyn AnyComponent>>,
+}
+
+impl PrefabInstance {
+    /// Add the component, replace the old one if the prefab has a component of the same type.
+    pub fn with<T: Component + Clone>(mut self, component: T) -> Self {
+        replace_or_push(&mut self.components, component);
+
+        self
+    }
+
+    pub fn without<T: Component>(mut self) -> Self {
+        self.components
+            .retain(|component| component.component_type_id() != TypeId::of::<T>());
+
+        self
+    }
+
+    pub fn get_mut<T: Component>(&mut self) -> Option<&mut T> {
+        self.components
+            .iter_mut()
+            .find_map(|component| component.as_any_mut().downcast_mut::<T>())
+    }
+}
+
+impl fmt::Debug for PrefabInstance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PrefabInstance")
+            .field("len", &self.components.len())
+            .finish()
+    }
+}
+
+fn replace_or_push<T: Component + Clone>(
+    components: &mut Vec<Box<dyn AnyComponent>>,
+    component: T,
+) {
+    if let Some(old) = components
+        .iter_mut()
+        .find(|old| old.component_type_id() == TypeId::of::<T>())
+    {
+        *old = Box::new(component);
+    } else {
+        components.push(Box::new(component));
+    }
+}
+
+/// The cache of prefabs keyed by name or path, insert it to `Resources` to share prefabs between systems.
+#[derive(Debug, Default)]
+pub struct PrefabCache {
+    prefabs: HashMap<String, Prefab>,
+}
+
+impl PrefabCache {
+    pub fn new() -> Self {
+        Self {
+            prefabs: Default::default(),
+        }
+    }
+
+    pub fn insert(&mut self, name: impl Into<String>, prefab: Prefab) -> Option<Prefab> {
+        self.prefabs.insert(name.into(), prefab)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Prefab> {
+        self.prefabs.get(name)
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<Prefab> {
+        self.prefabs.remove(name)
+    }
+
+    /// Get the prefab loaded from `path`, load it at the first time.
+    pub fn load(
+        &mut self,
+        path: &Path,
+        registry: &ComponentRegistry,
+    ) -> Result<&Prefab, SceneError> {
+        let key = path.to_string_lossy().into_owned();
+
+        if !self.prefabs.contains_key(&key) {
+            self.prefabs
+                .insert(key.clone(), Prefab::load(path, registry)?);
+        }
+
+        Ok(&self.prefabs[&key])
+    }
+
+    pub fn len(&self) -> usize {
+        self.prefabs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.prefabs.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{components::transform::Transform2D, legion::IntoQuery};
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Health(u32);
+
+    #[test]
+    fn instantiate_prefab() {
+        let mut world = World::default();
+        let prefab = Prefab::builder()
+            .with(Transform2D::at(1.0, 2.0))
+            .with(Health(10))
+            .build();
+
+        let mut cmd = CommandBuffer::new(&world);
+        prefab.instantiate(&mut cmd);
+        prefab.instantiate_with_override(&mut cmd, |instance| instance.with(Health(20)));
+        prefab.instantiate_with_override(&mut cmd, |instance| instance.without::<Health>());
+        cmd.flush(&mut world, &mut Default::default());
+
+        assert_eq!(world.len(), 3);
+        assert_eq!(<&Transform2D>::query().iter(&world).count(), 3);
+
+        let mut healths: Vec<u32> = <&Health>::query().iter(&world).map(|h| h.0).collect();
+        healths.sort_unstable();
+        assert_eq!(healths, vec![10, 20]);
+
+        assert_eq!(prefab.get::<Health>(), Some(&Health(10)));
+    }
+
+    #[test]
+    fn prefab_from_scene() {
+        let registry = ComponentRegistry::new();
+        let prefab =
+            Prefab::from_scene_str("entity 0\n    Transform2D: 1 2 0 1 1\n", &registry).unwrap();
+
+        let mut world = World::default();
+        let entity = prefab.spawn(&mut world);
+
+        let entry = world.entry(entity).unwrap();
+        assert_eq!(
+            entry.get_component::<Transform2D>().unwrap().position.x,
+            1.0
+        );
+    }
+}
@@ -1,16 +1,35 @@
 mod renderers;
 
-use renderers::{background_renderer::BackgroundRenderer, geometry_renderer::GeometryRenderer};
+use renderers::{
+    background_renderer::BackgroundRenderer, cursor_renderer::CursorRenderer,
+    geometry_renderer::GeometryRenderer, post_process_renderer::PostProcessRenderer,
+    sprite_renderer::SpriteRenderer, tilemap_renderer::TilemapRenderer,
+};
 
 use crate::{
-    app::{AppStage, AppStageBuilder},
-    components::{camera::Camera2D, time::Time, transform::Transform2D},
-    legion::{IntoQuery, Resources, World},
-    misc::{coordinates::Transformation, viewport::Viewport},
+    app::{AppStage, AppStageBuilder, AppStageTag},
+    assets::Assets,
+    components::{
+        camera::{Camera2D, RenderTarget, RenderTexture, RenderTextureHandle},
+        camera_shake::CameraShake,
+        hierarchy::GlobalTransform2D,
+        parallax::Parallax2D,
+        post_process::PostProcessStack,
+        sprite,
+        time::Time,
+        transform::Transform2D,
+    },
+    legion::{Entity, IntoQuery, Resources, World},
+    misc::{
+        coordinates::Transformation, debug_draw::DebugDraw, frame_stats::FrameStats,
+        viewport::Viewport,
+    },
     nalgebra::{Matrix4, Vector2},
     window::Window,
 };
 
+use std::collections::{HashMap, HashSet};
+
 // Quad vertex in world coordinate.
 #[cfg_attr(rustfmt, rustfmt_skip)]
 const QUAD_VERTEX: [f32; 16] = [
@@ -29,27 +48,131 @@ const QUAD_INDEX: [u16; 6] = [
 
 #[rustfmt::skip] const THOUSAND:    usize = 1 << 10;
 #[rustfmt::skip] const MILLION:     usize = THOUSAND * THOUSAND;
+
 #[rustfmt::skip] const KB:          u64 = 1 << 10;
 #[rustfmt::skip] const MB:          u64 = KB * KB;
 
+/// The format of depth textures, the stencil is used by `RenderGroup`s.
+const DEPTH_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Depth24PlusStencil8;
+
 pub(crate) fn create_app_stage_render(window: &Window) -> AppStage {
     let mut r2d = Render2D::new(window);
     let mut bg_rder = BackgroundRenderer::new(&r2d);
     let mut g2d_rder = GeometryRenderer::new(&r2d);
+    let mut tm_rder = TilemapRenderer::new(&r2d);
+    let mut sp_rder = SpriteRenderer::new(&r2d);
+    let mut pp_rder = PostProcessRenderer::new(&r2d);
+    let mut cs_rder = CursorRenderer::new(&r2d);
 
     let render_process = move |world: &mut World, resources: &mut Resources| {
-        let trf = r2d.process(world, resources);
-        resources.insert(trf);
+        r2d.resize(resources);
+
+        // NOTE: cameras are rendered from low priority to high priority, the `Transformation` of the
+        // screen camera with the highest priority is used by `Input`.
+        // NOTE: the `CameraShake` is applied here, the `Transform2D` of camera isn't shaken.
+        let mut cameras = <(Entity, &Transform2D, &Camera2D, Option<&CameraShake>)>::query()
+            .iter(world)
+            .map(|(entity, transform2d, camera2d, shake)| {
+                let transform2d = shake.map_or(*transform2d, |shake| shake.apply(transform2d));
+                (*entity, transform2d, *camera2d)
+            })
+            .collect::<Vec<_>>();
+        cameras.sort_by_key(|(_, _, camera2d)| camera2d.priority);
+
+        let mut query_stack = <&PostProcessStack>::query();
+
+        // NOTE: debug shapes are drawn by every camera, over all entities.
+        let debug_shapes = resources
+            .get::<DebugDraw>()
+            .map(|debug_draw| debug_draw.shapes())
+            .unwrap_or_default();
+
+        let mut trf = Transformation::default();
+        // The render targets have been cleared in this frame.
+        let mut cleared = HashSet::new();
+        let mut draw_calls = 0;
 
         r2d.begin_draw();
 
-        bg_rder.render(&r2d, world, resources);
-        g2d_rder.render(&r2d, world, resources);
+        if cameras.is_empty() {
+            bg_rder.render(&r2d, &r2d.target(RenderTarget::Screen), true);
+            draw_calls += 1;
+        }
+
+        for (entity, transform2d, camera2d) in cameras.iter() {
+            let ct = match r2d.prepare_camera(transform2d, camera2d, resources) {
+                Some(ct) => ct,
+                None => continue,
+            };
+
+            if camera2d.render_target == RenderTarget::Screen {
+                trf = ct;
+            }
+
+            let clear = cleared.insert(camera2d.render_target);
+            let target = r2d.target(camera2d.render_target);
+
+            let stack = query_stack
+                .get(&*world, *entity)
+                .ok()
+                .filter(|stack| stack.enabled_effects().next().is_some());
+
+            match stack {
+                Some(stack) => {
+                    pp_rder.prepare(&r2d, &target, stack);
+
+                    let scene_target = pp_rder.scene_target(&target);
+                    bg_rder.render(&r2d, &scene_target, true);
+                    g2d_rder.render(
+                        &r2d,
+                        &scene_target,
+                        world,
+                        transform2d,
+                        camera2d,
+                        &debug_shapes,
+                    );
+                    draw_calls += tm_rder.render(&r2d, &scene_target, world, resources, camera2d);
+                    draw_calls += sp_rder.render(
+                        &r2d,
+                        &scene_target,
+                        world,
+                        resources,
+                        transform2d,
+                        camera2d,
+                    );
+
+                    pp_rder.render(&r2d, &target, stack, resources, clear);
+                    draw_calls += 2 + stack.enabled_effects().count();
+                }
+                None => {
+                    bg_rder.render(&r2d, &target, clear);
+                    g2d_rder.render(&r2d, &target, world, transform2d, camera2d, &debug_shapes);
+                    draw_calls += tm_rder.render(&r2d, &target, world, resources, camera2d);
+                    draw_calls +=
+                        sp_rder.render(&r2d, &target, world, resources, transform2d, camera2d);
+                    draw_calls += 2;
+                }
+            }
+        }
+
+        // NOTE: the custom cursor is drawn over all cameras.
+        draw_calls += cs_rder.render(&r2d, &r2d.target(RenderTarget::Screen), resources);
 
         r2d.finish_draw();
+
+        if let Some(mut debug_draw) = resources.get_mut::<DebugDraw>() {
+            debug_draw.update();
+        }
+        if let Some(mut frame_stats) = resources.get_mut::<FrameStats>() {
+            frame_stats.draw_calls = draw_calls;
+        }
+
+        r2d.drop_unused_render_textures(resources);
+        resources.insert(trf);
     };
 
-    AppStageBuilder::new(String::from("default_render"))
+    AppStageBuilder::new("default_render")
+        .tag(AppStageTag::Render)
         .add_thread_local_fn_process(render_process)
         .build()
 }
@@ -158,6 +281,8 @@ struct Render2D {
     depth_texture: Texture,
 
     viewport: Viewport,
+    // The off-screen framebuffers of `RenderTexture`s.
+    render_textures: HashMap<RenderTextureHandle, RenderTextureTarget>,
     // // NOTE: 临时性数据, 用于决定是否更新shader
     // vhash: u64,
     // fhash: u64,
@@ -200,7 +325,8 @@ impl Render2D {
             mapped_at_creation: false,
         });
 
-        let depth_texture = Texture::create_depth_texture(&gpu.device, &gpu.sc_desc);
+        let depth_texture =
+            Texture::create_depth_texture(&gpu.device, gpu.sc_desc.width, gpu.sc_desc.height);
 
         let (width, height) = window.resolution();
         let viewport = Viewport::new_in_screen(
@@ -219,6 +345,7 @@ impl Render2D {
             depth_texture,
 
             viewport,
+            render_textures: Default::default(),
             // // NOTE: 临时性数据
             // vhash: 0,
             // fhash: 0,
@@ -236,16 +363,13 @@ impl Render2D {
         }
     }
 
-    fn process(&mut self, world: &mut World, resources: &mut Resources) -> Transformation {
-        // Get window size.
-        let (width, height) = {
-            let window = resources
-                .get::<Window>()
-                .expect("ERR: Not find window resource.");
-            window.resolution()
-        };
+    /// Resize swap chain and depth texture to the size of window.
+    fn resize(&mut self, resources: &Resources) {
+        let (width, height) = resources
+            .get::<Window>()
+            .expect("ERR: Not find window resource.")
+            .physical_size();
 
-        // Resize swap_chain and depth texture.
         if self.gpu.sc_desc.width != width || self.gpu.sc_desc.height != height {
             self.gpu.sc_desc.width = width;
             self.gpu.sc_desc.height = height;
@@ -255,70 +379,163 @@ impl Render2D {
                 .device
                 .create_swap_chain(&self.gpu.surface, &self.gpu.sc_desc);
 
-            self.depth_texture = Texture::create_depth_texture(&self.gpu.device, &self.gpu.sc_desc);
+            self.depth_texture = Texture::create_depth_texture(&self.gpu.device, width, height);
         }
+    }
 
-        // Get camera2d.
-        let mut query_camera2d = <(&Transform2D, &Camera2D)>::query();
+    /// Write the datas of camera to utility buffer and set the viewport, create the off-screen framebuffer
+    /// if the camera renders to a `RenderTexture`.
+    ///
+    /// Return the `Transformation` of camera in logical pixels, or `None` if the `RenderTexture` of camera
+    /// not exists.
+    fn prepare_camera(
+        &mut self,
+        transform2d: &Transform2D,
+        camera2d: &Camera2D,
+        resources: &Resources,
+    ) -> Option<Transformation> {
+        let ((width, height), (logical_width, logical_height)) = match camera2d.render_target {
+            RenderTarget::Screen => {
+                let window = resources
+                    .get::<Window>()
+                    .expect("ERR: Not find window resource.");
+                (window.physical_size(), window.logical_size())
+            }
+            RenderTarget::Texture(handle) => {
+                let render_texture = *resources.get::<Assets<RenderTexture>>()?.get(handle)?;
+                self.prepare_render_texture(handle, &render_texture);
+
+                let (width, height) = (render_texture.width, render_texture.height);
+                ((width, height), (width as f32, height as f32))
+            }
+        };
 
-        // Render a frame if there has a camera.
         let mut ct = Transformation::default();
-        if let Some((transform2d, camera2d)) = query_camera2d.iter(world).next() {
-            ct.mx_view = transform2d.to_homogeneous_3d().try_inverse().unwrap();
-            ct.mx_proj = {
-                #[cfg_attr(rustfmt, rustfmt_skip)]
-                let opengl_to_wgpu_matrix: Matrix4<f32> = Matrix4::new(
-                    1.0, 0.0, 0.0, 0.0,
-                    0.0, 1.0, 0.0, 0.0,
-                    0.0, 0.0, 0.5, 0.5,
-                    0.0, 0.0, 0.0, 1.0,
-                );
-
-                opengl_to_wgpu_matrix * camera2d.to_orthographic_homogeneous()
-            };
-            let viewport =
-                Viewport::new_in_screen(width as f32, height as f32, camera2d.aspect_ratio());
-            ct.mx_viewport = viewport.to_homogeneous_3d();
-
-            let time = resources
-                .get::<Time>()
-                .expect("ERR: Not find time resource.");
-
-            // Write matrix data to utility buffer.
-            self.gpu.queue.write_buffer(
-                &self.utility_buf,
-                0,
-                bytemuck::cast_slice(ct.mx_view.as_slice()),
-            );
-            self.gpu.queue.write_buffer(
-                &self.utility_buf,
-                64,
-                bytemuck::cast_slice(ct.mx_proj.as_slice()),
-            );
-            self.gpu.queue.write_buffer(
-                &self.utility_buf,
-                128,
-                bytemuck::cast_slice(ct.mx_viewport.as_slice()),
+        ct.mx_view = transform2d.to_homogeneous_3d().try_inverse().unwrap();
+        ct.mx_proj = {
+            #[cfg_attr(rustfmt, rustfmt_skip)]
+            let opengl_to_wgpu_matrix: Matrix4<f32> = Matrix4::new(
+                1.0, 0.0, 0.0, 0.0,
+                0.0, 1.0, 0.0, 0.0,
+                0.0, 0.0, 0.5, 0.5,
+                0.0, 0.0, 0.0, 1.0,
             );
 
-            // Write viewport_size to utility buffer.
-            self.gpu.queue.write_buffer(
-                &self.utility_buf,
-                192,
-                bytemuck::cast_slice(Vector2::new(width as f32, height as f32).as_slice()),
-            );
+            opengl_to_wgpu_matrix * camera2d.to_orthographic_homogeneous()
+        };
+        let viewport = camera2d.viewport_in(width as f32, height as f32);
+        ct.mx_viewport = viewport.to_homogeneous_3d();
+
+        let time = resources
+            .get::<Time>()
+            .expect("ERR: Not find time resource.");
+
+        // Write matrix data to utility buffer.
+        self.gpu.queue.write_buffer(
+            &self.utility_buf,
+            0,
+            bytemuck::cast_slice(ct.mx_view.as_slice()),
+        );
+        self.gpu.queue.write_buffer(
+            &self.utility_buf,
+            64,
+            bytemuck::cast_slice(ct.mx_proj.as_slice()),
+        );
+        self.gpu.queue.write_buffer(
+            &self.utility_buf,
+            128,
+            bytemuck::cast_slice(ct.mx_viewport.as_slice()),
+        );
+
+        // Write viewport_size to utility buffer.
+        self.gpu.queue.write_buffer(
+            &self.utility_buf,
+            192,
+            bytemuck::cast_slice(Vector2::new(width as f32, height as f32).as_slice()),
+        );
 
-            // Write time(delta, total) to utility buffer.
-            self.gpu.queue.write_buffer(
-                &self.utility_buf,
-                200,
-                bytemuck::cast_slice(&[time.delta().as_secs_f32(), time.total().as_secs_f32()]),
+        // Write time(delta, total) to utility buffer.
+        self.gpu.queue.write_buffer(
+            &self.utility_buf,
+            200,
+            bytemuck::cast_slice(&[time.delta().as_secs_f32(), time.total().as_secs_f32()]),
+        );
+
+        self.viewport = viewport;
+
+        // NOTE: the gpu draws in physical pixels, but the `Transformation` handed to `Input` works in
+        // logical pixels, so cursor positions map to the same world point on any scale factor.
+        ct.mx_viewport = camera2d
+            .viewport_in(logical_width, logical_height)
+            .to_homogeneous_3d();
+
+        Some(ct)
+    }
+
+    /// Create the off-screen framebuffer of `RenderTexture`, or recreate it if the size has changed.
+    fn prepare_render_texture(
+        &mut self,
+        handle: RenderTextureHandle,
+        render_texture: &RenderTexture,
+    ) {
+        let size = (render_texture.width, render_texture.height);
+
+        if self.render_textures.get(&handle).map(|target| target.size) != Some(size) {
+            let device = &self.gpu.device;
+
+            self.render_textures.insert(
+                handle,
+                RenderTextureTarget {
+                    size,
+                    color: Texture::create_color_texture(
+                        device,
+                        size.0,
+                        size.1,
+                        self.gpu.sc_desc.format,
+                    ),
+                    depth: Texture::create_depth_texture(device, size.0, size.1),
+                },
             );
+        }
+    }
 
-            self.viewport = viewport;
+    /// Free the off-screen framebuffers whose `RenderTexture` has been removed.
+    fn drop_unused_render_textures(&mut self, resources: &Resources) {
+        match resources.get::<Assets<RenderTexture>>() {
+            Some(assets) => self
+                .render_textures
+                .retain(|handle, _| assets.contains(*handle)),
+            None => self.render_textures.clear(),
         }
+    }
 
-        ct
+    /// The attachments to render to, must be called between `begin_draw` and `finish_draw`.
+    fn target(&self, render_target: RenderTarget) -> RenderPassTarget<'_> {
+        match render_target {
+            RenderTarget::Screen => RenderPassTarget {
+                color: &self
+                    .gpu
+                    .frame
+                    .as_ref()
+                    .expect("ERR: Not call begin_draw.")
+                    .output
+                    .view,
+                depth: &self.depth_texture.view,
+                size: (self.gpu.sc_desc.width, self.gpu.sc_desc.height),
+            },
+            RenderTarget::Texture(handle) => {
+                let target = self
+                    .render_textures
+                    .get(&handle)
+                    .expect("ERR: The render texture is not prepared.");
+
+                RenderPassTarget {
+                    color: &target.color.view,
+                    depth: &target.depth.view,
+                    size: target.size,
+                }
+            }
+        }
     }
 
     fn finish_draw(&mut self) {
@@ -330,6 +547,42 @@ impl Render2D {
     }
 }
 
+/// The color and depth attachments of a render pass.
+struct RenderPassTarget<'a> {
+    color: &'a wgpu::TextureView,
+    depth: &'a wgpu::TextureView,
+    // The size of attachments in pixels.
+    size: (u32, u32),
+}
+
+impl<'a> RenderPassTarget<'a> {
+    /// The pixels of viewport inside the attachments, (x, y, width, height), passed to `set_scissor_rect`.
+    fn scissor_rect(&self, viewport: &Viewport) -> (u32, u32, u32, u32) {
+        let Viewport { x, y, w, h, .. } = *viewport;
+
+        let (left, top) = (
+            (x.max(0.0) as u32).min(self.size.0),
+            (y.max(0.0) as u32).min(self.size.1),
+        );
+        let right = ((x + w).max(0.0).ceil() as u32).min(self.size.0);
+        let bottom = ((y + h).max(0.0).ceil() as u32).min(self.size.1);
+
+        (
+            left,
+            top,
+            right.saturating_sub(left),
+            bottom.saturating_sub(top),
+        )
+    }
+}
+
+/// The off-screen framebuffer of a `RenderTexture`.
+struct RenderTextureTarget {
+    size: (u32, u32),
+    color: Texture,
+    depth: Texture,
+}
+
 struct Texture {
     #[allow(dead_code)]
     texture: wgpu::Texture,
@@ -339,18 +592,112 @@ struct Texture {
 }
 
 impl Texture {
-    fn create_depth_texture(device: &wgpu::Device, sc_desc: &wgpu::SwapChainDescriptor) -> Self {
+    /// Create a sampled texture and upload the pixels of image to it.
+    fn from_image(device: &wgpu::Device, queue: &wgpu::Queue, image: &sprite::Texture) -> Self {
+        let size = wgpu::Extent3d {
+            width: image.width(),
+            height: image.height(),
+            depth: 1,
+        };
+
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("image texture"),
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::Rgba8Unorm,
+            usage: wgpu::TextureUsage::SAMPLED | wgpu::TextureUsage::COPY_DST,
+        });
+
+        queue.write_texture(
+            wgpu::TextureCopyView {
+                texture: &texture,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+            },
+            image.pixels(),
+            wgpu::TextureDataLayout {
+                offset: 0,
+                bytes_per_row: 4 * image.width(),
+                rows_per_image: image.height(),
+            },
+            size,
+        );
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        // NOTE: sample the nearest texel, the regions of atlas don't bleed into each other.
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Nearest,
+            min_filter: wgpu::FilterMode::Nearest,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        Self {
+            texture,
+            view,
+            sampler,
+        }
+    }
+
+    fn create_color_texture(
+        device: &wgpu::Device,
+        width: u32,
+        height: u32,
+        format: wgpu::TextureFormat,
+    ) -> Self {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("render texture"),
+            size: wgpu::Extent3d {
+                width,
+                height,
+                depth: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format,
+            usage: wgpu::TextureUsage::RENDER_ATTACHMENT
+                | wgpu::TextureUsage::SAMPLED
+                | wgpu::TextureUsage::COPY_SRC,
+        });
+
+        let view = texture.create_view(&wgpu::TextureViewDescriptor::default());
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        Self {
+            texture,
+            view,
+            sampler,
+        }
+    }
+
+    fn create_depth_texture(device: &wgpu::Device, width: u32, height: u32) -> Self {
         let texture = device.create_texture(&wgpu::TextureDescriptor {
             label: Some("depth texture"),
             size: wgpu::Extent3d {
-                width: sc_desc.width,
-                height: sc_desc.height,
+                width,
+                height,
                 depth: 1,
             },
             mip_level_count: 1,
             sample_count: 1,
             dimension: wgpu::TextureDimension::D2,
-            format: wgpu::TextureFormat::Depth32Float,
+            format: DEPTH_FORMAT,
             usage: wgpu::TextureUsage::RENDER_ATTACHMENT | wgpu::TextureUsage::SAMPLED,
         });
 
@@ -376,3 +723,19 @@ impl Texture {
         }
     }
 }
+
+// The transform the entity is rendered with, the `GlobalTransform2D` if it has one, the `Parallax2D` offset
+// applied.
+fn render_transform(
+    transform: &Transform2D,
+    global: Option<&GlobalTransform2D>,
+    parallax: Option<&Parallax2D>,
+) -> Transform2D {
+    let transform = global.map_or(transform, |global| &global.0);
+    parallax.map_or(*transform, |parallax| parallax.apply(transform))
+}
+
+// Whether the two boxes of (min, max) overlap.
+fn overlaps(a: &(Vector2<f32>, Vector2<f32>), b: &(Vector2<f32>, Vector2<f32>)) -> bool {
+    a.0.x <= b.1.x && a.1.x >= b.0.x && a.0.y <= b.1.y && a.1.y >= b.0.y
+}
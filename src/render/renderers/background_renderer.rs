@@ -1,10 +1,6 @@
-use super::super::{Gpu, Render2D, Viewport};
+use super::super::{Gpu, Render2D, RenderPassTarget, Viewport, DEPTH_FORMAT};
 
-use crate::{
-    legion::{Resources, World},
-    misc::color::Rgba,
-    nalgebra::Vector4,
-};
+use crate::{misc::color::Rgba, nalgebra::Vector4};
 
 use std::mem::size_of;
 
@@ -105,7 +101,7 @@ impl BackgroundRenderer {
                 ..Default::default()
             },
             depth_stencil: Some(wgpu::DepthStencilState {
-                format: wgpu::TextureFormat::Depth32Float,
+                format: DEPTH_FORMAT,
                 depth_write_enabled: true,
                 depth_compare: wgpu::CompareFunction::LessEqual,
                 stencil: wgpu::StencilState::default(),
@@ -121,13 +117,9 @@ impl BackgroundRenderer {
         }
     }
 
-    pub fn render(&mut self, r2d: &Render2D, _world: &World, _resources: &Resources) {
-        let Gpu {
-            device,
-            queue,
-            frame,
-            ..
-        } = &r2d.gpu;
+    /// Render background to the target, clear the target before rendering if `clear` is true.
+    pub fn render(&mut self, r2d: &Render2D, target: &RenderPassTarget, clear: bool) {
+        let Gpu { device, queue, .. } = &r2d.gpu;
 
         let Viewport {
             x,
@@ -138,7 +130,12 @@ impl BackgroundRenderer {
             max_depth,
         } = r2d.viewport;
 
-        let frame = frame.as_ref().expect("ERR: Not call begin_draw");
+        // NOTE: only the first camera of the target clears it, others keep what have been rendered.
+        let color_load = if clear {
+            wgpu::LoadOp::Clear(Rgba::SOFT_BLACK.to_wgpu_color())
+        } else {
+            wgpu::LoadOp::Load
+        };
 
         let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
             label: Some("background encoder"),
@@ -149,20 +146,23 @@ impl BackgroundRenderer {
             let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("background render pass"),
                 color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
-                    attachment: &frame.output.view,
+                    attachment: target.color,
                     resolve_target: None,
                     ops: wgpu::Operations {
-                        load: wgpu::LoadOp::Clear(Rgba::SOFT_BLACK.to_wgpu_color()),
+                        load: color_load,
                         store: true,
                     },
                 }],
                 depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachmentDescriptor {
-                    attachment: &r2d.depth_texture.view,
+                    attachment: target.depth,
                     depth_ops: Some(wgpu::Operations {
                         load: wgpu::LoadOp::Clear(1.0),
                         store: false,
                     }),
-                    stencil_ops: None,
+                    stencil_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(0),
+                        store: false,
+                    }),
                 }),
             });
 
@@ -170,6 +170,9 @@ impl BackgroundRenderer {
 
             rpass.set_pipeline(&self.pipeline);
             rpass.set_viewport(x, y, w, h, min_depth, max_depth);
+            // NOTE: only draw in the viewport of camera, the other cameras may share the target(split-screen).
+            let (sx, sy, sw, sh) = target.scissor_rect(&r2d.viewport);
+            rpass.set_scissor_rect(sx, sy, sw, sh);
             rpass.set_vertex_buffer(0, r2d.quad_vertex_buf.slice(..));
             rpass.set_index_buffer(r2d.quad_index_buf.slice(..), wgpu::IndexFormat::Uint16);
             rpass.set_bind_group(0, &self.bind_group, &[]);
@@ -0,0 +1,253 @@
+use super::super::{Gpu, Render2D, RenderPassTarget, Texture};
+
+use crate::{
+    components::sprite,
+    input::Input,
+    legion::Resources,
+    nalgebra::Vector4,
+    window::{CursorImage, CursorSettings, Window},
+};
+
+use std::mem::size_of;
+
+/// The rect of cursor image in `NDC`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct CursorInstance {
+    rect_min: [f32; 2],
+    rect_max: [f32; 2],
+}
+
+/// Renderer which renders the image of `CursorIcon::Custom` at the cursor position, over everything on
+/// the screen.
+pub(in super::super) struct CursorRenderer {
+    instance_buf: wgpu::Buffer,
+
+    texture_bind_group_layout: wgpu::BindGroupLayout,
+    pipeline: wgpu::RenderPipeline,
+
+    // The uploaded cursor image and its bind group.
+    cursor: Option<(CursorImage, Texture, wgpu::BindGroup)>,
+}
+
+impl CursorRenderer {
+    pub fn new(r2d: &Render2D) -> Self {
+        let Gpu {
+            device, sc_desc, ..
+        } = &r2d.gpu;
+
+        let instance_buf = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("cursor instance buffer"),
+            size: size_of::<CursorInstance>() as u64,
+            usage: wgpu::BufferUsage::VERTEX | wgpu::BufferUsage::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let texture_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("cursor texture bind group layout"),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStage::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStage::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler {
+                            filtering: true,
+                            comparison: false,
+                        },
+                        count: None,
+                    },
+                ],
+            });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("cursor pipeline layout"),
+            bind_group_layouts: &[&texture_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let vert_shader = device.create_shader_module(&wgpu::ShaderModuleDescriptor {
+            label: Some("cursor vertex shader"),
+            source: wgpu::util::make_spirv(include_bytes!(
+                "../../../assets/shaders/cursor/cursor.vert.spv"
+            )),
+            flags: wgpu::ShaderFlags::empty(),
+        });
+
+        let frag_shader = device.create_shader_module(&wgpu::ShaderModuleDescriptor {
+            label: Some("cursor fragment shader"),
+            source: wgpu::util::make_spirv(include_bytes!(
+                "../../../assets/shaders/cursor/cursor.frag.spv"
+            )),
+            flags: wgpu::ShaderFlags::empty(),
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("cursor pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &vert_shader,
+                entry_point: "main",
+                buffers: &[
+                    wgpu::VertexBufferLayout {
+                        array_stride: size_of::<Vector4<f32>>() as wgpu::BufferAddress,
+                        step_mode: wgpu::InputStepMode::Vertex,
+                        attributes: &wgpu::vertex_attr_array![0 => Float4],
+                    },
+                    wgpu::VertexBufferLayout {
+                        array_stride: size_of::<CursorInstance>() as wgpu::BufferAddress,
+                        step_mode: wgpu::InputStepMode::Instance,
+                        attributes: &wgpu::vertex_attr_array![1 => Float2, 2 => Float2],
+                    },
+                ],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &frag_shader,
+                entry_point: "main",
+                targets: &[wgpu::ColorTargetState {
+                    format: sc_desc.format,
+                    color_blend: wgpu::BlendState {
+                        src_factor: wgpu::BlendFactor::SrcAlpha,
+                        dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+                        operation: wgpu::BlendOperation::Add,
+                    },
+                    alpha_blend: wgpu::BlendState {
+                        src_factor: wgpu::BlendFactor::One,
+                        dst_factor: wgpu::BlendFactor::One,
+                        operation: wgpu::BlendOperation::Max,
+                    },
+                    write_mask: wgpu::ColorWrite::ALL,
+                }],
+            }),
+            primitive: wgpu::PrimitiveState {
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: wgpu::CullMode::None,
+                ..Default::default()
+            },
+            depth_stencil: None,
+            multisample: Default::default(),
+        });
+
+        Self {
+            instance_buf,
+
+            texture_bind_group_layout,
+            pipeline,
+
+            cursor: None,
+        }
+    }
+
+    /// Render the custom cursor of `Window` to the screen target, the hotspot of image is at the cursor
+    /// position.
+    ///
+    /// Do nothing if there is no custom cursor or the cursor is hidden by `CursorSettings`.
+    ///
+    /// Return the number of draw calls.
+    pub fn render(
+        &mut self,
+        r2d: &Render2D,
+        target: &RenderPassTarget,
+        resources: &Resources,
+    ) -> usize {
+        let Gpu { device, queue, .. } = &r2d.gpu;
+
+        let window = match resources.get::<Window>() {
+            Some(window) => window,
+            None => return 0,
+        };
+        let image = match window.custom_cursor() {
+            Some(image) if image.width > 0 && image.height > 0 => image,
+            _ => return 0,
+        };
+        let visible = resources
+            .get::<CursorSettings>()
+            .map_or(true, |settings| settings.visible());
+        let position = match resources.get::<Input>() {
+            Some(input) if visible => input.mouse.physical_position(),
+            _ => return 0,
+        };
+
+        // Upload the image if it's changed.
+        if self.cursor.as_ref().map_or(true, |(old, ..)| old != image) {
+            let pixels =
+                match sprite::Texture::from_rgba(image.width, image.height, image.rgba.clone()) {
+                    Ok(pixels) => pixels,
+                    Err(_) => return 0,
+                };
+
+            let texture = Texture::from_image(device, queue, &pixels);
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("cursor texture bind group"),
+                layout: &self.texture_bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(&texture.view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(&texture.sampler),
+                    },
+                ],
+            });
+
+            self.cursor = Some((image.clone(), texture, bind_group));
+        }
+
+        // NOTE: the image is drawn in physical pixels, from the top left corner of window.
+        let (w, h) = (target.size.0 as f32, target.size.1 as f32);
+        let left = position.0 - image.hotspot.0 as f32;
+        let top = position.1 - image.hotspot.1 as f32;
+        let to_ndc = |x: f32, y: f32| [x / w * 2.0 - 1.0, 1.0 - y / h * 2.0];
+
+        let instance = CursorInstance {
+            rect_min: to_ndc(left, top + image.height as f32),
+            rect_max: to_ndc(left + image.width as f32, top),
+        };
+        queue.write_buffer(&self.instance_buf, 0, bytemuck::bytes_of(&instance));
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("cursor encoder"),
+        });
+
+        encoder.insert_debug_marker("render cursor");
+        {
+            let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("cursor render pass"),
+                color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
+                    attachment: target.color,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Load,
+                        store: true,
+                    },
+                }],
+                depth_stencil_attachment: None,
+            });
+
+            rpass.set_pipeline(&self.pipeline);
+            // NOTE: the cursor is not limited to the viewport of any camera.
+            rpass.set_viewport(0.0, 0.0, w, h, 0.0, 1.0);
+            rpass.set_vertex_buffer(0, r2d.quad_vertex_buf.slice(..));
+            rpass.set_index_buffer(r2d.quad_index_buf.slice(..), wgpu::IndexFormat::Uint16);
+            rpass.set_vertex_buffer(1, self.instance_buf.slice(..));
+            rpass.set_bind_group(0, &self.cursor.as_ref().unwrap().2, &[]);
+
+            rpass.draw_indexed(0..6, 0, 0..1);
+        }
+
+        queue.submit(Some(encoder.finish()));
+
+        1
+    }
+}
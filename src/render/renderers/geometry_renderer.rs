@@ -1,16 +1,24 @@
-use super::super::{Gpu, Render2D, Viewport, MILLION};
+use super::super::{
+    overlaps, render_transform, Gpu, Render2D, RenderPassTarget, Viewport, DEPTH_FORMAT, MILLION,
+};
 
 use crate::{
     components::{
+        blend::BlendMode,
+        camera::{Camera2D, RenderLayer},
         geometry::{Assembly, Geometry},
+        hierarchy::GlobalTransform2D,
+        parallax::Parallax2D,
+        particle::ParticleEmitter2D,
+        stencil::{RenderGroup, StencilMask, StencilMode},
         transform::Transform2D,
     },
-    legion::{IntoQuery, Resources, World},
+    legion::{IntoQuery, World},
     nalgebra::Vector4,
     Instance,
 };
 
-use std::mem::size_of;
+use std::{collections::BTreeMap, mem::size_of, ops::Range};
 
 #[rustfmt::skip] const MAX_TRANSFORM2D_COUNT:   usize = 2 * MILLION;
 #[rustfmt::skip] const MAX_GEOMETRY_COUNT:      usize = 2 * MILLION;
@@ -31,6 +39,19 @@ pub(in super::super) struct GeometryRenderer {
     // For `Geometry2D` rendering.
     bind_group: wgpu::BindGroup,
     pipeline: wgpu::RenderPipeline,
+    // The pipelines of entities with `BlendMode`, indexed by `BlendMode::index`.
+    blend_pipelines: Vec<wgpu::RenderPipeline>,
+    // The pipelines of entities in `RenderGroup`, indexed by `StencilMode::index`.
+    stencil_pipelines: Vec<wgpu::RenderPipeline>,
+}
+
+/// The pipeline of a range of instances.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Batch {
+    Plain,
+    Blend(BlendMode),
+    /// With the stencil reference of the group.
+    Stencil(StencilMode, u32),
 }
 
 impl GeometryRenderer {
@@ -142,75 +163,191 @@ impl GeometryRenderer {
             flags: wgpu::ShaderFlags::empty(),
         });
 
-        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("geometry pipeline"),
-            layout: Some(&pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &vert_shader,
-                entry_point: "main",
-                buffers: &[
-                    wgpu::VertexBufferLayout {
-                        array_stride: size_of::<Vector4<f32>>() as wgpu::BufferAddress,
-                        step_mode: wgpu::InputStepMode::Vertex,
-                        attributes: &wgpu::vertex_attr_array![0 => Float4],
-                    },
-                    wgpu::VertexBufferLayout {
-                        array_stride: size_of::<(u32, u32)>() as wgpu::BufferAddress,
-                        step_mode: wgpu::InputStepMode::Instance,
-                        attributes: &wgpu::vertex_attr_array![1 => Uint2],
-                    },
-                ],
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &frag_shader,
-                entry_point: "main",
-                targets: &[wgpu::ColorTargetState {
-                    format: sc_desc.format,
-                    color_blend: wgpu::BlendState {
-                        src_factor: wgpu::BlendFactor::SrcAlpha,
-                        dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
-                        operation: wgpu::BlendOperation::Add,
-                    },
-                    alpha_blend: wgpu::BlendState {
-                        src_factor: wgpu::BlendFactor::One,
-                        dst_factor: wgpu::BlendFactor::One,
-                        operation: wgpu::BlendOperation::Max,
-                    },
-                    write_mask: wgpu::ColorWrite::ALL,
-                }],
-            }),
-            primitive: wgpu::PrimitiveState {
-                front_face: wgpu::FrontFace::Ccw,
-                cull_mode: wgpu::CullMode::None,
-                ..Default::default()
-            },
-            depth_stencil: Some(wgpu::DepthStencilState {
-                format: wgpu::TextureFormat::Depth32Float,
-                depth_write_enabled: true,
-                depth_compare: wgpu::CompareFunction::LessEqual,
-                stencil: wgpu::StencilState::default(),
-                bias: wgpu::DepthBiasState::default(),
-                clamp_depth: device.features().contains(wgpu::Features::DEPTH_CLAMPING),
-            }),
-            multisample: Default::default(),
+        // NOTE: the same as the fragment shader but discards the uncovered fragments, only the masks use it.
+        let mask_frag_shader = device.create_shader_module(&wgpu::ShaderModuleDescriptor {
+            label: Some("geometry mask fragment shader"),
+            source: wgpu::util::make_spirv(include_bytes!(
+                "../../../assets/shaders/geometry/geometry_mask.frag.spv"
+            )),
+            flags: wgpu::ShaderFlags::empty(),
         });
 
+        let create_pipeline = |frag_shader: &wgpu::ShaderModule,
+                               color_blend: wgpu::BlendState,
+                               depth_write_enabled: bool,
+                               write_mask: wgpu::ColorWrite,
+                               stencil: wgpu::StencilState| {
+            device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("geometry pipeline"),
+                layout: Some(&pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &vert_shader,
+                    entry_point: "main",
+                    buffers: &[
+                        wgpu::VertexBufferLayout {
+                            array_stride: size_of::<Vector4<f32>>() as wgpu::BufferAddress,
+                            step_mode: wgpu::InputStepMode::Vertex,
+                            attributes: &wgpu::vertex_attr_array![0 => Float4],
+                        },
+                        wgpu::VertexBufferLayout {
+                            array_stride: size_of::<(u32, u32)>() as wgpu::BufferAddress,
+                            step_mode: wgpu::InputStepMode::Instance,
+                            attributes: &wgpu::vertex_attr_array![1 => Uint2],
+                        },
+                    ],
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: frag_shader,
+                    entry_point: "main",
+                    targets: &[wgpu::ColorTargetState {
+                        format: sc_desc.format,
+                        color_blend,
+                        alpha_blend: wgpu::BlendState {
+                            src_factor: wgpu::BlendFactor::One,
+                            dst_factor: wgpu::BlendFactor::One,
+                            operation: wgpu::BlendOperation::Max,
+                        },
+                        write_mask,
+                    }],
+                }),
+                primitive: wgpu::PrimitiveState {
+                    front_face: wgpu::FrontFace::Ccw,
+                    cull_mode: wgpu::CullMode::None,
+                    ..Default::default()
+                },
+                depth_stencil: Some(wgpu::DepthStencilState {
+                    format: DEPTH_FORMAT,
+                    depth_write_enabled,
+                    depth_compare: wgpu::CompareFunction::LessEqual,
+                    stencil,
+                    bias: wgpu::DepthBiasState::default(),
+                    clamp_depth: device.features().contains(wgpu::Features::DEPTH_CLAMPING),
+                }),
+                multisample: Default::default(),
+            })
+        };
+
+        let pipeline = create_pipeline(
+            &frag_shader,
+            Self::color_blend(BlendMode::Alpha),
+            true,
+            wgpu::ColorWrite::ALL,
+            wgpu::StencilState::default(),
+        );
+        let blend_pipelines = BlendMode::ALL
+            .iter()
+            .map(|mode| {
+                create_pipeline(
+                    &frag_shader,
+                    Self::color_blend(*mode),
+                    *mode == BlendMode::Opaque,
+                    wgpu::ColorWrite::ALL,
+                    wgpu::StencilState::default(),
+                )
+            })
+            .collect();
+        // NOTE: the masks write no color and no depth, so they don't hide the entities behind them.
+        let stencil_pipelines = StencilMode::ALL
+            .iter()
+            .map(|mode| match mode {
+                StencilMode::Write => create_pipeline(
+                    &mask_frag_shader,
+                    Self::color_blend(BlendMode::Alpha),
+                    false,
+                    wgpu::ColorWrite::empty(),
+                    Self::stencil_state(*mode),
+                ),
+                _ => create_pipeline(
+                    &frag_shader,
+                    Self::color_blend(BlendMode::Alpha),
+                    true,
+                    wgpu::ColorWrite::ALL,
+                    Self::stencil_state(*mode),
+                ),
+            })
+            .collect();
+
         Self {
             instance_buf,
             storage_buf,
 
             bind_group,
             pipeline,
+            blend_pipelines,
+            stencil_pipelines,
         }
     }
 
-    pub fn render(&mut self, r2d: &Render2D, world: &World, _resources: &Resources) {
-        let Gpu {
-            device,
-            queue,
-            frame,
-            ..
-        } = &r2d.gpu;
+    // The masks replace the stencil value with the reference of group, the others compare with it.
+    fn stencil_state(mode: StencilMode) -> wgpu::StencilState {
+        let (compare, pass_op, write_mask) = match mode {
+            StencilMode::Write => (
+                wgpu::CompareFunction::Always,
+                wgpu::StencilOperation::Replace,
+                0xFF,
+            ),
+            StencilMode::ReadInside => (
+                wgpu::CompareFunction::Equal,
+                wgpu::StencilOperation::Keep,
+                0x00,
+            ),
+            StencilMode::ReadOutside => (
+                wgpu::CompareFunction::NotEqual,
+                wgpu::StencilOperation::Keep,
+                0x00,
+            ),
+        };
+        let face = || wgpu::StencilFaceState {
+            compare,
+            fail_op: wgpu::StencilOperation::Keep,
+            // the masks are written even if they are behind the other entities.
+            depth_fail_op: pass_op,
+            pass_op,
+        };
+
+        wgpu::StencilState {
+            front: face(),
+            back: face(),
+            read_mask: 0xFF,
+            write_mask,
+        }
+    }
+
+    // NOTE: the fragment shader outputs the color multiplied by the coverage of shape.
+    fn color_blend(mode: BlendMode) -> wgpu::BlendState {
+        let (src_factor, dst_factor) = match mode {
+            BlendMode::Opaque => (wgpu::BlendFactor::One, wgpu::BlendFactor::OneMinusSrcAlpha),
+            BlendMode::Alpha => (
+                wgpu::BlendFactor::SrcAlpha,
+                wgpu::BlendFactor::OneMinusSrcAlpha,
+            ),
+            BlendMode::Additive => (wgpu::BlendFactor::SrcAlpha, wgpu::BlendFactor::One),
+            BlendMode::Multiply => (
+                wgpu::BlendFactor::DstColor,
+                wgpu::BlendFactor::OneMinusSrcAlpha,
+            ),
+            BlendMode::Screen => (wgpu::BlendFactor::One, wgpu::BlendFactor::OneMinusSrcColor),
+            BlendMode::Overlay => (wgpu::BlendFactor::DstColor, wgpu::BlendFactor::SrcColor),
+        };
+
+        wgpu::BlendState {
+            src_factor,
+            dst_factor,
+            operation: wgpu::BlendOperation::Add,
+        }
+    }
+
+    /// Render the entities seen by the camera and the `extra` geometries in `world space` to the target.
+    pub fn render(
+        &mut self,
+        r2d: &Render2D,
+        target: &RenderPassTarget,
+        world: &World,
+        camera_transform: &Transform2D,
+        camera2d: &Camera2D,
+        extra: &[Geometry],
+    ) {
+        let Gpu { device, queue, .. } = &r2d.gpu;
 
         let Viewport {
             x,
@@ -221,20 +358,19 @@ impl GeometryRenderer {
             max_depth,
         } = r2d.viewport;
 
-        let frame = frame.as_ref().expect("ERR: Not call begin_draw.");
-
         let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
             label: Some("general encoder"),
         });
 
-        let (i_count, i_buf_size) = self.copy_data_to_gpu(&mut encoder, r2d, world);
+        let (batches, i_buf_size) =
+            self.copy_data_to_gpu(&mut encoder, r2d, world, camera_transform, camera2d, extra);
 
         encoder.insert_debug_marker("render geometry");
         {
             let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
                 label: Some("geometry render pass"),
                 color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
-                    attachment: &(frame.output.view),
+                    attachment: target.color,
                     resolve_target: None,
                     ops: wgpu::Operations {
                         load: wgpu::LoadOp::Load,
@@ -242,19 +378,24 @@ impl GeometryRenderer {
                     },
                 }],
                 depth_stencil_attachment: Some(wgpu::RenderPassDepthStencilAttachmentDescriptor {
-                    attachment: &r2d.depth_texture.view,
+                    attachment: target.depth,
                     depth_ops: Some(wgpu::Operations {
                         load: wgpu::LoadOp::Clear(1.0),
                         store: false,
                     }),
-                    stencil_ops: None,
+                    stencil_ops: Some(wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(0),
+                        store: false,
+                    }),
                 }),
             });
 
             rpass.push_debug_group("Set datas");
 
-            rpass.set_pipeline(&self.pipeline);
             rpass.set_viewport(x, y, w, h, min_depth, max_depth);
+            // NOTE: only draw in the viewport of camera, the other cameras may share the target(split-screen).
+            let (sx, sy, sw, sh) = target.scissor_rect(&r2d.viewport);
+            rpass.set_scissor_rect(sx, sy, sw, sh);
             rpass.set_vertex_buffer(0, r2d.quad_vertex_buf.slice(..));
             rpass.set_index_buffer(r2d.quad_index_buf.slice(..), wgpu::IndexFormat::Uint16);
             rpass.set_vertex_buffer(1, self.instance_buf.slice(0..i_buf_size));
@@ -262,16 +403,31 @@ impl GeometryRenderer {
 
             rpass.pop_debug_group();
 
-            rpass.draw_indexed(0..6, 0, 0..i_count as u32);
+            for (batch, instances) in batches {
+                match batch {
+                    Batch::Plain => rpass.set_pipeline(&self.pipeline),
+                    Batch::Blend(mode) => rpass.set_pipeline(&self.blend_pipelines[mode.index()]),
+                    Batch::Stencil(mode, reference) => {
+                        rpass.set_pipeline(&self.stencil_pipelines[mode.index()]);
+                        rpass.set_stencil_reference(reference);
+                    }
+                }
+
+                rpass.draw_indexed(0..6, 0, instances);
+            }
         }
 
         queue.submit(Some(encoder.finish()));
     }
 
-    /// Collect `Transform2D`, `Geometry` and calculate `Index Pair`, then
-    /// copy them to the memory of video card.
+    /// Collect `Transform2D`, `Geometry`(also particles of `ParticleEmitter2D`) seen by the camera and the `extra`
+    /// geometries, calculate `Index Pair`, then copy them to the memory of video card.
     ///
-    /// Return instance count and instance size.
+    /// The geometries whose `world_aabb` doesn't overlap the `world_bounds` of the camera are culled.
+    ///
+    /// Return the instance ranges of pipelines in the order of drawing and instance size. The entities in
+    /// `RenderGroup`s are drawn after the others, group by group, with `BlendMode` ignored, then the `extra`
+    /// geometries.
     ///
     /// #Panics
     ///
@@ -284,7 +440,10 @@ impl GeometryRenderer {
         encoder: &mut wgpu::CommandEncoder,
         r2d: &Render2D,
         world: &World,
-    ) -> (usize, wgpu::BufferAddress) {
+        camera_transform: &Transform2D,
+        camera2d: &Camera2D,
+        extra: &[Geometry],
+    ) -> (Vec<(Batch, Range<u32>)>, wgpu::BufferAddress) {
         let Gpu { device, .. } = &r2d.gpu;
 
         let t_st = 0;
@@ -323,75 +482,214 @@ impl GeometryRenderer {
         let mut g_count: usize = 0;
         let mut i_count: usize = 0;
 
+        let mut batches = Vec::new();
+
+        let bounds = camera2d.world_bounds(camera_transform);
+        let pixel_size = camera2d.pixel_size();
+
+        // (order, transform index, geometry index) of the entities with `BlendMode`, indexed by `BlendMode::index`.
+        let mut blended: Vec<Vec<(u8, u32, u32)>> = vec![Vec::new(); BlendMode::ALL.len()];
+        // The same of the entities in `RenderGroup`s, indexed by `StencilMode::index` in each group.
+        let mut grouped: BTreeMap<RenderGroup, Vec<Vec<(u8, u32, u32)>>> = BTreeMap::new();
+
+        // Panic if `count` items can't be written to the buffer of `max` items, it's checked before every
+        // unchecked write.
+        macro_rules! ensure_capacity {
+            ($count:expr, $max:expr, $name:expr) => {
+                if $count > $max {
+                    panic!("ERR: The number of {} exceeds the limit: {}", $name, $max);
+                }
+            };
+        }
+
+        // Write the index pair to the buffer, or hold it to sort if the entity has `BlendMode` or is in a group.
+        macro_rules! push_index_pair {
+            ($blend:expr, $stencil:expr, $t:expr, $g:expr) => {
+                // NOTE: the order is only read for the entities which are sorted.
+                let sorted = || (g_slice.get_unchecked($g).order(), $t as u32, $g as u32);
+
+                match ($stencil, $blend) {
+                    (Some((group, mode)), _) => {
+                        let modes = grouped
+                            .entry(group)
+                            .or_insert_with(|| vec![Vec::new(); StencilMode::ALL.len()]);
+                        modes[mode.index()].push(sorted());
+                    }
+                    (None, Some(mode)) => blended[mode.index()].push(sorted()),
+                    (None, None) => {
+                        ensure_capacity!(i_count + 1, MAX_INDEX_PAIR_COUNT, "Index_Pair");
+                        *i_slice.get_unchecked_mut(i_count) = ($t as u32, $g as u32);
+                        i_count += 1;
+                    }
+                }
+            };
+        }
+
         // Copy `Transform2D` and `Geometry` data from `World` to the buffer which is mapped to staging_buf.
         unsafe {
-            let mut q01 = <(&Transform2D, &Geometry)>::query();
-            let mut q02 = <(&Transform2D, &Assembly)>::query();
-            let mut q03 = <(&Instance<Transform2D>, &Geometry)>::query();
-            let mut q04 = <(&Instance<Transform2D>, &Assembly)>::query();
+            // NOTE: the transform is nested with the components which change where it's rendered.
+            let mut q01 = <(
+                (
+                    &Transform2D,
+                    Option<&GlobalTransform2D>,
+                    Option<&Parallax2D>,
+                ),
+                &Geometry,
+                Option<&RenderLayer>,
+                Option<&BlendMode>,
+                Option<&RenderGroup>,
+                Option<&StencilMask>,
+                Option<&StencilMode>,
+            )>::query();
+            let mut q02 = <(
+                (
+                    &Transform2D,
+                    Option<&GlobalTransform2D>,
+                    Option<&Parallax2D>,
+                ),
+                &Assembly,
+                Option<&RenderLayer>,
+                Option<&BlendMode>,
+                Option<&RenderGroup>,
+                Option<&StencilMask>,
+                Option<&StencilMode>,
+            )>::query();
+            let mut q03 = <(
+                &Instance<Transform2D>,
+                &Geometry,
+                Option<&RenderLayer>,
+                Option<&BlendMode>,
+                Option<&RenderGroup>,
+                Option<&StencilMask>,
+                Option<&StencilMode>,
+            )>::query();
+            let mut q04 = <(
+                &Instance<Transform2D>,
+                &Assembly,
+                Option<&RenderLayer>,
+                Option<&BlendMode>,
+                Option<&RenderGroup>,
+                Option<&StencilMask>,
+                Option<&StencilMode>,
+            )>::query();
+            let mut q05 = <(
+                &ParticleEmitter2D,
+                Option<&RenderLayer>,
+                Option<&BlendMode>,
+                Option<&RenderGroup>,
+                Option<&StencilMask>,
+                Option<&StencilMode>,
+            )>::query();
+
+            q01.for_each(world, |((t, gt, p), g, l, b, r, m, sm)| {
+                let s = StencilMode::of(r, m, sm);
+                let t = &render_transform(t, gt, p);
+
+                if !camera2d.sees(l) || !overlaps(&bounds, &g.world_aabb(t, pixel_size)) {
+                    return;
+                }
 
-            q01.for_each(world, |(t, g)| {
+                ensure_capacity!(t_count + 1, MAX_TRANSFORM2D_COUNT, "Transform2D");
+                ensure_capacity!(g_count + 1, MAX_GEOMETRY_COUNT, "Geometry");
                 *t_slice.get_unchecked_mut(t_count) = *t;
                 *g_slice.get_unchecked_mut(g_count) = *g;
-                *i_slice.get_unchecked_mut(i_count) = (t_count as u32, g_count as u32);
+                push_index_pair!(b, s, t_count, g_count);
 
                 t_count += 1;
                 g_count += 1;
-                i_count += 1;
             });
 
-            q02.for_each(world, |(t, gs)| {
-                *t_slice.get_unchecked_mut(t_count) = *t;
+            q02.for_each(world, |((t, gt, p), gs, l, b, r, m, sm)| {
+                let s = StencilMode::of(r, m, sm);
+                let t = &render_transform(t, gt, p);
+
+                if !camera2d.sees(l)
+                    || !gs
+                        .iter()
+                        .any(|g| overlaps(&bounds, &g.world_aabb(t, pixel_size)))
+                {
+                    return;
+                }
 
                 let g_len = gs.len();
 
+                ensure_capacity!(t_count + 1, MAX_TRANSFORM2D_COUNT, "Transform2D");
+                ensure_capacity!(g_count + g_len, MAX_GEOMETRY_COUNT, "Geometry");
+                *t_slice.get_unchecked_mut(t_count) = *t;
+
                 let g_part = &mut g_slice[g_count..g_count + g_len];
                 g_part.copy_from_slice(gs);
 
                 for _ in 0..g_len {
-                    *i_slice.get_unchecked_mut(i_count) = (t_count as u32, g_count as u32);
+                    push_index_pair!(b, s, t_count, g_count);
 
                     g_count += 1;
-                    i_count += 1;
                 }
 
                 t_count += 1;
             });
 
-            q03.for_each(world, |(ts, g)| {
-                let t_len = ts.len();
+            q03.for_each(world, |(ts, g, l, b, r, m, sm)| {
+                let s = StencilMode::of(r, m, sm);
 
-                let t_part = &mut t_slice[t_count..t_count + t_len];
-                t_part.copy_from_slice(ts);
+                if !camera2d.sees(l) {
+                    return;
+                }
+
+                // NOTE: the geometry is shared by the transforms, only the corners of its box are transformed.
+                let local_aabb = g.local_aabb();
+                let mut visible = ts
+                    .iter()
+                    .filter(|t| overlaps(&bounds, &g.world_aabb_of(local_aabb, t, pixel_size)))
+                    .peekable();
+                if visible.peek().is_none() {
+                    return;
+                }
 
+                ensure_capacity!(g_count + 1, MAX_GEOMETRY_COUNT, "Geometry");
                 *g_slice.get_unchecked_mut(g_count) = *g;
 
-                for _ in 0..t_len {
-                    *i_slice.get_unchecked_mut(i_count) = (t_count as u32, g_count as u32);
+                for t in visible {
+                    ensure_capacity!(t_count + 1, MAX_TRANSFORM2D_COUNT, "Transform2D");
+                    *t_slice.get_unchecked_mut(t_count) = *t;
+                    push_index_pair!(b, s, t_count, g_count);
 
                     t_count += 1;
-                    i_count += 1;
                 }
 
                 g_count += 1;
             });
 
-            q04.for_each(world, |(ts, gs)| {
+            // The boxes of the geometries of an assembly, reused by the assemblies.
+            let mut local_aabbs = Vec::new();
+            q04.for_each(world, |(ts, gs, l, b, r, m, sm)| {
+                let s = StencilMode::of(r, m, sm);
+
+                if !camera2d.sees(l) {
+                    return;
+                }
+
                 let t_len = ts.len();
                 let g_len = gs.len();
 
+                ensure_capacity!(t_count + t_len, MAX_TRANSFORM2D_COUNT, "Transform2D");
+                ensure_capacity!(g_count + g_len, MAX_GEOMETRY_COUNT, "Geometry");
                 let t_part = &mut t_slice[t_count..t_count + t_len];
                 let g_part = &mut g_slice[g_count..g_count + g_len];
 
                 t_part.copy_from_slice(ts);
                 g_part.copy_from_slice(gs);
 
+                local_aabbs.clear();
+                local_aabbs.extend(gs.iter().map(|g| g.local_aabb()));
                 for t in 0..t_len {
                     for g in 0..g_len {
-                        *i_slice.get_unchecked_mut(i_count) =
-                            ((t_count + t) as u32, (g_count + g) as u32);
-
-                        i_count += 1;
+                        if overlaps(
+                            &bounds,
+                            &gs[g].world_aabb_of(local_aabbs[g], &ts[t], pixel_size),
+                        ) {
+                            push_index_pair!(b, s, t_count + t, g_count + g);
+                        }
                     }
                 }
 
@@ -399,26 +697,103 @@ impl GeometryRenderer {
                 g_count += g_len;
             });
 
-            if t_count > MAX_TRANSFORM2D_COUNT {
-                panic!(
-                    "ERR: The number of Transform2D exceeds the limit: {}",
-                    MAX_TRANSFORM2D_COUNT
-                );
+            q05.for_each(world, |(emitter, l, b, r, m, sm)| {
+                let s = StencilMode::of(r, m, sm);
+
+                if !camera2d.sees(l) {
+                    return;
+                }
+
+                for (t, g) in emitter
+                    .renderables()
+                    .filter(|(t, g)| overlaps(&bounds, &g.world_aabb(t, pixel_size)))
+                {
+                    ensure_capacity!(t_count + 1, MAX_TRANSFORM2D_COUNT, "Transform2D");
+                    ensure_capacity!(g_count + 1, MAX_GEOMETRY_COUNT, "Geometry");
+                    *t_slice.get_unchecked_mut(t_count) = t;
+                    *g_slice.get_unchecked_mut(g_count) = g;
+                    push_index_pair!(b, s, t_count, g_count);
+
+                    t_count += 1;
+                    g_count += 1;
+                }
+            });
+
+            // Append the entities with `BlendMode` after the others, mode by mode.
+            let unblended_count = i_count;
+            let mut ranges = Vec::with_capacity(BlendMode::ALL.len());
+            for mode in BlendMode::ALL.iter() {
+                let pairs = &mut blended[mode.index()];
+
+                // Back to front, opaque entities rely on the depth test.
+                if *mode != BlendMode::Opaque {
+                    pairs.sort_by_key(|(order, _, _)| *order);
+                }
+
+                ensure_capacity!(i_count + pairs.len(), MAX_INDEX_PAIR_COUNT, "Index_Pair");
+                let start = i_count;
+                for (_, t, g) in pairs.iter() {
+                    *i_slice.get_unchecked_mut(i_count) = (*t, *g);
+                    i_count += 1;
+                }
+
+                ranges.push((Batch::Blend(*mode), start as u32..i_count as u32));
             }
 
-            if g_count > MAX_GEOMETRY_COUNT {
-                panic!(
-                    "ERR: The number of Geometry exceeds the limit: {}",
-                    MAX_GEOMETRY_COUNT
-                );
+            // Draw the opaque entities first, then the entities without `BlendMode`, then the others.
+            batches.push(ranges[BlendMode::Opaque.index()].clone());
+            batches.push((Batch::Plain, 0..unblended_count as u32));
+            batches.extend(
+                ranges
+                    .into_iter()
+                    .filter(|(batch, _)| *batch != Batch::Blend(BlendMode::Opaque)),
+            );
+
+            // Then the groups, the masks of a group are written before the entities clipped by them.
+            for (group, modes) in grouped.iter_mut() {
+                for mode in StencilMode::ALL.iter() {
+                    let pairs = &mut modes[mode.index()];
+                    pairs.sort_by_key(|(order, _, _)| *order);
+
+                    ensure_capacity!(i_count + pairs.len(), MAX_INDEX_PAIR_COUNT, "Index_Pair");
+                    let start = i_count;
+                    for (_, t, g) in pairs.iter() {
+                        *i_slice.get_unchecked_mut(i_count) = (*t, *g);
+                        i_count += 1;
+                    }
+
+                    batches.push((
+                        Batch::Stencil(*mode, group.stencil_reference()),
+                        start as u32..i_count as u32,
+                    ));
+                }
             }
 
-            if i_count > MAX_INDEX_PAIR_COUNT {
-                panic!(
-                    "ERR: The number of Index_Pair exceeds the limit: {}",
-                    MAX_INDEX_PAIR_COUNT
-                );
+            // The `extra` geometries are drawn last, over all entities.
+            if !extra.is_empty() {
+                let g_len = extra.len();
+
+                ensure_capacity!(t_count + 1, MAX_TRANSFORM2D_COUNT, "Transform2D");
+                ensure_capacity!(g_count + g_len, MAX_GEOMETRY_COUNT, "Geometry");
+                ensure_capacity!(i_count + g_len, MAX_INDEX_PAIR_COUNT, "Index_Pair");
+                *t_slice.get_unchecked_mut(t_count) = Transform2D::default();
+
+                let g_part = &mut g_slice[g_count..g_count + g_len];
+                g_part.copy_from_slice(extra);
+
+                let start = i_count;
+                for _ in 0..g_len {
+                    *i_slice.get_unchecked_mut(i_count) = (t_count as u32, g_count as u32);
+
+                    g_count += 1;
+                    i_count += 1;
+                }
+
+                t_count += 1;
+                batches.push((Batch::Plain, start as u32..i_count as u32));
             }
+
+            batches.retain(|(_, instances)| !instances.is_empty());
         }
 
         r2d.staging_buf.unmap();
@@ -434,6 +809,6 @@ impl GeometryRenderer {
         // Copy index pair data from staging to instance.
         encoder.copy_buffer_to_buffer(&r2d.staging_buf, i_st, &self.instance_buf, 0, i_buf_size);
 
-        (i_count, i_buf_size)
+        (batches, i_buf_size)
     }
 }
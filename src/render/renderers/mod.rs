@@ -1,2 +1,6 @@
 pub mod background_renderer;
+pub mod cursor_renderer;
 pub mod geometry_renderer;
+pub mod post_process_renderer;
+pub mod sprite_renderer;
+pub mod tilemap_renderer;
@@ -0,0 +1,291 @@
+use super::super::{Gpu, Render2D, RenderPassTarget, Texture};
+
+use crate::{
+    components::{post_process::PostProcessStack, time::Time},
+    legion::Resources,
+    misc::color::Rgba,
+};
+
+use std::collections::HashMap;
+
+// The size of `Params` in shader: `vec4 params0; vec4 params1; vec2 texel_size; float t_total;`.
+const PARAMS_BUF_SIZE: u64 = 48;
+
+/// Renderer which applies `PostProcessStack` to what a camera has rendered.
+///
+/// The camera renders to the first of two intermediate textures, then every effect reads one and writes
+/// the other, the last effect writes to the real target.
+pub(in super::super) struct PostProcessRenderer {
+    params_buf: wgpu::Buffer,
+    sampler: wgpu::Sampler,
+
+    bind_group_layout: wgpu::BindGroupLayout,
+    pipeline_layout: wgpu::PipelineLayout,
+    vert_shader: wgpu::ShaderModule,
+    // Pipelines cached by `PostEffect::name`.
+    pipelines: HashMap<&'static str, wgpu::RenderPipeline>,
+
+    size: (u32, u32),
+    textures: Vec<Texture>,
+}
+
+impl PostProcessRenderer {
+    pub fn new(r2d: &Render2D) -> Self {
+        let Gpu { device, .. } = &r2d.gpu;
+
+        let params_buf = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("post process params buffer"),
+            size: PARAMS_BUF_SIZE,
+            usage: wgpu::BufferUsage::UNIFORM | wgpu::BufferUsage::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("post process sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            address_mode_w: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Linear,
+            min_filter: wgpu::FilterMode::Linear,
+            mipmap_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("post process bind group layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStage::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStage::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler {
+                        filtering: true,
+                        comparison: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 2,
+                    visibility: wgpu::ShaderStage::FRAGMENT,
+                    ty: wgpu::BindingType::Buffer {
+                        ty: wgpu::BufferBindingType::Uniform,
+                        has_dynamic_offset: false,
+                        min_binding_size: None,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("post process pipeline layout"),
+            bind_group_layouts: &[&bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let vert_shader = device.create_shader_module(&wgpu::ShaderModuleDescriptor {
+            label: Some("post process vertex shader"),
+            source: wgpu::util::make_spirv(include_bytes!(
+                "../../../assets/shaders/post_process/fullscreen.vert.spv"
+            )),
+            flags: wgpu::ShaderFlags::empty(),
+        });
+
+        Self {
+            params_buf,
+            sampler,
+
+            bind_group_layout,
+            pipeline_layout,
+            vert_shader,
+            pipelines: Default::default(),
+
+            size: (0, 0),
+            textures: Vec::new(),
+        }
+    }
+
+    /// Create the intermediate textures in the size of target and the pipelines of effects.
+    pub fn prepare(&mut self, r2d: &Render2D, target: &RenderPassTarget, stack: &PostProcessStack) {
+        let Gpu {
+            device, sc_desc, ..
+        } = &r2d.gpu;
+
+        if self.size != target.size || self.textures.is_empty() {
+            self.size = target.size;
+            self.textures = (0..2)
+                .map(|_| {
+                    Texture::create_color_texture(
+                        device,
+                        target.size.0,
+                        target.size.1,
+                        sc_desc.format,
+                    )
+                })
+                .collect();
+        }
+
+        for effect in stack.enabled_effects() {
+            if self.pipelines.contains_key(effect.name()) {
+                continue;
+            }
+
+            let frag_shader = device.create_shader_module(&wgpu::ShaderModuleDescriptor {
+                label: Some(effect.name()),
+                source: wgpu::util::make_spirv(effect.fragment_shader()),
+                flags: wgpu::ShaderFlags::empty(),
+            });
+
+            let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+                label: Some("post process pipeline"),
+                layout: Some(&self.pipeline_layout),
+                vertex: wgpu::VertexState {
+                    module: &self.vert_shader,
+                    entry_point: "main",
+                    buffers: &[],
+                },
+                fragment: Some(wgpu::FragmentState {
+                    module: &frag_shader,
+                    entry_point: "main",
+                    targets: &[wgpu::ColorTargetState {
+                        format: sc_desc.format,
+                        color_blend: wgpu::BlendState {
+                            src_factor: wgpu::BlendFactor::One,
+                            dst_factor: wgpu::BlendFactor::Zero,
+                            operation: wgpu::BlendOperation::Add,
+                        },
+                        alpha_blend: wgpu::BlendState {
+                            src_factor: wgpu::BlendFactor::One,
+                            dst_factor: wgpu::BlendFactor::Zero,
+                            operation: wgpu::BlendOperation::Add,
+                        },
+                        write_mask: wgpu::ColorWrite::ALL,
+                    }],
+                }),
+                primitive: wgpu::PrimitiveState {
+                    front_face: wgpu::FrontFace::Ccw,
+                    cull_mode: wgpu::CullMode::None,
+                    ..Default::default()
+                },
+                depth_stencil: None,
+                multisample: Default::default(),
+            });
+
+            self.pipelines.insert(effect.name(), pipeline);
+        }
+    }
+
+    /// The target the camera should render to before `render`, must be called after `prepare`.
+    pub fn scene_target<'a>(&'a self, target: &RenderPassTarget<'a>) -> RenderPassTarget<'a> {
+        RenderPassTarget {
+            color: &self.textures[0].view,
+            depth: target.depth,
+            size: target.size,
+        }
+    }
+
+    /// Apply the effects in order and write the result to the viewport of target, clear the rest of target
+    /// if `clear` is true.
+    pub fn render(
+        &self,
+        r2d: &Render2D,
+        target: &RenderPassTarget,
+        stack: &PostProcessStack,
+        resources: &Resources,
+        clear: bool,
+    ) {
+        let Gpu { device, queue, .. } = &r2d.gpu;
+
+        let t_total = resources
+            .get::<Time>()
+            .map_or(0.0, |time| time.total().as_secs_f32());
+
+        let effects = stack.enabled_effects().collect::<Vec<_>>();
+
+        for (index, effect) in effects.iter().enumerate() {
+            let is_last = index + 1 == effects.len();
+
+            let source = &self.textures[index % 2];
+            let (attachment, load) = if !is_last {
+                (&self.textures[(index + 1) % 2].view, wgpu::LoadOp::Load)
+            } else if clear {
+                (
+                    target.color,
+                    wgpu::LoadOp::Clear(Rgba::SOFT_BLACK.to_wgpu_color()),
+                )
+            } else {
+                (target.color, wgpu::LoadOp::Load)
+            };
+
+            // Write the params of effect, the write happens before the next submit.
+            let mut params = [0f32; 12];
+            params[..8].copy_from_slice(&effect.params());
+            params[8] = 1.0 / self.size.0 as f32;
+            params[9] = 1.0 / self.size.1 as f32;
+            params[10] = t_total;
+            queue.write_buffer(&self.params_buf, 0, bytemuck::cast_slice(&params));
+
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("post process bind group"),
+                layout: &self.bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry {
+                        binding: 0,
+                        resource: wgpu::BindingResource::TextureView(&source.view),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 1,
+                        resource: wgpu::BindingResource::Sampler(&self.sampler),
+                    },
+                    wgpu::BindGroupEntry {
+                        binding: 2,
+                        resource: wgpu::BindingResource::Buffer {
+                            buffer: &self.params_buf,
+                            offset: 0,
+                            size: None,
+                        },
+                    },
+                ],
+            });
+
+            let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("post process encoder"),
+            });
+
+            encoder.insert_debug_marker(effect.name());
+            {
+                let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                    label: Some("post process render pass"),
+                    color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
+                        attachment,
+                        resolve_target: None,
+                        ops: wgpu::Operations { load, store: true },
+                    }],
+                    depth_stencil_attachment: None,
+                });
+
+                rpass.set_pipeline(&self.pipelines[effect.name()]);
+                rpass.set_bind_group(0, &bind_group, &[]);
+
+                // NOTE: the last effect only writes the viewport of camera, keep what other cameras rendered.
+                if is_last {
+                    let (sx, sy, sw, sh) = target.scissor_rect(&r2d.viewport);
+                    rpass.set_scissor_rect(sx, sy, sw, sh);
+                }
+
+                rpass.draw(0..3, 0..1);
+            }
+
+            queue.submit(Some(encoder.finish()));
+        }
+    }
+}
@@ -0,0 +1,449 @@
+use super::super::{
+    overlaps, render_transform, Gpu, Render2D, RenderPassTarget, Texture, Viewport, THOUSAND,
+};
+
+use crate::{
+    components::{
+        camera::{Camera2D, RenderLayer},
+        hierarchy::GlobalTransform2D,
+        parallax::Parallax2D,
+        sprite::{Sprite2D, TextureAtlas, TextureHandle},
+        text::{FontAtlas, Text2D},
+        transform::Transform2D,
+    },
+    legion::{IntoQuery, Resources, World},
+    misc::debug_draw::DebugDraw,
+    nalgebra::{Point2, Vector4},
+};
+
+use std::{collections::HashMap, mem::size_of, ops::Range};
+
+#[rustfmt::skip] const MAX_SPRITE_COUNT:        usize = 256 * THOUSAND;
+
+#[rustfmt::skip] const INSTANCE_BUF_SIZE:       u64 = (size_of::<SpriteInstance>() * MAX_SPRITE_COUNT) as u64;
+
+/// The per-instance datas of a sprite.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct SpriteInstance {
+    position: [f32; 2],
+    complex: [f32; 2],
+    scale: [f32; 2],
+    size: [f32; 2],
+    pivot: [f32; 2],
+    uv_min: [f32; 2],
+    uv_max: [f32; 2],
+    // The color tint in hex(0xRRGGBBAA).
+    color: u32,
+}
+
+impl SpriteInstance {
+    fn new(transform2d: &Transform2D, sprite: &Sprite2D, texture_size: (u32, u32)) -> Self {
+        let (uv_min, uv_max) = sprite.uv_rect(texture_size);
+
+        Self {
+            position: transform2d.position.into(),
+            complex: [transform2d.rotation.re, transform2d.rotation.im],
+            scale: transform2d.scale.into(),
+            size: sprite.size.into(),
+            pivot: sprite.pivot.into(),
+            uv_min: uv_min.into(),
+            uv_max: uv_max.into(),
+            color: sprite.color_tint.to_u32_rgba(),
+        }
+    }
+}
+
+/// Renderer which renders `Sprite2D`, `Text2D` and the labels of `DebugDraw` with the textures in
+/// `TextureAtlas`, one draw call per texture.
+pub(in super::super) struct SpriteRenderer {
+    instance_buf: wgpu::Buffer,
+
+    bind_group: wgpu::BindGroup,
+    texture_bind_group_layout: wgpu::BindGroupLayout,
+    pipeline: wgpu::RenderPipeline,
+
+    // The uploaded textures of `TextureAtlas` and their bind groups.
+    textures: HashMap<TextureHandle, (Texture, wgpu::BindGroup)>,
+}
+
+impl SpriteRenderer {
+    pub fn new(r2d: &Render2D) -> Self {
+        let Gpu {
+            device, sc_desc, ..
+        } = &r2d.gpu;
+
+        let instance_buf = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("sprite instance buffer"),
+            size: INSTANCE_BUF_SIZE,
+            usage: wgpu::BufferUsage::VERTEX | wgpu::BufferUsage::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("sprite bind group layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStage::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("sprite bind group"),
+            layout: &bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::Buffer {
+                    buffer: &r2d.utility_buf,
+                    offset: 0,
+                    size: None,
+                },
+            }],
+        });
+
+        let texture_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("sprite texture bind group layout"),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStage::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStage::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler {
+                            filtering: true,
+                            comparison: false,
+                        },
+                        count: None,
+                    },
+                ],
+            });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("sprite pipeline layout"),
+            bind_group_layouts: &[&bind_group_layout, &texture_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let vert_shader = device.create_shader_module(&wgpu::ShaderModuleDescriptor {
+            label: Some("sprite vertex shader"),
+            source: wgpu::util::make_spirv(include_bytes!(
+                "../../../assets/shaders/sprite/sprite.vert.spv"
+            )),
+            flags: wgpu::ShaderFlags::empty(),
+        });
+
+        let frag_shader = device.create_shader_module(&wgpu::ShaderModuleDescriptor {
+            label: Some("sprite fragment shader"),
+            source: wgpu::util::make_spirv(include_bytes!(
+                "../../../assets/shaders/sprite/sprite.frag.spv"
+            )),
+            flags: wgpu::ShaderFlags::empty(),
+        });
+
+        // NOTE: sprites are drawn over the geometries in the order of textures, no depth test.
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("sprite pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &vert_shader,
+                entry_point: "main",
+                buffers: &[
+                    wgpu::VertexBufferLayout {
+                        array_stride: size_of::<Vector4<f32>>() as wgpu::BufferAddress,
+                        step_mode: wgpu::InputStepMode::Vertex,
+                        attributes: &wgpu::vertex_attr_array![0 => Float4],
+                    },
+                    wgpu::VertexBufferLayout {
+                        array_stride: size_of::<SpriteInstance>() as wgpu::BufferAddress,
+                        step_mode: wgpu::InputStepMode::Instance,
+                        attributes: &wgpu::vertex_attr_array![
+                            1 => Float2,
+                            2 => Float2,
+                            3 => Float2,
+                            4 => Float2,
+                            5 => Float2,
+                            6 => Float2,
+                            7 => Float2,
+                            8 => Uint
+                        ],
+                    },
+                ],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &frag_shader,
+                entry_point: "main",
+                targets: &[wgpu::ColorTargetState {
+                    format: sc_desc.format,
+                    color_blend: wgpu::BlendState {
+                        src_factor: wgpu::BlendFactor::SrcAlpha,
+                        dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+                        operation: wgpu::BlendOperation::Add,
+                    },
+                    alpha_blend: wgpu::BlendState {
+                        src_factor: wgpu::BlendFactor::One,
+                        dst_factor: wgpu::BlendFactor::One,
+                        operation: wgpu::BlendOperation::Max,
+                    },
+                    write_mask: wgpu::ColorWrite::ALL,
+                }],
+            }),
+            primitive: wgpu::PrimitiveState {
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: wgpu::CullMode::None,
+                ..Default::default()
+            },
+            depth_stencil: None,
+            multisample: Default::default(),
+        });
+
+        Self {
+            instance_buf,
+
+            bind_group,
+            texture_bind_group_layout,
+            pipeline,
+
+            textures: Default::default(),
+        }
+    }
+
+    /// Render the sprites and texts seen by the camera to the target, then the labels of `DebugDraw` over them.
+    ///
+    /// Do nothing if there is no `TextureAtlas` resource, the texts are skipped if there is no `FontAtlas`
+    /// resource.
+    ///
+    /// Return the number of draw calls.
+    ///
+    /// # Panics
+    ///
+    /// Panic if the number of visible sprites exceeds the limit: `MAX_SPRITE_COUNT`.
+    pub fn render(
+        &mut self,
+        r2d: &Render2D,
+        target: &RenderPassTarget,
+        world: &World,
+        resources: &Resources,
+        camera_transform: &Transform2D,
+        camera2d: &Camera2D,
+    ) -> usize {
+        let Gpu { device, queue, .. } = &r2d.gpu;
+
+        let atlas = match resources.get::<TextureAtlas>() {
+            Some(atlas) => atlas,
+            None => return 0,
+        };
+
+        let fonts = resources.get::<FontAtlas>();
+        let debug_draw = resources.get::<DebugDraw>();
+
+        let (instances, batches) = self.collect(
+            &atlas,
+            fonts.as_deref(),
+            debug_draw.as_deref(),
+            world,
+            camera_transform,
+            camera2d,
+        );
+        if batches.is_empty() {
+            return 0;
+        }
+
+        if instances.len() > MAX_SPRITE_COUNT {
+            panic!(
+                "ERR: The number of Sprite2D exceeds the limit: {}",
+                MAX_SPRITE_COUNT
+            );
+        }
+
+        // Upload the textures drawn the first time.
+        for (handle, _) in batches.iter() {
+            if !self.textures.contains_key(handle) {
+                let texture = Texture::from_image(device, queue, atlas.get(*handle).unwrap());
+                let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("sprite texture bind group"),
+                    layout: &self.texture_bind_group_layout,
+                    entries: &[
+                        wgpu::BindGroupEntry {
+                            binding: 0,
+                            resource: wgpu::BindingResource::TextureView(&texture.view),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 1,
+                            resource: wgpu::BindingResource::Sampler(&texture.sampler),
+                        },
+                    ],
+                });
+
+                self.textures.insert(*handle, (texture, bind_group));
+            }
+        }
+
+        queue.write_buffer(&self.instance_buf, 0, bytemuck::cast_slice(&instances));
+        let i_buf_size = (instances.len() * size_of::<SpriteInstance>()) as wgpu::BufferAddress;
+
+        let Viewport {
+            x,
+            y,
+            w,
+            h,
+            min_depth,
+            max_depth,
+        } = r2d.viewport;
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("sprite encoder"),
+        });
+
+        encoder.insert_debug_marker("render sprite");
+        {
+            let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("sprite render pass"),
+                color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
+                    attachment: target.color,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Load,
+                        store: true,
+                    },
+                }],
+                depth_stencil_attachment: None,
+            });
+
+            rpass.push_debug_group("Set datas");
+
+            rpass.set_pipeline(&self.pipeline);
+            rpass.set_viewport(x, y, w, h, min_depth, max_depth);
+            // NOTE: only draw in the viewport of camera, the other cameras may share the target(split-screen).
+            let (sx, sy, sw, sh) = target.scissor_rect(&r2d.viewport);
+            rpass.set_scissor_rect(sx, sy, sw, sh);
+            rpass.set_vertex_buffer(0, r2d.quad_vertex_buf.slice(..));
+            rpass.set_index_buffer(r2d.quad_index_buf.slice(..), wgpu::IndexFormat::Uint16);
+            rpass.set_vertex_buffer(1, self.instance_buf.slice(0..i_buf_size));
+            rpass.set_bind_group(0, &self.bind_group, &[]);
+
+            rpass.pop_debug_group();
+
+            for (handle, instances) in batches.iter() {
+                rpass.set_bind_group(1, &self.textures[handle].1, &[]);
+                rpass.draw_indexed(0..6, 0, instances.clone());
+            }
+        }
+
+        queue.submit(Some(encoder.finish()));
+
+        batches.len()
+    }
+
+    /// Collect the instances of the sprites and the glyphs seen by the camera, grouped by texture, the glyphs
+    /// of `DebugDraw` labels come last.
+    ///
+    /// Return the instances and the instance range of each texture, the sprites whose texture not
+    /// exists or is empty are skipped.
+    fn collect(
+        &self,
+        atlas: &TextureAtlas,
+        fonts: Option<&FontAtlas>,
+        debug_draw: Option<&DebugDraw>,
+        world: &World,
+        camera_transform: &Transform2D,
+        camera2d: &Camera2D,
+    ) -> (Vec<SpriteInstance>, Vec<(TextureHandle, Range<u32>)>) {
+        let bounds = camera2d.world_bounds(camera_transform);
+
+        let mut sprites = Vec::new();
+        let push_sprite = |sprites: &mut Vec<_>, t: &Transform2D, sprite: &Sprite2D| {
+            let size = match atlas.size(sprite.texture_handle) {
+                Some(size) if size.0 > 0 && size.1 > 0 => size,
+                _ => return,
+            };
+
+            if overlaps(&bounds, &sprite.world_aabb(t)) {
+                sprites.push((sprite.texture_handle, SpriteInstance::new(t, sprite, size)));
+            }
+        };
+
+        <(
+            (
+                &Transform2D,
+                Option<&GlobalTransform2D>,
+                Option<&Parallax2D>,
+            ),
+            &Sprite2D,
+            Option<&RenderLayer>,
+        )>::query()
+        .for_each(world, |((t, gt, p), sprite, l)| {
+            if camera2d.sees(l) {
+                push_sprite(&mut sprites, &render_transform(t, gt, p), sprite);
+            }
+        });
+
+        if let Some(fonts) = fonts {
+            <(
+                (
+                    &Transform2D,
+                    Option<&GlobalTransform2D>,
+                    Option<&Parallax2D>,
+                ),
+                &Text2D,
+                Option<&RenderLayer>,
+            )>::query()
+            .for_each(world, |((t, gt, p), text, l)| {
+                if !camera2d.sees(l) {
+                    return;
+                }
+
+                let t = &render_transform(t, gt, p);
+
+                for (position, glyph) in text.sprites(fonts) {
+                    let glyph_transform = Transform2D {
+                        position: t.transform_point2(&Point2::from(position)).coords,
+                        ..*t
+                    };
+                    push_sprite(&mut sprites, &glyph_transform, &glyph);
+                }
+            });
+        }
+
+        // NOTE: the sort is stable, sprites of the same texture keep the order of query.
+        sprites.sort_by_key(|(handle, _)| *handle);
+
+        // NOTE: debug labels are drawn by every camera, over all sprites.
+        if let (Some(fonts), Some(debug_draw)) = (fonts, debug_draw) {
+            for (position, glyph) in debug_draw.text_sprites(fonts) {
+                push_sprite(
+                    &mut sprites,
+                    &Transform2D::at(position.x, position.y),
+                    &glyph,
+                );
+            }
+        }
+
+        let mut batches: Vec<(TextureHandle, Range<u32>)> = Vec::new();
+        for (index, (handle, _)) in sprites.iter().enumerate() {
+            match batches.last_mut() {
+                Some((last, range)) if last == handle => range.end = index as u32 + 1,
+                _ => batches.push((*handle, index as u32..index as u32 + 1)),
+            }
+        }
+
+        (
+            sprites.into_iter().map(|(_, instance)| instance).collect(),
+            batches,
+        )
+    }
+}
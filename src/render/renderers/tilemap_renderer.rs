@@ -0,0 +1,337 @@
+use super::super::{Gpu, Render2D, RenderPassTarget, Texture, Viewport, THOUSAND};
+
+use crate::{
+    assets::{Assets, Handle},
+    components::{
+        camera::{Camera2D, RenderLayer},
+        sprite,
+        tilemap::{TileInstance, TilemapRenderer2D},
+    },
+    legion::{IntoQuery, Resources, World},
+    nalgebra::Vector4,
+};
+
+use std::{collections::HashMap, mem::size_of, ops::Range};
+
+#[rustfmt::skip] const MAX_TILE_COUNT:          usize = 256 * THOUSAND;
+
+#[rustfmt::skip] const INSTANCE_BUF_SIZE:       u64 = (size_of::<TileInstanceRaw>() * MAX_TILE_COUNT) as u64;
+
+/// The per-instance datas of a tile.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+struct TileInstanceRaw {
+    position: [f32; 2],
+    size: [f32; 2],
+    uv_min: [f32; 2],
+    uv_max: [f32; 2],
+}
+
+impl From<&TileInstance> for TileInstanceRaw {
+    fn from(tile: &TileInstance) -> Self {
+        Self {
+            position: tile.position.into(),
+            size: tile.size.into(),
+            uv_min: tile.uv.min.into(),
+            uv_max: tile.uv.max.into(),
+        }
+    }
+}
+
+/// Renderer which renders the tiles prepared in `TilemapRenderer2D::batch` with the tilesets in
+/// `Assets<Texture>`, one draw call per tilemap.
+pub(in super::super) struct TilemapRenderer {
+    instance_buf: wgpu::Buffer,
+
+    bind_group: wgpu::BindGroup,
+    texture_bind_group_layout: wgpu::BindGroupLayout,
+    pipeline: wgpu::RenderPipeline,
+
+    // The uploaded tilesets and their bind groups.
+    tilesets: HashMap<Handle<sprite::Texture>, (Texture, wgpu::BindGroup)>,
+}
+
+impl TilemapRenderer {
+    pub fn new(r2d: &Render2D) -> Self {
+        let Gpu {
+            device, sc_desc, ..
+        } = &r2d.gpu;
+
+        let instance_buf = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("tilemap instance buffer"),
+            size: INSTANCE_BUF_SIZE,
+            usage: wgpu::BufferUsage::VERTEX | wgpu::BufferUsage::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("tilemap bind group layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStage::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false,
+                    min_binding_size: None,
+                },
+                count: None,
+            }],
+        });
+
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("tilemap bind group"),
+            layout: &bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: wgpu::BindingResource::Buffer {
+                    buffer: &r2d.utility_buf,
+                    offset: 0,
+                    size: None,
+                },
+            }],
+        });
+
+        let texture_bind_group_layout =
+            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+                label: Some("tileset bind group layout"),
+                entries: &[
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 0,
+                        visibility: wgpu::ShaderStage::FRAGMENT,
+                        ty: wgpu::BindingType::Texture {
+                            sample_type: wgpu::TextureSampleType::Float { filterable: true },
+                            view_dimension: wgpu::TextureViewDimension::D2,
+                            multisampled: false,
+                        },
+                        count: None,
+                    },
+                    wgpu::BindGroupLayoutEntry {
+                        binding: 1,
+                        visibility: wgpu::ShaderStage::FRAGMENT,
+                        ty: wgpu::BindingType::Sampler {
+                            filtering: true,
+                            comparison: false,
+                        },
+                        count: None,
+                    },
+                ],
+            });
+
+        let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("tilemap pipeline layout"),
+            bind_group_layouts: &[&bind_group_layout, &texture_bind_group_layout],
+            push_constant_ranges: &[],
+        });
+
+        let vert_shader = device.create_shader_module(&wgpu::ShaderModuleDescriptor {
+            label: Some("tilemap vertex shader"),
+            source: wgpu::util::make_spirv(include_bytes!(
+                "../../../assets/shaders/tilemap/tilemap.vert.spv"
+            )),
+            flags: wgpu::ShaderFlags::empty(),
+        });
+
+        let frag_shader = device.create_shader_module(&wgpu::ShaderModuleDescriptor {
+            label: Some("tilemap fragment shader"),
+            source: wgpu::util::make_spirv(include_bytes!(
+                "../../../assets/shaders/tilemap/tilemap.frag.spv"
+            )),
+            flags: wgpu::ShaderFlags::empty(),
+        });
+
+        // NOTE: tilemaps are drawn over the geometries and under the sprites, no depth test.
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("tilemap pipeline"),
+            layout: Some(&pipeline_layout),
+            vertex: wgpu::VertexState {
+                module: &vert_shader,
+                entry_point: "main",
+                buffers: &[
+                    wgpu::VertexBufferLayout {
+                        array_stride: size_of::<Vector4<f32>>() as wgpu::BufferAddress,
+                        step_mode: wgpu::InputStepMode::Vertex,
+                        attributes: &wgpu::vertex_attr_array![0 => Float4],
+                    },
+                    wgpu::VertexBufferLayout {
+                        array_stride: size_of::<TileInstanceRaw>() as wgpu::BufferAddress,
+                        step_mode: wgpu::InputStepMode::Instance,
+                        attributes: &wgpu::vertex_attr_array![
+                            1 => Float2,
+                            2 => Float2,
+                            3 => Float2,
+                            4 => Float2
+                        ],
+                    },
+                ],
+            },
+            fragment: Some(wgpu::FragmentState {
+                module: &frag_shader,
+                entry_point: "main",
+                targets: &[wgpu::ColorTargetState {
+                    format: sc_desc.format,
+                    color_blend: wgpu::BlendState {
+                        src_factor: wgpu::BlendFactor::SrcAlpha,
+                        dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+                        operation: wgpu::BlendOperation::Add,
+                    },
+                    alpha_blend: wgpu::BlendState {
+                        src_factor: wgpu::BlendFactor::One,
+                        dst_factor: wgpu::BlendFactor::One,
+                        operation: wgpu::BlendOperation::Max,
+                    },
+                    write_mask: wgpu::ColorWrite::ALL,
+                }],
+            }),
+            primitive: wgpu::PrimitiveState {
+                front_face: wgpu::FrontFace::Ccw,
+                cull_mode: wgpu::CullMode::None,
+                ..Default::default()
+            },
+            depth_stencil: None,
+            multisample: Default::default(),
+        });
+
+        Self {
+            instance_buf,
+
+            bind_group,
+            texture_bind_group_layout,
+            pipeline,
+
+            tilesets: Default::default(),
+        }
+    }
+
+    /// Render the prepared tiles of the tilemaps seen by the camera to the target.
+    ///
+    /// Do nothing if there is no `Assets<Texture>` resource, the tilemaps whose tileset not exists are
+    /// skipped.
+    ///
+    /// Return the number of draw calls.
+    ///
+    /// # Panics
+    ///
+    /// Panic if the number of prepared tiles exceeds the limit: `MAX_TILE_COUNT`.
+    pub fn render(
+        &mut self,
+        r2d: &Render2D,
+        target: &RenderPassTarget,
+        world: &World,
+        resources: &Resources,
+        camera2d: &Camera2D,
+    ) -> usize {
+        let Gpu { device, queue, .. } = &r2d.gpu;
+
+        let textures = match resources.get::<Assets<sprite::Texture>>() {
+            Some(textures) => textures,
+            None => return 0,
+        };
+
+        let mut instances: Vec<TileInstanceRaw> = Vec::new();
+        let mut batches: Vec<(Handle<sprite::Texture>, Range<u32>)> = Vec::new();
+
+        <(&TilemapRenderer2D, Option<&RenderLayer>)>::query().for_each(world, |(tilemap, l)| {
+            if !camera2d.sees(l) || tilemap.batch().is_empty() {
+                return;
+            }
+
+            match textures.get(tilemap.tileset) {
+                Some(tileset) if tileset.width() > 0 && tileset.height() > 0 => {}
+                _ => return,
+            }
+
+            let start = instances.len() as u32;
+            instances.extend(tilemap.batch().iter().map(TileInstanceRaw::from));
+            batches.push((tilemap.tileset, start..instances.len() as u32));
+        });
+
+        if batches.is_empty() {
+            return 0;
+        }
+
+        if instances.len() > MAX_TILE_COUNT {
+            panic!(
+                "ERR: The number of tiles exceeds the limit: {}",
+                MAX_TILE_COUNT
+            );
+        }
+
+        // Upload the tilesets drawn the first time.
+        for (handle, _) in batches.iter() {
+            if !self.tilesets.contains_key(handle) {
+                let texture = Texture::from_image(device, queue, textures.get(*handle).unwrap());
+                let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                    label: Some("tileset bind group"),
+                    layout: &self.texture_bind_group_layout,
+                    entries: &[
+                        wgpu::BindGroupEntry {
+                            binding: 0,
+                            resource: wgpu::BindingResource::TextureView(&texture.view),
+                        },
+                        wgpu::BindGroupEntry {
+                            binding: 1,
+                            resource: wgpu::BindingResource::Sampler(&texture.sampler),
+                        },
+                    ],
+                });
+
+                self.tilesets.insert(*handle, (texture, bind_group));
+            }
+        }
+
+        queue.write_buffer(&self.instance_buf, 0, bytemuck::cast_slice(&instances));
+        let i_buf_size = (instances.len() * size_of::<TileInstanceRaw>()) as wgpu::BufferAddress;
+
+        let Viewport {
+            x,
+            y,
+            w,
+            h,
+            min_depth,
+            max_depth,
+        } = r2d.viewport;
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("tilemap encoder"),
+        });
+
+        encoder.insert_debug_marker("render tilemap");
+        {
+            let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("tilemap render pass"),
+                color_attachments: &[wgpu::RenderPassColorAttachmentDescriptor {
+                    attachment: target.color,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Load,
+                        store: true,
+                    },
+                }],
+                depth_stencil_attachment: None,
+            });
+
+            rpass.push_debug_group("Set datas");
+
+            rpass.set_pipeline(&self.pipeline);
+            rpass.set_viewport(x, y, w, h, min_depth, max_depth);
+            // NOTE: only draw in the viewport of camera, the other cameras may share the target(split-screen).
+            let (sx, sy, sw, sh) = target.scissor_rect(&r2d.viewport);
+            rpass.set_scissor_rect(sx, sy, sw, sh);
+            rpass.set_vertex_buffer(0, r2d.quad_vertex_buf.slice(..));
+            rpass.set_index_buffer(r2d.quad_index_buf.slice(..), wgpu::IndexFormat::Uint16);
+            rpass.set_vertex_buffer(1, self.instance_buf.slice(0..i_buf_size));
+            rpass.set_bind_group(0, &self.bind_group, &[]);
+
+            rpass.pop_debug_group();
+
+            for (handle, instances) in batches.iter() {
+                rpass.set_bind_group(1, &self.tilesets[handle].1, &[]);
+                rpass.draw_indexed(0..6, 0, instances.clone());
+            }
+        }
+
+        queue.submit(Some(encoder.finish()));
+
+        batches.len()
+    }
+}
@@ -0,0 +1,846 @@
+use crate::{
+    assets::Handle,
+    components::{
+        camera::{Camera2D, RenderTarget},
+        geometry::{Assembly, Geometry},
+        transform::Transform2D,
+    },
+    legion::{storage::Component, Entity, IntoQuery, Resources, World},
+    prefab::AnyComponent,
+    snapshot::ComponentRegistry,
+};
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    error::Error,
+    fmt::{self, Write as _},
+    fs, io,
+    path::Path,
+    str::FromStr,
+};
+
+/// The component which can be saved to and loaded from a scene file.
+///
+/// A component is saved as a line of text, usually the fields separated by whitespace. Entities referenced
+/// by the component should be saved as the ids given by `SceneEntities`, so the references survive loading.
+pub trait SceneComponent: Component + Sized {
+    /// The unique name of the component in scene files.
+    const NAME: &'static str;
+
+    fn save(&self, entities: &SceneEntities) -> String;
+
+    fn load(data: &str, entities: &SceneEntities) -> Result<Self, SceneError>;
+}
+
+/// The map between entities and their stable ids in a scene file.
+#[derive(Debug, Default)]
+pub struct SceneEntities {
+    ids: HashMap<Entity, u64>,
+    entities: HashMap<u64, Entity>,
+}
+
+impl SceneEntities {
+    pub fn id(&self, entity: Entity) -> Option<u64> {
+        self.ids.get(&entity).copied()
+    }
+
+    pub fn entity(&self, id: u64) -> Option<Entity> {
+        self.entities.get(&id).copied()
+    }
+
+    pub(crate) fn into_ids(self) -> HashMap<Entity, u64> {
+        self.ids
+    }
+
+    fn insert(&mut self, id: u64, entity: Entity) {
+        self.ids.insert(entity, id);
+        self.entities.insert(id, entity);
+    }
+}
+
+#[derive(Debug)]
+pub enum SceneError {
+    Io(io::Error),
+    /// The line of the file can't be parsed.
+    Parse(usize, String),
+    UnknownComponent(String),
+    /// The data of component is invalid.
+    InvalidData(&'static str, String),
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SceneError::Io(err) => write!(f, "{}", err),
+            SceneError::Parse(line, text) => write!(f, "line {}: can't parse `{}`", line, text),
+            SceneError::UnknownComponent(name) => write!(f, "unknown component `{}`", name),
+            SceneError::InvalidData(name, data) => {
+                write!(f, "invalid data of component `{}`: `{}`", name, data)
+            }
+        }
+    }
+}
+
+impl Error for SceneError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SceneError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for SceneError {
+    fn from(err: io::Error) -> Self {
+        SceneError::Io(err)
+    }
+}
+
+/// An entity of `Scene`, the components are serialized as `(name, data)` of the scene file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SceneEntity {
+    /// The stable id of the entity in the scene, see `SceneEntities`.
+    pub id: u64,
+    pub components: Vec<(String, String)>,
+}
+
+/// A named collection of entities, load it by `instantiate_scene` and unload it by `unload_scene`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Scene {
+    pub name: String,
+    pub entities: Vec<SceneEntity>,
+}
+
+impl Scene {
+    /// Read the scene file, the scene is named after the file stem.
+    pub fn read(path: &Path) -> Result<Self, SceneError> {
+        let name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+
+        Self::parse(name, &fs::read_to_string(path)?)
+    }
+
+    /// Parse the scene text, see `save_scene` for the format.
+    pub fn parse(name: impl Into<String>, text: &str) -> Result<Self, SceneError> {
+        let entities = parse_scene(text)?
+            .into_iter()
+            .map(|(id, components)| SceneEntity {
+                id,
+                components: components
+                    .into_iter()
+                    .map(|(_, name, data)| (String::from(name), String::from(data)))
+                    .collect(),
+            })
+            .collect();
+
+        Ok(Self {
+            name: name.into(),
+            entities,
+        })
+    }
+
+    /// Serialize the entities with registered `SceneComponent`s in `world`, the scene is unnamed.
+    pub fn from_world(world: &World, registry: &ComponentRegistry) -> Self {
+        Self::from_world_with_ids(world, registry).0
+    }
+
+    /// Like `Scene::from_world`, also return the ids given to the entities of `world`.
+    pub(crate) fn from_world_with_ids(
+        world: &World,
+        registry: &ComponentRegistry,
+    ) -> (Self, SceneEntities) {
+        let mut entities = SceneEntities::default();
+        let mut next_id = 0;
+
+        for scene_type in registry.scene_types() {
+            for entity in (scene_type.entities)(world) {
+                if entities.id(entity).is_none() {
+                    entities.insert(next_id, entity);
+                    next_id += 1;
+                }
+            }
+        }
+
+        let mut components: BTreeMap<u64, Vec<(String, String)>> = BTreeMap::new();
+        for scene_type in registry.scene_types() {
+            for (entity, data) in (scene_type.save)(world, &entities) {
+                components
+                    .entry(entities.id(entity).unwrap())
+                    .or_default()
+                    .push((String::from(scene_type.name), data));
+            }
+        }
+
+        let scene = Self {
+            name: String::new(),
+            entities: components
+                .into_iter()
+                .map(|(id, components)| SceneEntity { id, components })
+                .collect(),
+        };
+
+        (scene, entities)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+}
+
+impl fmt::Display for Scene {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for entity in self.entities.iter() {
+            writeln!(f, "entity {}", entity.id)?;
+
+            for (name, data) in entity.components.iter() {
+                writeln!(f, "    {}: {}", name, data)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// The entities spawned by `instantiate_scene`, pass it to `unload_scene` to remove them.
+#[derive(Debug)]
+pub struct SceneInstance {
+    entities: Vec<Entity>,
+}
+
+impl SceneInstance {
+    /// The spawned entities in the order of the scene.
+    pub fn entities(&self) -> &[Entity] {
+        &self.entities
+    }
+
+    pub fn len(&self) -> usize {
+        self.entities.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entities.is_empty()
+    }
+}
+
+/// The type-erased save and load functions of a registered `SceneComponent`.
+#[derive(Clone, Copy)]
+pub(crate) struct SceneType {
+    name: &'static str,
+    entities: fn(&World) -> Vec<Entity>,
+    save: fn(&World, &SceneEntities) -> Vec<(Entity, String)>,
+    load: fn(&mut World, Entity, &str, &SceneEntities) -> Result<(), SceneError>,
+    load_boxed: fn(&str, &SceneEntities) -> Result<Box<dyn AnyComponent>, SceneError>,
+}
+
+impl SceneType {
+    pub(crate) fn of<T: SceneComponent + Clone>() -> Self {
+        fn entities<T: SceneComponent>(world: &World) -> Vec<Entity> {
+            <(Entity, &T)>::query()
+                .iter(world)
+                .map(|(entity, _)| *entity)
+                .collect()
+        }
+
+        fn save<T: SceneComponent>(world: &World, ids: &SceneEntities) -> Vec<(Entity, String)> {
+            <(Entity, &T)>::query()
+                .iter(world)
+                .map(|(entity, component)| (*entity, component.save(ids)))
+                .collect()
+        }
+
+        fn load<T: SceneComponent>(
+            world: &mut World,
+            entity: Entity,
+            data: &str,
+            ids: &SceneEntities,
+        ) -> Result<(), SceneError> {
+            let component = T::load(data, ids)?;
+            world.entry(entity).unwrap().add_component(component);
+
+            Ok(())
+        }
+
+        fn load_boxed<T: SceneComponent + Clone>(
+            data: &str,
+            ids: &SceneEntities,
+        ) -> Result<Box<dyn AnyComponent>, SceneError> {
+            Ok(Box::new(T::load(data, ids)?))
+        }
+
+        Self {
+            name: T::NAME,
+            entities: entities::<T>,
+            save: save::<T>,
+            load: load::<T>,
+            load_boxed: load_boxed::<T>,
+        }
+    }
+
+    pub(crate) fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub(crate) fn load_boxed(
+        &self,
+        data: &str,
+        ids: &SceneEntities,
+    ) -> Result<Box<dyn AnyComponent>, SceneError> {
+        (self.load_boxed)(data, ids)
+    }
+}
+
+/// The entities parsed from scene text, each entity is its id and the components of
+/// `(line number, name, data)`.
+pub(crate) type ParsedScene<'a> = Vec<(u64, Vec<(usize, &'a str, &'a str)>)>;
+
+pub(crate) fn parse_scene(text: &str) -> Result<ParsedScene, SceneError> {
+    let mut parsed: ParsedScene = Vec::new();
+
+    for (index, line) in text.lines().enumerate() {
+        let line_number = index + 1;
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if let Some(id) = line.strip_prefix("entity ") {
+            let id = u64::from_str(id.trim())
+                .map_err(|_| SceneError::Parse(line_number, String::from(line)))?;
+            parsed.push((id, Vec::new()));
+        } else if let (Some((name, data)), Some((_, components))) =
+            (split_component(line), parsed.last_mut())
+        {
+            components.push((line_number, name, data));
+        } else {
+            return Err(SceneError::Parse(line_number, String::from(line)));
+        }
+    }
+
+    Ok(parsed)
+}
+
+/// Find the registered scene type by name.
+pub(crate) fn find_scene_type<'a>(
+    registry: &'a ComponentRegistry,
+    name: &str,
+) -> Result<&'a SceneType, SceneError> {
+    registry
+        .scene_types()
+        .find(|scene_type| scene_type.name() == name)
+        .ok_or_else(|| SceneError::UnknownComponent(String::from(name)))
+}
+
+/// Save the entities with registered `SceneComponent`s to the file.
+///
+/// # File format
+///
+/// ```text
+/// entity 0
+///     Transform2D: 0 0 0 1 1
+/// entity 1
+///     ...
+/// ```
+pub fn save_scene(
+    world: &World,
+    registry: &ComponentRegistry,
+    path: &Path,
+) -> Result<(), SceneError> {
+    fs::write(path, scene_to_string(world, registry))?;
+
+    Ok(())
+}
+
+/// Spawn the entities saved in the file, use the `ComponentRegistry` in `resources` if exists.
+pub fn load_scene(
+    path: &Path,
+    world: &mut World,
+    resources: &mut Resources,
+) -> Result<Vec<Entity>, SceneError> {
+    let text = fs::read_to_string(path)?;
+
+    if let Some(registry) = resources.get::<ComponentRegistry>() {
+        scene_from_str(&text, world, &registry)
+    } else {
+        scene_from_str(&text, world, &ComponentRegistry::new())
+    }
+}
+
+pub fn scene_to_string(world: &World, registry: &ComponentRegistry) -> String {
+    Scene::from_world(world, registry).to_string()
+}
+
+/// Write the geometries of all entities with `Transform2D` to a SVG file, see `scene_to_svg`.
+pub fn export_scene_to_svg(world: &World, path: &Path) -> Result<(), io::Error> {
+    fs::write(path, scene_to_svg(world))
+}
+
+/// The SVG document of the `Geometry`s and `Assembly`s of all entities with `Transform2D`.
+///
+/// The geometries are drawn from low order to high order, the view box fits all of them.
+pub fn scene_to_svg(world: &World) -> String {
+    let mut geometries = <(&Transform2D, &Geometry)>::query()
+        .iter(world)
+        .map(|(transform, geometry)| (*transform, *geometry))
+        .collect::<Vec<_>>();
+
+    for (transform, assembly) in <(&Transform2D, &Assembly)>::query().iter(world) {
+        geometries.extend(assembly.iter().map(|geometry| (*transform, *geometry)));
+    }
+
+    geometries.sort_by_key(|(_, geometry)| geometry.order());
+
+    let (mut min, mut max) = ((f32::MAX, f32::MAX), (f32::MIN, f32::MIN));
+    for (transform, geometry) in geometries.iter() {
+        for p in geometry.svg_points(transform) {
+            min = (min.0.min(p.x), min.1.min(p.y));
+            max = (max.0.max(p.x), max.1.max(p.y));
+        }
+    }
+    if min.0 > max.0 {
+        min = (0.0, 0.0);
+        max = (0.0, 0.0);
+    }
+
+    let mut text = String::new();
+    writeln!(
+        text,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{} {} {} {}\">",
+        min.0,
+        min.1,
+        max.0 - min.0,
+        max.1 - min.1
+    )
+    .unwrap();
+
+    for (transform, geometry) in geometries.iter() {
+        let path = geometry.to_svg_path(transform);
+        if !path.is_empty() {
+            writeln!(text, "    {}", path).unwrap();
+        }
+    }
+
+    text.push_str("</svg>\n");
+    text
+}
+
+/// Spawn the entities in the scene text, return the spawned entities in order of their ids.
+pub fn scene_from_str(
+    text: &str,
+    world: &mut World,
+    registry: &ComponentRegistry,
+) -> Result<Vec<Entity>, SceneError> {
+    // NOTE: parse the whole text first, so the entities can be referenced before they are defined.
+    let parsed = parse_scene(text)?;
+
+    spawn_entities(
+        parsed
+            .into_iter()
+            .map(|(id, components)| {
+                let components = components
+                    .into_iter()
+                    .map(|(line_number, name, data)| (Some(line_number), name, data))
+                    .collect();
+                (id, components)
+            })
+            .collect(),
+        world,
+        registry,
+    )
+}
+
+/// Spawn all entities of the scene, the spawned entities are removed if any component fails to load.
+pub fn instantiate_scene(
+    scene: &Scene,
+    world: &mut World,
+    registry: &ComponentRegistry,
+) -> Result<SceneInstance, SceneError> {
+    let entities = spawn_entities(
+        scene
+            .entities
+            .iter()
+            .map(|entity| {
+                let components = entity
+                    .components
+                    .iter()
+                    .map(|(name, data)| (None, name.as_str(), data.as_str()))
+                    .collect();
+                (entity.id, components)
+            })
+            .collect(),
+        world,
+        registry,
+    )?;
+
+    Ok(SceneInstance { entities })
+}
+
+/// Remove all entities of the scene instance, return the count of removed entities.
+///
+/// The entities already removed are skipped.
+pub fn unload_scene(instance: SceneInstance, world: &mut World) -> usize {
+    instance
+        .entities
+        .into_iter()
+        .filter(|entity| world.remove(*entity))
+        .count()
+}
+
+// Spawn the entities of (id, [(line number, name, data)]), all or nothing.
+fn spawn_entities(
+    parsed: Vec<(u64, Vec<(Option<usize>, &str, &str)>)>,
+    world: &mut World,
+    registry: &ComponentRegistry,
+) -> Result<Vec<Entity>, SceneError> {
+    let mut entities = SceneEntities::default();
+    let spawned: Vec<Entity> = parsed
+        .iter()
+        .map(|(id, _)| {
+            let entity = world.push(());
+            entities.insert(*id, entity);
+            entity
+        })
+        .collect();
+
+    let mut load = || -> Result<(), SceneError> {
+        for ((_, components), entity) in parsed.iter().zip(spawned.iter()) {
+            for (line_number, name, data) in components {
+                let scene_type = find_scene_type(registry, name)?;
+
+                (scene_type.load)(world, *entity, data, &entities).map_err(|err| {
+                    match (err, line_number) {
+                        (SceneError::InvalidData(..), Some(line_number)) => {
+                            SceneError::Parse(*line_number, String::from(*data))
+                        }
+                        (err, _) => err,
+                    }
+                })?;
+            }
+        }
+
+        Ok(())
+    };
+
+    match load() {
+        Ok(()) => Ok(spawned),
+        Err(err) => {
+            for entity in spawned {
+                world.remove(entity);
+            }
+
+            Err(err)
+        }
+    }
+}
+
+fn split_component(line: &str) -> Option<(&str, &str)> {
+    let colon = line.find(':')?;
+
+    Some((line[..colon].trim(), line[colon + 1..].trim()))
+}
+
+/// Parse `count` floats separated by whitespace, a helper to implement `SceneComponent::load`.
+pub fn parse_f32s(name: &'static str, data: &str, count: usize) -> Result<Vec<f32>, SceneError> {
+    let values = data
+        .split_whitespace()
+        .map(f32::from_str)
+        .collect::<Result<Vec<f32>, _>>()
+        .map_err(|_| SceneError::InvalidData(name, String::from(data)))?;
+
+    if values.len() == count {
+        Ok(values)
+    } else {
+        Err(SceneError::InvalidData(name, String::from(data)))
+    }
+}
+
+impl SceneComponent for Transform2D {
+    const NAME: &'static str = "Transform2D";
+
+    fn save(&self, _: &SceneEntities) -> String {
+        format!(
+            "{} {} {} {} {}",
+            self.position.x,
+            self.position.y,
+            self.angle(),
+            self.scale.x,
+            self.scale.y
+        )
+    }
+
+    fn load(data: &str, _: &SceneEntities) -> Result<Self, SceneError> {
+        let v = parse_f32s(Self::NAME, data, 5)?;
+
+        Ok(Transform2D::new(v[0], v[1], v[2], v[3], v[4]))
+    }
+}
+
+impl SceneComponent for Camera2D {
+    const NAME: &'static str = "Camera2D";
+
+    /// `width height priority layer_mask target orthographic_size fixed_aspect x y w h`, the target is
+    /// `screen` or `texture:<handle id>`, the optional values are `none` if not set.
+    fn save(&self, _: &SceneEntities) -> String {
+        let optional = |value: Option<f32>| value.map_or(String::from("none"), |v| v.to_string());
+        let target = match self.render_target {
+            RenderTarget::Screen => String::from("screen"),
+            RenderTarget::Texture(handle) => format!("texture:{}", handle.id()),
+        };
+        let (position, size) = self.viewport();
+
+        format!(
+            "{} {} {} {} {} {} {} {} {} {} {}",
+            self.width,
+            self.height,
+            self.priority,
+            self.layer_mask,
+            target,
+            optional(self.orthographic_size),
+            optional(self.fixed_aspect),
+            position.x,
+            position.y,
+            size.x,
+            size.y
+        )
+    }
+
+    /// NOTE: `width height` of old scene files is loaded with the defaults of the rest.
+    fn load(data: &str, _: &SceneEntities) -> Result<Self, SceneError> {
+        let invalid = || SceneError::InvalidData(Self::NAME, String::from(data));
+        let tokens = data.split_whitespace().collect::<Vec<_>>();
+
+        if tokens.len() != 2 && tokens.len() != 11 {
+            return Err(invalid());
+        }
+
+        let mut camera = Camera2D::new(
+            u32::from_str(tokens[0]).map_err(|_| invalid())?,
+            u32::from_str(tokens[1]).map_err(|_| invalid())?,
+        );
+        if tokens.len() == 2 {
+            return Ok(camera);
+        }
+
+        let positive = |token: &str| match token {
+            "none" => Ok(None),
+            _ => match f32::from_str(token) {
+                Ok(v) if v.is_finite() && v > 0.0 => Ok(Some(v)),
+                _ => Err(invalid()),
+            },
+        };
+        let viewport = tokens[7..]
+            .iter()
+            .map(|token| f32::from_str(token).map_err(|_| invalid()))
+            .collect::<Result<Vec<f32>, _>>()?;
+        let (x, y, w, h) = (viewport[0], viewport[1], viewport[2], viewport[3]);
+
+        camera.priority = i32::from_str(tokens[2]).map_err(|_| invalid())?;
+        camera.layer_mask = u32::from_str(tokens[3]).map_err(|_| invalid())?;
+        camera.render_target = match tokens[4] {
+            "screen" => RenderTarget::Screen,
+            target => target
+                .strip_prefix("texture:")
+                .and_then(|id| u64::from_str(id).ok())
+                .map(|id| RenderTarget::Texture(Handle::new(id)))
+                .ok_or_else(invalid)?,
+        };
+        camera.orthographic_size = positive(tokens[5])?;
+        camera.fixed_aspect = positive(tokens[6])?;
+
+        // NOTE: the same check as `Camera2D::set_viewport`, which panics.
+        if !(x.max(0.0) < (x + w).min(1.0) && y.max(0.0) < (y + h).min(1.0)) {
+            return Err(invalid());
+        }
+        camera.set_viewport(x, y, w, h);
+
+        Ok(camera)
+    }
+}
+
+impl SceneComponent for Geometry {
+    const NAME: &'static str = "Geometry";
+
+    fn save(&self, _: &SceneEntities) -> String {
+        self.to_words()
+            .iter()
+            .map(|word| format!("{:08x}", word))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn load(data: &str, _: &SceneEntities) -> Result<Self, SceneError> {
+        let mut words = [0u32; 8];
+        let mut tokens = data.split_whitespace();
+
+        for word in words.iter_mut() {
+            *word = tokens
+                .next()
+                .and_then(|token| u32::from_str_radix(token, 16).ok())
+                .ok_or_else(|| SceneError::InvalidData(Self::NAME, String::from(data)))?;
+        }
+
+        Ok(Geometry::from_words(words))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        components::geometry::{
+            BorderDecoration, BorderThickness, Geometry1DType, Geometry2DType, InnerDecoration,
+        },
+        misc::color::Rgba,
+        nalgebra::Vector2,
+    };
+
+    #[test]
+    fn scene_round_trip() {
+        let mut world = World::default();
+        let registry = ComponentRegistry::new();
+
+        let geometry = Geometry::new_1d(
+            Geometry1DType::Segment,
+            BorderDecoration::Dash,
+            Rgba::RED,
+            BorderThickness::ScreenSpace(2.0),
+            3,
+            Vector2::new(1.0, 2.0),
+            Vector2::new(3.0, 4.0),
+        );
+        world.push((Transform2D::new(1.0, 2.0, 45.0, 3.0, 4.0), geometry));
+        world.push((Camera2D::new(800, 600),));
+
+        let text = scene_to_string(&world, &registry);
+
+        let mut loaded = World::default();
+        let entities = scene_from_str(&text, &mut loaded, &registry).unwrap();
+        assert_eq!(entities.len(), 2);
+
+        let mut query = <(&Transform2D, &Geometry)>::query();
+        let (transform, geometry) = query.iter(&loaded).next().unwrap();
+        assert_eq!(transform.position, Vector2::new(1.0, 2.0));
+        assert_eq!(geometry.end_point_uncheck(), Vector2::new(3.0, 4.0));
+        assert_eq!(geometry.order(), 3);
+    }
+
+    #[test]
+    fn camera_round_trip() {
+        let mut world = World::default();
+        let registry = ComponentRegistry::new();
+
+        let mut camera = Camera2D::new(800, 600)
+            .with_orthographic_size(5.0)
+            .with_fixed_aspect(16.0 / 9.0)
+            .with_viewport(0.5, 0.0, 0.5, 1.0);
+        camera.priority = -2;
+        camera.layer_mask = 0b101;
+        camera.render_target = RenderTarget::Texture(Handle::new(7));
+        world.push((camera,));
+
+        let text = scene_to_string(&world, &registry);
+
+        let mut loaded = World::default();
+        scene_from_str(&text, &mut loaded, &registry).unwrap();
+
+        let loaded = <&Camera2D>::query().iter(&loaded).next().copied().unwrap();
+        assert_eq!((loaded.width, loaded.height), (800, 600));
+        assert_eq!(loaded.priority, -2);
+        assert_eq!(loaded.layer_mask, 0b101);
+        assert_eq!(loaded.render_target, camera.render_target);
+        assert_eq!(loaded.orthographic_size, Some(5.0));
+        assert_eq!(loaded.fixed_aspect, Some(16.0 / 9.0));
+        assert_eq!(loaded.viewport(), camera.viewport());
+
+        // the old format of `width height` is still loaded.
+        let old = Camera2D::load("800 600", &SceneEntities::default()).unwrap();
+        assert_eq!(old.render_target, RenderTarget::Screen);
+        assert_eq!(old.orthographic_size, None);
+        assert!(Camera2D::load(
+            "800 600 0 1 screen 0 none 0 0 1 1",
+            &SceneEntities::default()
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn scene_error_display() {
+        let err = SceneError::UnknownComponent(String::from("Unknown"));
+        assert_eq!(err.to_string(), "unknown component `Unknown`");
+
+        let err: Box<dyn Error> = Box::new(SceneError::Parse(3, String::from("oops")));
+        assert_eq!(err.to_string(), "line 3: can't parse `oops`");
+    }
+
+    #[test]
+    fn scene_svg() {
+        let mut world = World::default();
+
+        let geometry = Geometry::new_2d(
+            Geometry2DType::Square,
+            BorderDecoration::None,
+            Rgba::RED,
+            BorderThickness::LocalSpace(0.0),
+            InnerDecoration::Solid,
+            Rgba::RED,
+            0,
+            Vector2::new(0.0, 0.0),
+            0.0,
+            2.0,
+        );
+        world.push((Transform2D::at(1.0, 1.0), geometry));
+
+        let svg = scene_to_svg(&world);
+        assert_eq!(svg.matches("<path").count(), 1);
+        assert!(svg.contains("fill=\"#ff0000\""));
+        assert!(svg.contains("stroke=\"none\""));
+        assert!(svg.trim_end().ends_with("</svg>"));
+    }
+
+    #[test]
+    fn unknown_component() {
+        let mut world = World::default();
+        let registry = ComponentRegistry::new();
+
+        let result = scene_from_str("entity 0\n    Unknown: 1 2\n", &mut world, &registry);
+        assert!(matches!(result, Err(SceneError::UnknownComponent(_))));
+    }
+
+    #[test]
+    fn instantiate_and_unload_scene() {
+        let mut world = World::default();
+        let registry = ComponentRegistry::new();
+
+        let scene = Scene::parse(
+            "level",
+            "entity 0\n    Transform2D: 1 2 0 1 1\nentity 1\n    Camera2D: 800 600\n",
+        )
+        .unwrap();
+        assert_eq!(scene.len(), 2);
+        assert_eq!(Scene::parse("level", &scene.to_string()).unwrap(), scene);
+
+        let player = world.push((Transform2D::default(),));
+        let instance = instantiate_scene(&scene, &mut world, &registry).unwrap();
+        assert_eq!(instance.len(), 2);
+        assert_eq!(world.len(), 3);
+
+        assert_eq!(unload_scene(instance, &mut world), 2);
+        assert_eq!(world.len(), 1);
+        assert!(world.contains(player));
+
+        // nothing is left if a component fails to load.
+        let broken = Scene::parse(
+            "broken",
+            "entity 0\n    Transform2D: 1 2 0 1 1\nentity 1\n    Camera2D: x\n",
+        )
+        .unwrap();
+        assert!(instantiate_scene(&broken, &mut world, &registry).is_err());
+        assert_eq!(world.len(), 1);
+    }
+}
@@ -0,0 +1,532 @@
+use crate::{
+    components::{camera::Camera2D, geometry::Geometry, transform::Transform2D},
+    legion::{
+        any,
+        storage::{Component, ComponentTypeId},
+        world::Duplicate,
+        Entity, Resources, World,
+    },
+    scene::{instantiate_scene, Scene, SceneComponent, SceneError, SceneType},
+};
+
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+    fmt, str,
+};
+
+/// The component types which are moved by `transfer_entity`, serialized by `snapshot_world` and saved by
+/// `save_scene`.
+///
+/// Built-in components are registered by default, register your own components by
+/// `AppStageBuilder::register_component`.
+pub struct ComponentRegistry {
+    merger: Duplicate,
+    // The component types known by `merger`.
+    registered: HashSet<ComponentTypeId>,
+    scene_types: Vec<SceneType>,
+}
+
+impl ComponentRegistry {
+    pub fn new() -> Self {
+        let mut merger = Duplicate::default();
+
+        merger.register_copy::<Transform2D>();
+        merger.register_copy::<Camera2D>();
+        merger.register_copy::<Geometry>();
+
+        Self {
+            merger,
+            registered: vec![
+                ComponentTypeId::of::<Transform2D>(),
+                ComponentTypeId::of::<Camera2D>(),
+                ComponentTypeId::of::<Geometry>(),
+            ]
+            .into_iter()
+            .collect(),
+            scene_types: vec![
+                SceneType::of::<Transform2D>(),
+                SceneType::of::<Camera2D>(),
+                SceneType::of::<Geometry>(),
+            ],
+        }
+    }
+
+    /// Register the component to be moved by `transfer_entity`, it's not serialized by snapshots.
+    pub fn register<T: Component + Clone>(&mut self) {
+        if self.registered.insert(ComponentTypeId::of::<T>()) {
+            self.merger.register_clone::<T>();
+        }
+    }
+
+    /// Whether the component type is moved by `transfer_entity`.
+    pub fn is_registered(&self, ty: ComponentTypeId) -> bool {
+        self.registered.contains(&ty)
+    }
+
+    /// Register the component to be copied by snapshots and saved to scene files.
+    pub fn register_scene<T: SceneComponent + Clone>(&mut self) {
+        self.register::<T>();
+
+        if self.scene_types.iter().all(|ty| ty.name() != T::NAME) {
+            self.scene_types.push(SceneType::of::<T>());
+        }
+    }
+
+    pub(crate) fn scene_types(&self) -> impl Iterator<Item = &SceneType> {
+        self.scene_types.iter()
+    }
+}
+
+impl Default for ComponentRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for ComponentRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ComponentRegistry")
+            .field(
+                "scene_types",
+                &self
+                    .scene_types
+                    .iter()
+                    .map(|ty| ty.name())
+                    .collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+/// The registered `SceneComponent`s of all entities in a `World`, serialized to bytes.
+///
+/// The entities are saved as stable ids, the snapshot keeps the map from the live entities to the ids, so
+/// `restore_world` can tell which restored entity was which live entity.
+pub struct WorldSnapshot {
+    bytes: Vec<u8>,
+    // live entity -> id in snapshot.
+    entity_ids: HashMap<Entity, u64>,
+}
+
+impl WorldSnapshot {
+    /// Wrap the bytes of `WorldSnapshot::into_bytes`, e.g. received from network.
+    ///
+    /// NOTE: the live entities are unknown to the bytes, so `restore_world` returns an empty entity map, use
+    /// `WorldSnapshot::id` on the sender side to refer to the entities.
+    pub fn from_bytes(bytes: Vec<u8>) -> Self {
+        Self {
+            bytes,
+            entity_ids: HashMap::new(),
+        }
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    /// The stable id of the live entity in the snapshot.
+    pub fn id(&self, entity: Entity) -> Option<u64> {
+        self.entity_ids.get(&entity).copied()
+    }
+
+    /// The count of entities in the snapshot.
+    pub fn len(&self) -> usize {
+        self.entity_ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entity_ids.is_empty()
+    }
+}
+
+impl fmt::Debug for WorldSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WorldSnapshot")
+            .field("bytes", &self.bytes.len())
+            .field("len", &self.entity_ids.len())
+            .finish()
+    }
+}
+
+/// Serialize the registered `SceneComponent`s of all entities in `world`, in the format of scene files.
+///
+/// Components not registered in `ComponentRegistry` are not saved.
+pub fn snapshot_world(world: &World, resources: &Resources) -> WorldSnapshot {
+    let (scene, entities) = if let Some(registry) = resources.get::<ComponentRegistry>() {
+        Scene::from_world_with_ids(world, &registry)
+    } else {
+        Scene::from_world_with_ids(world, &ComponentRegistry::new())
+    };
+
+    WorldSnapshot {
+        bytes: scene.to_string().into_bytes(),
+        entity_ids: entities.into_ids(),
+    }
+}
+
+/// Replace all entities in `world` with the entities in `snapshot`.
+///
+/// The restored entities get new ids, the returned map is from the entities at the time of snapshot
+/// to the restored entities. `world` is left untouched if the snapshot fails to load.
+pub fn restore_world(
+    snapshot: &WorldSnapshot,
+    world: &mut World,
+    resources: &Resources,
+) -> Result<HashMap<Entity, Entity>, SceneError> {
+    let text = str::from_utf8(&snapshot.bytes)
+        .map_err(|_| SceneError::Parse(0, String::from("ERR: the snapshot is not utf-8")))?;
+    let scene = Scene::parse("", text)?;
+
+    let mut restored = World::default();
+    let instance = if let Some(registry) = resources.get::<ComponentRegistry>() {
+        instantiate_scene(&scene, &mut restored, &registry)?
+    } else {
+        instantiate_scene(&scene, &mut restored, &ComponentRegistry::new())?
+    };
+
+    let restored_ids: HashMap<u64, Entity> = scene
+        .entities
+        .iter()
+        .zip(instance.entities())
+        .map(|(entity, restored)| (entity.id, *restored))
+        .collect();
+
+    // NOTE: the entities keep their ids when moved between worlds.
+    world.clear();
+    world.move_from(&mut restored, &any());
+
+    Ok(snapshot
+        .entity_ids
+        .iter()
+        .filter_map(|(live, id)| restored_ids.get(id).map(|restored| (*live, *restored)))
+        .collect())
+}
+
+/// Why an entity can't be moved by `transfer_entity` or `EntityTransfers`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TransferError {
+    /// The entity doesn't exist in the world moved from.
+    NoSuchEntity(Entity),
+    /// No busy `AppStage` has the name.
+    NoSuchStage(String),
+    /// The names of the components not registered to `ComponentRegistry`, the entity is left untouched.
+    UnregisteredComponents(Vec<String>),
+}
+
+impl fmt::Display for TransferError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransferError::NoSuchEntity(entity) => write!(f, "no such entity: {:?}", entity),
+            TransferError::NoSuchStage(name) => write!(f, "no such stage: `{}`", name),
+            TransferError::UnregisteredComponents(names) => write!(
+                f,
+                "the components are not registered to `ComponentRegistry`: {}",
+                names.join(", ")
+            ),
+        }
+    }
+}
+
+impl Error for TransferError {}
+
+/// Move the entity from `from_world` to `to_world`, e.g. between the worlds of `AppStage`s created by
+/// `AppStageBuilder::with_own_world`.
+///
+/// Only the registered components can be moved, like `snapshot_world`. Return the entity in `to_world`, or
+/// an error if the entity doesn't exist in `from_world` or has components not registered, the entity is
+/// not removed then.
+pub fn transfer_entity(
+    from_world: &mut World,
+    to_world: &mut World,
+    entity: Entity,
+    resources: &Resources,
+) -> Result<Entity, TransferError> {
+    match resources.get_mut::<ComponentRegistry>() {
+        Some(mut registry) => transfer_with(from_world, to_world, entity, &mut registry),
+        None => transfer_with(from_world, to_world, entity, &mut ComponentRegistry::new()),
+    }
+}
+
+fn transfer_with(
+    from_world: &mut World,
+    to_world: &mut World,
+    entity: Entity,
+    registry: &mut ComponentRegistry,
+) -> Result<Entity, TransferError> {
+    let unregistered = match from_world.entry_ref(entity) {
+        Ok(entry) => entry
+            .archetype()
+            .layout()
+            .component_types()
+            .iter()
+            .filter(|ty| !registry.is_registered(**ty))
+            .map(|ty| ty.to_string())
+            .collect::<Vec<_>>(),
+        Err(_) => return Err(TransferError::NoSuchEntity(entity)),
+    };
+
+    if !unregistered.is_empty() {
+        return Err(TransferError::UnregisteredComponents(unregistered));
+    }
+
+    let moved = to_world.clone_from_single(from_world, entity, &mut registry.merger);
+    from_world.remove(entity);
+
+    Ok(moved)
+}
+
+/// The requests to move entities between the worlds of `AppStage`s, applied by the `App` at the end of
+/// frame.
+///
+/// The stages without own world share one world, so an entity is moved into or out of it by the name of
+/// any such stage.
+#[derive(Debug, Default)]
+pub struct EntityTransfers {
+    // (entity, name of stage to move to) requested by the stage running now.
+    pending: Vec<(Entity, String)>,
+    // (name of stage to move from, entity, name of stage to move to) requested in this frame.
+    requests: Vec<(String, Entity, String)>,
+    // The entity moved in last frame, mapped to the entity in the new world or the error.
+    results: Vec<(Entity, Result<Entity, TransferError>)>,
+}
+
+impl EntityTransfers {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Move the entity from the world of running stage to the world of stage `to_stage` at the end of frame.
+    pub fn request(&mut self, entity: Entity, to_stage: &str) {
+        self.pending.push((entity, String::from(to_stage)));
+    }
+
+    /// The transfers applied at the end of last frame.
+    pub fn results(&self) -> impl Iterator<Item = &(Entity, Result<Entity, TransferError>)> {
+        self.results.iter()
+    }
+
+    /// Mark the pending requests as requested by the stage.
+    pub(crate) fn flush_pending(&mut self, from_stage: &str) {
+        self.requests.extend(
+            self.pending
+                .drain(..)
+                .map(|(entity, to_stage)| (String::from(from_stage), entity, to_stage)),
+        );
+    }
+
+    pub(crate) fn take_requests(&mut self) -> Vec<(String, Entity, String)> {
+        std::mem::take(&mut self.requests)
+    }
+
+    pub(crate) fn set_results(&mut self, results: Vec<(Entity, Result<Entity, TransferError>)>) {
+        self.results = results;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        components::geometry::{
+            BorderDecoration, BorderThickness, Geometry2DType, InnerDecoration,
+        },
+        legion::IntoQuery,
+        misc::color::Rgba,
+        nalgebra::Vector2,
+        scene::SceneEntities,
+    };
+
+    #[derive(Debug, Clone, PartialEq)]
+    struct Name(String);
+
+    impl SceneComponent for Name {
+        const NAME: &'static str = "Name";
+
+        fn save(&self, _: &SceneEntities) -> String {
+            self.0.clone()
+        }
+
+        fn load(data: &str, _: &SceneEntities) -> Result<Self, SceneError> {
+            Ok(Name(String::from(data)))
+        }
+    }
+
+    #[test]
+    fn restore_builtin_components() {
+        let mut world = World::default();
+        let resources = Resources::default();
+
+        let geometry = Geometry::new_2d(
+            Geometry2DType::Circle,
+            BorderDecoration::Solid,
+            Rgba::WHITE,
+            BorderThickness::LocalSpace(1.0),
+            InnerDecoration::Solid,
+            Rgba::BLACK,
+            0,
+            Vector2::new(1.0, 2.0),
+            0.0,
+            4.0,
+        );
+
+        let e0 = world.push((Transform2D::new(1.0, 2.0, 0.5, 3.0, 4.0), geometry));
+        let e1 = world.push((Transform2D::default(), Camera2D::new(800, 600)));
+
+        let snapshot = snapshot_world(&world, &resources);
+        assert_eq!(snapshot.len(), 2);
+
+        for transform in <&mut Transform2D>::query().iter_mut(&mut world) {
+            transform.position = Vector2::new(100.0, 100.0);
+        }
+        world.remove(e1);
+        world.push((Transform2D::default(),));
+
+        let map = restore_world(&snapshot, &mut world, &resources).unwrap();
+        assert_eq!(world.len(), 2);
+
+        let entry = world.entry(map[&e0]).unwrap();
+        let transform = entry.get_component::<Transform2D>().unwrap();
+        assert_eq!(transform.position, Vector2::new(1.0, 2.0));
+        assert_eq!(
+            entry
+                .get_component::<Geometry>()
+                .unwrap()
+                .position_uncheck(),
+            Vector2::new(1.0, 2.0)
+        );
+
+        let entry = world.entry(map[&e1]).unwrap();
+        assert_eq!(entry.get_component::<Camera2D>().unwrap().width, 800);
+    }
+
+    #[test]
+    fn restore_registered_components() {
+        let mut world = World::default();
+        let mut resources = Resources::default();
+
+        let mut registry = ComponentRegistry::new();
+        registry.register_scene::<Name>();
+        resources.insert(registry);
+
+        let e0 = world.push((Name(String::from("yam")), Transform2D::default()));
+
+        let snapshot = snapshot_world(&world, &resources);
+        world
+            .entry(e0)
+            .unwrap()
+            .add_component(Name(String::from("changed")));
+
+        let map = restore_world(&snapshot, &mut world, &resources).unwrap();
+        let entry = world.entry(map[&e0]).unwrap();
+        assert_eq!(
+            entry.get_component::<Name>().unwrap(),
+            &Name(String::from("yam"))
+        );
+    }
+
+    #[test]
+    fn restore_from_bytes() {
+        let mut world = World::default();
+        let resources = Resources::default();
+
+        let e0 = world.push((Transform2D::at(1.0, 2.0),));
+        world.push((Transform2D::at(3.0, 4.0), Camera2D::new(800, 600)));
+
+        let snapshot = snapshot_world(&world, &resources);
+        assert_eq!(snapshot.len(), 2);
+        assert!(snapshot.id(e0).is_some());
+
+        let bytes = snapshot.into_bytes();
+        assert!(!bytes.is_empty());
+
+        let mut remote = World::default();
+        remote.push((Transform2D::default(),));
+
+        let map =
+            restore_world(&WorldSnapshot::from_bytes(bytes), &mut remote, &resources).unwrap();
+        assert!(map.is_empty());
+        assert_eq!(remote.len(), 2);
+
+        let mut positions = <&Transform2D>::query()
+            .iter(&remote)
+            .map(|transform| transform.position)
+            .collect::<Vec<_>>();
+        positions.sort_by(|a, b| a.x.partial_cmp(&b.x).unwrap());
+        assert_eq!(
+            positions,
+            vec![Vector2::new(1.0, 2.0), Vector2::new(3.0, 4.0)]
+        );
+        assert_eq!(<&Camera2D>::query().iter(&remote).count(), 1);
+    }
+
+    #[test]
+    fn keep_world_if_restore_fails() {
+        let mut world = World::default();
+        let resources = Resources::default();
+        world.push((Transform2D::default(),));
+
+        let snapshot = WorldSnapshot::from_bytes(b"entity 0\n    Unknown: 1 2\n".to_vec());
+        assert!(restore_world(&snapshot, &mut world, &resources).is_err());
+        assert_eq!(world.len(), 1);
+    }
+
+    #[test]
+    fn transfer_entity_between_worlds() {
+        let mut menu = World::default();
+        let mut game = World::default();
+        let resources = Resources::default();
+
+        let e0 = menu.push((Transform2D::at(1.0, 2.0), Camera2D::new(800, 600)));
+
+        let moved = transfer_entity(&mut menu, &mut game, e0, &resources).unwrap();
+        assert!(menu.is_empty());
+        assert_eq!(
+            game.entry(moved)
+                .unwrap()
+                .get_component::<Transform2D>()
+                .unwrap()
+                .position,
+            Vector2::new(1.0, 2.0)
+        );
+
+        assert_eq!(
+            transfer_entity(&mut menu, &mut game, e0, &resources),
+            Err(TransferError::NoSuchEntity(e0))
+        );
+    }
+
+    #[test]
+    fn keep_entity_with_unregistered_components() {
+        let mut menu = World::default();
+        let mut game = World::default();
+        let mut resources = Resources::default();
+
+        let e0 = menu.push((Transform2D::default(), Name(String::from("yam"))));
+
+        match transfer_entity(&mut menu, &mut game, e0, &resources) {
+            Err(TransferError::UnregisteredComponents(names)) => {
+                assert_eq!(names.len(), 1);
+                assert!(names[0].contains("Name"));
+            }
+            result => panic!("unexpected result: {:?}", result),
+        }
+        assert!(menu.contains(e0));
+        assert!(game.is_empty());
+
+        let mut registry = ComponentRegistry::new();
+        registry.register::<Name>();
+        resources.insert(registry);
+
+        let moved = transfer_entity(&mut menu, &mut game, e0, &resources).unwrap();
+        assert!(!menu.contains(e0));
+        assert_eq!(
+            game.entry(moved).unwrap().get_component::<Name>().unwrap(),
+            &Name(String::from("yam"))
+        );
+    }
+}
@@ -0,0 +1,137 @@
+use crate::legion::{Resources, World};
+
+use std::{
+    fmt,
+    future::Future,
+    pin::Pin,
+    ptr,
+    task::{Context, Poll, RawWaker, RawWakerVTable, Waker},
+};
+
+/// A future run by `TaskPool`.
+pub type Task = Pin<Box<dyn Future<Output = ()> + Send>>;
+
+/// The pending futures, insert it to `Resources` and add `poll_tasks_system` to poll them each frame.
+///
+/// There's no executor behind it, every future is polled once per frame whether it's woken or not. The
+/// futures for loading or I/O should do the work in the background(e.g. a thread with a channel) and stay
+/// cheap to poll.
+#[derive(Default)]
+pub struct TaskPool {
+    tasks: Vec<Task>,
+}
+
+impl TaskPool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the future, it's polled first in the next run of `poll_tasks_system`.
+    pub fn spawn<F: Future<Output = ()> + Send + 'static>(&mut self, future: F) {
+        self.tasks.push(Box::pin(future));
+    }
+
+    /// The count of pending futures.
+    pub fn len(&self) -> usize {
+        self.tasks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tasks.is_empty()
+    }
+
+    /// Drop all pending futures.
+    pub fn clear(&mut self) {
+        self.tasks.clear();
+    }
+
+    // Poll every future once, the completed ones are removed.
+    fn poll_all(&mut self) {
+        let waker = noop_waker();
+        let mut cx = Context::from_waker(&waker);
+
+        self.tasks = std::mem::take(&mut self.tasks)
+            .into_iter()
+            .filter_map(|mut task| match task.as_mut().poll(&mut cx) {
+                Poll::Pending => Some(task),
+                Poll::Ready(()) => None,
+            })
+            .collect();
+    }
+}
+
+impl fmt::Debug for TaskPool {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TaskPool")
+            .field("tasks", &self.tasks.len())
+            .finish()
+    }
+}
+
+/// Poll the futures of `TaskPool` once.
+///
+/// Add it by `AppStageBuilder::add_thread_local_fn_process(poll_tasks_system)`.
+pub fn poll_tasks_system(_: &mut World, resources: &mut Resources) {
+    if let Some(mut pool) = resources.get_mut::<TaskPool>() {
+        pool.poll_all();
+    }
+}
+
+// NOTE: the futures are polled every frame anyway, so waking does nothing.
+fn noop_waker() -> Waker {
+    fn clone(_: *const ()) -> RawWaker {
+        RawWaker::new(ptr::null(), &VTABLE)
+    }
+    fn noop(_: *const ()) {}
+
+    static VTABLE: RawWakerVTable = RawWakerVTable::new(clone, noop, noop, noop);
+
+    unsafe { Waker::from_raw(RawWaker::new(ptr::null(), &VTABLE)) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    // Pending for the count of polls, then ready.
+    struct Countdown(u32, Arc<AtomicUsize>);
+
+    impl Future for Countdown {
+        type Output = ();
+
+        fn poll(mut self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<()> {
+            if self.0 == 0 {
+                self.1.fetch_add(1, Ordering::SeqCst);
+                Poll::Ready(())
+            } else {
+                self.0 -= 1;
+                Poll::Pending
+            }
+        }
+    }
+
+    #[test]
+    fn poll_tasks_to_completion() {
+        let mut world = World::default();
+        let mut resources = Resources::default();
+        let done = Arc::new(AtomicUsize::new(0));
+
+        let mut pool = TaskPool::new();
+        pool.spawn(Countdown(0, done.clone()));
+        pool.spawn(Countdown(2, done.clone()));
+        resources.insert(pool);
+
+        poll_tasks_system(&mut world, &mut resources);
+        assert_eq!(done.load(Ordering::SeqCst), 1);
+        assert_eq!(resources.get::<TaskPool>().unwrap().len(), 1);
+
+        poll_tasks_system(&mut world, &mut resources);
+        poll_tasks_system(&mut world, &mut resources);
+        assert_eq!(done.load(Ordering::SeqCst), 2);
+        assert!(resources.get::<TaskPool>().unwrap().is_empty());
+    }
+}
@@ -1,8 +1,14 @@
 //! NOTE: The `window` module is only in a usable state and will be gradually improved afterwards.
 
+use crate::input::{GamepadAxis, KeyCode, MouseButton};
+
 use winit::{
     dpi::{PhysicalPosition, PhysicalSize},
-    window::Window as Window_w,
+    event::{
+        DeviceId, ElementState, Event, KeyboardInput, ModifiersState, MouseScrollDelta, TouchPhase,
+        WindowEvent,
+    },
+    window::{CursorIcon as CursorIcon_w, Icon, Window as Window_w, WindowId},
 };
 
 pub type VideoMode = winit::monitor::VideoMode;
@@ -10,16 +16,294 @@ pub type MonitorHandle = winit::monitor::MonitorHandle;
 pub type Fullscreen = winit::window::Fullscreen;
 pub type NotSupportError = winit::error::NotSupportedError;
 pub type ExternalError = winit::error::ExternalError;
+pub type BadIcon = winit::window::BadIcon;
+
+/// The fullscreen state of `Window`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FullscreenMode {
+    Windowed,
+    /// Borderless fullscreen on the current monitor.
+    Borderless,
+    /// Exclusive fullscreen with the video mode, which changes the resolution of monitor.
+    Exclusive(VideoMode),
+}
+
+/// Sent when the size or the scale factor of the window changes, read it from `Events<WindowResized>`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WindowResized {
+    pub physical: (u32, u32),
+    pub logical: (f32, f32),
+    pub scale: f32,
+}
+
+impl WindowResized {
+    pub(crate) fn new(physical: PhysicalSize<u32>, scale_factor: f64) -> Self {
+        let logical = physical.to_logical::<f32>(scale_factor);
+
+        Self {
+            physical: (physical.width, physical.height),
+            logical: (logical.width, logical.height),
+            scale: scale_factor as f32,
+        }
+    }
+}
+
+/// An event injected by `Window::inject_event` or `TestApp::inject_event`, it's processed like the same
+/// event from the OS.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SyntheticWindowEvent {
+    KeyDown(KeyCode),
+    KeyUp(KeyCode),
+    /// A typed character, see `Keyboard::text_input`.
+    Character(char),
+    MouseDown(MouseButton),
+    MouseUp(MouseButton),
+    /// The cursor moves to the position in physical pixels, from the top left corner of window.
+    CursorMoved(f32, f32),
+    /// The wheel scrolls the lines horizontally and vertically.
+    MouseWheel(f32, f32),
+    /// The stick of gamepad moves to the value in [-1, 1], see `Gamepad`.
+    GamepadAxis(GamepadAxis, f32),
+    /// The window is resized to the physical size, see `Window::set_resolution`.
+    Resized(u32, u32),
+}
+
+impl SyntheticWindowEvent {
+    /// The winit event passed to `Input::apply`, `None` for `Resized` and `GamepadAxis`.
+    #[allow(deprecated)]
+    pub(crate) fn to_input_event(self) -> Option<Event<'static, ()>> {
+        // SAFETY: the dummy ids are only passed to `Input::apply` which never compares them or passes them
+        // to winit, they stand for the only window and device.
+        let (window_id, device_id) = unsafe { (WindowId::dummy(), DeviceId::dummy()) };
+        let modifiers = ModifiersState::empty();
+
+        let key = |state, keycode| WindowEvent::KeyboardInput {
+            device_id,
+            input: KeyboardInput {
+                scancode: 0,
+                state,
+                virtual_keycode: Some(keycode),
+                modifiers,
+            },
+            is_synthetic: false,
+        };
+        let button = |state, button| WindowEvent::MouseInput {
+            device_id,
+            state,
+            button,
+            modifiers,
+        };
+
+        let event = match self {
+            SyntheticWindowEvent::KeyDown(keycode) => key(ElementState::Pressed, keycode),
+            SyntheticWindowEvent::KeyUp(keycode) => key(ElementState::Released, keycode),
+            SyntheticWindowEvent::Character(c) => WindowEvent::ReceivedCharacter(c),
+            SyntheticWindowEvent::MouseDown(b) => button(ElementState::Pressed, b),
+            SyntheticWindowEvent::MouseUp(b) => button(ElementState::Released, b),
+            SyntheticWindowEvent::CursorMoved(x, y) => WindowEvent::CursorMoved {
+                device_id,
+                position: PhysicalPosition::new(x as f64, y as f64),
+                modifiers,
+            },
+            SyntheticWindowEvent::MouseWheel(x, y) => WindowEvent::MouseWheel {
+                device_id,
+                delta: MouseScrollDelta::LineDelta(x, y),
+                phase: TouchPhase::Moved,
+                modifiers,
+            },
+            SyntheticWindowEvent::Resized(..) | SyntheticWindowEvent::GamepadAxis(..) => {
+                return None
+            }
+        };
+
+        Some(Event::WindowEvent { window_id, event })
+    }
+}
+
+/// An RGBA8 image used as the cursor, row by row from top to bottom.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CursorImage {
+    pub width: u32,
+    pub height: u32,
+    pub rgba: Vec<u8>,
+    /// The pixel of the image at the cursor position.
+    pub hotspot: (u32, u32),
+}
+
+impl CursorImage {
+    /// # Panics
+    ///
+    /// Panics if the length of `rgba` is not `width * height * 4`.
+    pub fn from_rgba(width: u32, height: u32, rgba: Vec<u8>, hotspot: (u32, u32)) -> Self {
+        let len = (width as usize)
+            .checked_mul(height as usize)
+            .and_then(|len| len.checked_mul(4));
+        assert_eq!(
+            Some(rgba.len()),
+            len,
+            "ERR: The length of pixels doesn't match the size of cursor image."
+        );
+
+        Self {
+            width,
+            height,
+            rgba,
+            hotspot,
+        }
+    }
+}
+
+/// The icon of cursor, the standard cursors of OS or a custom image.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CursorIcon {
+    Default,
+    Crosshair,
+    Hand,
+    Arrow,
+    Move,
+    Text,
+    Wait,
+    Help,
+    Progress,
+    NotAllowed,
+    Grab,
+    Grabbing,
+    EwResize,
+    NsResize,
+    NeswResize,
+    NwseResize,
+    ZoomIn,
+    ZoomOut,
+    /// NOTE: winit can't set the image of OS cursor, so the OS cursor is hidden and the render stage draws
+    /// the image at the cursor position, over everything on the screen.
+    Custom(CursorImage),
+}
+
+impl CursorIcon {
+    fn to_winit(&self) -> Option<CursorIcon_w> {
+        let icon = match self {
+            Self::Default => CursorIcon_w::Default,
+            Self::Crosshair => CursorIcon_w::Crosshair,
+            Self::Hand => CursorIcon_w::Hand,
+            Self::Arrow => CursorIcon_w::Arrow,
+            Self::Move => CursorIcon_w::Move,
+            Self::Text => CursorIcon_w::Text,
+            Self::Wait => CursorIcon_w::Wait,
+            Self::Help => CursorIcon_w::Help,
+            Self::Progress => CursorIcon_w::Progress,
+            Self::NotAllowed => CursorIcon_w::NotAllowed,
+            Self::Grab => CursorIcon_w::Grab,
+            Self::Grabbing => CursorIcon_w::Grabbing,
+            Self::EwResize => CursorIcon_w::EwResize,
+            Self::NsResize => CursorIcon_w::NsResize,
+            Self::NeswResize => CursorIcon_w::NeswResize,
+            Self::NwseResize => CursorIcon_w::NwseResize,
+            Self::ZoomIn => CursorIcon_w::ZoomIn,
+            Self::ZoomOut => CursorIcon_w::ZoomOut,
+            Self::Custom(_) => return None,
+        };
+
+        Some(icon)
+    }
+}
+
+impl Default for CursorIcon {
+    fn default() -> Self {
+        Self::Default
+    }
+}
+
+/// The cursor state the `App` applies to the window at the start of next frame.
+///
+/// Prefer it to the cursor functions of `Window` in systems, the changes of a frame are applied together.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CursorSettings {
+    visible: bool,
+    confined: bool,
+    icon: CursorIcon,
+    // Why the window failed to apply `confined`.
+    confine_error: Option<String>,
+
+    changed: bool,
+}
+
+impl CursorSettings {
+    pub fn new() -> Self {
+        Self {
+            visible: true,
+            confined: false,
+            icon: CursorIcon::Default,
+            confine_error: None,
+
+            changed: false,
+        }
+    }
+
+    pub fn visible(&self) -> bool {
+        self.visible
+    }
+
+    pub fn confined(&self) -> bool {
+        self.confined
+    }
+
+    pub fn icon(&self) -> &CursorIcon {
+        &self.icon
+    }
+
+    /// Why the window failed to confine the cursor in last frame, `confined` is turned off then.
+    pub fn confine_error(&self) -> Option<&str> {
+        self.confine_error.as_deref()
+    }
+
+    pub fn set_cursor_visible(&mut self, visible: bool) {
+        self.visible = visible;
+        self.changed = true;
+    }
+
+    /// Keep the cursor in the window, e.g. hide and confine the cursor for mouse look.
+    pub fn set_cursor_confined(&mut self, confined: bool) {
+        self.confined = confined;
+        self.changed = true;
+    }
+
+    pub fn set_cursor_icon(&mut self, icon: CursorIcon) {
+        self.icon = icon;
+        self.changed = true;
+    }
+}
+
+impl Default for CursorSettings {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 /// `Window` is a simple wrapper for `winit::window::Window`, which cuts out many functions of
 /// `winit::window::Window`, only keeps what `yam` cares about.
 pub struct Window {
     pub(crate) window: Window_w,
+    custom_cursor: Option<CursorImage>,
+    injected_events: Vec<SyntheticWindowEvent>,
 }
 
 impl Window {
     pub(crate) fn new(window: Window_w) -> Self {
-        Self { window }
+        Self {
+            window,
+            custom_cursor: None,
+            injected_events: Vec::new(),
+        }
+    }
+
+    /// Inject the event as if it came from the OS, it's processed with the events of this frame, e.g. to
+    /// replay the recorded input.
+    pub fn inject_event(&mut self, event: SyntheticWindowEvent) {
+        self.injected_events.push(event);
+    }
+
+    pub(crate) fn take_injected_events(&mut self) -> Vec<SyntheticWindowEvent> {
+        std::mem::take(&mut self.injected_events)
     }
 
     pub fn available_monitors(&self) -> impl Iterator<Item = MonitorHandle> {
@@ -34,21 +318,51 @@ impl Window {
         self.window.primary_monitor()
     }
 
-    pub fn fullscreen(&self) -> Option<Fullscreen> {
-        self.window.fullscreen()
+    pub fn fullscreen(&self) -> FullscreenMode {
+        match self.window.fullscreen() {
+            None => FullscreenMode::Windowed,
+            Some(Fullscreen::Borderless(_)) => FullscreenMode::Borderless,
+            Some(Fullscreen::Exclusive(video_mode)) => FullscreenMode::Exclusive(video_mode),
+        }
     }
 
-    pub fn set_fullscreen(&mut self, fullscreen: Option<Fullscreen>) {
+    pub fn set_fullscreen(&mut self, mode: FullscreenMode) {
+        let fullscreen = match mode {
+            FullscreenMode::Windowed => None,
+            FullscreenMode::Borderless => Some(Fullscreen::Borderless(self.current_monitor())),
+            FullscreenMode::Exclusive(video_mode) => Some(Fullscreen::Exclusive(video_mode)),
+        };
+
         self.window.set_fullscreen(fullscreen);
     }
 
-    /// Return the resolution of the window.
+    /// Return the resolution of the window in physical pixels, same as `physical_size`.
     pub fn resolution(&self) -> (u32, u32) {
+        self.physical_size()
+    }
+
+    /// Return the size of the window in physical pixels, which is what the swap chain renders to.
+    pub fn physical_size(&self) -> (u32, u32) {
         let size = self.window.inner_size();
 
         (size.width, size.height)
     }
 
+    /// Return the size of the window in logical pixels, which is `physical_size / scale_factor`.
+    pub fn logical_size(&self) -> (f32, f32) {
+        let size = self
+            .window
+            .inner_size()
+            .to_logical::<f32>(self.window.scale_factor());
+
+        (size.width, size.height)
+    }
+
+    /// Return the ratio of physical pixels to logical pixels, e.g. `2.0` on most Retina displays.
+    pub fn scale_factor(&self) -> f32 {
+        self.window.scale_factor() as f32
+    }
+
     /// Modifies the resolution of the window.
     pub fn set_resolution(&mut self, width: u32, height: u32) {
         self.window.set_inner_size(PhysicalSize::new(width, height));
@@ -102,13 +416,65 @@ impl Window {
         self.window.set_cursor_visible(visible);
     }
 
-    // TODO: After finish texture module.
-    pub fn set_cursor_icon(&mut self) {
-        todo!()
+    /// Keep the cursor in the window, same as `set_cursor_grab`.
+    pub fn set_cursor_confined(&mut self, confined: bool) -> Result<(), ExternalError> {
+        self.set_cursor_grab(confined)
+    }
+
+    /// Change the icon of the cursor, a `CursorIcon::Custom` hides the OS cursor.
+    pub fn set_cursor_icon(&mut self, icon: CursorIcon) {
+        match icon.to_winit() {
+            Some(icon) => {
+                self.window.set_cursor_icon(icon);
+
+                // show the OS cursor hidden by the custom cursor.
+                if self.custom_cursor.take().is_some() {
+                    self.window.set_cursor_visible(true);
+                }
+            }
+            None => {
+                if let CursorIcon::Custom(image) = icon {
+                    self.window.set_cursor_visible(false);
+                    self.custom_cursor = Some(image);
+                }
+            }
+        }
+    }
+
+    /// The image set by `CursorIcon::Custom`, drawn at the cursor position by the render stage.
+    pub fn custom_cursor(&self) -> Option<&CursorImage> {
+        self.custom_cursor.as_ref()
+    }
+
+    /// Apply the `CursorSettings` changed in last frame.
+    pub(crate) fn apply_cursor_settings(&mut self, settings: &mut CursorSettings) {
+        if !settings.changed {
+            return;
+        }
+        settings.changed = false;
+
+        self.set_cursor_icon(settings.icon.clone());
+        self.set_cursor_visible(settings.visible && self.custom_cursor.is_none());
+
+        settings.confine_error = match self.set_cursor_confined(settings.confined) {
+            Ok(()) => None,
+            Err(err) => {
+                settings.confined = false;
+                Some(err.to_string())
+            }
+        };
+    }
+
+    /// Set the icon of the window from RGBA8 pixels, row by row from top to bottom.
+    pub fn set_icon(&mut self, rgba_data: &[u8], width: u32, height: u32) -> Result<(), BadIcon> {
+        let icon = Icon::from_rgba(rgba_data.to_vec(), width, height)?;
+        self.window.set_window_icon(Some(icon));
+
+        Ok(())
     }
 
-    // TODO: After finish texture module.
-    pub fn set_window_icon(&mut self) {
-        todo!()
+    /// Remove the icon of the window, use the default icon of platform.
+    pub fn clear_icon(&mut self) {
+        self.window.set_window_icon(None);
     }
 }
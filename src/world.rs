@@ -0,0 +1,130 @@
+use crate::legion::{
+    query::{component, DefaultFilter, EntityFilter, IntoQuery},
+    storage::Component,
+    Entity, World,
+};
+
+use std::ops::BitAnd;
+
+/// The single-call utilities for the common queries on `World`, e.g. in system setup and test assertions.
+pub trait QueryExt {
+    /// The component of the entity, `None` if the entity or the component doesn't exist.
+    fn get_component<T: Component>(&self, entity: Entity) -> Option<&T>;
+
+    fn get_component_mut<T: Component>(&mut self, entity: Entity) -> Option<&mut T>;
+
+    /// The count of entities matching the filter, e.g. `component::<Enemy>()`.
+    fn entity_count<F>(&self, filter: F) -> usize
+    where
+        F: EntityFilter,
+        <Entity as DefaultFilter>::Filter: BitAnd<F>,
+        <<Entity as DefaultFilter>::Filter as BitAnd<F>>::Output: EntityFilter;
+
+    /// Any entity matching the filter, e.g. `component::<Player>()`.
+    fn first_entity<F>(&self, filter: F) -> Option<Entity>
+    where
+        F: EntityFilter,
+        <Entity as DefaultFilter>::Filter: BitAnd<F>,
+        <<Entity as DefaultFilter>::Filter as BitAnd<F>>::Output: EntityFilter;
+}
+
+impl QueryExt for World {
+    fn get_component<T: Component>(&self, entity: Entity) -> Option<&T> {
+        self.entry_ref(entity).ok()?.into_component::<T>().ok()
+    }
+
+    fn get_component_mut<T: Component>(&mut self, entity: Entity) -> Option<&mut T> {
+        self.entry(entity)?.into_component_mut::<T>().ok()
+    }
+
+    fn entity_count<F>(&self, filter: F) -> usize
+    where
+        F: EntityFilter,
+        <Entity as DefaultFilter>::Filter: BitAnd<F>,
+        <<Entity as DefaultFilter>::Filter as BitAnd<F>>::Output: EntityFilter,
+    {
+        <Entity>::query().filter(filter).iter(self).count()
+    }
+
+    fn first_entity<F>(&self, filter: F) -> Option<Entity>
+    where
+        F: EntityFilter,
+        <Entity as DefaultFilter>::Filter: BitAnd<F>,
+        <<Entity as DefaultFilter>::Filter as BitAnd<F>>::Output: EntityFilter,
+    {
+        <Entity>::query().filter(filter).iter(self).next().copied()
+    }
+}
+
+/// Remove all entities with the component `T` right now, return the count of removed entities.
+///
+/// Call it in a thread local fn, e.g. `AppStageBuilder::add_thread_local_fn_process`.
+pub fn despawn_all_with<T: Component>(world: &mut World) -> usize {
+    despawn_all_with_filter(world, component::<T>())
+}
+
+/// Remove all entities matching the filter right now, e.g. `component::<Enemy>() & !component::<Boss>()`,
+/// return the count of removed entities.
+pub fn despawn_all_with_filter<F>(world: &mut World, filter: F) -> usize
+where
+    F: EntityFilter,
+    <Entity as DefaultFilter>::Filter: BitAnd<F>,
+    <<Entity as DefaultFilter>::Filter as BitAnd<F>>::Output: EntityFilter,
+{
+    let entities = <Entity>::query()
+        .filter(filter)
+        .iter(world)
+        .copied()
+        .collect::<Vec<_>>();
+
+    entities
+        .into_iter()
+        .filter(|entity| world.remove(*entity))
+        .count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    struct Enemy;
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    struct Boss;
+
+    #[test]
+    fn despawn_entities_with_component() {
+        let mut world = World::default();
+        world.extend(vec![(Enemy, 0u32), (Enemy, 1u32)]);
+        world.push((Enemy, Boss));
+        world.push((0u32,));
+
+        assert_eq!(
+            despawn_all_with_filter(&mut world, component::<Enemy>() & !component::<Boss>()),
+            2
+        );
+        assert_eq!(world.len(), 2);
+
+        assert_eq!(despawn_all_with::<Enemy>(&mut world), 1);
+        assert_eq!(despawn_all_with::<Enemy>(&mut world), 0);
+        assert_eq!(world.len(), 1);
+    }
+
+    #[test]
+    fn query_ext() {
+        let mut world = World::default();
+        let boss = world.push((Enemy, Boss, 10u32));
+        world.push((Enemy, 1u32));
+
+        assert_eq!(world.entity_count(component::<Enemy>()), 2);
+        assert_eq!(world.first_entity(component::<Boss>()), Some(boss));
+        assert_eq!(world.first_entity(component::<f32>()), None);
+
+        *world.get_component_mut::<u32>(boss).unwrap() += 5;
+        assert_eq!(world.get_component::<u32>(boss), Some(&15));
+        assert_eq!(world.get_component::<f32>(boss), None);
+
+        world.remove(boss);
+        assert_eq!(world.get_component::<u32>(boss), None);
+    }
+}